@@ -0,0 +1,19 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ray_tracer_challenge::{testing::random_spheres_camera, world::World};
+
+fn random_spheres(c: &mut Criterion) {
+    let camera = random_spheres_camera(60, 40);
+    let mut group = c.benchmark_group("random_spheres");
+    group.sample_size(10);
+
+    for n in [50, 400] {
+        let world = World::random_spheres(n, 42);
+        group.bench_with_input(BenchmarkId::new("render", n), &world, |b, world| {
+            b.iter(|| camera.render(world))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, random_spheres);
+criterion_main!(benches);
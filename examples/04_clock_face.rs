@@ -1,22 +1,11 @@
-use std::f64::consts::PI;
-
 use ray_tracer_challenge::{
-    canvas::Canvas, color::Colors, error::RayTraceResult, transformation::Transformation,
-    tuple::Tuple,
+    canvas::Canvas, color::Colors, error::RayTraceResult, sketch::draw_clock_face,
 };
 
 fn main() -> RayTraceResult<()> {
     let mut c = Canvas::fill_with(100, 100, Colors::White.into());
 
-    for i in 0..12 {
-        let point = Tuple::point(0.0, 45.0, 0.0);
-        let transformation = Transformation::identity()
-            .rotate_z(i as f64 * ((2.0 * PI) / 12.0))
-            .translation(50.0, 50.0, 0.0);
-        let point = transformation * point;
-
-        c[point] = Colors::Red.into();
-    }
+    draw_clock_face(&mut c, 45.0, Colors::Red.into());
 
     c.save("clock")?;
 
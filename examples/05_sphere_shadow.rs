@@ -24,7 +24,7 @@ fn main() -> RayTraceResult<()> {
                 Tuple::vector(-200.0 + x as f64, -200.0 + y as f64, 500.0),
             );
 
-            c[(x, y)] = if sphere.intersects(r).len() > 0 {
+            c[(x, y)] = if !sphere.intersects(&r).is_empty() {
                 Colors::Red.into()
             } else {
                 Colors::Black.into()
@@ -33,7 +33,7 @@ fn main() -> RayTraceResult<()> {
                 Tuple::vector(-200.0 + x as f64, -200.0 + y as f64, 500.0).normalize(),
             );
 
-            let intersections = r.intersections(sphere.clone());
+            let intersections = r.intersections(&sphere);
 
             c[(x, y)] = if let Some(hit) = intersections.hit() {
                 let point = r.position(hit.t());
@@ -44,12 +44,12 @@ fn main() -> RayTraceResult<()> {
                     .normal_at(sphere.id(), point, hit.clone())
                     .unwrap();
                 let eye = -r.direction();
-                hit.object()
-                    .write()
-                    .unwrap()
+                let object = hit.object();
+                let object = object.read().unwrap();
+                object
                     .material(hit.object_id())
                     .unwrap()
-                    .lighting(hit.object().clone(), light, point, eye, normal, false)
+                    .lighting(&*object, light, point, eye, normal, false)
             } else {
                 Colors::Black.into()
             };
@@ -0,0 +1,10 @@
+use ray_tracer_challenge::{
+    error::RayTraceResult,
+    testing::{random_spheres_camera, random_spheres_world},
+};
+
+fn main() -> RayTraceResult<()> {
+    random_spheres_camera(300, 200)
+        .render(&random_spheres_world())
+        .save("random_spheres")
+}
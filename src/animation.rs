@@ -0,0 +1,218 @@
+use crate::{transformation::Transformation, tuple::Tuple};
+
+/**
+   A value that can be blended between two keyframes, where `t` runs from 0
+   at `self` to 1 at `other`.
+*/
+pub trait Interpolate: Clone {
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Tuple {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+/**
+   One step of a transformation, as written in scene files. Keeping the
+   steps rather than the matrix they make lets a rotation be blended by its
+   angle instead of by the entries of the matrix.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransformStep {
+    Translate(f64, f64, f64),
+    Scale(f64, f64, f64),
+    RotateX(f64),
+    RotateY(f64),
+    RotateZ(f64),
+    Shear(f64, f64, f64, f64, f64, f64),
+}
+
+impl TransformStep {
+    pub fn apply(&self, transformation: &Transformation) -> Transformation {
+        match *self {
+            Self::Translate(x, y, z) => transformation.translation(x, y, z),
+            Self::Scale(x, y, z) => transformation.scale(x, y, z),
+            Self::RotateX(radians) => transformation.rotate_x(radians),
+            Self::RotateY(radians) => transformation.rotate_y(radians),
+            Self::RotateZ(radians) => transformation.rotate_z(radians),
+            Self::Shear(xy, xz, yx, yz, zx, zy) => transformation.shear(xy, xz, yx, yz, zx, zy),
+        }
+    }
+
+    /**
+       The transformation made by applying `steps` in order.
+    */
+    pub fn transformation(steps: &[TransformStep]) -> Transformation {
+        steps
+            .iter()
+            .fold(Transformation::identity(), |transformation, step| {
+                step.apply(&transformation)
+            })
+    }
+}
+
+/**
+   Steps of the same kind blend their arguments. Different kinds of step
+   cannot be blended, so the earlier one holds until the next keyframe.
+*/
+impl Interpolate for TransformStep {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        let lerp = |a: f64, b: f64| a.interpolate(&b, t);
+
+        match (*self, *other) {
+            (Self::Translate(x1, y1, z1), Self::Translate(x2, y2, z2)) => {
+                Self::Translate(lerp(x1, x2), lerp(y1, y2), lerp(z1, z2))
+            }
+            (Self::Scale(x1, y1, z1), Self::Scale(x2, y2, z2)) => {
+                Self::Scale(lerp(x1, x2), lerp(y1, y2), lerp(z1, z2))
+            }
+            (Self::RotateX(a), Self::RotateX(b)) => Self::RotateX(lerp(a, b)),
+            (Self::RotateY(a), Self::RotateY(b)) => Self::RotateY(lerp(a, b)),
+            (Self::RotateZ(a), Self::RotateZ(b)) => Self::RotateZ(lerp(a, b)),
+            (Self::Shear(a1, a2, a3, a4, a5, a6), Self::Shear(b1, b2, b3, b4, b5, b6)) => {
+                Self::Shear(
+                    lerp(a1, b1),
+                    lerp(a2, b2),
+                    lerp(a3, b3),
+                    lerp(a4, b4),
+                    lerp(a5, b5),
+                    lerp(a6, b6),
+                )
+            }
+            _ if t < 1.0 => *self,
+            _ => *other,
+        }
+    }
+}
+
+/**
+   Lists of steps blend step by step when they have the same length, and
+   otherwise hold like mismatched steps do.
+*/
+impl Interpolate for Vec<TransformStep> {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        if self.len() != other.len() {
+            return if t < 1.0 { self.clone() } else { other.clone() };
+        }
+
+        self.iter()
+            .zip(other)
+            .map(|(a, b)| a.interpolate(b, t))
+            .collect()
+    }
+}
+
+/**
+   Values at points in time, blended linearly in between. Before the first
+   key and after the last the value holds.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframes<T> {
+    keys: Vec<(f64, T)>,
+}
+
+impl<T: Interpolate> Keyframes<T> {
+    pub fn new() -> Self {
+        Self { keys: vec![] }
+    }
+
+    /**
+       Add a key at `time`, replacing any key already there.
+    */
+    pub fn with_key(mut self, time: f64, value: T) -> Self {
+        match self.keys.binary_search_by(|(key, _)| key.total_cmp(&time)) {
+            Ok(index) => self.keys[index].1 = value,
+            Err(index) => self.keys.insert(index, (time, value)),
+        }
+        self
+    }
+
+    pub fn keys(&self) -> &[(f64, T)] {
+        &self.keys
+    }
+
+    pub fn start(&self) -> Option<f64> {
+        self.keys.first().map(|(time, _)| *time)
+    }
+
+    pub fn end(&self) -> Option<f64> {
+        self.keys.last().map(|(time, _)| *time)
+    }
+
+    pub fn at(&self, time: f64) -> Option<T> {
+        let next = self.keys.partition_point(|(key, _)| *key <= time);
+        if next == 0 {
+            return self.keys.first().map(|(_, value)| value.clone());
+        }
+        if next == self.keys.len() {
+            return self.keys.last().map(|(_, value)| value.clone());
+        }
+
+        let (start, from) = &self.keys[next - 1];
+        let (end, to) = &self.keys[next];
+        Some(from.interpolate(to, (time - start) / (end - start)))
+    }
+}
+
+impl<T: Interpolate> Default for Keyframes<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::util::eq_f64;
+
+    use super::*;
+
+    #[test]
+    fn keyframes_blend_between_keys_and_hold_outside_them() {
+        let keys = Keyframes::new()
+            .with_key(2.0, 10.0)
+            .with_key(0.0, 0.0)
+            .with_key(4.0, 0.0);
+
+        assert_eq!(Some(0.0), keys.start());
+        assert_eq!(Some(4.0), keys.end());
+        assert!(eq_f64(0.0, keys.at(-1.0).unwrap()));
+        assert!(eq_f64(5.0, keys.at(1.0).unwrap()));
+        assert!(eq_f64(10.0, keys.at(2.0).unwrap()));
+        assert!(eq_f64(2.5, keys.at(3.5).unwrap()));
+        assert!(eq_f64(0.0, keys.at(9.0).unwrap()));
+        assert_eq!(None, Keyframes::<f64>::new().at(1.0));
+    }
+
+    #[test]
+    fn rotations_blend_by_their_angle() {
+        let keys = Keyframes::new()
+            .with_key(0.0, vec![TransformStep::RotateY(0.0)])
+            .with_key(1.0, vec![TransformStep::RotateY(PI)]);
+
+        let halfway = TransformStep::transformation(&keys.at(0.5).unwrap());
+
+        assert_eq!(
+            Tuple::point(0.0, 0.0, -1.0),
+            halfway * Tuple::point(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn steps_that_differ_hold_until_the_next_key() {
+        let from = vec![TransformStep::Scale(1.0, 1.0, 1.0)];
+        let to = vec![TransformStep::Translate(0.0, 1.0, 0.0)];
+
+        assert_eq!(from, from.interpolate(&to, 0.9));
+        assert_eq!(to, from.interpolate(&to, 1.0));
+    }
+}
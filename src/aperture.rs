@@ -0,0 +1,300 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{canvas::Canvas, sampling::square_to_disk};
+
+/**
+   The shape of the opening in a lens. Out of focus highlights take this
+   shape, so a camera whose aperture is a pentagon blurs points of light
+   into pentagons. Every aperture fits within the unit circle.
+*/
+#[derive(Debug, Clone, Default)]
+pub enum Aperture {
+    #[default]
+    Circle,
+    /**
+       A regular polygon with `blades` corners on the unit circle, the way
+       the blades of an iris diaphragm close down. A `rotation` of 0 puts
+       the first corner straight up.
+    */
+    Polygon {
+        blades: usize,
+        rotation: f64,
+    },
+    Mask(Arc<ApertureMask>),
+}
+
+impl Aperture {
+    pub fn polygon(blades: usize) -> Self {
+        Self::Polygon {
+            blades: blades.max(3),
+            rotation: 0.0,
+        }
+    }
+
+    pub fn pentagon() -> Self {
+        Self::polygon(5)
+    }
+
+    pub fn hexagon() -> Self {
+        Self::polygon(6)
+    }
+
+    /**
+       An aperture shaped like the bright parts of `image`, see
+       `ApertureMask`.
+    */
+    pub fn mask(image: &Canvas) -> Self {
+        Self::Mask(Arc::new(ApertureMask::new(image)))
+    }
+
+    /**
+       Turn a polygon by `rotation` radians. Other apertures are unchanged.
+    */
+    pub fn with_rotation(self, rotation: f64) -> Self {
+        match self {
+            Self::Polygon { blades, .. } => Self::Polygon { blades, rotation },
+            aperture => aperture,
+        }
+    }
+
+    /**
+       Map a point in the unit square to a point on the aperture, so that
+       evenly spread samples cover its area evenly.
+    */
+    pub fn sample(&self, (u, v): (f64, f64)) -> (f64, f64) {
+        match self {
+            Self::Circle => square_to_disk((u, v)),
+            Self::Polygon { blades, rotation } => {
+                // Each blade edge and the center make one of `blades` equal
+                // triangles, so pick a triangle with `u` and then a point in it.
+                let scaled = u * *blades as f64;
+                let blade = (scaled.floor() as usize).min(blades - 1);
+                let (a, b) = ((scaled - blade as f64).clamp(0.0, 1.0), v);
+                let corner = |i: usize| {
+                    let angle = rotation + 2.0 * PI * i as f64 / *blades as f64;
+                    (angle.sin(), angle.cos())
+                };
+                let (first, second) = (corner(blade), corner(blade + 1));
+                let root = a.sqrt();
+
+                (
+                    root * (1.0 - b) * first.0 + root * b * second.0,
+                    root * (1.0 - b) * first.1 + root * b * second.1,
+                )
+            }
+            Self::Mask(mask) => mask.sample((u, v)),
+        }
+    }
+
+    /**
+       Whether `point` lies on the aperture.
+    */
+    pub fn includes(&self, (x, y): (f64, f64)) -> bool {
+        match self {
+            Self::Circle => x * x + y * y <= 1.0,
+            Self::Polygon { blades, rotation } => {
+                let apothem = (PI / *blades as f64).cos();
+                (0..*blades).all(|i| {
+                    let angle = rotation + PI * (2 * i + 1) as f64 / *blades as f64;
+                    x * angle.sin() + y * angle.cos() <= apothem + 1e-9
+                })
+            }
+            Self::Mask(mask) => mask.transmission((x, y)) > 0.0,
+        }
+    }
+}
+
+/**
+   An aperture drawn as an image, where the brightness of each pixel is how
+   much light passes through that part of the lens. The image is centered
+   and scaled so its longer side spans the unit circle's diameter. Brighter
+   parts are sampled more often, so they show up brighter in the bokeh.
+*/
+#[derive(Debug)]
+pub struct ApertureMask {
+    width: usize,
+    height: usize,
+    transmission: Vec<f64>,
+    rows: Vec<f64>,
+    columns: Vec<Vec<f64>>,
+}
+
+impl ApertureMask {
+    pub fn new(image: &Canvas) -> Self {
+        let (width, height) = (image.width(), image.height());
+        let transmission = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|pixel| {
+                let color = image[pixel];
+                ((color.red() + color.green() + color.blue()) / 3.0).max(0.0)
+            })
+            .collect::<Vec<_>>();
+
+        let columns = transmission
+            .chunks(width.max(1))
+            .map(cumulative)
+            .collect::<Vec<_>>();
+        let rows = cumulative(
+            &columns
+                .iter()
+                .map(|row| row.last().copied().unwrap_or(0.0))
+                .collect::<Vec<_>>(),
+        );
+
+        Self {
+            width,
+            height,
+            transmission,
+            rows,
+            columns,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn scale(&self) -> f64 {
+        self.width.max(self.height).max(1) as f64 / 2.0
+    }
+
+    /**
+       How much light passes through the mask at `point`, from 0 to the
+       brightest pixel's brightness.
+    */
+    pub fn transmission(&self, (x, y): (f64, f64)) -> f64 {
+        let scale = self.scale();
+        let column = x * scale + self.width as f64 / 2.0;
+        let row = self.height as f64 / 2.0 - y * scale;
+        if column < 0.0 || row < 0.0 {
+            return 0.0;
+        }
+        let (column, row) = (column as usize, row as usize);
+        if column >= self.width || row >= self.height {
+            return 0.0;
+        }
+
+        self.transmission[row * self.width + column]
+    }
+
+    /**
+       A point on the mask, picking a row with `v` and then a pixel along
+       it with `u`, each in proportion to how much light they let through.
+       A mask that lets no light through only ever gives its center.
+    */
+    pub fn sample(&self, (u, v): (f64, f64)) -> (f64, f64) {
+        let Some((row, fy)) = pick(&self.rows, v) else {
+            return (0.0, 0.0);
+        };
+        let Some((column, fx)) = pick(&self.columns[row], u) else {
+            return (0.0, 0.0);
+        };
+        let scale = self.scale();
+
+        (
+            (column as f64 + fx - self.width as f64 / 2.0) / scale,
+            (self.height as f64 / 2.0 - row as f64 - fy) / scale,
+        )
+    }
+}
+
+/**
+   Running totals of `weights`.
+*/
+fn cumulative(weights: &[f64]) -> Vec<f64> {
+    weights
+        .iter()
+        .scan(0.0, |total, weight| {
+            *total += weight;
+            Some(*total)
+        })
+        .collect()
+}
+
+/**
+   The index whose share of the running `totals` holds `u`, and how far
+   through that share `u` falls.
+*/
+fn pick(totals: &[f64], u: f64) -> Option<(usize, f64)> {
+    let total = *totals.last()?;
+    if total <= 0.0 {
+        return None;
+    }
+
+    let target = u.clamp(0.0, 1.0) * total;
+    let index = totals
+        .partition_point(|&running| running <= target)
+        .min(totals.partition_point(|&running| running < total));
+    let before = if index == 0 { 0.0 } else { totals[index - 1] };
+    let share = totals[index] - before;
+
+    Some((index, ((target - before) / share).clamp(0.0, 1.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::{Color, Colors};
+
+    use super::*;
+
+    fn grid(count: usize) -> impl Iterator<Item = (f64, f64)> {
+        (0..count * count).map(move |i| {
+            (
+                ((i % count) as f64 + 0.5) / count as f64,
+                ((i / count) as f64 + 0.5) / count as f64,
+            )
+        })
+    }
+
+    #[test]
+    fn polygon_samples_stay_inside_the_polygon() {
+        for aperture in [Aperture::pentagon(), Aperture::hexagon().with_rotation(0.3)] {
+            for sample in grid(20) {
+                assert!(aperture.includes(aperture.sample(sample)));
+            }
+        }
+
+        assert!(Aperture::pentagon().includes((0.0, 0.99)));
+        assert!(!Aperture::pentagon().includes((0.0, -0.9)));
+    }
+
+    #[test]
+    fn polygon_samples_cover_every_blade() {
+        let aperture = Aperture::hexagon();
+        let mut sectors = [0; 6];
+
+        for sample in grid(12) {
+            let (x, y) = aperture.sample(sample);
+            let angle = x.atan2(y).rem_euclid(2.0 * PI);
+            sectors[(angle / (PI / 3.0)) as usize % 6] += 1;
+        }
+
+        assert!(sectors.iter().all(|&count| count == 24));
+    }
+
+    #[test]
+    fn a_mask_is_sampled_where_it_lets_light_through() {
+        let mut image = Canvas::new(4, 2);
+        image[(3, 0)] = Colors::White.into();
+        image[(0, 1)] = Color::new(0.5, 0.5, 0.5);
+        let aperture = Aperture::mask(&image);
+
+        let samples = grid(10)
+            .map(|sample| aperture.sample(sample))
+            .collect::<Vec<_>>();
+
+        assert!(samples.iter().all(|&point| aperture.includes(point)));
+        let top_right = samples.iter().filter(|(x, y)| *x > 0.0 && *y > 0.0).count();
+        assert_eq!(70, top_right);
+        let (x, y) = aperture.sample((1.0, 1.0));
+        assert!(x.abs() <= 1.0 && y.abs() <= 1.0);
+        assert_eq!(
+            (0.0, 0.0),
+            Aperture::mask(&Canvas::new(3, 3)).sample((0.5, 0.5))
+        );
+    }
+}
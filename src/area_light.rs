@@ -0,0 +1,320 @@
+use crate::{color::Color, sampling::Sampler, tuple::Tuple, world::World};
+
+/**
+   A rectangular light spanned by two edges from `corner`, divided into a
+   grid of `usteps` by `vsteps` cells that are each sampled once. Added to
+   a `World` as a `Light`, it is shaded from a jittered point in every cell
+   and casts soft shadows.
+*/
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AreaLight {
+    corner: Tuple,
+    uvec: Tuple,
+    usteps: usize,
+    vvec: Tuple,
+    vsteps: usize,
+    intensity: Color,
+}
+
+impl AreaLight {
+    pub fn new(
+        corner: Tuple,
+        full_uvec: Tuple,
+        usteps: usize,
+        full_vvec: Tuple,
+        vsteps: usize,
+        intensity: Color,
+    ) -> Self {
+        let usteps = usteps.max(1);
+        let vsteps = vsteps.max(1);
+
+        Self {
+            corner,
+            uvec: full_uvec / usteps as f64,
+            usteps,
+            vvec: full_vvec / vsteps as f64,
+            vsteps,
+            intensity,
+        }
+    }
+
+    pub fn corner(&self) -> Tuple {
+        self.corner
+    }
+
+    /**
+       One cell's edge along u.
+    */
+    pub fn uvec(&self) -> Tuple {
+        self.uvec
+    }
+
+    pub fn usteps(&self) -> usize {
+        self.usteps
+    }
+
+    /**
+       One cell's edge along v.
+    */
+    pub fn vvec(&self) -> Tuple {
+        self.vvec
+    }
+
+    pub fn vsteps(&self) -> usize {
+        self.vsteps
+    }
+
+    pub fn samples(&self) -> usize {
+        self.usteps * self.vsteps
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    /**
+       The middle of the light.
+    */
+    pub fn position(&self) -> Tuple {
+        self.corner
+            + self.uvec * (self.usteps as f64 / 2.0)
+            + self.vvec * (self.vsteps as f64 / 2.0)
+    }
+
+    /**
+       The point in cell `(u, v)` at `jitter`, a fraction of the way across
+       the cell along each edge.
+    */
+    pub fn point_on_light(&self, u: usize, v: usize, (du, dv): (f64, f64)) -> Tuple {
+        self.corner + self.uvec * (u as f64 + du) + self.vvec * (v as f64 + dv)
+    }
+
+    /**
+       One point in every cell, row by row, placed within its cell by the
+       next sample from `sampler`.
+    */
+    pub fn points(&self, sampler: &dyn Sampler) -> Vec<Tuple> {
+        sampler
+            .samples(self.samples())
+            .into_iter()
+            .enumerate()
+            .map(|(i, jitter)| self.point_on_light(i % self.usteps, i / self.usteps, jitter))
+            .collect()
+    }
+
+    /**
+       The fraction of the light's points that can see `point` in `world`.
+    */
+    pub fn intensity_at(&self, point: Tuple, world: &World, sampler: &dyn Sampler) -> f64 {
+        let points = self.points(sampler);
+        let visible = points
+            .iter()
+            .filter(|&&light| !world.is_occluded(point, light))
+            .count();
+
+        visible as f64 / points.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::Colors,
+        intersection::ray::Ray,
+        light::Light,
+        sampling::SequenceSampler,
+        shape::{material::Material, plane::Plane, sphere::Sphere, Shape},
+        testing::{colors_match, default_world},
+        transformation::Transformation,
+    };
+
+    use super::*;
+
+    fn centered() -> SequenceSampler {
+        SequenceSampler::new(vec![0.5])
+    }
+
+    #[test]
+    fn creating_an_area_light() {
+        let corner = Tuple::point(0.0, 0.0, 0.0);
+        let v1 = Tuple::vector(2.0, 0.0, 0.0);
+        let v2 = Tuple::vector(0.0, 0.0, 1.0);
+
+        let light = AreaLight::new(corner, v1, 4, v2, 2, Colors::White.into());
+
+        assert_eq!(corner, light.corner());
+        assert_eq!(Tuple::vector(0.5, 0.0, 0.0), light.uvec());
+        assert_eq!(4, light.usteps());
+        assert_eq!(Tuple::vector(0.0, 0.0, 0.5), light.vvec());
+        assert_eq!(2, light.vsteps());
+        assert_eq!(8, light.samples());
+        assert_eq!(Tuple::point(1.0, 0.0, 0.5), light.position());
+    }
+
+    #[test]
+    fn finding_a_single_point_on_an_area_light() {
+        let light = AreaLight::new(
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::vector(2.0, 0.0, 0.0),
+            4,
+            Tuple::vector(0.0, 0.0, 1.0),
+            2,
+            Colors::White.into(),
+        );
+
+        for (u, v, expected) in [
+            (0, 0, Tuple::point(0.25, 0.0, 0.25)),
+            (1, 0, Tuple::point(0.75, 0.0, 0.25)),
+            (0, 1, Tuple::point(0.25, 0.0, 0.75)),
+            (2, 0, Tuple::point(1.25, 0.0, 0.25)),
+            (3, 1, Tuple::point(1.75, 0.0, 0.75)),
+        ] {
+            assert_eq!(expected, light.point_on_light(u, v, (0.5, 0.5)));
+        }
+    }
+
+    #[test]
+    fn the_area_light_intensity_function() {
+        let w = default_world();
+        let light = AreaLight::new(
+            Tuple::point(-0.5, -0.5, -5.0),
+            Tuple::vector(1.0, 0.0, 0.0),
+            2,
+            Tuple::vector(0.0, 1.0, 0.0),
+            2,
+            Colors::White.into(),
+        );
+
+        for (point, expected) in [
+            (Tuple::point(0.0, 0.0, 2.0), 0.0),
+            (Tuple::point(1.0, -1.0, 2.0), 0.25),
+            (Tuple::point(1.5, 0.0, 2.0), 0.5),
+            (Tuple::point(1.25, 1.25, 3.0), 0.75),
+            (Tuple::point(0.0, 0.0, -2.0), 1.0),
+        ] {
+            assert_eq!(expected, light.intensity_at(point, &w, &centered()));
+        }
+    }
+
+    #[test]
+    fn finding_a_single_point_on_a_jittered_area_light() {
+        let light = AreaLight::new(
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::vector(2.0, 0.0, 0.0),
+            4,
+            Tuple::vector(0.0, 0.0, 1.0),
+            2,
+            Colors::White.into(),
+        );
+        let points = light.points(&SequenceSampler::new(vec![0.3, 0.7]));
+
+        for (u, v, expected) in [
+            (0, 0, Tuple::point(0.15, 0.0, 0.35)),
+            (1, 0, Tuple::point(0.65, 0.0, 0.35)),
+            (0, 1, Tuple::point(0.15, 0.0, 0.85)),
+            (2, 0, Tuple::point(1.15, 0.0, 0.35)),
+            (3, 1, Tuple::point(1.65, 0.0, 0.85)),
+        ] {
+            assert_eq!(expected, points[v * light.usteps() + u]);
+        }
+    }
+
+    #[test]
+    fn the_area_light_with_jittered_samples() {
+        let w = default_world();
+        let light = AreaLight::new(
+            Tuple::point(-0.5, -0.5, -5.0),
+            Tuple::vector(1.0, 0.0, 0.0),
+            2,
+            Tuple::vector(0.0, 1.0, 0.0),
+            2,
+            Colors::White.into(),
+        );
+        let sampler = SequenceSampler::new(vec![0.7, 0.3, 0.9, 0.1, 0.5]);
+
+        for (point, expected) in [
+            (Tuple::point(0.0, 0.0, 2.0), 0.0),
+            (Tuple::point(1.0, -1.0, 2.0), 0.5),
+            (Tuple::point(1.5, 0.0, 2.0), 0.75),
+            (Tuple::point(1.25, 1.25, 3.0), 0.75),
+            (Tuple::point(0.0, 0.0, -2.0), 1.0),
+        ] {
+            assert_eq!(expected, light.intensity_at(point, &w, &sampler));
+        }
+    }
+
+    #[test]
+    fn a_world_lit_by_an_area_light_shades_with_it() {
+        let light = AreaLight::new(
+            Tuple::point(-1.0, 6.0, -1.0),
+            Tuple::vector(2.0, 0.0, 0.0),
+            2,
+            Tuple::vector(0.0, 0.0, 2.0),
+            2,
+            Colors::White.into(),
+        );
+        let mut w = World::new();
+        w.add_light(light);
+        w.add_shape(Plane::new().into());
+        let mut sphere = Sphere::new();
+        sphere.set_transformation(Transformation::identity().translation(0.0, 2.0, 0.0));
+        w.add_shape(sphere.into());
+
+        let under = Tuple::point(0.0, 1e-4, 0.0);
+        let beside = Tuple::point(10.0, 1e-4, 0.0);
+        assert_eq!(0.0, Light::from(light).intensity_at(under, &w));
+        assert_eq!(1.0, Light::from(light).intensity_at(beside, &w));
+
+        let toward = |point: Tuple| {
+            let origin = point + Tuple::vector(0.0, 1.0, -5.0);
+            w.color_at(Ray::new(origin, (point - origin).normalize()))
+        };
+        assert_eq!(Color::new(0.1, 0.1, 0.1), toward(Tuple::origin()));
+        assert!(toward(Tuple::point(10.0, 0.0, 0.0)).red() > 0.1);
+    }
+
+    #[test]
+    fn lighting_samples_the_area_light() {
+        let light = AreaLight::new(
+            Tuple::point(-0.5, -0.5, -5.0),
+            Tuple::vector(1.0, 0.0, 0.0),
+            2,
+            Tuple::vector(0.0, 1.0, 0.0),
+            2,
+            Colors::White.into(),
+        );
+        let shape = Sphere::new();
+        let material = Material::new()
+            .with_ambient(0.1)
+            .with_diffuse(0.9)
+            .with_specular(0.0);
+        let eye = Tuple::point(0.0, 0.0, -5.0);
+
+        for (point, expected) in [
+            (
+                Tuple::point(0.0, 0.0, -1.0),
+                Color::new(0.9965, 0.9965, 0.9965),
+            ),
+            (
+                Tuple::point(0.0, 2f64.sqrt() / 2.0, -2f64.sqrt() / 2.0),
+                Color::new(0.62318, 0.62318, 0.62318),
+            ),
+        ] {
+            let eye_v = (eye - point).normalize();
+            let normal_v = Tuple::vector(point.x(), point.y(), point.z());
+
+            let result = material.lighting_with_samples(
+                &shape,
+                light.intensity(),
+                &light.points(&centered()),
+                point,
+                eye_v,
+                normal_v,
+                1.0,
+                1.0,
+            );
+
+            assert!(colors_match(expected, result), "{result:?}");
+        }
+    }
+}
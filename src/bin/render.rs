@@ -0,0 +1,154 @@
+use std::{env, ops::ControlFlow, path::Path, process, time::Duration, time::Instant};
+
+use ray_tracer_challenge::{error::RayTraceResult, metadata::RenderMetadata, scene::Scene};
+
+#[cfg(feature = "distributed")]
+use {
+    ray_tracer_challenge::distributed::{self, Coordinator},
+    std::net::TcpListener,
+};
+
+const USAGE: &str =
+    "usage: render <scene.yml> [-o output.ppm] [--set key=value]... [--watch [divisor]] [--frames n]";
+
+#[cfg(feature = "distributed")]
+const DISTRIBUTED_USAGE: &str = "       render <scene.yml> --workers host:port[,host:port]... [-o output.ppm] [--set key=value]... [--frames n]
+       render --worker host:port";
+
+/**
+   How much smaller than the final image previews are rendered in watch mode.
+*/
+const PREVIEW_DIVISOR: usize = 4;
+
+fn main() -> RayTraceResult<()> {
+    let mut args = env::args().skip(1).peekable();
+    let mut scene_path = None;
+    let mut output = None;
+    let mut overrides = vec![];
+    let mut watch = None;
+    let mut frames = None;
+    #[cfg(feature = "distributed")]
+    let mut workers: Vec<String> = vec![];
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(args.next().unwrap_or_else(|| usage_error())),
+            "--set" => overrides.push(args.next().unwrap_or_else(|| usage_error())),
+            "--watch" => {
+                watch = Some(
+                    args.next_if(|arg| arg.parse::<usize>().is_ok())
+                        .map_or(PREVIEW_DIVISOR, |divisor| divisor.parse().unwrap()),
+                )
+            }
+            "--frames" => {
+                frames = Some(
+                    args.next()
+                        .and_then(|frames| frames.parse::<usize>().ok())
+                        .unwrap_or_else(|| usage_error()),
+                )
+            }
+            #[cfg(feature = "distributed")]
+            "--workers" => workers.extend(
+                args.next()
+                    .unwrap_or_else(|| usage_error())
+                    .split(',')
+                    .map(String::from),
+            ),
+            #[cfg(feature = "distributed")]
+            "--worker" => {
+                let address = args.next().unwrap_or_else(|| usage_error());
+                println!("Waiting for tiles on {address}");
+                return distributed::serve(TcpListener::bind(address)?);
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                #[cfg(feature = "distributed")]
+                println!("{DISTRIBUTED_USAGE}");
+                return Ok(());
+            }
+            _ if arg.starts_with('-') || scene_path.is_some() => usage_error(),
+            _ => scene_path = Some(arg),
+        }
+    }
+    let Some(scene_path) = scene_path else {
+        usage_error();
+    };
+
+    let name = Path::new(&scene_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let output = output.unwrap_or(name.clone());
+
+    #[cfg(feature = "distributed")]
+    if !workers.is_empty() {
+        let source = std::fs::read_to_string(&scene_path)?;
+        let coordinator = overrides
+            .iter()
+            .fold(Coordinator::new(&source), |coordinator, assignment| {
+                coordinator.with_setting(assignment)
+            });
+        if let Some(frames) = frames {
+            return coordinator.render_animation(&workers, frames, &output);
+        }
+        let start = Instant::now();
+        let image = coordinator.render(&workers)?;
+        let metadata = RenderMetadata::new()
+            .with_scene(&name)
+            .with_duration(start.elapsed());
+        return image.save_with_metadata(&output, &metadata);
+    }
+
+    let Some(divisor) = watch else {
+        let mut scene = Scene::parse_file(&scene_path)?;
+        for assignment in &overrides {
+            scene.set(assignment)?;
+        }
+        if let Some(frames) = frames {
+            return scene.render_animation(frames, &output);
+        }
+        return render(&scene, &name, &output);
+    };
+
+    Scene::watch(&scene_path, Duration::from_millis(250), |scene| {
+        let result = scene.and_then(|mut scene| {
+            for assignment in &overrides {
+                scene.set(assignment)?;
+            }
+            scene.preview(divisor);
+            render(&scene, &name, &output)
+        });
+        match result {
+            Ok(()) => println!("Rendered {output}, watching {scene_path} for changes"),
+            Err(e) => eprintln!("{}", e.to_string().trim_end()),
+        }
+        ControlFlow::Continue(())
+    });
+
+    Ok(())
+}
+
+/**
+   Print how to run the renderer and exit, for arguments it can't use.
+*/
+fn usage_error() -> ! {
+    eprintln!("{USAGE}");
+    #[cfg(feature = "distributed")]
+    eprintln!("{DISTRIBUTED_USAGE}");
+    process::exit(2);
+}
+
+fn render(scene: &Scene, name: &str, output: &str) -> RayTraceResult<()> {
+    let start = Instant::now();
+    let image = scene.render();
+
+    let settings = scene.render_settings();
+    let mut metadata = RenderMetadata::new()
+        .with_scene(name)
+        .with_samples(settings.samples())
+        .with_duration(start.elapsed());
+    if let Some(seed) = settings.seed() {
+        metadata = metadata.with_seed(seed);
+    }
+    image.save_with_metadata(output, &metadata)
+}
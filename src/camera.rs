@@ -1,18 +1,111 @@
-use indicatif::{ProgressBar, ProgressStyle};
-use rayon::iter::{ParallelBridge, ParallelIterator};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use uuid::Uuid;
 
 use crate::{
-    canvas::Canvas, intersection::ray::Ray, transformation::Transformation, tuple::Tuple,
-    util::eq_f64, world::World,
+    aperture::Aperture,
+    canvas::Canvas,
+    color::{Color, Colors},
+    integrator::{Integrator, Whitted},
+    intersection::{
+        ray::{Ray, RayKind},
+        scratch::Scratch,
+    },
+    progress::Progress,
+    sampling::{mix_seed, radical_inverse, seed_rng, with_rng, Sampler, StratifiedSampler},
+    shape::group::Descendants,
+    transformation::Transformation,
+    tuple::Tuple,
+    util::eq_f64,
+    world::World,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdPass {
+    Object,
+    Material,
+}
+
+/**
+   A rectangle of pixels in an image, with its top left corner at (`x`, `y`).
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Tile {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /**
+       Cover a `width` by `height` image with tiles `size` pixels square,
+       row by row. Tiles along the right and bottom edges are cut short.
+    */
+    pub fn split(width: usize, height: usize, size: usize) -> Vec<Self> {
+        let size = size.max(1);
+
+        (0..height)
+            .step_by(size)
+            .flat_map(|y| {
+                (0..width)
+                    .step_by(size)
+                    .map(move |x| Self::new(x, y, size.min(width - x), size.min(height - y)))
+            })
+            .collect()
+    }
+
+    pub fn x(&self) -> usize {
+        self.x
+    }
+
+    pub fn y(&self) -> usize {
+        self.y
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
 pub struct Camera {
     h_size: f64,
     v_size: f64,
     transform: Transformation,
     half_width: f64,
     half_height: f64,
-    pixel_size: f64,
+    pixel_width: f64,
+    pixel_height: f64,
+    letterbox: Option<(f64, f64)>,
+    seed: Option<u64>,
+    accumulation_pass: Option<AtomicUsize>,
+    integrator: Box<dyn Integrator>,
+    progress: Progress,
+    aperture_radius: f64,
+    focal_distance: f64,
+    aperture: Aperture,
+    lens_samples: usize,
+    shutter_open: f64,
+    shutter_close: f64,
+    motion_samples: usize,
 }
 
 impl Camera {
@@ -25,29 +118,390 @@ impl Camera {
             (half_view * aspect, half_view)
         };
 
+        Self::from_half_extents(h_size, v_size, half_width, half_height)
+    }
+
+    /**
+       Create a camera with separate horizontal and vertical fields of view.
+       Pixels are only square when the fields of view match the image's aspect
+       ratio, which allows anamorphic output.
+    */
+    pub fn with_fields_of_view(
+        h_size: usize,
+        v_size: usize,
+        horizontal_field_of_view: f64,
+        vertical_field_of_view: f64,
+    ) -> Self {
+        Self::from_half_extents(
+            h_size,
+            v_size,
+            (horizontal_field_of_view / 2.0).tan(),
+            (vertical_field_of_view / 2.0).tan(),
+        )
+    }
+
+    fn from_half_extents(h_size: usize, v_size: usize, half_width: f64, half_height: f64) -> Self {
         Self {
             v_size: v_size as f64,
             h_size: h_size as f64,
             transform: Transformation::identity(),
             half_width,
             half_height,
-            pixel_size: (half_width * 2.0) / h_size as f64,
+            pixel_width: (half_width * 2.0) / h_size as f64,
+            pixel_height: (half_height * 2.0) / v_size as f64,
+            letterbox: None,
+            seed: None,
+            accumulation_pass: None,
+            integrator: Box::new(Whitted),
+            progress: Progress::default(),
+            aperture_radius: 0.0,
+            focal_distance: 1.0,
+            aperture: Aperture::default(),
+            lens_samples: 1,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            motion_samples: 1,
         }
     }
 
+    /**
+       Stretch each pixel to `pixel_aspect` times its height while keeping the
+       horizontal field of view, e.g. 2.0 for footage shot through a 2x
+       anamorphic lens.
+    */
+    pub fn with_pixel_aspect(mut self, pixel_aspect: f64) -> Self {
+        self.pixel_height = self.pixel_width / pixel_aspect;
+        self.half_height = self.pixel_height * self.v_size / 2.0;
+        self
+    }
+
+    /**
+       Render `pixels` extra pixels on every side of the frame. The pixel size
+       is unchanged so the original frame can be cropped back out.
+    */
+    pub fn with_overscan(mut self, pixels: usize) -> Self {
+        let pixels = pixels as f64;
+        self.h_size += pixels * 2.0;
+        self.v_size += pixels * 2.0;
+        self.half_width += pixels * self.pixel_width;
+        self.half_height += pixels * self.pixel_height;
+        self
+    }
+
+    /**
+       Restrict rendering to a centered gate with the given display aspect
+       ratio, fitted to the current frame. Pixels outside the gate are left
+       black instead of being traced.
+    */
+    pub fn with_letterbox(mut self, aspect: f64) -> Self {
+        let frame_aspect = self.half_width / self.half_height;
+        self.letterbox = if aspect > frame_aspect {
+            Some((self.half_width, self.half_width / aspect))
+        } else {
+            Some((self.half_height * aspect, self.half_height))
+        };
+        self
+    }
+
+    /**
+       Seed the random numbers used for each pixel from `seed` and the
+       pixel's position, so renders with the same seed are identical however
+       the work is split between threads.
+    */
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /**
+       Shade rays with `integrator` instead of the default `Whitted`.
+    */
+    pub fn with_integrator<T: Integrator + 'static>(mut self, integrator: T) -> Self {
+        self.integrator = Box::new(integrator);
+        self
+    }
+
+    pub fn integrator(&self) -> &dyn Integrator {
+        self.integrator.as_ref()
+    }
+
+    pub fn with_progress(mut self, progress: Progress) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn progress(&self) -> Progress {
+        self.progress
+    }
+
+    /**
+       Trace rays from across a lens of `aperture_radius` instead of from a
+       single point, so only things `focal_distance` in front of the camera
+       are sharp. An aperture radius of 0 is a pinhole, where everything is.
+    */
+    pub fn with_depth_of_field(mut self, aperture_radius: f64, focal_distance: f64) -> Self {
+        self.aperture_radius = aperture_radius.max(0.0);
+        self.focal_distance = focal_distance;
+        self
+    }
+
+    /**
+       The shape of the lens opening, which out of focus highlights take.
+    */
+    pub fn with_aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    /**
+       Average `lens_samples` rays from across the lens for each pixel.
+       Depth of field is noisy with a single ray per pixel unless many
+       accumulated passes are blended together.
+    */
+    pub fn with_lens_samples(mut self, lens_samples: usize) -> Self {
+        self.lens_samples = lens_samples.max(1);
+        self
+    }
+
+    pub fn aperture_radius(&self) -> f64 {
+        self.aperture_radius
+    }
+
+    pub fn focal_distance(&self) -> f64 {
+        self.focal_distance
+    }
+
+    pub fn aperture(&self) -> &Aperture {
+        &self.aperture
+    }
+
+    pub fn lens_samples(&self) -> usize {
+        self.lens_samples
+    }
+
+    fn has_lens(&self) -> bool {
+        self.aperture_radius > 0.0
+    }
+
+    /**
+       Keep the shutter open from `open` to `close`, measured in the units
+       of an animation's keyframes from the time of the frame, so shapes
+       that move while it is open blur along their path. See
+       `render_motion`.
+    */
+    pub fn with_shutter(mut self, open: f64, close: f64) -> Self {
+        self.shutter_open = open;
+        self.shutter_close = close.max(open);
+        self
+    }
+
+    /**
+       Average renders at `motion_samples` times while the shutter is open.
+    */
+    pub fn with_motion_samples(mut self, motion_samples: usize) -> Self {
+        self.motion_samples = motion_samples.max(1);
+        self
+    }
+
+    pub fn shutter_open(&self) -> f64 {
+        self.shutter_open
+    }
+
+    pub fn shutter_close(&self) -> f64 {
+        self.shutter_close
+    }
+
+    pub fn motion_samples(&self) -> usize {
+        self.motion_samples
+    }
+
+    /**
+       The times to render at while the shutter is open, one in each of
+       `motion_samples` equal slices of it. A seeded camera jitters them
+       within their slices, the same way on every render; otherwise each is
+       in the middle of its slice.
+    */
+    pub fn shutter_times(&self) -> Vec<f64> {
+        let mut rng = self.seed.map(StdRng::seed_from_u64);
+        let slice = (self.shutter_close - self.shutter_open) / self.motion_samples as f64;
+
+        (0..self.motion_samples)
+            .map(|i| {
+                let offset = rng.as_mut().map_or(0.5, |rng| rng.gen());
+                self.shutter_open + slice * (i as f64 + offset)
+            })
+            .collect()
+    }
+
+    /**
+       The seed for the render at the `sample`th shutter time. The first
+       keeps the camera's seed, so a camera without motion blur renders as
+       it always has.
+    */
+    pub fn motion_seed(&self, sample: usize) -> Option<u64> {
+        self.seed.map(|seed| {
+            if sample == 0 {
+                seed
+            } else {
+                mix_seed(seed, sample as u64)
+            }
+        })
+    }
+
+    /**
+       Shift every ray by a different sub-pixel offset, taken from the Halton
+       sequence, each time the camera renders, and give a seeded camera a new
+       seed for each pass. Blending the renders together with
+       `Canvas::accumulate` refines the image for as long as it is kept up.
+    */
+    pub fn with_accumulation(mut self) -> Self {
+        self.accumulation_pass = Some(AtomicUsize::new(0));
+        self
+    }
+
+    /**
+       How many renders have been made since accumulation was turned on.
+    */
+    pub fn accumulated_passes(&self) -> Option<usize> {
+        self.accumulation_pass
+            .as_ref()
+            .map(|pass| pass.load(Ordering::Relaxed))
+    }
+
+    /**
+       Move an accumulating camera on to its next pass. `render` does this
+       itself; callers rendering a pass a tile at a time call it once all of
+       the pass's tiles are done, so every tile is offset alike.
+    */
+    pub fn next_pass(&self) {
+        if let Some(pass) = &self.accumulation_pass {
+            pass.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn subpixel_offset_for_pass(&self) -> (f64, f64) {
+        match self.accumulated_passes() {
+            Some(pass) => (radical_inverse(2, pass + 1), radical_inverse(3, pass + 1)),
+            None => (0.5, 0.5),
+        }
+    }
+
+    /**
+       The seed for the current pass. The first keeps the camera's seed;
+       later passes count down from `u64::MAX` so they never share a seed
+       with `motion_seed`'s samples, which count up.
+    */
+    fn seed_for_pass(&self) -> Option<u64> {
+        let seed = self.seed?;
+        match self.accumulated_passes() {
+            Some(pass) if pass > 0 => Some(mix_seed(seed, u64::MAX - pass as u64)),
+            _ => Some(seed),
+        }
+    }
+
+    pub fn h_size(&self) -> usize {
+        self.h_size as usize
+    }
+
+    pub fn v_size(&self) -> usize {
+        self.v_size as usize
+    }
+
     pub fn set_transformation(&mut self, transformation: Transformation) {
         self.transform = transformation;
     }
 
-    fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
-        let x_offset = (px as f64 + 0.5) * self.pixel_size;
-        let y_offset = (py as f64 + 0.5) * self.pixel_size;
+    fn pixel_offset(&self, px: usize, py: usize) -> (f64, f64) {
+        self.subpixel_offset(px, py, (0.5, 0.5))
+    }
+
+    fn subpixel_offset(&self, px: usize, py: usize, (dx, dy): (f64, f64)) -> (f64, f64) {
+        let x_offset = (px as f64 + dx) * self.pixel_width;
+        let y_offset = (py as f64 + dy) * self.pixel_height;
+
+        (self.half_width - x_offset, self.half_height - y_offset)
+    }
+
+    fn in_gate(&self, px: usize, py: usize) -> bool {
+        let Some((gate_width, gate_height)) = self.letterbox else {
+            return true;
+        };
+        let (world_x, world_y) = self.pixel_offset(px, py);
+
+        world_x.abs() <= gate_width && world_y.abs() <= gate_height
+    }
+
+    /**
+       The ray through the center of pixel (`px`, `py`). With depth of field
+       it starts from a random point on the lens.
+    */
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        let lens = if self.has_lens() {
+            with_rng(|rng| (rng.gen(), rng.gen()))
+        } else {
+            (0.5, 0.5)
+        };
+        self.ray_through(px, py, (0.5, 0.5), lens)
+    }
+
+    /**
+       The ray through the center of pixel (`px`, `py`) from the point on
+       the lens that `lens`, in the unit square, maps to.
+    */
+    pub fn ray_for_pixel_through_lens(&self, px: usize, py: usize, lens: (f64, f64)) -> Ray {
+        self.ray_through(px, py, (0.5, 0.5), lens)
+    }
+
+    fn ray_through(&self, px: usize, py: usize, subpixel: (f64, f64), lens: (f64, f64)) -> Ray {
+        let lens = if self.has_lens() {
+            self.aperture.sample(lens)
+        } else {
+            (0.0, 0.0)
+        };
+        self.ray_from_lens_point(px, py, subpixel, lens)
+    }
+
+    /**
+       The ray through pixel (`px`, `py`) from the center of the lens, the
+       ray a pinhole camera would trace.
+    */
+    fn center_ray(&self, px: usize, py: usize, subpixel: (f64, f64)) -> Ray {
+        self.ray_from_lens_point(px, py, subpixel, (0.0, 0.0))
+    }
 
-        let world_x = self.half_width - x_offset;
-        let world_y = self.half_height - y_offset;
+    fn ray_from_lens_point(
+        &self,
+        px: usize,
+        py: usize,
+        subpixel: (f64, f64),
+        (lens_x, lens_y): (f64, f64),
+    ) -> Ray {
+        let (world_x, world_y) = self.subpixel_offset(px, py, subpixel);
 
         let transform_invese = self.transform.inverse().unwrap();
 
+        if self.has_lens() {
+            // Every ray through the pixel meets at its point on the focal
+            // plane, wherever on the lens it starts.
+            let focus = transform_invese.clone()
+                * Tuple::point(
+                    world_x * self.focal_distance,
+                    world_y * self.focal_distance,
+                    -self.focal_distance,
+                );
+            let origin = transform_invese
+                * Tuple::point(
+                    lens_x * self.aperture_radius,
+                    lens_y * self.aperture_radius,
+                    0.0,
+                );
+            return Ray::new(origin, (focus - origin).normalize());
+        }
+
         let pixel = transform_invese.clone() * Tuple::point(world_x, world_y, -1.0);
         let origin = transform_invese * Tuple::origin();
         let direction = (pixel - origin).normalize();
@@ -55,26 +509,208 @@ impl Camera {
         Ray::new(origin, direction)
     }
 
+    /**
+       The color of pixel (`x`, `y`) from `color_at`, averaged over the lens
+       samples when the camera has depth of field and `through_lens` is set,
+       and otherwise from the one ray through the center of the lens.
+    */
+    fn pixel_color<F: Fn(Ray, &mut Scratch) -> Color>(
+        &self,
+        x: usize,
+        y: usize,
+        subpixel: (f64, f64),
+        through_lens: bool,
+        scratch: &mut Scratch,
+        color_at: &F,
+    ) -> Color {
+        if !self.has_lens() || !through_lens {
+            return color_at(self.center_ray(x, y, subpixel), scratch);
+        }
+
+        let samples = StratifiedSampler::default().samples(self.lens_samples);
+        let total = samples
+            .iter()
+            .fold(Colors::Black.into(), |total: Color, &lens| {
+                total + color_at(self.ray_through(x, y, subpixel, lens), scratch)
+            });
+
+        total * (1.0 / samples.len() as f64)
+    }
+
     pub fn render(&self, world: &World) -> Canvas {
-        let mut image = Canvas::new(self.h_size as usize, self.v_size as usize);
-        let pb = ProgressBar::new((self.v_size * self.h_size) as u64);
-        pb.set_style(ProgressStyle::with_template("{wide_bar} {percent}% {eta} {msg}").unwrap());
-
-        let vecs = (0..self.v_size as usize)
-            .flat_map(|y| (0..self.h_size as usize).map(move |x| (x, y)))
-            .par_bridge()
-            .map(|(x, y)| {
-                let ray = self.ray_for_pixel(x, y);
-                let color = world.color_at(ray);
-                pb.inc(1);
-                (x, y, color)
+        let image = self.render_tile(world, self.full_frame());
+        self.next_pass();
+        image
+    }
+
+    /**
+       Render only the pixels in `tile`, exactly as `render` would color
+       them, into a canvas the size of the tile. Pasting the tiles of an
+       image back together gives the same image as rendering it whole.
+    */
+    pub fn render_tile(&self, world: &World, tile: Tile) -> Canvas {
+        self.render_pass(world, tile, self.seed_for_pass())
+    }
+
+    /**
+       Render `world` at each of `shutter_times`, calling `move_to` with
+       the time first so it can move the world's shapes to where they are
+       then, and average the renders. Each time is rendered with its own
+       `motion_seed`, so a seeded camera blurs the same way every time.
+    */
+    pub fn render_motion(&self, world: &World, mut move_to: impl FnMut(f64)) -> Canvas {
+        let tile = self.full_frame();
+        let mut image = Canvas::new(tile.width, tile.height);
+
+        for (sample, time) in self.shutter_times().into_iter().enumerate() {
+            move_to(time);
+            let pass = self.render_pass(world, tile, self.motion_seed(sample));
+            image.accumulate(&pass, 1.0 / (sample + 1) as f64);
+        }
+        self.next_pass();
+
+        image
+    }
+
+    fn render_pass(&self, world: &World, tile: Tile, seed: Option<u64>) -> Canvas {
+        world.prepare();
+        let sampler = StratifiedSampler::default();
+        self.render_tile_with(tile, seed, true, |ray, scratch| {
+            self.integrator
+                .li(ray, world, world.max_depth(), &sampler, scratch)
+        })
+    }
+
+    fn full_frame(&self) -> Tile {
+        Tile::new(0, 0, self.h_size as usize, self.v_size as usize)
+    }
+
+    /**
+       Render `world` once and return a copy of the image for each exposure
+       in `stops`, for comparing looks without rendering again.
+    */
+    pub fn render_brackets(&self, world: &World, stops: &[f64]) -> Vec<Canvas> {
+        let image = self.render(world);
+        stops.iter().map(|&stop| image.exposed(stop)).collect()
+    }
+
+    /**
+       Render the id of the frontmost shape or material under each pixel,
+       hashed into a color. Ids number shapes in the order they were added
+       to `world` and materials in the order they were made, so a scene
+       gets the same colors on every run. Pixels that hit nothing are left
+       black.
+    */
+    pub fn render_ids(&self, world: &World, pass: IdPass) -> Canvas {
+        world.prepare();
+        let numbers = id_numbers(world, pass);
+        self.render_with(|ray, scratch| {
+            world.intersects_for_into(&ray, RayKind::Camera, scratch.heap_mut());
+            let Some(hit) = scratch.heap().hit_with(world.precision()) else {
+                return Colors::Black.into();
+            };
+            Color::from_id(numbers.get(&hit.object_id()).copied().unwrap_or_default())
+        })
+    }
+
+    /**
+       The surface normal under each pixel, facing the camera, with x, y and
+       z stored as red, green and blue. Pixels that hit nothing are black.
+    */
+    pub fn render_normals(&self, world: &World) -> Canvas {
+        world.prepare();
+        self.render_with(|ray, _| {
+            world
+                .nearest_hit(&ray)
+                .map_or(Colors::Black.into(), |hit| hit.normal().into())
+        })
+    }
+
+    /**
+       The distance to the nearest surface under each pixel in every channel,
+       or infinity where there is none.
+    */
+    pub fn render_depth(&self, world: &World) -> Canvas {
+        world.prepare();
+        self.render_with(|ray, _| {
+            let distance = world
+                .nearest_hit(&ray)
+                .map_or(f64::INFINITY, |hit| hit.distance());
+            Color::new(distance, distance, distance)
+        })
+    }
+
+    /**
+       The id of the frontmost shape camera rays see under pixel (`px`, `py`),
+       the same id `render_ids` colors by. Like `render_ids`, it looks from
+       the center of the lens, so depth of field does not change the answer.
+       Pixels outside the image or the letterbox gate pick nothing.
+    */
+    pub fn pick(&self, world: &World, px: usize, py: usize) -> Option<Uuid> {
+        if px >= self.h_size as usize || py >= self.v_size as usize || !self.in_gate(px, py) {
+            return None;
+        }
+
+        world
+            .intersects_for(&self.center_ray(px, py, (0.5, 0.5)), RayKind::Camera)
+            .hit_with(world.precision())
+            .map(|hit| hit.object_id())
+    }
+
+    /**
+       Color every pixel with `color_at` from one ray through the center of
+       the lens. Ids, normals and depths can't be averaged across the lens
+       like colors can.
+    */
+    fn render_with<F: Fn(Ray, &mut Scratch) -> Color + Sync>(&self, color_at: F) -> Canvas {
+        self.render_tile_with(self.full_frame(), self.seed_for_pass(), false, color_at)
+    }
+
+    /**
+       Color every pixel in `tile` with `color_at`, in parallel, a chunk of
+       pixels at a time. Each worker thread gets one `Scratch` that it reuses
+       for all of its pixels.
+    */
+    fn render_tile_with<F: Fn(Ray, &mut Scratch) -> Color + Sync>(
+        &self,
+        tile: Tile,
+        seed: Option<u64>,
+        through_lens: bool,
+        color_at: F,
+    ) -> Canvas {
+        let (width, height) = (tile.width, tile.height);
+        let image_width = self.h_size as usize;
+        let subpixel = self.subpixel_offset_for_pass();
+        let pb = self.progress.bar((width * height) as u64);
+
+        let colors = (0..width * height)
+            .into_par_iter()
+            .chunks(self.progress.tile_size())
+            .map_init(Scratch::new, |scratch, chunk| {
+                let colors = chunk
+                    .into_iter()
+                    .map(|i| {
+                        let (x, y) = (tile.x + i % width, tile.y + i / width);
+                        let pixel = y * image_width + x;
+                        if let Some(seed) = seed {
+                            seed_rng(seed ^ (pixel as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+                        }
+                        if self.in_gate(x, y) {
+                            self.pixel_color(x, y, subpixel, through_lens, scratch, &color_at)
+                        } else {
+                            Colors::Black.into()
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                pb.inc(colors.len() as u64);
+                colors
             })
-            .collect_vec_list();
+            .flatten()
+            .collect::<Vec<_>>();
 
-        for v in vecs {
-            for (x, y, color) in v {
-                image[(x, y)] = color;
-            }
+        let mut image = Canvas::new(width, height);
+        for (i, color) in colors.into_iter().enumerate() {
+            image[(i % width, i / width)] = color;
         }
         pb.finish_with_message("Rendering complete");
 
@@ -82,11 +718,69 @@ impl Camera {
     }
 }
 
+/**
+   Number every shape in `world` from 1, walking groups depth first, or
+   with `IdPass::Material` give each shape the number of its material, in
+   the order the materials were made. Only shapes without children are
+   numbered, since rays only ever hit those, and material numbers don't
+   depend on how groups are divided into a bounding volume hierarchy.
+*/
+fn id_numbers(world: &World, pass: IdPass) -> HashMap<Uuid, u64> {
+    let leaves = world
+        .shapes()
+        .iter()
+        .cloned()
+        .flat_map(Descendants::new)
+        .map(|(shape, _)| shape)
+        .filter(|shape| shape.read().unwrap().child_shapes().is_empty())
+        .collect::<Vec<_>>();
+
+    match pass {
+        IdPass::Object => leaves
+            .iter()
+            .enumerate()
+            .map(|(i, shape)| (shape.id(), i as u64 + 1))
+            .collect(),
+        IdPass::Material => {
+            let materials = leaves
+                .iter()
+                .filter_map(|shape| {
+                    let id = shape.id();
+                    Some((id, shape.read().unwrap().material(id)?.id()))
+                })
+                .collect::<Vec<_>>();
+            let mut order = materials
+                .iter()
+                .map(|&(_, material)| material)
+                .collect::<Vec<_>>();
+            order.sort_unstable();
+            order.dedup();
+
+            materials
+                .into_iter()
+                .map(|(id, material)| (id, order.binary_search(&material).unwrap() as u64 + 1))
+                .collect()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::f64::consts::PI;
 
-    use crate::{color::Color, tuple::Tuple};
+    use crate::{
+        integrator::AmbientOcclusion,
+        point_light::PointLight,
+        shape::{
+            group::{Group, GroupContainer},
+            material::Material,
+            plane::Plane,
+            sphere::Sphere,
+            Shape,
+        },
+        tuple::Tuple,
+        world::LightSampling,
+    };
 
     use super::*;
 
@@ -102,13 +796,137 @@ mod tests {
     #[test]
     fn the_pixel_size_for_a_horizontal_canvas() {
         let c = Camera::new(200, 125, PI / 2.0);
-        assert!(eq_f64(0.01, c.pixel_size));
+        assert!(eq_f64(0.01, c.pixel_width));
+        assert!(eq_f64(0.01, c.pixel_height));
     }
 
     #[test]
     fn the_pixel_size_for_a_vertical_canvas() {
         let c = Camera::new(125, 200, PI / 2.0);
-        assert!(eq_f64(0.01, c.pixel_size));
+        assert!(eq_f64(0.01, c.pixel_width));
+        assert!(eq_f64(0.01, c.pixel_height));
+    }
+
+    #[test]
+    fn independent_fields_of_view_produce_non_square_pixels() {
+        let c = Camera::with_fields_of_view(200, 100, PI / 2.0, PI / 2.0);
+
+        assert!(eq_f64(0.01, c.pixel_width));
+        assert!(eq_f64(0.02, c.pixel_height));
+        assert_eq!(
+            Tuple::vector(0.995, 0.99, -1.0).normalize(),
+            c.ray_for_pixel(0, 0).direction()
+        );
+    }
+
+    #[test]
+    fn the_pixel_aspect_keeps_the_horizontal_field_of_view() {
+        let c = Camera::new(200, 100, PI / 2.0).with_pixel_aspect(2.0);
+
+        assert!(eq_f64(1.0, c.half_width));
+        assert!(eq_f64(0.01, c.pixel_width));
+        assert!(eq_f64(0.005, c.pixel_height));
+        assert!(eq_f64(0.25, c.half_height));
+    }
+
+    #[test]
+    fn overscan_adds_pixels_without_changing_their_size() {
+        let c = Camera::new(200, 100, PI / 2.0);
+        let overscanned = Camera::new(200, 100, PI / 2.0).with_overscan(10);
+
+        assert_eq!(220, overscanned.h_size());
+        assert_eq!(120, overscanned.v_size());
+        assert!(eq_f64(c.pixel_width, overscanned.pixel_width));
+        assert!(eq_f64(c.pixel_height, overscanned.pixel_height));
+        assert_eq!(
+            c.ray_for_pixel(0, 0).direction(),
+            overscanned.ray_for_pixel(10, 10).direction()
+        );
+    }
+
+    #[test]
+    fn seeded_renders_are_identical() {
+        let mut w = World::default();
+        w.add_light(PointLight::new(
+            Tuple::point(10.0, 10.0, -10.0),
+            Color::new(0.5, 0.5, 0.5),
+        ));
+        w.set_light_sampling(LightSampling::Uniform(1));
+        let mut c = Camera::new(11, 11, PI / 2.0).with_seed(3);
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+
+        let first = c.render(&w).into_iter().collect::<Vec<_>>();
+        let second = c.render(&w).into_iter().collect::<Vec<_>>();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn accumulating_renders_moves_the_rays_each_pass() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.0).with_accumulation();
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+
+        let mut image = c.render(&w);
+        let second = c.render(&w);
+        assert_eq!(Some(2), c.accumulated_passes());
+        assert_ne!(image[(5, 5)], second[(5, 5)]);
+
+        image.accumulate(&second, 0.5);
+        assert_eq!(Camera::new(11, 11, PI / 2.0).accumulated_passes(), None);
+    }
+
+    #[test]
+    fn only_whole_renders_move_on_to_the_next_pass() {
+        let w = World::default();
+        let c = Camera::new(11, 11, PI / 2.0)
+            .with_seed(3)
+            .with_accumulation();
+
+        c.render_tile(&w, Tile::new(0, 0, 5, 5));
+        c.render_tile(&w, Tile::new(5, 5, 6, 6));
+        c.render_ids(&w, IdPass::Object);
+        c.render_normals(&w);
+        c.render_depth(&w);
+        assert_eq!(Some(0), c.accumulated_passes());
+        assert_eq!(Some(3), c.seed_for_pass());
+
+        c.render(&w);
+        assert_eq!(Some(1), c.accumulated_passes());
+        assert_ne!(Some(3), c.seed_for_pass());
+        assert_ne!(c.motion_seed(1), c.seed_for_pass());
+
+        c.next_pass();
+        let third = c.seed_for_pass();
+        assert_eq!(Some(2), c.accumulated_passes());
+        assert_ne!(Some(3), third);
+        assert_ne!(c.motion_seed(2), third);
+    }
+
+    #[test]
+    fn a_letterbox_blacks_out_pixels_outside_the_gate() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.0).with_letterbox(11.0 / 3.0);
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+
+        let image = c.render(&w);
+
+        assert_eq!(Color::new(0.38066, 0.47583, 0.2855), image[(5, 5)]);
+        assert_ne!(Color::from(Colors::Black), image[(5, 4)]);
+        assert_eq!(Color::from(Colors::Black), image[(5, 3)]);
+        assert_eq!(Color::from(Colors::Black), image[(5, 7)]);
     }
 
     #[test]
@@ -160,4 +978,342 @@ mod tests {
 
         assert_eq!(Color::new(0.38066, 0.47583, 0.2855), image[(5, 5)])
     }
+
+    #[test]
+    fn a_scene_far_from_the_origin_renders_like_one_at_it() {
+        // Nothing is re-centered: rays and shapes keep their world
+        // coordinates, and only the surface offset and the sphere quadratic
+        // keep the far render matching.
+        let render = |(x, y, z): (f64, f64, f64)| {
+            let mut w = World::new();
+            w.add_light(PointLight::new(
+                Tuple::point(x - 10.0, y + 10.0, z - 10.0),
+                Color::new(1.0, 1.0, 1.0),
+            ));
+            let mut floor = Plane::new();
+            floor.set_transformation(Transformation::identity().translation(x, y - 1.0, z));
+            w.add_shape(floor.into());
+            for (offset, radius) in [(-1.5, 1.0), (1.0, 0.1)] {
+                let mut s = Sphere::new();
+                s.set_material(Material::new().with_reflective(0.5));
+                s.set_transformation(
+                    Transformation::identity()
+                        .scale(radius, radius, radius)
+                        .translation(x + offset, y + radius - 1.0, z),
+                );
+                w.add_shape(s.into());
+            }
+            let mut c = Camera::new(40, 30, PI / 3.0);
+            c.set_transformation(Transformation::view(
+                Tuple::point(x, y + 1.5, z - 6.0),
+                Tuple::point(x, y, z),
+                Tuple::vector(0.0, 1.0, 0.0),
+            ));
+
+            c.render(&w)
+        };
+
+        let near = render((0.0, 0.0, 0.0));
+        let far = render((310_000.0, 170_000.0, -230_000.0));
+
+        for (a, b) in near.into_iter().zip(far) {
+            assert!(a.approx_eq(&b, 1e-3));
+        }
+    }
+
+    #[test]
+    fn rendering_tiles_and_pasting_them_together_matches_a_whole_render() {
+        let w = World::default();
+        let mut c = Camera::new(11, 7, PI / 2.0)
+            .with_depth_of_field(0.2, 5.0)
+            .with_seed(3);
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+
+        let whole = c.render(&w);
+        let mut pasted = Canvas::new(11, 7);
+        let tiles = Tile::split(11, 7, 4);
+        for &tile in &tiles {
+            pasted.paste(&c.render_tile(&w, tile), tile.x(), tile.y());
+        }
+
+        assert_eq!(6, tiles.len());
+        assert_eq!(Tile::new(8, 4, 3, 3), tiles[5]);
+        assert!(whole.into_iter().eq(pasted));
+    }
+
+    #[test]
+    fn shutter_times_fall_in_even_slices_of_the_shutter() {
+        let c = Camera::new(1, 1, PI / 2.0)
+            .with_shutter(0.0, 1.0)
+            .with_motion_samples(4);
+
+        assert_eq!(vec![0.125, 0.375, 0.625, 0.875], c.shutter_times());
+        assert_eq!(vec![0.0], Camera::new(1, 1, PI / 2.0).shutter_times());
+
+        let seeded = c.with_seed(2);
+        let times = seeded.shutter_times();
+        assert_eq!(times, seeded.shutter_times());
+        for (i, time) in times.into_iter().enumerate() {
+            assert!(time >= i as f64 / 4.0 && time < (i + 1) as f64 / 4.0);
+        }
+    }
+
+    #[test]
+    fn rendering_over_the_shutter_averages_the_world_as_it_moves() {
+        let mut w = World::new();
+        let mut s = Sphere::new();
+        s.set_material(Material::new().with_ambient(1.0).with_diffuse(0.0));
+        w.add_shape(s.into());
+        w.add_light(PointLight::new(
+            Tuple::point(-10.0, 10.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut c = Camera::new(11, 11, PI / 2.0)
+            .with_shutter(0.0, 1.0)
+            .with_motion_samples(4);
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+        let sphere = w.shapes()[0].clone();
+        let mut times = vec![];
+
+        let image = c.render_motion(&w, |time| {
+            times.push(time);
+            sphere
+                .write()
+                .unwrap()
+                .set_transformation(Transformation::identity().translation(
+                    if time < 0.5 { 0.0 } else { 10.0 },
+                    0.0,
+                    0.0,
+                ));
+        });
+
+        assert_eq!(c.shutter_times(), times);
+        assert_eq!(Color::new(0.5, 0.5, 0.5), image[(5, 5)]);
+    }
+
+    #[test]
+    fn rays_from_across_the_lens_meet_at_the_focal_plane() {
+        let mut c = Camera::new(201, 101, PI / 2.0)
+            .with_depth_of_field(0.5, 4.0)
+            .with_aperture(Aperture::hexagon());
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 2.0, -5.0),
+            Tuple::point(0.0, 2.0, 0.0),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+        let pinhole = {
+            let mut pinhole = Camera::new(201, 101, PI / 2.0);
+            pinhole.set_transformation(c.transform.clone());
+            pinhole.ray_for_pixel(20, 30)
+        };
+        let focus = pinhole.position(4.0 / -(pinhole.direction() * Tuple::vector(0.0, 0.0, -1.0)));
+
+        for lens in [(0.5, 0.5), (0.0, 0.2), (0.9, 0.7), (0.3, 1.0)] {
+            let r = c.ray_for_pixel_through_lens(20, 30, lens);
+            let (x, y) = Aperture::hexagon().sample(lens);
+
+            assert_eq!(Tuple::point(-0.5 * x, 2.0 + 0.5 * y, -5.0), r.origin());
+            assert_eq!(focus, r.position((focus - r.origin()).magnitude()));
+        }
+    }
+
+    #[test]
+    fn depth_of_field_blurs_shapes_out_of_focus() {
+        let w = World::default();
+        let view = Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        );
+        let mut sharp = Camera::new(11, 11, PI / 2.0);
+        sharp.set_transformation(view.clone());
+        let mut blurred = Camera::new(11, 11, PI / 2.0)
+            .with_depth_of_field(0.5, 1.0)
+            .with_lens_samples(16)
+            .with_seed(5);
+        blurred.set_transformation(view);
+
+        let sharp = sharp.render(&w);
+        let blurred = blurred.render(&w);
+
+        assert_eq!(Color::from(Colors::Black), sharp[(7, 5)]);
+        assert_ne!(Color::from(Colors::Black), blurred[(7, 5)]);
+    }
+
+    #[test]
+    fn rendering_with_another_integrator() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.0).with_integrator(AmbientOcclusion::new(4, 1.0));
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+
+        let image = c.render(&w);
+
+        assert_eq!(Color::from(Colors::White), image[(0, 0)]);
+        let center = image[(5, 5)];
+        assert_eq!(center.red(), center.green());
+        assert_eq!(center.green(), center.blue());
+    }
+
+    #[test]
+    fn rendering_exposure_brackets() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+
+        let brackets = c.render_brackets(&w, &[-1.0, 0.0, 1.0]);
+
+        assert_eq!(3, brackets.len());
+        assert_eq!(Color::new(0.19033, 0.237915, 0.14275), brackets[0][(5, 5)]);
+        assert_eq!(Color::new(0.38066, 0.47583, 0.2855), brackets[1][(5, 5)]);
+        assert_eq!(Color::new(0.76132, 0.95166, 0.571), brackets[2][(5, 5)]);
+    }
+
+    #[test]
+    fn rendering_object_ids_with_a_camera() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+
+        let image = c.render_ids(&w, IdPass::Object);
+
+        assert_eq!(Color::from_id(1), image[(5, 5)]);
+        assert_eq!(Color::from(Colors::Black), image[(0, 0)]);
+    }
+
+    #[test]
+    fn ids_number_only_leaves_and_survive_dividing_groups() {
+        let (a, b) = (Material::new(), Material::new().with_ambient(0.5));
+        let group = GroupContainer::from(Group::new());
+        for (x, material) in [(-3.0, &a), (0.0, &b), (3.0, &a)] {
+            let mut s = Sphere::new();
+            s.set_material(material.clone());
+            s.set_transformation(Transformation::identity().translation(x, 0.0, 0.0));
+            group.add_child(s.into());
+        }
+        let mut w = World::new();
+        w.add_shape(group.clone().into());
+        let numbers = |pass| {
+            let mut numbers = id_numbers(&w, pass).into_values().collect::<Vec<_>>();
+            numbers.sort();
+            numbers
+        };
+        let materials = id_numbers(&w, IdPass::Material);
+
+        assert_eq!(vec![1, 2, 3], numbers(IdPass::Object));
+        assert_eq!(vec![1, 1, 2], numbers(IdPass::Material));
+        group.divide(1);
+        assert_eq!(vec![1, 2, 3], numbers(IdPass::Object));
+        assert_eq!(materials, id_numbers(&w, IdPass::Material));
+    }
+
+    #[test]
+    fn rendering_normals_and_depth() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+
+        let normals = c.render_normals(&w);
+        let depth = c.render_depth(&w);
+
+        assert_eq!(Color::new(0.0, 0.0, -1.0), normals[(5, 5)]);
+        assert_eq!(Color::new(4.0, 4.0, 4.0), depth[(5, 5)]);
+        assert_eq!(Color::from(Colors::Black), normals[(0, 0)]);
+        assert_eq!(f64::INFINITY, depth[(0, 0)].red());
+    }
+
+    #[test]
+    fn picking_the_frontmost_shape_under_a_pixel() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+
+        assert_eq!(Some(w.shapes()[0].id()), c.pick(&w, 5, 5));
+        assert_eq!(None, c.pick(&w, 0, 0));
+        assert_eq!(None, c.pick(&w, 11, 5));
+    }
+
+    #[test]
+    fn ids_and_aovs_look_through_the_center_of_the_lens() {
+        let w = World::default();
+        let view = Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        );
+        let mut pinhole = Camera::new(11, 11, PI / 2.0);
+        pinhole.set_transformation(view.clone());
+        let mut c = Camera::new(11, 11, PI / 2.0)
+            .with_depth_of_field(1.0, 2.0)
+            .with_lens_samples(8);
+        c.set_transformation(view);
+
+        let passes = |c: &Camera| {
+            let ids = c.render_ids(&w, IdPass::Object);
+            let normals = c.render_normals(&w);
+            let depth = c.render_depth(&w);
+            [(2, 5), (3, 3), (5, 5), (8, 6)].map(|(x, y)| {
+                (
+                    ids[(x, y)],
+                    normals[(x, y)],
+                    depth[(x, y)],
+                    c.pick(&w, x, y),
+                )
+            })
+        };
+
+        assert_eq!(passes(&pinhole), passes(&c));
+    }
+
+    #[test]
+    fn rendering_material_ids_with_a_camera() {
+        let mut w = World::new();
+        let material = Material::new().with_ambient(0.5);
+        let mut s1 = Sphere::new();
+        s1.set_material(material.clone());
+        s1.set_transformation(Transformation::identity().translation(-1.5, 0.0, 0.0));
+        let mut s2 = Sphere::new();
+        s2.set_material(material.clone());
+        s2.set_transformation(Transformation::identity().translation(1.5, 0.0, 0.0));
+        w.add_shape(s1.into());
+        w.add_shape(s2.into());
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transformation(Transformation::view(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::origin(),
+            Tuple::vector(0.0, 1.0, 0.0),
+        ));
+
+        let image = c.render_ids(&w, IdPass::Material);
+
+        assert_eq!(Color::from_id(1), image[(3, 5)]);
+        assert_eq!(Color::from_id(1), image[(7, 5)]);
+    }
 }
@@ -2,9 +2,10 @@ use std::{
     fs::File,
     io::Write,
     ops::{Index, IndexMut},
+    path::Path,
 };
 
-use crate::{color::Color, error::RayTraceResult, tuple::Tuple};
+use crate::{color::Color, error::RayTraceResult, metadata::RenderMetadata, tuple::Tuple};
 
 pub struct Canvas {
     width: usize,
@@ -31,10 +32,67 @@ impl Canvas {
         self.width
     }
 
+    /**
+       Blend `other` into this canvas, moving each pixel `weight` of the way
+       towards `other`'s. Blending pass `n` (counting from 0) with a weight of
+       `1 / (n + 1)` keeps the canvas at the mean of every pass so far.
+    */
+    pub fn accumulate(&mut self, other: &Canvas, weight: f64) {
+        assert!(
+            self.width == other.width && self.pixels.len() == other.pixels.len(),
+            "Can only accumulate canvases of the same size"
+        );
+
+        for (pixel, &color) in self.pixels.iter_mut().zip(&other.pixels) {
+            *pixel = *pixel * (1.0 - weight) + color * weight;
+        }
+    }
+
+    /**
+       Copy `other` onto this canvas with its top left corner at (`x`, `y`).
+       Pixels that fall outside this canvas are dropped.
+    */
+    pub fn paste(&mut self, other: &Canvas, x: usize, y: usize) {
+        for other_y in 0..other.height() {
+            for other_x in 0..other.width() {
+                let (to_x, to_y) = (x + other_x, y + other_y);
+                if to_x < self.width() && to_y < self.height() {
+                    self[(to_x, to_y)] = other[(other_x, other_y)];
+                }
+            }
+        }
+    }
+
+    /**
+       A copy brightened by `stops` stops of exposure, each one doubling
+       every pixel, or darkened by negative stops.
+    */
+    pub fn exposed(&self, stops: f64) -> Canvas {
+        let scale = 2f64.powf(stops);
+        Canvas {
+            width: self.width,
+            pixels: self.pixels.iter().map(|&color| color * scale).collect(),
+        }
+    }
+
     fn ppm_header(&self) -> String {
         format!("P3\n{} {}\n255", self.width(), self.height())
     }
 
+    /**
+       The PPM header with each metadata line as a comment after the magic
+       number.
+    */
+    fn ppm_header_with(&self, metadata: &RenderMetadata) -> String {
+        let comments = metadata
+            .lines(self.width(), self.height())
+            .into_iter()
+            .map(|line| format!("# {line}\n"))
+            .collect::<String>();
+
+        format!("P3\n{comments}{} {}\n255", self.width(), self.height())
+    }
+
     fn ppm_body(&self) -> String {
         let mut body = String::from("");
         for y in 0..self.height() {
@@ -64,7 +122,49 @@ impl Canvas {
         body
     }
 
+    /**
+       Write the canvas to `filename` as a PNG when it ends in `.png`, which
+       needs the `png` feature, and otherwise as a PPM, adding `.ppm` if
+       missing.
+    */
     pub fn save(self, filename: &str) -> RayTraceResult<()> {
+        if is_png(filename) {
+            return self.write_png(filename, None);
+        }
+        let header = self.ppm_header();
+        self.write_ppm(filename, &header)
+    }
+
+    /**
+       Like `save`, with `metadata` in the PPM header's comments or in the
+       PNG's text chunks.
+    */
+    pub fn save_with_metadata(
+        self,
+        filename: &str,
+        metadata: &RenderMetadata,
+    ) -> RayTraceResult<()> {
+        if is_png(filename) {
+            return self.write_png(filename, Some(metadata));
+        }
+        let header = self.ppm_header_with(metadata);
+        self.write_ppm(filename, &header)
+    }
+
+    #[cfg(feature = "png")]
+    fn write_png(&self, filename: &str, metadata: Option<&RenderMetadata>) -> RayTraceResult<()> {
+        let bytes = crate::image::encode_png(self, metadata)?;
+        Ok(std::fs::write(filename, bytes)?)
+    }
+
+    #[cfg(not(feature = "png"))]
+    fn write_png(&self, _filename: &str, _metadata: Option<&RenderMetadata>) -> RayTraceResult<()> {
+        Err(crate::error::RayTraceError::InvalidFormat(
+            "image: writing PNG files needs the png feature".to_string(),
+        ))
+    }
+
+    fn write_ppm(&self, filename: &str, header: &str) -> RayTraceResult<()> {
         let mut filename = filename.to_owned();
 
         if !filename.ends_with(".ppm") {
@@ -72,13 +172,19 @@ impl Canvas {
         }
 
         let mut file = File::create(filename)?;
-        let contents = format!("{}\n{}", self.ppm_header(), self.ppm_body());
+        let contents = format!("{}\n{}", header, self.ppm_body());
         file.write_all(contents.as_bytes())?;
 
         Ok(())
     }
 }
 
+fn is_png(filename: &str) -> bool {
+    Path::new(filename)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+}
+
 impl Index<(usize, usize)> for Canvas {
     type Output = Color;
 
@@ -144,6 +250,40 @@ mod tests {
         assert_eq!(red, c[(2, 3)]);
     }
 
+    #[test]
+    fn accumulating_passes_keeps_their_mean() {
+        let mut c = Canvas::fill_with(2, 2, Color::new(1.0, 0.0, 0.0));
+        let green = Canvas::fill_with(2, 2, Color::new(0.0, 1.0, 0.0));
+        let blue = Canvas::fill_with(2, 2, Color::new(0.0, 0.0, 1.0));
+
+        c.accumulate(&green, 1.0 / 2.0);
+        c.accumulate(&blue, 1.0 / 3.0);
+
+        for p in c {
+            assert_eq!(Color::new(1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0), p);
+        }
+    }
+
+    #[test]
+    fn pasting_a_canvas_clips_to_the_edges() {
+        let mut c = Canvas::new(3, 3);
+        let red = Color::new(1.0, 0.0, 0.0);
+
+        c.paste(&Canvas::fill_with(2, 2, red), 2, 1);
+
+        assert_eq!(red, c[(2, 1)]);
+        assert_eq!(red, c[(2, 2)]);
+        assert_eq!(Color::default(), c[(1, 1)]);
+    }
+
+    #[test]
+    fn each_stop_of_exposure_doubles_the_pixels() {
+        let canvas = Canvas::fill_with(2, 1, Color::new(0.2, 0.4, 0.1));
+
+        assert_eq!(Color::new(0.4, 0.8, 0.2), canvas.exposed(1.0)[(1, 0)]);
+        assert_eq!(Color::new(0.05, 0.1, 0.025), canvas.exposed(-2.0)[(0, 0)]);
+    }
+
     #[test]
     fn constructing_the_ppm_header() {
         let c = Canvas::new(5, 3);
@@ -152,6 +292,19 @@ mod tests {
         assert_eq!(expected, ppm)
     }
 
+    #[test]
+    fn metadata_is_written_as_header_comments() {
+        let c = Canvas::new(5, 3);
+        let metadata = RenderMetadata::new().with_scene("test").with_seed(42);
+
+        let ppm = c.ppm_header_with(&metadata);
+
+        assert_eq!(
+            "P3\n# scene: test\n# resolution: 5x3\n# seed: 42\n5 3\n255",
+            ppm
+        );
+    }
+
     #[test]
     fn constructing_the_ppm_pixel_data() {
         let mut c = Canvas::new(5, 3);
@@ -178,4 +331,21 @@ mod tests {
 "#;
         assert_eq!(expected, c.ppm_body());
     }
+
+    #[test]
+    fn saving_to_a_png_name_writes_a_png() {
+        let path = std::env::temp_dir().join(format!("canvas-{}.PNG", uuid::Uuid::new_v4()));
+        let c = Canvas::fill_with(2, 2, Color::new(1.0, 0.0, 0.0));
+
+        let saved = c.save(&path.to_string_lossy());
+        let bytes = std::fs::read(&path).unwrap_or_default();
+        let _ = std::fs::remove_file(&path);
+
+        if cfg!(feature = "png") {
+            assert!(saved.is_ok());
+            assert!(bytes.starts_with(b"\x89PNG"));
+        } else {
+            assert!(saved.is_err());
+        }
+    }
 }
@@ -1,9 +1,10 @@
 use std::{
     cmp::{max, min},
+    fmt::Display,
     ops::{Add, AddAssign, Mul, Sub},
 };
 
-use crate::tuple::Tuple;
+use crate::{tuple::Tuple, util::format_number};
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Color {
@@ -21,11 +22,11 @@ pub enum Colors {
 }
 
 impl Color {
-    pub fn new(red: f64, green: f64, blue: f64) -> Self {
+    pub const fn new(red: f64, green: f64, blue: f64) -> Self {
         Self { red, green, blue }
     }
 
-    pub fn new_scaled(red: u8, green: u8, blue: u8) -> Self {
+    pub const fn new_scaled(red: u8, green: u8, blue: u8) -> Self {
         Self {
             red: (red as f64) / 255.0,
             green: (green as f64) / 255.0,
@@ -33,18 +34,41 @@ impl Color {
         }
     }
 
-    pub fn red(&self) -> f64 {
+    pub const fn red(&self) -> f64 {
         self.red
     }
 
-    pub fn green(&self) -> f64 {
+    pub const fn green(&self) -> f64 {
         self.green
     }
 
-    pub fn blue(&self) -> f64 {
+    pub const fn blue(&self) -> f64 {
         self.blue
     }
 
+    /**
+       Hash an integer id into a color whose channels are exact 8 bit values,
+       so the id survives being written to an image. Black is never produced
+       and is left for pixels without an id.
+    */
+    pub fn from_id(id: u64) -> Self {
+        let mut hash = id.wrapping_add(0x9e3779b97f4a7c15);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+        hash ^= hash >> 31;
+
+        let [red, green, blue, ..] = hash.to_le_bytes();
+        if red == 0 && green == 0 && blue == 0 {
+            Self::new_scaled(0, 0, 1)
+        } else {
+            Self::new_scaled(red, green, blue)
+        }
+    }
+
+    pub fn approx_eq(&self, other: &Color, tolerance: f64) -> bool {
+        Tuple::from(*self).approx_eq(&Tuple::from(*other), tolerance)
+    }
+
     pub fn to_ppm(self) -> (u8, u8, u8) {
         let scaled = self * 255.0;
         (
@@ -81,6 +105,18 @@ impl From<Tuple> for Color {
     }
 }
 
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "color(red: {}, green: {}, blue: {})",
+            format_number(self.red, f),
+            format_number(self.green, f),
+            format_number(self.blue, f),
+        )
+    }
+}
+
 impl PartialEq for Color {
     fn eq(&self, other: &Self) -> bool {
         Tuple::from(*self) == Tuple::from(*other)
@@ -188,4 +224,38 @@ mod tests {
 
         assert_eq!(expected, c1 * c2);
     }
+
+    #[test]
+    fn id_colors_are_stable_and_survive_quantization() {
+        for id in 0..100 {
+            let c = Color::from_id(id);
+            let (red, green, blue) = c.to_ppm();
+
+            assert_eq!(c, Color::from_id(id));
+            assert_eq!(c, Color::new_scaled(red, green, blue));
+            assert_ne!(c, Colors::Black.into());
+        }
+        assert_ne!(Color::from_id(1), Color::from_id(2));
+    }
+
+    #[test]
+    fn colors_can_be_built_at_compile_time() {
+        const ORANGE: Color = Color::new(1.0, 0.5, 0.0);
+        const SCALED: Color = Color::new_scaled(255, 0, 0);
+
+        assert_eq!(Color::new(1.0, 0.5, 0.0), ORANGE);
+        assert_eq!(Color::from(Colors::Red), SCALED);
+    }
+
+    #[test]
+    fn displaying_a_color() {
+        assert_eq!(
+            "color(red: 1, green: 0.5, blue: 0)",
+            Color::new(1.0, 0.5, 0.0).to_string()
+        );
+        assert_eq!(
+            "color(red: 0.1, green: 0.2, blue: 0.3)",
+            format!("{:.1}", Color::new(0.1, 0.2, 0.3))
+        );
+    }
 }
@@ -0,0 +1,140 @@
+use std::f64::consts::PI;
+
+use crate::{
+    camera::Camera,
+    canvas::Canvas,
+    testing::{
+        book_camera, chapter_5_world, chapter_6_world, chapter_7_world, chapter_9_world,
+        cover_camera, cover_world, default_world, fresnel_camera, fresnel_world, group_camera,
+        hexagon_world, random_spheres_camera, random_spheres_world, reflect_refract_camera,
+        reflect_refract_world, refraction_camera, refraction_world, ringed_chapter_9_world,
+        sphere_shadow_camera, teapot_world,
+    },
+    transformation::Transformation,
+    tuple::Tuple,
+    world::World,
+};
+
+/**
+   A scene that can be rebuilt at any resolution, for checking the renderer
+   as a whole.
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct ExampleScene {
+    name: &'static str,
+    world: fn() -> World,
+    camera: fn(usize, usize) -> Camera,
+}
+
+impl ExampleScene {
+    pub fn new(
+        name: &'static str,
+        world: fn() -> World,
+        camera: fn(usize, usize) -> Camera,
+    ) -> Self {
+        Self {
+            name,
+            world,
+            camera,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn render(&self, width: usize, height: usize) -> Canvas {
+        (self.camera)(width, height).render(&(self.world)())
+    }
+}
+
+fn default_world_camera(width: usize, height: usize) -> Camera {
+    let mut camera = Camera::new(width, height, PI / 2.0);
+    camera.set_transformation(Transformation::view(
+        Tuple::point(0.0, 0.0, -5.0),
+        Tuple::origin(),
+        Tuple::vector(0.0, 1.0, 0.0),
+    ));
+    camera
+}
+
+fn teapot() -> World {
+    teapot_world().expect("the teapot ships in the examples directory")
+}
+
+/**
+   The scenes built into the crate, which the examples render, in the order
+   they appear in the book. The projectile and clock face examples only
+   plot points and have no scene to show.
+*/
+pub fn example_scenes() -> Vec<ExampleScene> {
+    vec![
+        ExampleScene::new("default world", default_world, default_world_camera),
+        ExampleScene::new("chapter 5", chapter_5_world, sphere_shadow_camera),
+        ExampleScene::new("chapter 6", chapter_6_world, sphere_shadow_camera),
+        ExampleScene::new("chapter 7", chapter_7_world, book_camera),
+        ExampleScene::new("chapter 9", chapter_9_world, book_camera),
+        ExampleScene::new("chapter 10", ringed_chapter_9_world, book_camera),
+        ExampleScene::new("fresnel", fresnel_world, fresnel_camera),
+        ExampleScene::new("refraction", refraction_world, refraction_camera),
+        ExampleScene::new(
+            "reflect and refract",
+            reflect_refract_world,
+            reflect_refract_camera,
+        ),
+        ExampleScene::new("hexagon", hexagon_world, group_camera),
+        ExampleScene::new("teapot", teapot, group_camera),
+        ExampleScene::new("cover", cover_world, cover_camera),
+        ExampleScene::new(
+            "random spheres",
+            random_spheres_world,
+            random_spheres_camera,
+        ),
+    ]
+}
+
+/**
+   Render each scene as a `thumb_width` by `thumb_height` thumbnail and tile
+   them left to right, top to bottom, `columns` to a row.
+*/
+pub fn contact_sheet(
+    scenes: &[ExampleScene],
+    thumb_width: usize,
+    thumb_height: usize,
+    columns: usize,
+) -> Canvas {
+    let columns = columns.clamp(1, scenes.len().max(1));
+    let rows = scenes.len().div_ceil(columns);
+    let mut sheet = Canvas::new(thumb_width * columns, thumb_height * rows.max(1));
+
+    for (i, scene) in scenes.iter().enumerate() {
+        let thumbnail = scene.render(thumb_width, thumb_height);
+        sheet.paste(
+            &thumbnail,
+            i % columns * thumb_width,
+            i / columns * thumb_height,
+        );
+    }
+
+    sheet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_contact_sheet_tiles_each_scene() {
+        let scenes = example_scenes();
+
+        let sheet = contact_sheet(&scenes, 11, 11, 4);
+
+        assert_eq!(13, scenes.len());
+        assert_eq!(44, sheet.width());
+        assert_eq!(44, sheet.height());
+        for (i, scene) in scenes.iter().enumerate() {
+            let (x, y) = (i % 4 * 11, i / 4 * 11);
+            assert_eq!(scene.render(11, 11)[(5, 5)], sheet[(x + 5, y + 5)]);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{canvas::Canvas, color::Color};
+
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/**
+   An edge avoiding à-trous wavelet filter (Dammertz et al. 2010). Each pass
+   blurs with a 5x5 kernel whose taps spread twice as far apart as the last
+   pass's, and neighbours only count towards a pixel when their color, normal
+   and depth are close to its own, so noise is smoothed without blurring
+   across the edges of shapes. Feed it the `render_normals` and
+   `render_depth` passes of the same camera.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Denoiser {
+    iterations: usize,
+    color_sigma: f64,
+    normal_sigma: f64,
+    depth_sigma: f64,
+}
+
+impl Denoiser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /**
+       How different two colors can be and still be blended. Larger values
+       smooth more noise and more detail.
+    */
+    pub fn with_color_sigma(mut self, color_sigma: f64) -> Self {
+        self.color_sigma = color_sigma;
+        self
+    }
+
+    pub fn with_normal_sigma(mut self, normal_sigma: f64) -> Self {
+        self.normal_sigma = normal_sigma;
+        self
+    }
+
+    pub fn with_depth_sigma(mut self, depth_sigma: f64) -> Self {
+        self.depth_sigma = depth_sigma;
+        self
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    pub fn color_sigma(&self) -> f64 {
+        self.color_sigma
+    }
+
+    pub fn normal_sigma(&self) -> f64 {
+        self.normal_sigma
+    }
+
+    pub fn depth_sigma(&self) -> f64 {
+        self.depth_sigma
+    }
+
+    pub fn apply(&self, color: &Canvas, normals: &Canvas, depth: &Canvas) -> Canvas {
+        let (width, height) = (color.width(), color.height());
+        let mut current = Canvas::new(width, height);
+        current.paste(color, 0, 0);
+
+        for iteration in 0..self.iterations {
+            let step = 1_isize << iteration;
+            // Later passes see smoother colors, so they are compared more strictly.
+            let color_sigma = self.color_sigma / 2f64.powi(iteration as i32);
+            let mut next = Canvas::new(width, height);
+
+            for y in 0..height {
+                for x in 0..width {
+                    let (center, normal, distance) =
+                        (current[(x, y)], normals[(x, y)], depth[(x, y)].red());
+                    let mut sum = Color::default();
+                    let mut total_weight = 0.0;
+
+                    for (j, ky) in KERNEL.iter().enumerate() {
+                        for (i, kx) in KERNEL.iter().enumerate() {
+                            let sample_x = x as isize + (i as isize - 2) * step;
+                            let sample_y = y as isize + (j as isize - 2) * step;
+                            if sample_x < 0
+                                || sample_y < 0
+                                || sample_x >= width as isize
+                                || sample_y >= height as isize
+                            {
+                                continue;
+                            }
+                            let (sample_x, sample_y) = (sample_x as usize, sample_y as usize);
+
+                            let sample = current[(sample_x, sample_y)];
+                            let weight = kx
+                                * ky
+                                * gaussian(squared_distance(center, sample), color_sigma)
+                                * gaussian(
+                                    squared_distance(normal, normals[(sample_x, sample_y)]),
+                                    self.normal_sigma,
+                                )
+                                * depth_weight(
+                                    distance,
+                                    depth[(sample_x, sample_y)].red(),
+                                    self.depth_sigma,
+                                );
+
+                            sum += sample * weight;
+                            total_weight += weight;
+                        }
+                    }
+
+                    next[(x, y)] = sum * (1.0 / total_weight);
+                }
+            }
+
+            current = next;
+        }
+
+        current
+    }
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            color_sigma: 0.5,
+            normal_sigma: 0.1,
+            depth_sigma: 0.1,
+        }
+    }
+}
+
+fn squared_distance(a: Color, b: Color) -> f64 {
+    let difference = a - b;
+    difference.red().powi(2) + difference.green().powi(2) + difference.blue().powi(2)
+}
+
+fn gaussian(squared_distance: f64, sigma: f64) -> f64 {
+    (-squared_distance / (sigma * sigma)).exp()
+}
+
+/**
+   Depths are compared relative to their size, so distant surfaces are not
+   held to a tighter tolerance than near ones. Two misses match.
+*/
+fn depth_weight(a: f64, b: f64, sigma: f64) -> f64 {
+    if a.is_infinite() && b.is_infinite() {
+        return 1.0;
+    }
+    let difference = (a - b).abs() / a.min(b).max(1.0);
+    if difference.is_finite() {
+        (-difference / sigma).exp()
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use crate::sampling::{seed_rng, with_rng};
+
+    use super::*;
+
+    fn noisy(width: usize, height: usize, base: impl Fn(usize) -> f64) -> Canvas {
+        seed_rng(11);
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let noise = with_rng(|rng| rng.gen::<f64>() * 0.2 - 0.1);
+                let value = base(x) + noise;
+                canvas[(x, y)] = Color::new(value, value, value);
+            }
+        }
+        canvas
+    }
+
+    fn spread(canvas: &Canvas, xs: std::ops::Range<usize>, mean: f64) -> f64 {
+        let mut worst: f64 = 0.0;
+        for y in 0..canvas.height() {
+            for x in xs.clone() {
+                worst = worst.max((canvas[(x, y)].red() - mean).abs());
+            }
+        }
+        worst
+    }
+
+    #[test]
+    fn denoising_smooths_a_flat_surface() {
+        let color = noisy(16, 16, |_| 0.5);
+        let normals = Canvas::fill_with(16, 16, Color::new(0.0, 0.0, -1.0));
+        let depth = Canvas::fill_with(16, 16, Color::new(4.0, 4.0, 4.0));
+
+        let denoised = Denoiser::new().apply(&color, &normals, &depth);
+
+        assert!(spread(&denoised, 0..16, 0.5) < spread(&color, 0..16, 0.5) / 2.0);
+    }
+
+    #[test]
+    fn denoising_keeps_depth_edges_sharp() {
+        let color = noisy(16, 16, |x| if x < 8 { 0.2 } else { 0.8 });
+        let normals = Canvas::fill_with(16, 16, Color::new(0.0, 0.0, -1.0));
+        let mut depth = Canvas::fill_with(16, 16, Color::new(2.0, 2.0, 2.0));
+        depth.paste(&Canvas::fill_with(8, 16, Color::new(9.0, 9.0, 9.0)), 8, 0);
+
+        let denoised = Denoiser::new()
+            .with_color_sigma(10.0)
+            .apply(&color, &normals, &depth);
+
+        assert!(spread(&denoised, 0..8, 0.2) < 0.1);
+        assert!(spread(&denoised, 8..16, 0.8) < 0.1);
+        let unchanged = Denoiser::new()
+            .with_iterations(0)
+            .apply(&color, &normals, &depth);
+        assert_eq!(color[(3, 3)], unchanged[(3, 3)]);
+    }
+}
@@ -0,0 +1,572 @@
+use std::{
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    camera::Tile,
+    canvas::Canvas,
+    color::Color,
+    error::{RayTraceError, RayTraceResult},
+    metadata::{frame_name, RenderMetadata},
+    scene::Scene,
+};
+
+/**
+   Renders one image across several machines. The frame is split into
+   tiles which are handed out one at a time to worker processes running
+   `serve`, so faster workers end up rendering more of them, and the tiles
+   they send back are pasted into one canvas. The tiles of a worker that
+   fails go to the others.
+
+   Workers are sent the text of the scene rather than its path, so the
+   files it refers to must be found from the directory each worker runs in,
+   as they are by `Scene::parse`.
+
+   The conversation is line based. The coordinator sends `scene <length>`
+   followed by that many bytes of scene, any number of `set <key=value>`
+   lines and an optional `time <t>`, then asks for one tile at a time with
+   `tile <x> <y> <width> <height>`. Workers answer each tile with `ok`
+   followed by its pixels row by row as little endian f64 red, green and
+   blue, or with `error <message>`, which is also the answer to a tile
+   outside the scene camera's frame or larger than `MAX_TILE_PIXELS`.
+
+   A worker that takes longer than the timeout to connect, accept a request
+   or send back a tile is treated as failed, so one that hangs cannot keep
+   its tile from the others.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coordinator {
+    source: String,
+    settings: Vec<String>,
+    tile_size: usize,
+    timeout: Duration,
+}
+
+/**
+   The largest scene a worker accepts, so a bad length cannot make it
+   allocate without bound.
+*/
+pub const MAX_SCENE_LENGTH: usize = 64 * 1024 * 1024;
+
+/**
+   The most pixels a worker renders for one tile, so a bad size cannot
+   make it allocate without bound either.
+*/
+pub const MAX_TILE_PIXELS: usize = 4096 * 4096;
+
+/**
+   How long a worker waits for its coordinator's next request before
+   giving up on it.
+*/
+pub const WORKER_TIMEOUT: Duration = Duration::from_secs(600);
+
+impl Coordinator {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            settings: vec![],
+            tile_size: 32,
+            timeout: Duration::from_secs(300),
+        }
+    }
+
+    /**
+       Apply `assignment` to the scene on every worker, as `Scene::set`
+       would.
+    */
+    pub fn with_setting(mut self, assignment: &str) -> Self {
+        self.settings.push(assignment.to_string());
+        self
+    }
+
+    pub fn with_tile_size(mut self, tile_size: usize) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    pub fn tile_size(&self) -> usize {
+        self.tile_size
+    }
+
+    /**
+       How long to wait on a worker before giving its tile to the others,
+       which has to be longer than the slowest tile takes to render.
+    */
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /**
+       Render the scene on `workers`. Fails only if every worker does, with
+       the last worker's error.
+    */
+    pub fn render<A: ToSocketAddrs + Sync>(&self, workers: &[A]) -> RayTraceResult<Canvas> {
+        self.render_at(workers, None)
+    }
+
+    /**
+       Render `frames` frames of the scene's animation on `workers` and save
+       them the way `Scene::render_animation` does.
+    */
+    pub fn render_animation<A: ToSocketAddrs + Sync>(
+        &self,
+        workers: &[A],
+        frames: usize,
+        pattern: &str,
+    ) -> RayTraceResult<()> {
+        let scene = build(&self.source, &self.settings, None)?;
+
+        for (frame, time) in scene.frame_times(frames).into_iter().enumerate() {
+            let started = Instant::now();
+            let image = self.render_at(workers, Some(time))?;
+            let metadata = RenderMetadata::new()
+                .with_samples(scene.render_settings().samples())
+                .with_duration(started.elapsed());
+            image.save_with_metadata(&frame_name(pattern, frame), &metadata)?;
+        }
+
+        Ok(())
+    }
+
+    fn render_at<A: ToSocketAddrs + Sync>(
+        &self,
+        workers: &[A],
+        time: Option<f64>,
+    ) -> RayTraceResult<Canvas> {
+        let camera = build(&self.source, &self.settings, time)?
+            .camera()
+            .copied()
+            .unwrap_or_default();
+        let (width, height) = (camera.width(), camera.height());
+
+        let mut tiles = Tile::split(width, height, self.tile_size);
+        tiles.reverse();
+        let remaining = AtomicUsize::new(tiles.len());
+        let tiles = Mutex::new(tiles);
+        let image = Mutex::new(Canvas::new(width, height));
+        let errors = Mutex::new(vec![]);
+
+        thread::scope(|scope| {
+            for worker in workers {
+                let (tiles, remaining, image, errors) = (&tiles, &remaining, &image, &errors);
+                scope.spawn(move || {
+                    if let Err(e) = self.drive(worker, time, tiles, remaining, image) {
+                        errors.lock().unwrap().push(e);
+                    }
+                });
+            }
+        });
+
+        if remaining.load(Ordering::SeqCst) > 0 {
+            return Err(errors
+                .into_inner()
+                .unwrap()
+                .pop()
+                .unwrap_or_else(|| invalid("there are no workers to render on")));
+        }
+
+        Ok(image.into_inner().unwrap())
+    }
+
+    /**
+       Send the scene to `worker`, then keep asking it for tiles until
+       every tile is done. A tile it fails to render goes back in the queue.
+    */
+    fn drive<A: ToSocketAddrs>(
+        &self,
+        worker: A,
+        time: Option<f64>,
+        tiles: &Mutex<Vec<Tile>>,
+        remaining: &AtomicUsize,
+        image: &Mutex<Canvas>,
+    ) -> RayTraceResult<()> {
+        let stream = connect(worker, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = BufWriter::new(stream);
+
+        write!(writer, "scene {}\n{}", self.source.len(), self.source)?;
+        for assignment in &self.settings {
+            writeln!(writer, "set {assignment}")?;
+        }
+        if let Some(time) = time {
+            writeln!(writer, "time {time}")?;
+        }
+
+        while remaining.load(Ordering::SeqCst) > 0 {
+            let Some(tile) = tiles.lock().unwrap().pop() else {
+                // Another worker still has a tile that may come back.
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            };
+            match request_tile(&mut reader, &mut writer, tile) {
+                Ok(pixels) => {
+                    image.lock().unwrap().paste(&pixels, tile.x(), tile.y());
+                    remaining.fetch_sub(1, Ordering::SeqCst);
+                }
+                Err(e) => {
+                    tiles.lock().unwrap().push(tile);
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/**
+   Connect to the first of the addresses `worker` resolves to that answers
+   within `timeout`.
+*/
+fn connect<A: ToSocketAddrs>(worker: A, timeout: Duration) -> RayTraceResult<TcpStream> {
+    let mut error = invalid("the worker has no address");
+    for address in worker.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => error = e.into(),
+        }
+    }
+
+    Err(error)
+}
+
+fn request_tile(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    tile: Tile,
+) -> RayTraceResult<Canvas> {
+    writeln!(
+        writer,
+        "tile {} {} {} {}",
+        tile.x(),
+        tile.y(),
+        tile.width(),
+        tile.height()
+    )?;
+    writer.flush()?;
+
+    let mut reply = String::new();
+    if reader.read_line(&mut reply)? == 0 {
+        return Err(invalid("the worker hung up"));
+    }
+    if let Some(message) = reply.trim_end().strip_prefix("error ") {
+        return Err(invalid(&format!("the worker failed: {message}")));
+    }
+    if reply.trim_end() != "ok" {
+        return Err(invalid(&format!("unexpected reply {}", reply.trim_end())));
+    }
+
+    let mut image = Canvas::new(tile.width(), tile.height());
+    let mut channels = [[0; 8]; 3];
+    for y in 0..tile.height() {
+        for x in 0..tile.width() {
+            for channel in &mut channels {
+                reader.read_exact(channel)?;
+            }
+            let [red, green, blue] = channels.map(f64::from_le_bytes);
+            image[(x, y)] = Color::new(red, green, blue);
+        }
+    }
+
+    Ok(image)
+}
+
+/**
+   Accept coordinators on `listener` for as long as it is open, rendering
+   the tiles each one asks for on its own thread. A coordinator that is
+   silent for `WORKER_TIMEOUT` is hung up on.
+*/
+pub fn serve(listener: TcpListener) -> RayTraceResult<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        thread::spawn(move || {
+            let result = stream
+                .set_read_timeout(Some(WORKER_TIMEOUT))
+                .and_then(|_| stream.set_write_timeout(Some(WORKER_TIMEOUT)))
+                .map_err(RayTraceError::from)
+                .and_then(|_| work(stream));
+            if let Err(e) = result {
+                eprintln!("{}", e.to_string().trim_end());
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/**
+   Render the tiles one coordinator asks for over `stream` until it hangs
+   up. The scene is parsed again only when it or its settings change, so
+   the tiles of one frame share it.
+*/
+pub fn work(stream: TcpStream) -> RayTraceResult<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    let mut source = String::new();
+    let mut settings = vec![];
+    let mut time = None;
+    let mut scene = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let line = line.trim_end();
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+
+        match command {
+            "scene" => {
+                let length = argument.parse()?;
+                if length > MAX_SCENE_LENGTH {
+                    return Err(invalid(&format!(
+                        "a scene of {length} bytes is more than the {MAX_SCENE_LENGTH} allowed"
+                    )));
+                }
+                let mut bytes = vec![0; length];
+                reader.read_exact(&mut bytes)?;
+                source = String::from_utf8(bytes).map_err(|e| invalid(&e.to_string()))?;
+                settings.clear();
+                time = None;
+                scene = None;
+            }
+            "set" => {
+                settings.push(argument.to_string());
+                scene = None;
+            }
+            "time" => {
+                time = Some(argument.parse()?);
+                scene = None;
+            }
+            "tile" => {
+                let tile = match (scene.as_ref(), parse_tile(argument)) {
+                    (_, Err(e)) => Err(e),
+                    (Some(_), Ok(tile)) => Ok(tile),
+                    (None, Ok(tile)) => build(&source, &settings, time).map(|built| {
+                        scene = Some(built);
+                        tile
+                    }),
+                }
+                .and_then(|tile| in_frame(scene.as_ref().unwrap(), tile));
+                match tile {
+                    Ok(tile) => {
+                        let image = scene.as_ref().unwrap().render_tile(tile);
+                        writeln!(writer, "ok")?;
+                        for color in image {
+                            for channel in [color.red(), color.green(), color.blue()] {
+                                writer.write_all(&channel.to_le_bytes())?;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        writeln!(writer, "error {}", e.to_string().trim().replace('\n', " "))?
+                    }
+                }
+                writer.flush()?;
+            }
+            _ => {
+                writeln!(writer, "error unknown command {command}")?;
+                writer.flush()?;
+            }
+        }
+    }
+}
+
+fn parse_tile(argument: &str) -> RayTraceResult<Tile> {
+    let numbers = argument
+        .split_whitespace()
+        .map(|number| number.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let [x, y, width, height] = numbers[..] else {
+        return Err(invalid(&format!(
+            "expected x y width height but found {argument}"
+        )));
+    };
+
+    if width.saturating_mul(height) > MAX_TILE_PIXELS {
+        return Err(invalid(&format!(
+            "a {width}x{height} tile is more than the {MAX_TILE_PIXELS} pixels allowed"
+        )));
+    }
+
+    Ok(Tile::new(x, y, width, height))
+}
+
+fn in_frame(scene: &Scene, tile: Tile) -> RayTraceResult<Tile> {
+    let camera = scene.camera().copied().unwrap_or_default();
+    if tile.x().saturating_add(tile.width()) > camera.width()
+        || tile.y().saturating_add(tile.height()) > camera.height()
+    {
+        return Err(invalid(&format!(
+            "a {}x{} tile at {} {} falls outside the {}x{} frame",
+            tile.width(),
+            tile.height(),
+            tile.x(),
+            tile.y(),
+            camera.width(),
+            camera.height()
+        )));
+    }
+
+    Ok(tile)
+}
+
+fn build(source: &str, settings: &[String], time: Option<f64>) -> RayTraceResult<Scene> {
+    let mut scene = Scene::parse(source)?;
+    for assignment in settings {
+        scene.set(assignment)?;
+    }
+    if let Some(time) = time {
+        scene.set_time(time);
+    }
+
+    Ok(scene)
+}
+
+fn invalid(message: &str) -> RayTraceError {
+    RayTraceError::InvalidFormat(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::*;
+
+    const SCENE: &str = "
+- add: camera
+  width: 23
+  height: 11
+  field-of-view: 0.8
+  from: [0, 1, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+- add: sphere
+  material:
+    color: [1, 0.2, 0.2]
+- add: plane
+  transform:
+    - [translate, 0, -1, 0]
+";
+
+    fn worker() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener));
+        address
+    }
+
+    fn closed_port() -> SocketAddr {
+        TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    #[test]
+    fn tiles_rendered_by_workers_make_up_the_whole_image() {
+        let (first, second) = (worker(), worker());
+        let coordinator = Coordinator::new(SCENE)
+            .with_setting("render.seed=4")
+            .with_setting("render.samples=2")
+            .with_tile_size(5);
+
+        let image = coordinator.render(&[first, second]).unwrap();
+
+        let mut scene = Scene::parse(SCENE).unwrap();
+        scene.set("render.seed=4").unwrap();
+        scene.set("render.samples=2").unwrap();
+        assert_eq!(23, image.width());
+        assert!(image.into_iter().eq(scene.render()));
+    }
+
+    #[test]
+    fn the_tiles_of_a_failed_worker_go_to_the_others() {
+        let coordinator = Coordinator::new(SCENE).with_tile_size(8);
+
+        let image = coordinator.render(&[closed_port(), worker()]).unwrap();
+
+        assert!(image.into_iter().eq(Scene::parse(SCENE).unwrap().render()));
+        assert!(coordinator.render(&[closed_port()]).is_err());
+        assert!(coordinator.render::<SocketAddr>(&[]).is_err());
+    }
+
+    #[test]
+    fn the_tiles_of_a_hung_worker_go_to_the_others() {
+        let hung = TcpListener::bind("127.0.0.1:0").unwrap();
+        let coordinator = Coordinator::new(SCENE)
+            .with_tile_size(8)
+            .with_timeout(Duration::from_millis(200));
+
+        let image = coordinator
+            .render(&[hung.local_addr().unwrap(), worker()])
+            .unwrap();
+
+        assert!(image.into_iter().eq(Scene::parse(SCENE).unwrap().render()));
+    }
+
+    #[test]
+    fn workers_refuse_scenes_that_are_too_long() {
+        let stream = TcpStream::connect(worker()).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = BufWriter::new(stream);
+        writeln!(writer, "scene {}", MAX_SCENE_LENGTH + 1).unwrap();
+        writer.flush().unwrap();
+
+        let mut reply = String::new();
+        assert_eq!(0, reader.read_line(&mut reply).unwrap());
+    }
+
+    #[test]
+    fn workers_refuse_tiles_outside_the_frame_or_too_large() {
+        let stream = TcpStream::connect(worker()).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = BufWriter::new(stream);
+        write!(writer, "scene {}\n{SCENE}", SCENE.len()).unwrap();
+
+        for tile in [
+            Tile::new(0, 0, 100_000, 100_000),
+            Tile::new(20, 0, 5, 5),
+            Tile::new(0, 11, 1, 1),
+        ] {
+            let error = request_tile(&mut reader, &mut writer, tile).err().unwrap();
+            assert!(error.to_string().contains("the worker failed"));
+        }
+        assert!(request_tile(&mut reader, &mut writer, Tile::new(18, 6, 5, 5)).is_ok());
+    }
+
+    #[test]
+    fn workers_report_scenes_they_cannot_build() {
+        let coordinator = Coordinator::new(SCENE).with_setting("camera.lens=7");
+
+        assert!(coordinator.render(&[worker()]).is_err());
+
+        let stream = TcpStream::connect(worker()).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = BufWriter::new(stream);
+        write!(writer, "scene 9\n- add: x\n").unwrap();
+        let error = request_tile(&mut reader, &mut writer, Tile::new(0, 0, 1, 1))
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().contains("the worker failed"));
+    }
+}
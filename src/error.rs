@@ -10,6 +10,8 @@ pub enum RayTraceError {
     RayCreationError(Tuple, Tuple),
     ParseFloatError(std::num::ParseFloatError),
     ParseIntError(std::num::ParseIntError),
+    InvalidFormat(String),
+    UnknownShape(String),
 }
 
 impl Display for RayTraceError {
@@ -20,10 +22,12 @@ impl Display for RayTraceError {
             IoError(e) => writeln!(f, "IO Error occurred: {}", e),
             RayCreationError(origin, point) => writeln!(
                 f,
-                "Could not create ray. {origin:?} must be a point and {point:?} must be a vector"
+                "Could not create ray. {origin} must be a point and {point} must be a vector"
             ),
             ParseFloatError(e) => writeln!(f, "ParseFloatError occured: {}", e),
             ParseIntError(e) => writeln!(f, "ParseIntError occured: {}", e),
+            InvalidFormat(e) => writeln!(f, "Invalid file format: {}", e),
+            UnknownShape(name) => writeln!(f, "No shape named {}", name),
         }
     }
 }
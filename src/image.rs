@@ -0,0 +1,250 @@
+use std::{fs, path::Path};
+
+use crate::{
+    canvas::Canvas,
+    color::Color,
+    error::{RayTraceError, RayTraceResult},
+};
+
+#[cfg(feature = "png")]
+use crate::metadata::RenderMetadata;
+
+fn invalid(message: &str) -> RayTraceError {
+    RayTraceError::InvalidFormat(format!("image: {message}"))
+}
+
+/**
+   Read an image into a canvas, as a PPM or, with the `png` feature, a PNG
+   file, chosen by its extension.
+*/
+pub fn load<T: AsRef<Path>>(path: T) -> RayTraceResult<Canvas> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    match extension.as_deref() {
+        Some("ppm") => parse_ppm(&fs::read_to_string(path)?),
+        #[cfg(feature = "png")]
+        Some("png") => decode_png(&fs::read(path)?),
+        #[cfg(not(feature = "png"))]
+        Some("png") => Err(invalid("reading PNG files needs the png feature")),
+        _ => Err(invalid(&format!(
+            "cannot read {}, only PPM and PNG files",
+            path.to_string_lossy()
+        ))),
+    }
+}
+
+/**
+   Read a plain (P3) PPM image. Comments may appear anywhere and values
+   may be split across lines however the writer liked, as `Canvas::save`
+   does. Values are scaled by the image's maximum to between 0 and 1.
+*/
+pub fn parse_ppm(source: &str) -> RayTraceResult<Canvas> {
+    let mut tokens = source
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(str::split_whitespace);
+
+    if tokens.next() != Some("P3") {
+        return Err(invalid("only plain PPM files starting with P3 are read"));
+    }
+    let mut header = || -> RayTraceResult<usize> {
+        Ok(tokens
+            .next()
+            .ok_or_else(|| invalid("the PPM header is cut short"))?
+            .parse()?)
+    };
+    let (width, height, scale) = (header()?, header()?, header()?);
+    if width == 0 || height == 0 || scale == 0 {
+        return Err(invalid("a PPM image needs a size and a maximum value"));
+    }
+
+    let values = tokens
+        .take(width * height * 3)
+        .map(|token| Ok(token.parse::<f64>()? / scale as f64))
+        .collect::<RayTraceResult<Vec<_>>>()?;
+    if values.len() < width * height * 3 {
+        return Err(invalid(&format!(
+            "a {width}x{height} PPM image needs {} values but has {}",
+            width * height * 3,
+            values.len()
+        )));
+    }
+
+    let mut canvas = Canvas::new(width, height);
+    for (i, rgb) in values.chunks_exact(3).enumerate() {
+        canvas[(i % width, i / width)] = Color::new(rgb[0], rgb[1], rgb[2]);
+    }
+
+    Ok(canvas)
+}
+
+/**
+   Read a PNG image. Grayscale, palette and 16 bit images are all read as
+   8 bit color, and any alpha channel is dropped.
+*/
+#[cfg(feature = "png")]
+pub fn decode_png(bytes: &[u8]) -> RayTraceResult<Canvas> {
+    use png::{ColorType, Decoder, Transformations};
+
+    let mut decoder = Decoder::new(bytes);
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| invalid(&e.to_string()))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .map_err(|e| invalid(&e.to_string()))?;
+
+    let channels = match info.color_type {
+        ColorType::Grayscale => 1,
+        ColorType::GrayscaleAlpha => 2,
+        ColorType::Rgb => 3,
+        ColorType::Rgba => 4,
+        ColorType::Indexed => return Err(invalid("the PNG palette was not expanded")),
+    };
+    let width = info.width as usize;
+    let mut canvas = Canvas::new(width, info.height as usize);
+    for (i, pixel) in buffer[..info.buffer_size()]
+        .chunks_exact(channels)
+        .enumerate()
+    {
+        canvas[(i % width, i / width)] = if channels < 3 {
+            Color::new_scaled(pixel[0], pixel[0], pixel[0])
+        } else {
+            Color::new_scaled(pixel[0], pixel[1], pixel[2])
+        };
+    }
+
+    Ok(canvas)
+}
+
+/**
+   Write a canvas as an 8 bit RGB PNG image, with each metadata field, if
+   given, in a tEXt chunk under its key.
+*/
+#[cfg(feature = "png")]
+pub fn encode_png(canvas: &Canvas, metadata: Option<&RenderMetadata>) -> RayTraceResult<Vec<u8>> {
+    use png::{BitDepth, ColorType, Encoder};
+
+    let (width, height) = (canvas.width(), canvas.height());
+    let mut bytes = vec![];
+    {
+        let mut encoder = Encoder::new(&mut bytes, width as u32, height as u32);
+        encoder.set_color(ColorType::Rgb);
+        encoder.set_depth(BitDepth::Eight);
+        for (key, value) in metadata.map_or(vec![], |metadata| metadata.fields(width, height)) {
+            encoder
+                .add_text_chunk(key.to_string(), value)
+                .map_err(|e| invalid(&e.to_string()))?;
+        }
+
+        let mut data = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let (red, green, blue) = canvas[(x, y)].to_ppm();
+                data.extend([red, green, blue]);
+            }
+        }
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&data))
+            .map_err(|e| invalid(&e.to_string()))?;
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading_a_file_with_the_wrong_magic_number() {
+        let ppm = "P32\n1 1\n255\n0 0 0\n";
+
+        assert!(parse_ppm(ppm).is_err());
+    }
+
+    #[test]
+    fn reading_a_ppm_returns_a_canvas_of_the_right_size_and_colors() {
+        let ppm = "P3\n\
+            10 2\n\
+            255\n\
+            0 0 0  1 1 1  2 2 2  3 3 3  4 4 4  5 5 5  6 6 6  7 7 7  8 8 8  9 9 9\n\
+            255 255 255  0 0 0  255 0 0  0 255 0  0 0 255  255 255 0  0 255 255\n\
+            255 0 255  127 127 127  51 102 153\n";
+        let canvas = parse_ppm(ppm).unwrap();
+
+        assert_eq!(10, canvas.width());
+        assert_eq!(2, canvas.height());
+        assert_eq!(Color::new(0.0, 0.0, 0.0), canvas[(0, 0)]);
+        assert_eq!(Color::new(1.0, 1.0, 1.0), canvas[(0, 1)]);
+        assert_eq!(Color::new(0.0, 1.0, 0.0), canvas[(3, 1)]);
+        assert_eq!(Color::new(0.2, 0.4, 0.6), canvas[(9, 1)]);
+    }
+
+    #[test]
+    fn ppm_comments_and_split_values_are_read() {
+        let ppm = "P3\n\
+            # this is a comment\n\
+            2 1 # size\n\
+            100\n\
+            100 100 100\n\
+            # another comment\n\
+            51\n\
+            51\n\
+            51\n";
+        let canvas = parse_ppm(ppm).unwrap();
+
+        assert_eq!(Color::new(1.0, 1.0, 1.0), canvas[(0, 0)]);
+        assert_eq!(Color::new(0.51, 0.51, 0.51), canvas[(1, 0)]);
+        assert!(parse_ppm("P3\n2 1\n255\n0 0 0\n").is_err());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn reading_a_png_returns_its_colors() {
+        let mut bytes = vec![];
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer
+                .write_image_data(&[255, 0, 0, 255, 0, 51, 255, 128])
+                .unwrap();
+        }
+        let canvas = decode_png(&bytes).unwrap();
+
+        assert_eq!(2, canvas.width());
+        assert_eq!(Color::new(1.0, 0.0, 0.0), canvas[(0, 0)]);
+        assert_eq!(Color::new(0.0, 0.2, 1.0), canvas[(1, 0)]);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn written_pngs_keep_their_colors_and_metadata() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas[(1, 0)] = Color::new(0.0, 0.2, 1.0);
+        let metadata = RenderMetadata::new().with_scene("cover").with_seed(7);
+
+        let bytes = encode_png(&canvas, Some(&metadata)).unwrap();
+        let reader = png::Decoder::new(&bytes[..]).read_info().unwrap();
+        let text = reader
+            .info()
+            .uncompressed_latin1_text
+            .iter()
+            .map(|chunk| (chunk.keyword.as_str(), chunk.text.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![("scene", "cover"), ("resolution", "2x1"), ("seed", "7")],
+            text
+        );
+        let decoded = decode_png(&bytes).unwrap();
+        assert_eq!(Color::new(0.0, 0.0, 0.0), decoded[(0, 0)]);
+        assert_eq!(Color::new(0.0, 0.2, 1.0), decoded[(1, 0)]);
+    }
+}
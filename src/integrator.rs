@@ -0,0 +1,325 @@
+use std::fmt::Debug;
+
+use crate::{
+    color::{Color, Colors},
+    intersection::{
+        prepcomputation::PrepComputations,
+        ray::{Ray, RayKind},
+        scratch::Scratch,
+    },
+    onb::Onb,
+    sampling::{square_to_cosine_hemisphere, Sampler},
+    util::EPSILON,
+    world::World,
+};
+
+/**
+   A way of turning the light arriving along a ray into a color, so cameras
+   can switch rendering algorithms without the world knowing about them.
+   `depth` is how many more bounces may be traced and `scratch` is the
+   calling thread's buffers.
+*/
+pub trait Integrator: Debug + Send + Sync {
+    fn li(
+        &self,
+        ray: Ray,
+        world: &World,
+        depth: usize,
+        sampler: &dyn Sampler,
+        scratch: &mut Scratch,
+    ) -> Color;
+}
+
+/**
+   The book's shading: direct light from every light, plus mirror
+   reflection and refraction. This is what cameras use by default.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Whitted;
+
+impl Integrator for Whitted {
+    fn li(
+        &self,
+        ray: Ray,
+        world: &World,
+        depth: usize,
+        _sampler: &dyn Sampler,
+        scratch: &mut Scratch,
+    ) -> Color {
+        world.trace(ray, depth, RayKind::Camera, 1.0, scratch)
+    }
+}
+
+/**
+   Ambient occlusion: white where all of `samples` cosine weighted rays over
+   the surface escape without hitting anything closer than `max_distance`,
+   darker the more are blocked. Rays that miss everything are white.
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct AmbientOcclusion {
+    samples: usize,
+    max_distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: usize, max_distance: f64) -> Self {
+        Self {
+            samples: samples.max(1),
+            max_distance,
+        }
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn max_distance(&self) -> f64 {
+        self.max_distance
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn li(
+        &self,
+        ray: Ray,
+        world: &World,
+        _depth: usize,
+        sampler: &dyn Sampler,
+        scratch: &mut Scratch,
+    ) -> Color {
+        world.intersects_for_into(&ray, RayKind::Camera, scratch.heap_mut());
+        let Some(hit) = scratch.heap().hit_with(world.precision()) else {
+            return Colors::White.into();
+        };
+        let comps = PrepComputations::from_scratch(hit, ray, scratch);
+        let onb = Onb::from_normal(comps.normal_v());
+
+        let open = sampler
+            .samples(self.samples)
+            .into_iter()
+            .filter(|&sample| {
+                let direction = onb.to_world(square_to_cosine_hemisphere(sample));
+                world.intersects_for_into(
+                    &Ray::new(comps.over_point(), direction),
+                    RayKind::Shadow,
+                    scratch.heap_mut(),
+                );
+                !scratch
+                    .heap()
+                    .iter()
+                    .any(|i| i.t() > EPSILON && i.t() <= self.max_distance)
+            })
+            .count();
+
+        let fraction = open as f64 / self.samples as f64;
+        Color::new(fraction, fraction, fraction)
+    }
+}
+
+/**
+   A path tracer: direct light as in `Whitted`, plus light bounced between
+   diffuse surfaces, followed along one cosine weighted direction per hit
+   in place of the material's ambient term. Mirror reflection is followed
+   too; transparency is not.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PathTracer;
+
+impl PathTracer {
+    fn trace(
+        &self,
+        ray: Ray,
+        kind: RayKind,
+        world: &World,
+        depth: usize,
+        sampler: &dyn Sampler,
+        scratch: &mut Scratch,
+    ) -> Color {
+        world.intersects_for_into(&ray, kind, scratch.heap_mut());
+        let Some(hit) = scratch.heap().hit_with(world.precision()) else {
+            return world
+                .sky()
+                .map_or(Colors::Black.into(), |sky| sky.color_at(ray.direction()));
+        };
+        let comps = PrepComputations::from_scratch(hit, ray, scratch);
+        let material = comps
+            .object()
+            .read()
+            .unwrap()
+            .material(comps.object_id())
+            .unwrap_or_default();
+        let material = match comps.vertex_color() {
+            Some(color) => material.with_color(color),
+            None => material,
+        }
+        .with_ambient(0.0);
+
+        let mut color = world.direct_lighting(&comps, &material);
+        if depth == 0 {
+            return color;
+        }
+
+        let albedo = material.pattern().color_at_surface(
+            &*comps.object().read().unwrap(),
+            comps.point(),
+            comps.normal_v(),
+        ) * material.diffuse();
+        let sample = sampler.samples(1)[0];
+        let direction =
+            Onb::from_normal(comps.normal_v()).to_world(square_to_cosine_hemisphere(sample));
+        let bounce = Ray::new(comps.bounce_origin(direction), direction);
+        color += albedo
+            * self.trace(
+                bounce,
+                RayKind::Secondary,
+                world,
+                depth - 1,
+                sampler,
+                scratch,
+            );
+
+        if material.reflective() > 0.0 {
+            let reflection = Ray::new(comps.reflect_origin(), comps.reflect_v());
+            color += self.trace(
+                reflection,
+                RayKind::Secondary,
+                world,
+                depth - 1,
+                sampler,
+                scratch,
+            ) * material.reflective();
+        }
+
+        color
+    }
+}
+
+impl Integrator for PathTracer {
+    fn li(
+        &self,
+        ray: Ray,
+        world: &World,
+        depth: usize,
+        sampler: &dyn Sampler,
+        scratch: &mut Scratch,
+    ) -> Color {
+        self.trace(ray, RayKind::Camera, world, depth, sampler, scratch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{
+        point_light::PointLight,
+        sampling::{seed_rng, RandomSampler, StratifiedSampler},
+        shape::{material::Material, plane::Plane, Shape},
+        transformation::Transformation,
+        tuple::Tuple,
+        util::eq_f64,
+    };
+
+    use super::*;
+
+    fn ground(world: &mut World) {
+        let mut floor = Plane::new();
+        floor.set_material(Material::new().with_ambient(0.5));
+        world.add_shape(floor.into());
+    }
+
+    #[test]
+    fn the_whitted_integrator_matches_color_at() {
+        let w = World::default();
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        let color = Whitted.li(
+            r,
+            &w,
+            w.max_depth(),
+            &StratifiedSampler::default(),
+            &mut Scratch::new(),
+        );
+
+        assert_eq!(w.color_at(r), color);
+    }
+
+    #[test]
+    fn ambient_occlusion_darkens_covered_surfaces() {
+        let mut w = World::new();
+        ground(&mut w);
+        let ao = AmbientOcclusion::new(16, 2.0);
+        let r = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        let sampler = StratifiedSampler::new(false);
+
+        assert_eq!(
+            Color::from(Colors::White),
+            ao.li(r, &w, 0, &sampler, &mut Scratch::new())
+        );
+
+        let mut ceiling = Plane::new();
+        ceiling.set_transformation(Transformation::identity().translation(0.0, 0.5, 0.0));
+        w.add_shape(ceiling.into());
+        let r = Ray::new(Tuple::point(0.0, 0.25, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+
+        assert_eq!(
+            Color::from(Colors::Black),
+            ao.li(r, &w, 0, &sampler, &mut Scratch::new())
+        );
+    }
+
+    #[test]
+    fn the_path_tracer_replaces_ambient_light_with_bounced_light() {
+        let mut w = World::new();
+        ground(&mut w);
+        w.add_light(PointLight::new(
+            Tuple::point(0.0, 10.0, 0.0),
+            Colors::White.into(),
+        ));
+        let r = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        let sampler = StratifiedSampler::default();
+
+        let direct = PathTracer.li(r, &w, 0, &sampler, &mut Scratch::new());
+        let whitted = Whitted.li(r, &w, 0, &sampler, &mut Scratch::new());
+        assert_eq!(whitted - Color::new(0.5, 0.5, 0.5), direct);
+
+        let bounced = PathTracer.li(r, &w, 3, &sampler, &mut Scratch::new());
+        assert_eq!(direct, bounced);
+    }
+
+    #[test]
+    fn the_path_tracer_bleeds_color_from_a_red_wall_onto_a_white_floor() {
+        let mut w = World::new();
+        ground(&mut w);
+        let mut wall = Plane::new();
+        wall.set_transformation(
+            Transformation::identity()
+                .rotate_z(PI / 2.0)
+                .translation(1.0, 0.0, 0.0),
+        );
+        wall.set_material(
+            Material::new()
+                .with_color(Colors::Red.into())
+                .with_specular(0.0),
+        );
+        w.add_shape(wall.into());
+        w.add_light(PointLight::new(
+            Tuple::point(-5.0, 10.0, 0.0),
+            Colors::White.into(),
+        ));
+        let r = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        seed_rng(7);
+
+        let direct = PathTracer.li(r, &w, 0, &RandomSampler, &mut Scratch::new());
+        let bounced = (0..64)
+            .map(|_| PathTracer.li(r, &w, 1, &RandomSampler, &mut Scratch::new()))
+            .fold(Color::from(Colors::Black), |sum, color| {
+                sum + color * (1.0 / 64.0)
+            });
+
+        assert!(eq_f64(direct.red(), direct.green()));
+        assert!(bounced.red() > direct.red() + 0.05);
+        assert!(eq_f64(direct.green(), bounced.green()));
+        assert!(eq_f64(direct.blue(), bounced.blue()));
+    }
+}
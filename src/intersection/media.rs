@@ -0,0 +1,249 @@
+use uuid::Uuid;
+
+use crate::shape::material::Material;
+
+use super::{IntersectionHeap, ShapeIntersection};
+
+#[derive(Debug, Clone)]
+pub struct Medium {
+    object_id: Uuid,
+    material: Material,
+}
+
+impl Medium {
+    pub fn object_id(&self) -> Uuid {
+        self.object_id
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn refractive_index(&self) -> f64 {
+        self.material.refractive_index()
+    }
+}
+
+/**
+   The transparent objects a ray is inside of, innermost last. Crossing a
+   surface either enters that object or, if the ray is already inside it,
+   leaves it, wherever it sits in the stack, so overlapping as well as nested
+   objects are handled.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct MediaStack {
+    media: Vec<Medium>,
+}
+
+impl MediaStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+       The media on either side of `hit`: the ones the ray is in as it
+       reaches the surface, and the ones it is in after crossing it.
+    */
+    pub fn around(hit: &ShapeIntersection, xs: &IntersectionHeap) -> (Self, Self) {
+        Self::around_in(hit, xs, &mut Vec::new())
+    }
+
+    /**
+       Like `around`, but sorts the intersections through `order` so a
+       caller can reuse its allocation.
+    */
+    pub fn around_in(
+        hit: &ShapeIntersection,
+        xs: &IntersectionHeap,
+        order: &mut Vec<usize>,
+    ) -> (Self, Self) {
+        let intersections = xs.inner.as_slice();
+        order.clear();
+        order.extend(0..intersections.len());
+        order.sort_by(|a, b| intersections[*a].cmp(&intersections[*b]));
+
+        let mut before = Self::new();
+        for i in order.iter().rev().map(|i| &intersections[*i]) {
+            if i == hit || i.t() > hit.t() {
+                break;
+            }
+            before.cross(i);
+        }
+
+        let mut after = before.clone();
+        after.cross(hit);
+        (before, after)
+    }
+
+    /**
+       Update the stack for a ray crossing the surface of the intersected
+       object.
+    */
+    pub fn cross(&mut self, intersection: &ShapeIntersection) {
+        let object_id = intersection.object_id();
+
+        if let Some(index) = self.position(object_id) {
+            self.media.remove(index);
+        } else if let Some(material) = intersection.object().read().unwrap().material(object_id) {
+            self.media.push(Medium {
+                object_id,
+                material,
+            });
+        }
+    }
+
+    fn position(&self, object_id: Uuid) -> Option<usize> {
+        self.media
+            .iter()
+            .rposition(|medium| medium.object_id == object_id)
+    }
+
+    pub fn contains(&self, object_id: Uuid) -> bool {
+        self.position(object_id).is_some()
+    }
+
+    /**
+       The innermost medium, or `None` when the ray is in empty space.
+    */
+    pub fn current(&self) -> Option<&Medium> {
+        self.media.last()
+    }
+
+    pub fn refractive_index(&self) -> f64 {
+        self.current().map(Medium::refractive_index).unwrap_or(1.0)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.media.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.media.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Medium> {
+        self.media.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        intersection::ray::Ray,
+        shape::{cube::Cube, group::GroupContainer, sphere::Sphere, Shape, ShapeContainer},
+        transformation::Transformation,
+        tuple::Tuple,
+    };
+
+    use super::*;
+
+    fn glass(scale: f64, refractive_index: f64) -> Sphere {
+        let mut sphere = Sphere::glassy();
+        sphere.set_transformation(Transformation::identity().scale(scale, scale, scale));
+        sphere.set_material(
+            Material::new()
+                .with_transparency(1.0)
+                .with_refractive_index(refractive_index),
+        );
+        sphere
+    }
+
+    fn intersections(shapes: &[ShapeContainer], ray: Ray) -> IntersectionHeap {
+        shapes
+            .iter()
+            .flat_map(|shape| {
+                shape
+                    .read()
+                    .unwrap()
+                    .intersects(&ray)
+                    .into_iter()
+                    .map(|i| ShapeIntersection::new(i.t(), shape.clone(), i.object()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn an_empty_stack_is_air() {
+        let stack = MediaStack::new();
+
+        assert!(stack.is_empty());
+        assert!(stack.current().is_none());
+        assert_eq!(1.0, stack.refractive_index());
+    }
+
+    #[test]
+    fn crossing_a_surface_twice_enters_then_leaves() {
+        let s = ShapeContainer::from(glass(1.0, 1.5));
+        let mut stack = MediaStack::new();
+
+        stack.cross(&ShapeIntersection::new(4.0, s.clone(), s.id()));
+        assert!(stack.contains(s.id()));
+        assert_eq!(1.5, stack.refractive_index());
+
+        stack.cross(&ShapeIntersection::new(6.0, s.clone(), s.id()));
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn leaving_an_overlapping_object_keeps_the_innermost_medium() {
+        let a = ShapeContainer::from(glass(1.0, 1.5));
+        let b = ShapeContainer::from(glass(1.0, 2.0));
+        let mut stack = MediaStack::new();
+
+        stack.cross(&ShapeIntersection::new(1.0, a.clone(), a.id()));
+        stack.cross(&ShapeIntersection::new(2.0, b.clone(), b.id()));
+        stack.cross(&ShapeIntersection::new(3.0, a.clone(), a.id()));
+
+        assert_eq!(1, stack.depth());
+        assert_eq!(b.id(), stack.current().unwrap().object_id());
+    }
+
+    #[test]
+    fn an_ice_cube_in_water_in_a_glass() {
+        let glass_cup = ShapeContainer::from(glass(2.0, 1.5));
+        let water = ShapeContainer::from(glass(1.5, 1.333));
+        let mut ice = Cube::new();
+        ice.set_transformation(Transformation::identity().scale(0.5, 0.5, 0.5));
+        ice.set_material(
+            Material::new()
+                .with_transparency(1.0)
+                .with_refractive_index(1.31),
+        );
+        let ice = ShapeContainer::from(ice);
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let xs = intersections(&[glass_cup, water, ice], r);
+
+        let expected = [
+            (1.0, 1.5, 1),
+            (1.5, 1.333, 2),
+            (1.333, 1.31, 3),
+            (1.31, 1.333, 2),
+            (1.333, 1.5, 1),
+            (1.5, 1.0, 0),
+        ];
+
+        assert_eq!(6, xs.len());
+        for (i, (n1, n2, depth)) in expected.into_iter().enumerate() {
+            let (before, after) = MediaStack::around(&xs[i], &xs);
+
+            assert_eq!(n1, before.refractive_index());
+            assert_eq!(n2, after.refractive_index());
+            assert_eq!(depth, after.depth());
+        }
+    }
+
+    #[test]
+    fn nested_objects_in_one_group_are_separate_media() {
+        let group = GroupContainer::default();
+        group.add_child(glass(2.0, 1.5).into());
+        group.add_child(glass(1.0, 2.0).into());
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let xs = intersections(&[group.into()], r);
+
+        let (before, after) = MediaStack::around(&xs[1], &xs);
+
+        assert_eq!(1.5, before.refractive_index());
+        assert_eq!(2.0, after.refractive_index());
+        assert_eq!(2, after.depth());
+    }
+}
@@ -2,12 +2,24 @@ extern crate self as ray_tracer_challenge;
 
 use std::{collections::BinaryHeap, ops::Index};
 
+use smallvec::SmallVec;
 use uuid::Uuid;
 
-use crate::{shape::ShapeContainer, util::eq_f64};
+use crate::{
+    shape::ShapeContainer,
+    util::{eq_f64, PrecisionPolicy},
+};
 
+pub mod media;
 pub mod prepcomputation;
 pub mod ray;
+pub mod scratch;
+
+/**
+   The intersections of a ray with a single shape. Most shapes are hit at
+   most twice, so these are kept inline instead of allocated.
+*/
+pub type Intersections = SmallVec<[Intersection; 4]>;
 
 #[derive(Debug, Clone)]
 pub struct Intersection {
@@ -51,6 +63,15 @@ impl Intersection {
     pub fn v(&self) -> Option<f64> {
         self.v
     }
+
+    /**
+       Order by distance along the ray, the nearest greatest as the heap
+       wants it, counting distances within `precision` of each other as
+       the same.
+    */
+    pub fn cmp_with(&self, other: &Self, precision: PrecisionPolicy) -> std::cmp::Ordering {
+        precision.compare(other.t, self.t)
+    }
 }
 
 impl PartialEq for Intersection {
@@ -63,12 +84,7 @@ impl Eq for Intersection {}
 
 impl PartialOrd for Intersection {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        use std::cmp::Ordering::*;
-        if eq_f64(self.t(), other.t()) {
-            Some(Equal)
-        } else {
-            Some(if self.t() < other.t() { Greater } else { Less })
-        }
+        Some(self.cmp_with(other, PrecisionPolicy::default()))
     }
 }
 
@@ -133,6 +149,13 @@ impl ShapeIntersection {
     pub fn v(&self) -> Option<f64> {
         self.v
     }
+
+    /**
+       Ordered the way `Intersection::cmp_with` orders intersections.
+    */
+    pub fn cmp_with(&self, other: &Self, precision: PrecisionPolicy) -> std::cmp::Ordering {
+        precision.compare(other.t, self.t)
+    }
 }
 
 impl PartialEq for ShapeIntersection {
@@ -145,12 +168,7 @@ impl Eq for ShapeIntersection {}
 
 impl PartialOrd for ShapeIntersection {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        use std::cmp::Ordering::*;
-        if eq_f64(self.t(), other.t()) {
-            Some(Equal)
-        } else {
-            Some(if self.t() < other.t() { Greater } else { Less })
-        }
+        Some(self.cmp_with(other, PrecisionPolicy::default()))
     }
 }
 
@@ -160,7 +178,7 @@ impl Ord for ShapeIntersection {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct IntersectionHeap {
     inner: BinaryHeap<ShapeIntersection>,
 }
@@ -172,24 +190,59 @@ impl IntersectionHeap {
         }
     }
 
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: BinaryHeap::with_capacity(capacity),
+        }
+    }
+
     pub fn push(&mut self, i: ShapeIntersection) {
         self.inner.push(i);
     }
 
+    /**
+       Remove every intersection but keep the allocation, so one heap can be
+       reused across rays.
+    */
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /**
+       Keep only the intersections `keep` accepts, in place.
+    */
+    pub fn retain<F: FnMut(&ShapeIntersection) -> bool>(&mut self, keep: F) {
+        self.inner.retain(keep);
+    }
+
     pub fn hit(&self) -> Option<ShapeIntersection> {
-        for i in 0..self.len() {
-            let i = &self[i];
-            if i.t.is_sign_positive() {
-                return Some(i.clone());
-            }
-        }
-        None
+        self.hit_with(PrecisionPolicy::default())
+    }
+
+    /**
+       The nearest intersection in front of the ray, with hits counted as
+       equally near under `precision`.
+    */
+    pub fn hit_with(&self, precision: PrecisionPolicy) -> Option<ShapeIntersection> {
+        self.inner
+            .iter()
+            .filter(|i| i.t.is_sign_positive())
+            .max_by(|a, b| a.cmp_with(b, precision))
+            .cloned()
     }
 
     pub fn len(&self) -> usize {
         self.inner.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
     pub fn iter(&self) -> std::collections::binary_heap::Iter<ShapeIntersection> {
         self.inner.iter()
     }
@@ -242,7 +295,9 @@ macro_rules! intersections {
 
 #[cfg(test)]
 mod tests {
-    use crate::{shape::sphere::Sphere, util::eq_f64};
+    use std::cmp::Ordering;
+
+    use crate::shape::sphere::Sphere;
 
     use super::*;
 
@@ -325,4 +380,48 @@ mod tests {
 
         assert_eq!(i4, hit.unwrap());
     }
+
+    #[test]
+    fn ordering_intersections_with_a_precision_policy() {
+        let s = ShapeContainer::from(Sphere::new());
+        let near = ShapeIntersection::new(1000.0, s.clone(), s.id());
+        let far = ShapeIntersection::new(1000.000001, s.clone(), s.id());
+
+        assert_eq!(Ordering::Equal, near.cmp(&far));
+        assert_eq!(
+            Ordering::Greater,
+            near.cmp_with(&far, PrecisionPolicy::Ulps(4))
+        );
+        assert_eq!(
+            Ordering::Equal,
+            near.cmp_with(&far, PrecisionPolicy::Relative(1e-6))
+        );
+        assert_eq!(
+            Ordering::Less,
+            Intersection::new(3.0, s.id()).cmp_with(
+                &Intersection::new(2.0, s.id()),
+                PrecisionPolicy::Relative(1e-6)
+            )
+        );
+
+        let xs = intersections![far.clone(), near.clone()];
+        assert_eq!(1000.0, xs.hit_with(PrecisionPolicy::Ulps(4)).unwrap().t());
+        let xs = intersections![near, far];
+        assert_eq!(1000.0, xs.hit_with(PrecisionPolicy::Ulps(4)).unwrap().t());
+    }
+
+    #[test]
+    fn clearing_a_heap_keeps_its_capacity() {
+        let s = ShapeContainer::from(Sphere::new());
+        let mut xs = IntersectionHeap::with_capacity(8);
+        assert!(xs.is_empty());
+        xs.push(ShapeIntersection::new(1.0, s.clone(), s.id()));
+        xs.push(ShapeIntersection::new(2.0, s.clone(), s.id()));
+
+        xs.clear();
+
+        assert!(xs.is_empty());
+        assert!(xs.hit().is_none());
+        assert!(xs.capacity() >= 8);
+    }
 }
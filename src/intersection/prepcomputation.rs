@@ -1,8 +1,11 @@
 use uuid::Uuid;
 
-use crate::{intersection::ray::Ray, shape::ShapeContainer, tuple::Tuple, util::EPSILON};
+use crate::{
+    color::Color, intersection::ray::Ray, shape::ShapeContainer, transformation::Transformation,
+    tuple::Tuple, util::EPSILON,
+};
 
-use super::{IntersectionHeap, ShapeIntersection};
+use super::{media::MediaStack, scratch::Scratch, IntersectionHeap, ShapeIntersection};
 
 #[derive(Debug, Clone)]
 pub struct PrepComputations {
@@ -12,16 +15,36 @@ pub struct PrepComputations {
     point: Tuple,
     over_point: Tuple,
     under_point: Tuple,
+    offset: f64,
     eye_v: Tuple,
     normal_v: Tuple,
     reflect_v: Tuple,
     n1: f64,
     n2: f64,
     inside: bool,
+    media: MediaStack,
+    vertex_color: Option<Color>,
 }
 
 impl PrepComputations {
     pub fn new(intersection: ShapeIntersection, ray: Ray, xs: &IntersectionHeap) -> Self {
+        Self::new_in(intersection, ray, xs, &mut Vec::new())
+    }
+
+    /**
+       Like `new`, for a hit among the intersections collected in `scratch`,
+       reusing its buffers.
+    */
+    pub fn from_scratch(intersection: ShapeIntersection, ray: Ray, scratch: &mut Scratch) -> Self {
+        Self::new_in(intersection, ray, &scratch.heap, &mut scratch.order)
+    }
+
+    fn new_in(
+        intersection: ShapeIntersection,
+        ray: Ray,
+        xs: &IntersectionHeap,
+        order: &mut Vec<usize>,
+    ) -> Self {
         let point = ray.position(intersection.t());
         let mut normal_v = intersection
             .object()
@@ -37,62 +60,41 @@ impl PrepComputations {
             normal_v = -normal_v
         }
 
-        let (mut n1, mut n2) = (0.0, 0.0);
-
-        let mut containers: Vec<(ShapeContainer, Uuid)> = vec![];
-
-        for i in xs.iter() {
-            if i == &intersection {
-                if let Some((last, last_id)) = containers.last() {
-                    n1 = last
-                        .read()
-                        .unwrap()
-                        .material(*last_id)
-                        .unwrap()
-                        .refractive_index()
-                } else {
-                    n1 = 1.0
-                }
-            }
-
-            if containers
-                .iter()
-                .any(|(c, _)| c.read().unwrap().id() == i.object().read().unwrap().id())
-            {
-                containers
-                    .retain(|(c, _)| c.read().unwrap().id() != i.object().read().unwrap().id());
-            } else {
-                containers.push((i.object().clone(), i.object_id()));
-            }
-
-            if i == &intersection {
-                if let Some((last, last_id)) = containers.last() {
-                    n2 = last
-                        .read()
-                        .unwrap()
-                        .material(*last_id)
-                        .unwrap()
-                        .refractive_index()
-                } else {
-                    n2 = 1.0
-                }
-                break;
-            }
-        }
+        let (entered_from, media) = MediaStack::around_in(&intersection, xs, order);
+        let vertex_color = intersection.u().zip(intersection.v()).and_then(|(u, v)| {
+            intersection
+                .object()
+                .read()
+                .unwrap()
+                .vertex_color(intersection.object_id(), u, v)
+        });
+
+        let offset = surface_offset(
+            intersection.t(),
+            point,
+            &intersection
+                .object()
+                .read()
+                .unwrap()
+                .world_transformation(intersection.object_id()),
+        );
 
         Self {
             t: intersection.t(),
             object: intersection.object().clone(),
             object_id: intersection.object_id,
             point,
-            over_point: point + normal_v * EPSILON,
-            under_point: point - normal_v * EPSILON,
+            over_point: point + normal_v * offset,
+            under_point: point - normal_v * offset,
+            offset,
             eye_v,
             normal_v,
             reflect_v: ray.direction().reflect(normal_v),
-            n1,
-            n2,
+            n1: entered_from.refractive_index(),
+            n2: media.refractive_index(),
             inside,
+            media,
+            vertex_color,
         }
     }
 
@@ -116,6 +118,32 @@ impl PrepComputations {
         self.over_point
     }
 
+    /**
+       Where a reflected ray starts: the over point, moved along the
+       reflection by the same bias. Pushing off along the normal alone barely
+       moves a ray leaving a steeply tilted mirror at a grazing angle away
+       from the surface, which shows up as speckled self-reflection.
+    */
+    pub fn reflect_origin(&self) -> Tuple {
+        self.over_point + self.reflect_v.normalize() * self.offset
+    }
+
+    /**
+       Where a ray continuing below the surface in `direction` starts, the
+       under point moved along the ray by the same bias.
+    */
+    pub fn refract_origin(&self, direction: Tuple) -> Tuple {
+        self.under_point + direction.normalize() * self.offset
+    }
+
+    /**
+       Where a ray leaving above the surface in `direction` starts, for
+       bounces other than the mirror reflection.
+    */
+    pub fn bounce_origin(&self, direction: Tuple) -> Tuple {
+        self.over_point + direction.normalize() * self.offset
+    }
+
     pub fn eye_v(&self) -> Tuple {
         self.eye_v
     }
@@ -128,6 +156,23 @@ impl PrepComputations {
         self.reflect_v
     }
 
+    /**
+       The direction of the refracted ray, or `None` under total internal
+       reflection.
+    */
+    pub fn refract_v(&self) -> Option<Tuple> {
+        let n_ratio = self.n1() / self.n2();
+        let cos_i = self.eye_v() * self.normal_v();
+        let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+
+        if sin2_t > 1.0 {
+            return None;
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some(self.normal_v() * (n_ratio * cos_i - cos_t) - self.eye_v() * n_ratio)
+    }
+
     pub fn n1(&self) -> f64 {
         self.n1
     }
@@ -140,6 +185,21 @@ impl PrepComputations {
         self.inside
     }
 
+    /**
+       The color interpolated from the hit shape's vertex colors, if it has
+       any. It replaces the material's color when shading.
+    */
+    pub fn vertex_color(&self) -> Option<Color> {
+        self.vertex_color
+    }
+
+    /**
+       The media a refracted ray travels through after crossing the surface.
+    */
+    pub fn media(&self) -> &MediaStack {
+        &self.media
+    }
+
     pub fn under_point(&self) -> Tuple {
         self.under_point
     }
@@ -163,15 +223,54 @@ impl PrepComputations {
     }
 }
 
+const RELATIVE_EPSILON: f64 = 1e-7;
+
+/**
+   Coordinates themselves round off far more finely than the `t` of a hit,
+   at about 2e-16 of their size, so distance from the origin only needs a
+   much smaller share of the offset.
+*/
+const COORDINATE_EPSILON: f64 = 1e-12;
+
+/**
+   How far over and under points are pushed off the surface. A fixed EPSILON
+   is lost to rounding once an object is scaled up or the hit is far along
+   the ray, so it grows with the object's scale and with `t`. It grows only
+   slowly with the distance from the origin, so a scene built tens of
+   thousands of units away shades like the same scene built around the
+   origin.
+*/
+fn surface_offset(t: f64, point: Tuple, transformation: &Transformation) -> f64 {
+    let scale = [
+        Tuple::vector(1.0, 0.0, 0.0),
+        Tuple::vector(0.0, 1.0, 0.0),
+        Tuple::vector(0.0, 0.0, 1.0),
+    ]
+    .into_iter()
+    .map(|axis| (transformation * axis).magnitude())
+    .fold(1.0, f64::max);
+    let magnitude = point.x().abs().max(point.y().abs()).max(point.z().abs());
+
+    (EPSILON * scale)
+        .max(t.abs() * RELATIVE_EPSILON)
+        .max(magnitude * COORDINATE_EPSILON)
+}
+
 #[cfg(test)]
 mod tests {
     use std::vec;
 
     use crate::{
         intersections,
-        shape::{material::Material, plane::Plane, sphere::Sphere, Shape},
+        shape::{
+            group::{Group, GroupContainer},
+            material::Material,
+            plane::Plane,
+            sphere::Sphere,
+            Shape,
+        },
         transformation::Transformation,
-        util::eq_f64,
+        util::{eq_f64, PrecisionPolicy},
     };
 
     use super::*;
@@ -307,6 +406,106 @@ mod tests {
         assert!(comps.point().z() < comps.under_point().z());
     }
 
+    #[test]
+    fn the_offset_grows_with_the_scale_of_the_object() {
+        let r = Ray::new(Tuple::point(0.0, 0.0, -500.0), Tuple::vector(0.0, 0.0, 1.0));
+        let mut s = Sphere::new();
+        s.set_transformation(Transformation::identity().scale(100.0, 100.0, 100.0));
+        let s = ShapeContainer::from(s);
+
+        let i = ShapeIntersection::new(400.0, s.clone(), s.id());
+        let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
+
+        assert!(eq_f64(
+            comps.point().z() - comps.over_point().z(),
+            EPSILON * 100.0
+        ));
+        assert!(eq_f64(
+            comps.under_point().z() - comps.point().z(),
+            EPSILON * 100.0
+        ));
+    }
+
+    #[test]
+    fn the_offset_grows_with_the_scale_of_a_shape_inside_a_group() {
+        let r = Ray::new(Tuple::point(0.0, 0.0, -500.0), Tuple::vector(0.0, 0.0, 1.0));
+        let mut s = Sphere::new();
+        s.set_transformation(Transformation::identity().scale(100.0, 100.0, 100.0));
+        let s = ShapeContainer::from(s);
+        let g = GroupContainer::from(Group::new());
+        g.add_child(s.clone());
+        let g: ShapeContainer = g.into();
+
+        for prepared in [false, true] {
+            if prepared {
+                g.write()
+                    .unwrap()
+                    .prepare(&Transformation::identity(), PrecisionPolicy::default());
+            }
+            let i = ShapeIntersection::new(400.0, g.clone(), s.id());
+            let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
+
+            assert!(eq_f64(
+                comps.point().z() - comps.over_point().z(),
+                EPSILON * 100.0
+            ));
+        }
+    }
+
+    #[test]
+    fn the_offset_grows_with_the_distance_to_the_hit() {
+        let r = Ray::new(Tuple::point(0.0, 1.0e6, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        let plane = ShapeContainer::from(Plane::new());
+
+        let i = ShapeIntersection::new(1.0e6, plane.clone(), plane.id());
+        let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
+
+        assert!(comps.over_point().y() > EPSILON * 10.0);
+        assert!(comps.under_point().y() < -EPSILON * 10.0);
+    }
+
+    #[test]
+    fn secondary_rays_start_along_their_direction_too() {
+        let r = Ray::new(Tuple::point(0.0, 1.0, -1.0), Tuple::vector(0.0, -1.0, 1.0));
+        let plane = ShapeContainer::from(Plane::new());
+
+        let i = ShapeIntersection::new(1.0, plane.clone(), plane.id());
+        let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
+
+        assert!(comps.reflect_origin().z() > comps.over_point().z());
+        assert!(comps.refract_origin(r.direction()).z() > comps.under_point().z());
+        assert!(comps.refract_origin(r.direction()).y() < comps.under_point().y());
+    }
+
+    #[test]
+    fn grazing_reflections_off_a_stretched_mirror_miss_it() {
+        let transformation = Transformation::identity()
+            .scale(100.0, 1.0e5, 100.0)
+            .translation(1.0e3, 0.0, 0.0);
+        let mut mirror = Sphere::new();
+        mirror.set_transformation(transformation.clone());
+        let mirror = ShapeContainer::from(mirror);
+        let tip = transformation.clone() * Tuple::point(0.0, 1.0, 0.0);
+
+        for angle in [1.0e-10, 1.0e-9, 1.0e-8] {
+            let direction = (transformation.clone()
+                * Tuple::vector(f64::cos(angle), -f64::sin(angle), 0.0))
+            .normalize();
+            let r = Ray::new(tip - direction * 100.0, direction);
+            let t = mirror.read().unwrap().intersects(&r)[0].t();
+            let i = ShapeIntersection::new(t, mirror.clone(), mirror.id());
+            let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
+
+            let from_over_point = Ray::new(comps.over_point(), comps.reflect_v());
+            let xs = mirror.read().unwrap().intersects(&from_over_point);
+            assert!(xs.iter().any(|x| x.t() > 0.0), "angle {angle}");
+
+            let reflected = Ray::new(comps.reflect_origin(), comps.reflect_v());
+            let xs = mirror.read().unwrap().intersects(&reflected);
+            assert!(xs.iter().all(|x| x.t() <= 0.0), "angle {angle}: {xs:?}");
+        }
+    }
+
     #[test]
     fn the_schlick_approximation_under_total_internal_reflection() {
         let shape = ShapeContainer::from(Sphere::glassy());
@@ -2,6 +2,13 @@ use crate::{shape::ShapeContainer, tuple::Tuple};
 
 use super::{IntersectionHeap, ShapeIntersection};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RayKind {
+    Camera,
+    Shadow,
+    Secondary,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct Ray {
     origin: Tuple,
@@ -25,9 +32,9 @@ impl Ray {
         self.origin + (self.direction * position)
     }
 
-    pub fn intersections(&self, shape: ShapeContainer) -> IntersectionHeap {
+    pub fn intersections(&self, shape: &ShapeContainer) -> IntersectionHeap {
         let mut heap = IntersectionHeap::new();
-        for i in shape.read().unwrap().intersects(*self) {
+        for i in shape.read().unwrap().intersects(self) {
             heap.push(ShapeIntersection::new_with_uv(
                 i.t(),
                 shape.clone(),
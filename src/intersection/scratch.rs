@@ -0,0 +1,29 @@
+use super::IntersectionHeap;
+
+/**
+   Buffers a thread reuses from one ray to the next so tracing does not
+   allocate on the hot path. Random numbers already come from the thread
+   local generator in `sampling`, so there is no generator here.
+*/
+#[derive(Debug, Default)]
+pub struct Scratch {
+    pub(crate) heap: IntersectionHeap,
+    pub(crate) order: Vec<usize>,
+}
+
+impl Scratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+       The heap the last traced ray's intersections were collected into.
+    */
+    pub fn heap(&self) -> &IntersectionHeap {
+        &self.heap
+    }
+
+    pub fn heap_mut(&mut self) -> &mut IntersectionHeap {
+        &mut self.heap
+    }
+}
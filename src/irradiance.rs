@@ -0,0 +1,266 @@
+use std::f64::consts::PI;
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    color::{Color, Colors},
+    intersection::{
+        ray::{Ray, RayKind},
+        scratch::Scratch,
+    },
+    sampling::{FibonacciSampler, Sampler},
+    tuple::Tuple,
+    world::World,
+};
+
+const AXES: [Tuple; 6] = [
+    Tuple::vector(1.0, 0.0, 0.0),
+    Tuple::vector(-1.0, 0.0, 0.0),
+    Tuple::vector(0.0, 1.0, 0.0),
+    Tuple::vector(0.0, -1.0, 0.0),
+    Tuple::vector(0.0, 0.0, 1.0),
+    Tuple::vector(0.0, 0.0, -1.0),
+];
+
+/**
+   Light arriving at a point from each of the six axis directions, scaled
+   so that a point surrounded by light of one color sees that color from
+   every side. Surfaces facing between the axes blend them by the squares
+   of their normal's components.
+*/
+type AmbientCube = [Color; 6];
+
+/**
+   A grid of probes spread evenly through a box, each holding the light
+   that reached it from the rest of the scene when it was baked. Looking up
+   a point blends the eight probes around it, which stands in for light
+   bounced between diffuse surfaces at the cost of a few lookups. Points
+   outside the box take the light of the nearest probes.
+
+   Probes that end up inside a shape only see its inside, so they come out
+   dark and darken the surfaces near them.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrradianceProbes {
+    min: Tuple,
+    max: Tuple,
+    counts: (usize, usize, usize),
+    probes: Vec<AmbientCube>,
+}
+
+impl IrradianceProbes {
+    /**
+       Bake `counts` probes between the corners `min` and `max` of `world`,
+       tracing `samples` rays from each. The rays are shaded the way
+       `World::color_at` shades them, so baking a world that already has
+       probes adds another bounce of light.
+    */
+    pub fn bake(
+        world: &World,
+        min: Tuple,
+        max: Tuple,
+        counts: (usize, usize, usize),
+        samples: usize,
+    ) -> Self {
+        let counts = (counts.0.max(1), counts.1.max(1), counts.2.max(1));
+        let directions = FibonacciSampler
+            .samples(samples.max(1))
+            .into_iter()
+            .map(|(u, v)| {
+                let z = 1.0 - 2.0 * v;
+                let radius = (1.0 - z * z).max(0.0).sqrt();
+                let phi = 2.0 * PI * u;
+                Tuple::vector(radius * phi.cos(), radius * phi.sin(), z)
+            })
+            .collect::<Vec<_>>();
+
+        world.prepare();
+        let mut grid = Self {
+            min,
+            max,
+            counts,
+            probes: vec![],
+        };
+        grid.probes = (0..counts.0 * counts.1 * counts.2)
+            .into_par_iter()
+            .map(|index| {
+                let cell = (
+                    index % counts.0,
+                    index / counts.0 % counts.1,
+                    index / (counts.0 * counts.1),
+                );
+                let position = grid.position(cell);
+                let mut scratch = Scratch::new();
+                let mut cube: AmbientCube = [Colors::Black.into(); 6];
+
+                for &direction in &directions {
+                    let color = world.trace(
+                        Ray::new(position, direction),
+                        world.max_depth(),
+                        RayKind::Secondary,
+                        1.0,
+                        &mut scratch,
+                    );
+                    for (face, axis) in cube.iter_mut().zip(AXES) {
+                        *face += color * (direction * axis).max(0.0);
+                    }
+                }
+
+                cube.map(|face| face * (4.0 / directions.len() as f64))
+            })
+            .collect();
+
+        grid
+    }
+
+    pub fn min(&self) -> Tuple {
+        self.min
+    }
+
+    pub fn max(&self) -> Tuple {
+        self.max
+    }
+
+    pub fn counts(&self) -> (usize, usize, usize) {
+        self.counts
+    }
+
+    /**
+       Where the probe at `cell` sits. A single probe along an axis sits
+       halfway across the box.
+    */
+    pub fn position(&self, (x, y, z): (usize, usize, usize)) -> Tuple {
+        let along = |i: usize, count: usize, min: f64, max: f64| {
+            if count == 1 {
+                (min + max) / 2.0
+            } else {
+                min + (max - min) * i as f64 / (count - 1) as f64
+            }
+        };
+
+        Tuple::point(
+            along(x, self.counts.0, self.min.x(), self.max.x()),
+            along(y, self.counts.1, self.min.y(), self.max.y()),
+            along(z, self.counts.2, self.min.z(), self.max.z()),
+        )
+    }
+
+    /**
+       The light arriving at a surface at `point` facing `normal`, blended
+       from the probes around it.
+    */
+    pub fn irradiance_at(&self, point: Tuple, normal: Tuple) -> Color {
+        let locate = |value: f64, count: usize, min: f64, max: f64| {
+            if count == 1 || max <= min {
+                return (0, 0, 0.0);
+            }
+            let scaled = ((value - min) / (max - min)).clamp(0.0, 1.0) * (count - 1) as f64;
+            let low = (scaled.floor() as usize).min(count - 2);
+            (low, low + 1, scaled - low as f64)
+        };
+        let (x0, x1, tx) = locate(point.x(), self.counts.0, self.min.x(), self.max.x());
+        let (y0, y1, ty) = locate(point.y(), self.counts.1, self.min.y(), self.max.y());
+        let (z0, z1, tz) = locate(point.z(), self.counts.2, self.min.z(), self.max.z());
+
+        let mut color: Color = Colors::Black.into();
+        for (z, wz) in [(z0, 1.0 - tz), (z1, tz)] {
+            for (y, wy) in [(y0, 1.0 - ty), (y1, ty)] {
+                for (x, wx) in [(x0, 1.0 - tx), (x1, tx)] {
+                    let weight = wx * wy * wz;
+                    if weight > 0.0 {
+                        color += self.probe_irradiance((x, y, z), normal) * weight;
+                    }
+                }
+            }
+        }
+
+        color
+    }
+
+    fn probe_irradiance(&self, (x, y, z): (usize, usize, usize), normal: Tuple) -> Color {
+        let cube = &self.probes[(z * self.counts.1 + y) * self.counts.0 + x];
+        let normal = normal.normalize();
+        let face = |component: f64, positive: usize| {
+            let side = if component >= 0.0 {
+                positive
+            } else {
+                positive + 1
+            };
+            cube[side] * (component * component)
+        };
+
+        face(normal.x(), 0) + face(normal.y(), 2) + face(normal.z(), 4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        point_light::PointLight,
+        shape::{material::Material, plane::Plane, Shape},
+        sky::Sky,
+    };
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct Overcast;
+
+    impl Sky for Overcast {
+        fn color_at(&self, _direction: Tuple) -> Color {
+            Color::new(0.5, 0.5, 0.5)
+        }
+    }
+
+    #[test]
+    fn probes_in_an_open_sky_see_the_sky_from_every_side() {
+        let mut w = World::new();
+        w.set_sky(Overcast);
+        let probes = IrradianceProbes::bake(
+            &w,
+            Tuple::point(-1.0, -1.0, -1.0),
+            Tuple::point(1.0, 1.0, 1.0),
+            (2, 2, 2),
+            256,
+        );
+
+        for normal in [Tuple::vector(0.0, 1.0, 0.0), Tuple::vector(1.0, -1.0, 0.5)] {
+            let color = probes.irradiance_at(Tuple::point(0.3, -0.2, 0.9), normal);
+            assert!(color.approx_eq(&Color::new(0.5, 0.5, 0.5), 0.01));
+        }
+    }
+
+    #[test]
+    fn probes_see_light_bounced_off_a_floor_from_below() {
+        let mut w = World::new();
+        w.set_sky(Overcast);
+        let mut floor = Plane::new();
+        floor.set_material(
+            Material::new()
+                .with_color(Color::new(1.0, 0.0, 0.0))
+                .with_ambient(1.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0),
+        );
+        w.add_shape(floor.into());
+        w.add_light(PointLight::new(
+            Tuple::point(0.0, 10.0, 0.0),
+            Colors::White.into(),
+        ));
+        let probes = IrradianceProbes::bake(
+            &w,
+            Tuple::point(-1.0, 1.0, -1.0),
+            Tuple::point(1.0, 1.0, 1.0),
+            (3, 1, 3),
+            256,
+        );
+
+        let point = Tuple::point(0.0, 1.0, 0.0);
+        let up = probes.irradiance_at(point, Tuple::vector(0.0, 1.0, 0.0));
+        let down = probes.irradiance_at(point, Tuple::vector(0.0, -1.0, 0.0));
+
+        assert!(up.approx_eq(&Color::new(0.5, 0.5, 0.5), 0.01));
+        assert!(down.red() > 0.9 && down.green() < 0.01);
+        assert_eq!(Tuple::point(0.0, 1.0, 0.0), probes.position((1, 0, 1)));
+    }
+}
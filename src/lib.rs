@@ -1,13 +1,45 @@
+pub mod animation;
+pub mod aperture;
+pub mod area_light;
 pub mod camera;
 pub mod canvas;
 pub mod color;
+pub mod contact_sheet;
+pub mod denoise;
+#[cfg(feature = "distributed")]
+pub mod distributed;
 pub mod error;
+pub mod image;
+pub mod integrator;
 pub mod intersection;
+pub mod irradiance;
+pub mod light;
+pub mod light_link;
+pub mod lighting;
+pub mod lsystem;
 pub mod matrix;
+pub mod mesh;
+pub mod metadata;
 pub mod obj;
+pub mod onb;
+pub mod parametric;
+pub mod ply;
 pub mod point_light;
+pub mod post;
+pub mod presets;
+pub mod progress;
+pub mod sampling;
+pub mod scene;
 pub mod shape;
+pub mod sketch;
+pub mod sky;
+pub mod spot_light;
+pub mod stats;
+pub mod testing;
+pub mod text;
 pub mod transformation;
 pub mod tuple;
-pub(crate) mod util;
+pub mod turntable;
+pub mod util;
+pub mod visualizer;
 pub mod world;
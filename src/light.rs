@@ -0,0 +1,126 @@
+use crate::{
+    area_light::AreaLight, color::Color, point_light::PointLight, sampling::RandomSampler,
+    spot_light::SpotLight, tuple::Tuple, world::World,
+};
+
+/**
+   Any of the lights a `World` can hold. Point, spot and area lights convert
+   into it, so they can be passed wherever a light is expected.
+*/
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Light {
+    Point(PointLight),
+    Spot(SpotLight),
+    Area(AreaLight),
+}
+
+impl Light {
+    /**
+       Where the light is, or the middle of an area light.
+    */
+    pub fn position(&self) -> Tuple {
+        match self {
+            Self::Point(light) => light.position(),
+            Self::Spot(light) => light.position(),
+            Self::Area(light) => light.position(),
+        }
+    }
+
+    pub fn intensity(&self) -> Color {
+        match self {
+            Self::Point(light) => light.intensity(),
+            Self::Spot(light) => light.intensity(),
+            Self::Area(light) => light.intensity(),
+        }
+    }
+
+    /**
+       The points to shade from: the light's position, or one point jittered
+       within every cell of an area light.
+    */
+    pub fn points(&self) -> Vec<Tuple> {
+        match self {
+            Self::Area(light) => light.points(&RandomSampler),
+            light => vec![light.position()],
+        }
+    }
+
+    /**
+       The fraction of the light's intensity that reaches `point`.
+    */
+    pub fn attenuation_at(&self, point: Tuple) -> f64 {
+        match self {
+            Self::Point(light) => light.attenuation_at(point),
+            Self::Spot(light) => light.attenuation_at(point),
+            Self::Area(_) => 1.0,
+        }
+    }
+
+    /**
+       The fraction of the light's intensity that reaches `point` in
+       `world`: nothing if it is in shadow, otherwise its attenuation. Only
+       part of an area light may be hidden, so it gives the fraction of its
+       jittered points that `point` can see.
+    */
+    pub fn intensity_at(&self, point: Tuple, world: &World) -> f64 {
+        match self {
+            Self::Point(light) => light.intensity_at(point, world),
+            Self::Spot(light) => light.intensity_at(point, world),
+            Self::Area(light) => light.intensity_at(point, world, &RandomSampler),
+        }
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Self::Point(light)
+    }
+}
+
+impl From<&PointLight> for Light {
+    fn from(light: &PointLight) -> Self {
+        Self::Point(*light)
+    }
+}
+
+impl From<SpotLight> for Light {
+    fn from(light: SpotLight) -> Self {
+        Self::Spot(light)
+    }
+}
+
+impl From<&SpotLight> for Light {
+    fn from(light: &SpotLight) -> Self {
+        Self::Spot(*light)
+    }
+}
+
+impl From<AreaLight> for Light {
+    fn from(light: AreaLight) -> Self {
+        Self::Area(light)
+    }
+}
+
+impl From<&AreaLight> for Light {
+    fn from(light: &AreaLight) -> Self {
+        Self::Area(*light)
+    }
+}
+
+impl From<&Light> for Light {
+    fn from(light: &Light) -> Self {
+        *light
+    }
+}
+
+impl PartialEq<PointLight> for Light {
+    fn eq(&self, other: &PointLight) -> bool {
+        matches!(self, Self::Point(light) if light == other)
+    }
+}
+
+impl PartialEq<Light> for PointLight {
+    fn eq(&self, other: &Light) -> bool {
+        other == self
+    }
+}
@@ -0,0 +1,40 @@
+use uuid::Uuid;
+
+/**
+   Which shapes a light illuminates. Shapes are matched by the id of either
+   the top level shape or the primitive that was hit, so a whole group can
+   be linked at once. Shadows are unaffected.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum LightLink {
+    #[default]
+    All,
+    Include(Vec<Uuid>),
+    Exclude(Vec<Uuid>),
+}
+
+impl LightLink {
+    pub fn affects(&self, ids: &[Uuid]) -> bool {
+        match self {
+            LightLink::All => true,
+            LightLink::Include(linked) => ids.iter().any(|id| linked.contains(id)),
+            LightLink::Exclude(linked) => !ids.iter().any(|id| linked.contains(id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn include_and_exclude_lists_match_any_of_the_ids() {
+        let (group, primitive, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        assert!(LightLink::All.affects(&[other]));
+        assert!(LightLink::Include(vec![group]).affects(&[group, primitive]));
+        assert!(!LightLink::Include(vec![group]).affects(&[other]));
+        assert!(!LightLink::Exclude(vec![primitive]).affects(&[group, primitive]));
+        assert!(LightLink::Exclude(vec![primitive]).affects(&[other]));
+    }
+}
@@ -0,0 +1,132 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color, point_light::PointLight, shape::bounded_box::BoundedBox, tuple::Tuple,
+    world::World,
+};
+
+/**
+   Where a rig is aimed and how far out its lights stand: the middle of the
+   subject's box and the radius of a sphere around it. An empty box stands
+   in for a unit sphere at the origin.
+*/
+fn frame(subject: &BoundedBox) -> (Tuple, f64) {
+    if subject.is_empty() {
+        return (Tuple::point(0.0, 0.0, 0.0), 1.0);
+    }
+    let radius = (subject.max() - subject.min()).magnitude() / 2.0;
+
+    (subject.center(), radius.max(f64::EPSILON))
+}
+
+/**
+   The point `distance` from `center` in the direction given by `azimuth`,
+   measured from -z towards +x, and `elevation` above the horizon.
+*/
+fn around(center: Tuple, distance: f64, azimuth: f64, elevation: f64) -> Tuple {
+    center
+        + Tuple::vector(
+            azimuth.sin() * elevation.cos(),
+            elevation.sin(),
+            -azimuth.cos() * elevation.cos(),
+        ) * distance
+}
+
+/**
+   Light `subject` for a camera looking at it from -z with the classic
+   three lights, all standing three times the subject's radius away: a key
+   light high on the left at full `intensity`, a fill light low on the right
+   at 40% and a rim light behind and above at 60% to lift the silhouette
+   off the background.
+*/
+pub fn three_point(world: &mut World, subject: &BoundedBox, intensity: Color) {
+    let (center, radius) = frame(subject);
+    let distance = radius * 3.0;
+
+    for (azimuth, elevation, share) in [
+        (-PI / 4.0, PI / 4.0, 1.0),
+        (PI / 3.0, PI / 12.0, 0.4),
+        (PI * 5.0 / 6.0, PI / 3.0, 0.6),
+    ] {
+        world.add_light(PointLight::new(
+            around(center, distance, azimuth, elevation),
+            intensity * share,
+        ));
+    }
+}
+
+/**
+   Light `subject` evenly from above with `count` lights spread over a dome
+   four times its radius away, splitting `intensity` between them. More
+   lights give softer shadows at the cost of more shadow rays.
+*/
+pub fn studio_dome(world: &mut World, subject: &BoundedBox, count: usize, intensity: Color) {
+    let (center, radius) = frame(subject);
+    let count = count.max(1);
+    let golden_angle = PI * (3.0 - 5f64.sqrt());
+
+    for i in 0..count {
+        let elevation = (1.0 - (i as f64 + 0.5) / count as f64).asin();
+        world.add_light(PointLight::new(
+            around(center, radius * 4.0, golden_angle * i as f64, elevation),
+            intensity * (1.0 / count as f64),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::Colors,
+        shape::{sphere::Sphere, Shape},
+        transformation::Transformation,
+        util::eq_f64,
+    };
+
+    use super::*;
+
+    fn subject() -> BoundedBox {
+        let mut sphere = Sphere::new();
+        sphere.set_transformation(Transformation::identity().translation(0.0, 1.0, 0.0));
+        sphere.parent_space_bounds()
+    }
+
+    #[test]
+    fn a_three_point_rig_surrounds_the_subject() {
+        let mut world = World::new();
+        three_point(&mut world, &subject(), Colors::White.into());
+        let center = Tuple::point(0.0, 1.0, 0.0);
+        let lights = world.lights();
+
+        assert_eq!(3, lights.len());
+        for light in lights {
+            assert!(eq_f64(
+                3.0 * 3f64.sqrt(),
+                (light.position() - center).magnitude()
+            ));
+            assert!(light.position().y() > center.y());
+        }
+        assert!(lights[0].position().x() < 0.0 && lights[0].position().z() < 0.0);
+        assert!(lights[1].position().x() > 0.0 && lights[1].position().z() < 0.0);
+        assert!(lights[2].position().z() > 0.0);
+        assert_eq!(Color::new(0.4, 0.4, 0.4), lights[1].intensity());
+    }
+
+    #[test]
+    fn a_studio_dome_shares_its_intensity_above_the_subject() {
+        let mut world = World::new();
+        studio_dome(&mut world, &subject(), 8, Color::new(2.0, 2.0, 2.0));
+
+        assert_eq!(8, world.lights().len());
+        let total = world
+            .lights()
+            .iter()
+            .fold(Color::new(0.0, 0.0, 0.0), |sum, light| {
+                sum + light.intensity()
+            });
+        assert_eq!(Color::new(2.0, 2.0, 2.0), total);
+        for light in world.lights() {
+            assert!(light.position().y() > 1.0);
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use std::{collections::HashMap, f64::consts::PI};
+
+use crate::{
+    shape::{cylinder::Cylinder, group::GroupContainer, material::Material, sphere::Sphere, Shape},
+    transformation::Transformation,
+    tuple::Tuple,
+};
+
+/**
+   A Lindenmayer system: an axiom rewritten by rules a number of times, then
+   drawn by a turtle into cylinders and spheres. The turtle starts at the
+   origin heading up +y and understands
+
+   - `F` draw a branch one step forward, `f` move forward without drawing
+   - `+` `-` turn left and right, `&` `^` pitch down and up, `\` `/` roll
+     left and right, all by the angle, and `|` turn around
+   - `[` `]` start and end a branch, which becomes a group of its own
+   - `!` taper the following branches' length and radius
+   - `@` draw a leaf sphere
+
+   Every other symbol is only there to be rewritten.
+*/
+#[derive(Debug, Clone)]
+pub struct LSystem {
+    axiom: String,
+    rules: HashMap<char, String>,
+    angle: f64,
+    step: f64,
+    radius: f64,
+    taper: f64,
+    leaf_radius: f64,
+    branch_material: Material,
+    leaf_material: Material,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Turtle {
+    position: Tuple,
+    heading: Tuple,
+    left: Tuple,
+    up: Tuple,
+    step: f64,
+    radius: f64,
+}
+
+impl Turtle {
+    fn turn(&mut self, angle: f64) {
+        self.heading = rotate(self.heading, self.up, angle);
+        self.left = rotate(self.left, self.up, angle);
+    }
+
+    fn pitch(&mut self, angle: f64) {
+        self.heading = rotate(self.heading, self.left, angle);
+        self.up = rotate(self.up, self.left, angle);
+    }
+
+    fn roll(&mut self, angle: f64) {
+        self.left = rotate(self.left, self.heading, angle);
+        self.up = rotate(self.up, self.heading, angle);
+    }
+}
+
+/**
+   Rotate `v` by `angle` around the unit vector `axis` (Rodrigues' formula).
+*/
+fn rotate(v: Tuple, axis: Tuple, angle: f64) -> Tuple {
+    v * angle.cos() + (axis ^ v) * angle.sin() + axis * ((axis * v) * (1.0 - angle.cos()))
+}
+
+impl LSystem {
+    pub fn new(axiom: &str) -> Self {
+        Self {
+            axiom: axiom.to_string(),
+            rules: HashMap::new(),
+            angle: 25f64.to_radians(),
+            step: 1.0,
+            radius: 0.1,
+            taper: 0.7,
+            leaf_radius: 0.2,
+            branch_material: Material::default(),
+            leaf_material: Material::default(),
+        }
+    }
+
+    /**
+       Replace every `symbol` with `replacement` on each rewrite.
+    */
+    pub fn with_rule(mut self, symbol: char, replacement: &str) -> Self {
+        self.rules.insert(symbol, replacement.to_string());
+        self
+    }
+
+    pub fn with_angle(mut self, angle: f64) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    pub fn with_step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn with_radius(mut self, radius: f64) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn with_taper(mut self, taper: f64) -> Self {
+        self.taper = taper;
+        self
+    }
+
+    pub fn with_leaf_radius(mut self, leaf_radius: f64) -> Self {
+        self.leaf_radius = leaf_radius;
+        self
+    }
+
+    pub fn with_branch_material(mut self, branch_material: Material) -> Self {
+        self.branch_material = branch_material;
+        self
+    }
+
+    pub fn with_leaf_material(mut self, leaf_material: Material) -> Self {
+        self.leaf_material = leaf_material;
+        self
+    }
+
+    pub fn axiom(&self) -> &str {
+        &self.axiom
+    }
+
+    pub fn angle(&self) -> f64 {
+        self.angle
+    }
+
+    pub fn step(&self) -> f64 {
+        self.step
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn taper(&self) -> f64 {
+        self.taper
+    }
+
+    pub fn leaf_radius(&self) -> f64 {
+        self.leaf_radius
+    }
+
+    /**
+       The axiom after `depth` rounds of rewriting.
+    */
+    pub fn expand(&self, depth: usize) -> String {
+        (0..depth).fold(self.axiom.clone(), |word, _| {
+            word.chars()
+                .map(|c| match self.rules.get(&c) {
+                    Some(replacement) => replacement.clone(),
+                    None => c.to_string(),
+                })
+                .collect()
+        })
+    }
+
+    /**
+       Draw the axiom after `depth` rewrites. A `]` without a matching `[`
+       is ignored and branches still open at the end are closed.
+    */
+    pub fn build(&self, depth: usize) -> GroupContainer {
+        let mut turtle = Turtle {
+            position: Tuple::point(0.0, 0.0, 0.0),
+            heading: Tuple::vector(0.0, 1.0, 0.0),
+            left: Tuple::vector(-1.0, 0.0, 0.0),
+            up: Tuple::vector(0.0, 0.0, 1.0),
+            step: self.step,
+            radius: self.radius,
+        };
+        let mut branches = vec![(GroupContainer::default(), turtle)];
+
+        for symbol in self.expand(depth).chars() {
+            let group = &branches.last().unwrap().0;
+            match symbol {
+                'F' => {
+                    let end = turtle.position + turtle.heading * turtle.step;
+                    let mut branch = Cylinder::between(turtle.position, end, turtle.radius);
+                    branch.set_closed(true);
+                    branch.set_material(self.branch_material.clone());
+                    group.add_child(branch.into());
+                    turtle.position = end;
+                }
+                'f' => turtle.position = turtle.position + turtle.heading * turtle.step,
+                '@' => {
+                    let mut leaf = Sphere::new();
+                    leaf.set_transformation(
+                        Transformation::identity()
+                            .scale(self.leaf_radius, self.leaf_radius, self.leaf_radius)
+                            .translation(
+                                turtle.position.x(),
+                                turtle.position.y(),
+                                turtle.position.z(),
+                            ),
+                    );
+                    leaf.set_material(self.leaf_material.clone());
+                    group.add_child(leaf.into());
+                }
+                '+' => turtle.turn(self.angle),
+                '-' => turtle.turn(-self.angle),
+                '&' => turtle.pitch(self.angle),
+                '^' => turtle.pitch(-self.angle),
+                '\\' => turtle.roll(self.angle),
+                '/' => turtle.roll(-self.angle),
+                '|' => turtle.turn(PI),
+                '!' => {
+                    turtle.step *= self.taper;
+                    turtle.radius *= self.taper;
+                }
+                '[' => branches.push((GroupContainer::default(), turtle)),
+                ']' if branches.len() > 1 => {
+                    let (branch, saved) = branches.pop().unwrap();
+                    branches.last().unwrap().0.add_child(branch.into());
+                    turtle = saved;
+                }
+                _ => {}
+            }
+        }
+
+        while branches.len() > 1 {
+            let (branch, _) = branches.pop().unwrap();
+            branches.last().unwrap().0.add_child(branch.into());
+        }
+
+        branches.pop().unwrap().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{intersection::ray::Ray, util::eq_f64};
+
+    use super::*;
+
+    #[test]
+    fn rules_rewrite_every_symbol_at_once() {
+        let algae = LSystem::new("A").with_rule('A', "AB").with_rule('B', "A");
+
+        assert_eq!("A", algae.expand(0));
+        assert_eq!("ABAABABA", algae.expand(4));
+    }
+
+    #[test]
+    fn the_turtle_draws_branches_and_turns() {
+        let group = LSystem::new("F+F").with_angle(PI / 2.0).build(0);
+        let bounds = group.read().unwrap().bounds();
+
+        assert_eq!(2, group.read().unwrap().children().len());
+        assert!(eq_f64(-1.0, bounds.min().x()));
+        assert!(eq_f64(1.1, bounds.max().y()));
+        assert!(!group
+            .read()
+            .unwrap()
+            .intersects(&Ray::new(
+                Tuple::point(-0.5, 1.0, -5.0),
+                Tuple::vector(0.0, 0.0, 1.0)
+            ))
+            .is_empty());
+    }
+
+    #[test]
+    fn branches_are_groups_the_turtle_returns_from() {
+        let group = LSystem::new("[-F@]F@")
+            .with_angle(PI / 2.0)
+            .with_leaf_radius(0.5)
+            .build(0);
+        let children = group.read().unwrap().children();
+
+        assert_eq!(3, children.len());
+        assert_eq!("Group", children[0].read().unwrap().type_name());
+        assert!(eq_f64(
+            1.5,
+            children[0].read().unwrap().parent_space_bounds().max().x()
+        ));
+        assert_eq!(
+            Transformation::identity()
+                .scale(0.5, 0.5, 0.5)
+                .translation(0.0, 1.0, 0.0),
+            children[2].read().unwrap().transformation()
+        );
+    }
+
+    #[test]
+    fn tapering_shortens_and_thins_later_branches() {
+        let group = LSystem::new("F!F").with_taper(0.5).build(0);
+        let bounds = group.read().unwrap().bounds();
+
+        assert!(eq_f64(1.5, bounds.max().y()));
+        assert!(eq_f64(0.1, bounds.max().x()));
+    }
+}
@@ -1,31 +1,45 @@
 use std::{
+    fmt::Display,
     ops::{Index, IndexMut, Mul},
-    sync::{Arc, RwLock},
-    vec,
+    sync::OnceLock,
 };
 
-use crate::{tuple::Tuple, util::eq_f64};
+use crate::{
+    tuple::Tuple,
+    util::{eq_f64, format_number, PrecisionPolicy},
+};
+
+/**
+   Matrices are at most 4x4, so values live in a fixed array and matrices
+   can be built in const contexts.
+*/
+const MAX_ELEMENTS: usize = 16;
 
 #[derive(Debug, Clone)]
 pub struct Matrix {
     width: usize,
-    value: Vec<f64>,
-    det: Arc<RwLock<Option<f64>>>,
+    height: usize,
+    value: [f64; MAX_ELEMENTS],
+    det: OnceLock<f64>,
 }
 
 impl Matrix {
-    pub fn new(width: usize, height: usize) -> Self {
+    pub const fn new(width: usize, height: usize) -> Self {
+        assert!(width * height <= MAX_ELEMENTS);
         Matrix {
             width,
-            value: vec![f64::default(); width * height],
-            det: Arc::new(RwLock::new(None)),
+            height,
+            value: [0.0; MAX_ELEMENTS],
+            det: OnceLock::new(),
         }
     }
 
-    pub fn identity(dimension: usize) -> Self {
+    pub const fn identity(dimension: usize) -> Self {
         let mut m = Matrix::new(dimension, dimension);
-        for i in 0..dimension {
-            m[(i, i)] = 1.0
+        let mut i = 0;
+        while i < dimension {
+            m.value[i * dimension + i] = 1.0;
+            i += 1;
         }
         m
     }
@@ -35,51 +49,47 @@ impl Matrix {
     }
 
     pub fn height(&self) -> usize {
-        self.value.len() / self.width
+        self.height
     }
 
-    fn row(&self, row: usize) -> Vec<f64> {
-        self.value[row * self.width..row * self.width + self.width]
-            .iter()
-            .map(|v| *v)
-            .collect()
+    fn values(&self) -> &[f64] {
+        &self.value[..self.width * self.height]
     }
 
-    fn column(&self, column: usize) -> Vec<f64> {
-        self.value
+    pub fn row(&self, row: usize) -> &[f64] {
+        &self.value[row * self.width..row * self.width + self.width]
+    }
+
+    pub fn column(&self, column: usize) -> impl Iterator<Item = f64> + '_ {
+        self.values()
             .iter()
             .skip(column)
             .step_by(self.width)
-            .map(|v| *v)
-            .collect()
+            .copied()
+    }
+
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[f64]> {
+        self.values().chunks(self.width)
     }
 
     pub fn transpose(&self) -> Self {
-        Matrix {
-            width: self.height(),
-            value: (0..self.width)
-                .into_iter()
-                .map(|c| self.column(c))
-                .flat_map(|c| c.into_iter())
-                .collect(),
-            det: Arc::new(RwLock::new(None)),
+        let mut m = Matrix::new(self.height(), self.width());
+        for (i, v) in (0..self.width).flat_map(|c| self.column(c)).enumerate() {
+            m.value[i] = v;
         }
+        m
     }
 
     fn determinate(&self) -> f64 {
-        if let Some(det) = *self.det.read().unwrap() {
-            return det;
-        }
-        let mut det = 0.0;
-        if self.width() == 2 || self.height() == 2 {
-            det = self[(0, 0)] * self[(1, 1)] - self[(0, 1)] * self[(1, 0)];
-        } else {
-            for col in 0..self.width() {
-                det += self[(0, col)] * self.cofactor(0, col);
+        *self.det.get_or_init(|| {
+            if self.width() == 2 || self.height() == 2 {
+                self[(0, 0)] * self[(1, 1)] - self[(0, 1)] * self[(1, 0)]
+            } else {
+                (0..self.width())
+                    .map(|col| self[(0, col)] * self.cofactor(0, col))
+                    .sum()
             }
-        }
-        *self.det.write().unwrap() = Some(det);
-        det
+        })
     }
 
     fn sub_matrix(&self, row: usize, column: usize) -> Matrix {
@@ -112,12 +122,30 @@ impl Matrix {
         }
     }
 
-    fn is_invertible(&self) -> bool {
-        !eq_f64(0.0, self.determinate())
+    pub fn is_invertible(&self) -> bool {
+        self.is_invertible_with(PrecisionPolicy::default())
+    }
+
+    /**
+       Whether the determinant is too small to divide by under `precision`.
+       It is measured against the product of the lengths of the rows, the
+       largest it could be, so that a relative policy accepts a matrix that
+       shrinks everything a hundredfold as readily as the identity.
+    */
+    pub fn is_invertible_with(&self, precision: PrecisionPolicy) -> bool {
+        let scale = (0..self.height())
+            .map(|row| self.row(row).iter().map(|v| v * v).sum::<f64>().sqrt())
+            .product();
+
+        !precision.is_negligible(self.determinate(), scale)
     }
 
     pub fn inverse(&self) -> Option<Self> {
-        if !self.is_invertible() {
+        self.inverse_with(PrecisionPolicy::default())
+    }
+
+    pub fn inverse_with(&self, precision: PrecisionPolicy) -> Option<Self> {
+        if !self.is_invertible_with(precision) {
             return None;
         }
 
@@ -142,11 +170,11 @@ impl Default for Matrix {
 
 impl From<Vec<Vec<f64>>> for Matrix {
     fn from(value: Vec<Vec<f64>>) -> Self {
-        Matrix {
-            width: value[0].len(),
-            value: value.into_iter().flat_map(|r| r).collect(),
-            det: Arc::new(RwLock::new(None)),
+        let mut m = Matrix::new(value[0].len(), value.len());
+        for (i, v) in value.into_iter().flatten().enumerate() {
+            m.value[i] = v;
         }
+        m
     }
 }
 
@@ -160,18 +188,54 @@ impl Index<(usize, usize)> for Matrix {
 
 impl IndexMut<(usize, usize)> for Matrix {
     fn index_mut(&mut self, (y, x): (usize, usize)) -> &mut Self::Output {
+        self.det.take();
         &mut self.value[y * self.width + x]
     }
 }
 
+/**
+   One row per line between bars, with each column right-aligned to its
+   widest value. A precision such as `{:.3}` applies to every value.
+*/
+impl Display for Matrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cells = self
+            .values()
+            .iter()
+            .map(|&value| format_number(value, f))
+            .collect::<Vec<_>>();
+        let widths = (0..self.width)
+            .map(|column| {
+                (0..self.height)
+                    .map(|row| cells[row * self.width + column].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        for row in 0..self.height {
+            if row > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "|")?;
+            for (column, width) in widths.iter().enumerate() {
+                write!(f, " {:>width$}", cells[row * self.width + column])?;
+            }
+            write!(f, " |")?;
+        }
+
+        Ok(())
+    }
+}
+
 impl PartialEq for Matrix {
     fn eq(&self, other: &Self) -> bool {
         self.width == other.width
-            && self.value.len() == other.value.len()
+            && self.height == other.height
             && self
-                .value
+                .values()
                 .iter()
-                .zip(other.value.iter())
+                .zip(other.values().iter())
                 .all(|(l, r)| eq_f64(*l, *r))
     }
 }
@@ -185,8 +249,8 @@ impl Mul for &Matrix {
             for column in 0..self.width() {
                 m[(row, column)] = self
                     .row(row)
-                    .into_iter()
-                    .zip(rhs.column(column).into_iter())
+                    .iter()
+                    .zip(rhs.column(column))
                     .map(|(l, r)| l * r)
                     .sum()
             }
@@ -200,9 +264,8 @@ impl Mul<Tuple> for &Matrix {
 
     fn mul(self, rhs: Tuple) -> Self::Output {
         assert!(self.height() == 4 && self.width() == 4);
-        let vals = (0..self.height())
-            .into_iter()
-            .map(|i| self.row(i))
+        let vals = self
+            .iter_rows()
             .map(|r| Tuple::new(r[0], r[1], r[2], r[3]))
             .map(|t| t * rhs)
             .collect::<Vec<_>>();
@@ -354,10 +417,19 @@ mod tests {
             vec![5.0, 4.0, 3.0, 2.0],
         ]);
 
-        assert_eq!(vec![1.0, 5.0, 9.0, 5.0,], a.column(0));
-        assert_eq!(vec![2.0, 6.0, 8.0, 4.0,], a.column(1));
-        assert_eq!(vec![3.0, 7.0, 7.0, 3.0,], a.column(2));
-        assert_eq!(vec![4.0, 8.0, 6.0, 2.0,], a.column(3));
+        assert_eq!(vec![1.0, 5.0, 9.0, 5.0,], a.column(0).collect::<Vec<_>>());
+        assert_eq!(vec![2.0, 6.0, 8.0, 4.0,], a.column(1).collect::<Vec<_>>());
+        assert_eq!(vec![3.0, 7.0, 7.0, 3.0,], a.column(2).collect::<Vec<_>>());
+        assert_eq!(vec![4.0, 8.0, 6.0, 2.0,], a.column(3).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn iterating_over_the_rows_of_a_matrix() {
+        let a = Matrix::from(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+
+        let rows = a.iter_rows().collect::<Vec<_>>();
+
+        assert_eq!(rows, vec![&[1.0, 2.0, 3.0][..], &[4.0, 5.0, 6.0][..]]);
     }
 
     #[test]
@@ -529,6 +601,26 @@ mod tests {
         assert!(!a.is_invertible());
     }
 
+    #[test]
+    fn a_relative_policy_inverts_small_scalings() {
+        let mut a = Matrix::identity(4);
+        for i in 0..3 {
+            a[(i, i)] = 0.01;
+        }
+
+        assert!(!a.is_invertible());
+        assert!(a.is_invertible_with(PrecisionPolicy::Relative(1e-9)));
+        assert!(a.is_invertible_with(PrecisionPolicy::Ulps(4)));
+        assert!(eq_f64(
+            100.0,
+            a.inverse_with(PrecisionPolicy::Relative(1e-9)).unwrap()[(1, 1)]
+        ));
+
+        let mut flat = a.clone();
+        flat[(1, 1)] = 0.0;
+        assert!(!flat.is_invertible_with(PrecisionPolicy::Relative(1e-9)));
+    }
+
     #[test]
     fn calculating_the_inverse_of_a_matrix() {
         let a = Matrix::from(vec![
@@ -626,4 +718,15 @@ mod tests {
         let c = &a * &b;
         assert_eq!(a, &c * &b.inverse().unwrap());
     }
+
+    #[test]
+    fn displaying_a_matrix_aligns_its_columns() {
+        let a = Matrix::from(vec![vec![1.0, -20.0, 3.5], vec![100.0, 0.0, -0.0]]);
+
+        assert_eq!("|   1 -20 3.5 |\n| 100   0   0 |", a.to_string());
+        assert_eq!(
+            "|   1.0 -20.0 3.5 |\n| 100.0   0.0 0.0 |",
+            format!("{:.1}", a)
+        );
+    }
 }
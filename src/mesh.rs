@@ -0,0 +1,385 @@
+use crate::{
+    color::Color,
+    error::{RayTraceError, RayTraceResult},
+    intersection::ray::Ray,
+    obj::subdivide,
+    onb::Onb,
+    sampling::{square_to_cosine_hemisphere, Sampler, StratifiedSampler},
+    shape::{
+        group::GroupContainer, material::pattern::Pattern, smooth_triangle::SmoothTriangle,
+        ShapeContainer,
+    },
+    transformation::Transformation,
+    tuple::Tuple,
+    util::EPSILON,
+    world::World,
+};
+
+/**
+   An indexed triangle mesh. Unlike a group of triangles, the faces share
+   their vertices, so the surface can be reshaped and its normals rebuilt
+   before it is turned into shapes.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesh {
+    vertices: Vec<Tuple>,
+    faces: Vec<[usize; 3]>,
+}
+
+impl Mesh {
+    pub fn new(vertices: Vec<Tuple>, faces: Vec<[usize; 3]>) -> RayTraceResult<Self> {
+        if faces.iter().flatten().any(|&i| i >= vertices.len()) {
+            return Err(RayTraceError::InvalidFormat(
+                "mesh face refers to a missing vertex".to_string(),
+            ));
+        }
+
+        Ok(Self { vertices, faces })
+    }
+
+    /**
+       A flat square from -1 to 1 on the x and z axes, facing up, split into
+       `divisions` by `divisions` cells of two triangles each.
+    */
+    pub fn grid(divisions: usize) -> Self {
+        let divisions = divisions.max(1);
+        let side = divisions + 1;
+        let vertices = (0..side * side)
+            .map(|i| {
+                let x = (i % side) as f64 / divisions as f64 * 2.0 - 1.0;
+                let z = (i / side) as f64 / divisions as f64 * 2.0 - 1.0;
+                Tuple::point(x, 0.0, z)
+            })
+            .collect();
+        let faces = (0..divisions * divisions)
+            .flat_map(|cell| {
+                let corner = cell / divisions * side + cell % divisions;
+                let (a, b, c, d) = (corner, corner + 1, corner + side, corner + side + 1);
+                [[a, b, c], [b, d, c]]
+            })
+            .collect();
+
+        Self { vertices, faces }
+    }
+
+    pub fn vertices(&self) -> &[Tuple] {
+        &self.vertices
+    }
+
+    pub fn faces(&self) -> &[[usize; 3]] {
+        &self.faces
+    }
+
+    /**
+       The face normal scaled by twice the face's area, wound the same way
+       as `Triangle`'s normal.
+    */
+    fn weighted_face_normal(&self, [p1, p2, p3]: [usize; 3]) -> Tuple {
+        let e1 = self.vertices[p2] - self.vertices[p1];
+        let e2 = self.vertices[p3] - self.vertices[p1];
+
+        e2 ^ e1
+    }
+
+    /**
+       A normal for each vertex, averaged from the faces around it with
+       larger faces counting for more. Vertices not used by any face point
+       up.
+    */
+    pub fn vertex_normals(&self) -> Vec<Tuple> {
+        let mut normals = vec![Tuple::vector(0.0, 0.0, 0.0); self.vertices.len()];
+
+        for &face in &self.faces {
+            let normal = self.weighted_face_normal(face);
+            for i in face {
+                normals[i] = normals[i] + normal;
+            }
+        }
+
+        normals
+            .into_iter()
+            .map(|n| {
+                if n.magnitude() > 0.0 {
+                    n.normalize()
+                } else {
+                    Tuple::vector(0.0, 1.0, 0.0)
+                }
+            })
+            .collect()
+    }
+
+    /**
+       Push every vertex along its normal by `amount` times the brightness
+       (the mean of the color channels) of `pattern` at that vertex. The
+       normals of the new mesh follow the displaced surface.
+    */
+    pub fn displace(&self, pattern: &dyn Pattern, amount: f64) -> Self {
+        let vertices = self
+            .vertices
+            .iter()
+            .zip(self.vertex_normals())
+            .map(|(&vertex, normal)| {
+                let color = pattern.color_at_world(vertex);
+                let height = (color.red() + color.green() + color.blue()) / 3.0;
+                vertex + normal * height * amount
+            })
+            .collect();
+
+        Self {
+            vertices,
+            faces: self.faces.clone(),
+        }
+    }
+
+    /**
+       Smooth triangles for every face, grouped as for OBJ files with at most
+       `max_triangles_per_group` per group.
+    */
+    pub fn as_group(&self, max_triangles_per_group: usize) -> GroupContainer {
+        self.build_group(max_triangles_per_group, None)
+    }
+
+    /**
+       Like `as_group`, but with a color for each vertex blended across the
+       faces in place of the material's color.
+    */
+    pub fn as_colored_group(
+        &self,
+        max_triangles_per_group: usize,
+        colors: &[Color],
+    ) -> RayTraceResult<GroupContainer> {
+        if colors.len() != self.vertices.len() {
+            return Err(RayTraceError::InvalidFormat(format!(
+                "expected {} vertex colors but got {}",
+                self.vertices.len(),
+                colors.len()
+            )));
+        }
+
+        Ok(self.build_group(max_triangles_per_group, Some(colors)))
+    }
+
+    fn build_group(
+        &self,
+        max_triangles_per_group: usize,
+        colors: Option<&[Color]>,
+    ) -> GroupContainer {
+        let normals = self.vertex_normals();
+        let triangles = self
+            .faces
+            .iter()
+            .map(|&[a, b, c]| {
+                let triangle = SmoothTriangle::new(
+                    self.vertices[a],
+                    self.vertices[b],
+                    self.vertices[c],
+                    normals[a],
+                    normals[b],
+                    normals[c],
+                );
+                match colors {
+                    Some(colors) => triangle.with_vertex_colors(colors[a], colors[b], colors[c]),
+                    None => triangle,
+                }
+                .into()
+            })
+            .collect::<Vec<ShapeContainer>>();
+
+        subdivide(triangles, max_triangles_per_group)
+    }
+
+    /**
+       Bake ambient occlusion for each vertex: the share of `samples` cosine
+       weighted rays over the vertex normal that escape `world` without
+       hitting anything closer than `max_distance`. Multiply the result into
+       the mesh's color with `as_colored_group` to darken creases without
+       tracing occlusion rays while rendering. `transformation` places the
+       mesh in `world`, as it is given to the group built from it. Include
+       the mesh in `world` for it to shadow itself.
+    */
+    pub fn bake_ambient_occlusion(
+        &self,
+        world: &World,
+        transformation: &Transformation,
+        samples: usize,
+        max_distance: f64,
+    ) -> Vec<f64> {
+        let samples = samples.max(1);
+        let normal_transformation = transformation
+            .inverse()
+            .expect("Could not find inverse")
+            .transpose();
+
+        self.vertices
+            .iter()
+            .zip(self.vertex_normals())
+            .map(|(&vertex, normal)| {
+                let vertex = transformation * vertex;
+                let mut normal = &normal_transformation * normal;
+                normal.as_vector();
+                let normal = normal.normalize();
+                let onb = Onb::from_normal(normal);
+                let origin = vertex + normal * EPSILON;
+                let open = StratifiedSampler::default()
+                    .samples(samples)
+                    .into_iter()
+                    .filter(|&sample| {
+                        let direction = onb.to_world(square_to_cosine_hemisphere(sample));
+                        !world
+                            .intersects(&Ray::new(origin, direction))
+                            .iter()
+                            .any(|i| i.t() > EPSILON && i.t() <= max_distance)
+                    })
+                    .count();
+
+                open as f64 / samples as f64
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{
+        camera::Camera,
+        color::Colors,
+        point_light::PointLight,
+        shape::{
+            material::{
+                pattern::{solid::SolidPattern, TestPattern},
+                Material,
+            },
+            plane::Plane,
+            Shape,
+        },
+        stats::SceneStats,
+        util::eq_f64,
+    };
+
+    use super::*;
+
+    #[test]
+    fn faces_must_refer_to_existing_vertices() {
+        let vertices = vec![Tuple::origin(), Tuple::point(1.0, 0.0, 0.0)];
+
+        assert!(Mesh::new(vertices, vec![[0, 1, 2]]).is_err());
+    }
+
+    #[test]
+    fn a_grid_is_flat_and_faces_up() {
+        let mesh = Mesh::grid(2);
+
+        assert_eq!(9, mesh.vertices().len());
+        assert_eq!(8, mesh.faces().len());
+        for normal in mesh.vertex_normals() {
+            assert_eq!(Tuple::vector(0.0, 1.0, 0.0), normal);
+        }
+    }
+
+    #[test]
+    fn displacing_by_a_solid_pattern_lifts_the_whole_surface() {
+        let pattern = SolidPattern::new(Color::new(0.5, 0.5, 0.5));
+
+        let mesh = Mesh::grid(2).displace(&pattern, 2.0);
+
+        for vertex in mesh.vertices() {
+            assert!(eq_f64(1.0, vertex.y()));
+        }
+        assert_eq!(Tuple::vector(0.0, 1.0, 0.0), mesh.vertex_normals()[4]);
+    }
+
+    #[test]
+    fn displacing_by_a_varying_pattern_tilts_the_normals() {
+        let mesh = Mesh::grid(2).displace(&TestPattern::default(), 1.0);
+
+        assert_eq!(Tuple::point(0.0, 0.0, 0.0), mesh.vertices()[4]);
+        assert!(eq_f64(2.0 / 3.0, mesh.vertices()[8].y()));
+
+        let normal = mesh.vertex_normals()[4];
+        assert!(eq_f64(1.0, normal.magnitude()));
+        assert!(normal.x() < 0.0 && normal.z() < 0.0 && normal.y() > 0.0);
+    }
+
+    #[test]
+    fn a_mesh_becomes_a_group_of_smooth_triangles() {
+        let group = Mesh::grid(4).as_group(8);
+        let stats = SceneStats::new(&[group.into()], &[]);
+
+        assert_eq!(32, stats.count("SmoothTriangle"));
+        assert!(stats.bvh_nodes() > 1);
+    }
+
+    #[test]
+    fn ambient_occlusion_counts_rays_blocked_within_range() {
+        let mesh = Mesh::grid(2);
+        let mut world = World::new();
+        world.add_shape(mesh.as_group(8).into());
+
+        assert!(mesh
+            .bake_ambient_occlusion(&world, &Transformation::identity(), 16, 10.0)
+            .iter()
+            .all(|&ao| eq_f64(1.0, ao)));
+
+        let mut ceiling = Plane::new();
+        ceiling.set_transformation(Transformation::identity().translation(0.0, 1.0, 0.0));
+        world.add_shape(ceiling.into());
+
+        assert!(mesh
+            .bake_ambient_occlusion(&world, &Transformation::identity(), 16, 0.5)
+            .iter()
+            .all(|&ao| eq_f64(1.0, ao)));
+        assert!(mesh
+            .bake_ambient_occlusion(&world, &Transformation::identity(), 16, 1000.0)
+            .iter()
+            .all(|&ao| eq_f64(0.0, ao)));
+    }
+
+    #[test]
+    fn baked_occlusion_becomes_vertex_colors() {
+        let mesh = Mesh::grid(1);
+        let colors = vec![Color::new(0.5, 0.5, 0.5); 4];
+
+        assert!(mesh.as_colored_group(8, &colors).is_ok());
+        assert!(mesh.as_colored_group(8, &colors[..3]).is_err());
+    }
+
+    #[test]
+    fn occlusion_is_baked_and_rendered_where_the_mesh_is_placed() {
+        let mesh = Mesh::grid(2);
+        let placement = Transformation::identity().translation(0.0, 2.0, 0.0);
+        let mut world = World::new();
+        let mut ceiling = Plane::new();
+        ceiling.set_transformation(Transformation::identity().translation(0.0, 1.0, 0.0));
+        world.add_shape(ceiling.into());
+
+        let occlusion = mesh.bake_ambient_occlusion(&world, &placement, 16, 1000.0);
+        let colors = occlusion
+            .iter()
+            .map(|&ao| Color::new(ao, ao, ao))
+            .collect::<Vec<_>>();
+        let group = mesh.as_colored_group(8, &colors).unwrap();
+        group.write().unwrap().set_transformation(placement);
+        group.set_material_recursive(
+            Material::new()
+                .with_ambient(1.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0),
+        );
+        world.add_shape(group.into());
+        world.add_light(PointLight::new(
+            Tuple::point(0.0, 10.0, 0.0),
+            Colors::White.into(),
+        ));
+        let mut camera = Camera::new(5, 5, PI / 4.0);
+        camera.set_transformation(Transformation::view(
+            Tuple::point(0.0, 5.0, 0.0),
+            Tuple::point(0.0, 2.0, 0.0),
+            Tuple::vector(0.0, 0.0, 1.0),
+        ));
+
+        assert!(occlusion.iter().all(|&ao| eq_f64(1.0, ao)));
+        assert_eq!(Color::from(Colors::White), camera.render(&world)[(2, 2)]);
+    }
+}
@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+/**
+   Details of how an image was rendered, written into saved images as PPM
+   comments or PNG text chunks.
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderMetadata {
+    scene: Option<String>,
+    seed: Option<u64>,
+    samples: Option<usize>,
+    duration: Option<Duration>,
+}
+
+impl RenderMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_scene(mut self, scene: &str) -> Self {
+        self.scene = Some(scene.to_string());
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = Some(samples);
+        self
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn scene(&self) -> Option<&str> {
+        self.scene.as_deref()
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn samples(&self) -> Option<usize> {
+        self.samples
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /**
+       A key and value for each field that is set, plus the resolution.
+    */
+    pub fn fields(&self, width: usize, height: usize) -> Vec<(&'static str, String)> {
+        let mut fields = vec![];
+
+        if let Some(scene) = &self.scene {
+            fields.push(("scene", scene.clone()));
+        }
+        fields.push(("resolution", format!("{width}x{height}")));
+        if let Some(seed) = self.seed {
+            fields.push(("seed", seed.to_string()));
+        }
+        if let Some(samples) = self.samples {
+            fields.push(("samples", samples.to_string()));
+        }
+        if let Some(duration) = self.duration {
+            fields.push(("duration", format!("{:.3}s", duration.as_secs_f64())));
+        }
+
+        fields
+    }
+
+    /**
+       One `key: value` line for each of `fields`.
+    */
+    pub fn lines(&self, width: usize, height: usize) -> Vec<String> {
+        self.fields(width, height)
+            .into_iter()
+            .map(|(key, value)| format!("{key}: {value}"))
+            .collect()
+    }
+}
+
+/**
+   Fill a printf style frame number placeholder such as `%04d` or `%d` in
+   `pattern`. Patterns without a placeholder get `_%04d` appended.
+*/
+pub fn frame_name(pattern: &str, frame: usize) -> String {
+    let placeholder = pattern.find('%').and_then(|start| {
+        let end = start + 1 + pattern[start + 1..].find('d')?;
+        let digits = &pattern[start + 1..end];
+        if !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        Some((start, end, digits.parse::<usize>().unwrap_or(0)))
+    });
+
+    match placeholder {
+        Some((start, end, width)) => format!(
+            "{}{frame:0width$}{}",
+            &pattern[..start],
+            &pattern[end + 1..]
+        ),
+        None => format!("{pattern}_{frame:04}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filling_frame_number_placeholders() {
+        assert_eq!("orbit_0007.ppm", frame_name("orbit_%04d.ppm", 7));
+        assert_eq!("orbit_12", frame_name("orbit_%d", 12));
+        assert_eq!("orbit_12345", frame_name("orbit_%03d", 12345));
+        assert_eq!("orbit_0003", frame_name("orbit", 3));
+    }
+
+    #[test]
+    fn metadata_lines_skip_unset_fields() {
+        let metadata = RenderMetadata::new()
+            .with_scene("cover")
+            .with_samples(16)
+            .with_duration(Duration::from_millis(1500));
+
+        assert_eq!(
+            vec![
+                "scene: cover",
+                "resolution: 640x480",
+                "samples: 16",
+                "duration: 1.500s"
+            ],
+            metadata.lines(640, 480)
+        );
+    }
+}
@@ -7,32 +7,109 @@ use std::{
 use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::{
+    color::Color,
     error::RayTraceResult,
     shape::{
+        bounded_box::BoundedBox,
         group::{Group, GroupContainer},
         smooth_triangle::SmoothTriangle,
         triangle::Triangle,
         ShapeContainer,
     },
     tuple::Tuple,
+    util::EPSILON,
 };
 
 pub struct OBJParser {
     groups: HashMap<String, Vec<ShapeContainer>>,
     default_group: GroupContainer,
+    welded_vertices: usize,
+    degenerate_triangles: usize,
 }
 
-fn fan_triangulation(verticies: Vec<Tuple>, normals: Vec<Tuple>) -> Vec<ShapeContainer> {
+/**
+   Snaps each new vertex onto an earlier one within `tolerance`, using a grid
+   of `tolerance` sized cells so only neighbouring cells are searched.
+*/
+struct VertexWelder {
+    tolerance: f64,
+    cells: HashMap<(i64, i64, i64), Vec<Tuple>>,
+}
+
+impl VertexWelder {
+    fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance: tolerance.max(f64::MIN_POSITIVE),
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, vertex: Tuple) -> (i64, i64, i64) {
+        (
+            (vertex.x() / self.tolerance).floor() as i64,
+            (vertex.y() / self.tolerance).floor() as i64,
+            (vertex.z() / self.tolerance).floor() as i64,
+        )
+    }
+
+    /**
+       The vertex to use in place of `vertex`, and whether it was welded.
+    */
+    fn weld(&mut self, vertex: Tuple) -> (Tuple, bool) {
+        let (x, y, z) = self.cell(vertex);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let existing = self.cells.get(&(x + dx, y + dy, z + dz)).and_then(|c| {
+                        c.iter()
+                            .find(|&&v| (v - vertex).magnitude() <= self.tolerance)
+                    });
+                    if let Some(&existing) = existing {
+                        return (existing, true);
+                    }
+                }
+            }
+        }
+
+        self.cells.entry((x, y, z)).or_default().push(vertex);
+        (vertex, false)
+    }
+}
+
+/**
+   A triangle whose corners are (nearly) collinear has no usable normal.
+*/
+fn is_degenerate(p1: Tuple, p2: Tuple, p3: Tuple) -> bool {
+    let e1 = p2 - p1;
+    let e2 = p3 - p1;
+
+    (e1 ^ e2).magnitude() <= EPSILON * e1.magnitude() * e2.magnitude()
+}
+
+pub(crate) fn fan_triangulation(
+    verticies: Vec<Tuple>,
+    normals: Vec<Tuple>,
+    colors: Vec<Option<Color>>,
+) -> Vec<ShapeContainer> {
     let mut triangles = vec![];
 
-    if normals.is_empty() {
-        for i in 1..(verticies.len() - 1) {
-            let tri = Triangle::new(verticies[0], verticies[i], verticies[i + 1]);
-            triangles.push(tri.into());
+    for i in 1..verticies.len().saturating_sub(1) {
+        if is_degenerate(verticies[0], verticies[i], verticies[i + 1]) {
+            continue;
         }
-    } else {
-        for i in 1..(verticies.len() - 1) {
-            let tri = SmoothTriangle::new(
+        let corner_colors = match (colors.first(), colors.get(i), colors.get(i + 1)) {
+            (Some(Some(c1)), Some(Some(c2)), Some(Some(c3))) => Some((*c1, *c2, *c3)),
+            _ => None,
+        };
+
+        if normals.is_empty() {
+            let mut tri = Triangle::new(verticies[0], verticies[i], verticies[i + 1]);
+            if let Some((c1, c2, c3)) = corner_colors {
+                tri = tri.with_vertex_colors(c1, c2, c3);
+            }
+            triangles.push(tri.into());
+        } else {
+            let mut tri = SmoothTriangle::new(
                 verticies[0],
                 verticies[i],
                 verticies[i + 1],
@@ -40,6 +117,9 @@ fn fan_triangulation(verticies: Vec<Tuple>, normals: Vec<Tuple>) -> Vec<ShapeCon
                 normals[i],
                 normals[i + 1],
             );
+            if let Some((c1, c2, c3)) = corner_colors {
+                tri = tri.with_vertex_colors(c1, c2, c3);
+            }
             triangles.push(tri.into());
         }
     }
@@ -47,16 +127,45 @@ fn fan_triangulation(verticies: Vec<Tuple>, normals: Vec<Tuple>) -> Vec<ShapeCon
 }
 
 impl OBJParser {
+    /**
+       Parse an OBJ file, welding vertices closer together than `EPSILON`.
+    */
     pub fn parse_file<T: AsRef<Path> + Clone>(path: T) -> RayTraceResult<Self> {
+        Self::parse_file_with_tolerance(path, EPSILON)
+    }
+
+    /**
+       Parse an OBJ file, welding vertices no further apart than
+       `weld_tolerance` and dropping triangles left with no area.
+    */
+    pub fn parse_file_with_tolerance<T: AsRef<Path> + Clone>(
+        path: T,
+        weld_tolerance: f64,
+    ) -> RayTraceResult<Self> {
         let file_string = fs::read_to_string(path.clone())?;
+        let pb = ProgressBar::new(file_string.lines().count() as u64);
+        pb.set_style(ProgressStyle::with_template("{wide_bar} {percent}% {eta} {msg}").unwrap());
+
+        let parser = Self::parse(&file_string, weld_tolerance, &pb)?;
+        pb.finish_with_message(format!(
+            "Finished importing {} (welded {} vertices, dropped {} degenerate triangles)",
+            path.as_ref().to_string_lossy(),
+            parser.welded_vertices,
+            parser.degenerate_triangles
+        ));
+        Ok(parser)
+    }
+
+    fn parse(file_string: &str, weld_tolerance: f64, pb: &ProgressBar) -> RayTraceResult<Self> {
+        let mut welder = VertexWelder::new(weld_tolerance);
+        let mut welded_vertices = 0;
+        let mut degenerate_triangles = 0;
         let mut verticies = vec![];
+        let mut vertex_colors = vec![];
         let mut normals = vec![];
         let default_group = GroupContainer::from(Group::new());
         let mut current_group: Option<String> = None;
         let mut groups: HashMap<String, Vec<ShapeContainer>> = HashMap::new();
-        let lines = file_string.lines().collect::<Vec<_>>();
-        let pb = ProgressBar::new(lines.len() as u64);
-        pb.set_style(ProgressStyle::with_template("{wide_bar} {percent}% {eta} {msg}").unwrap());
 
         for line in file_string.lines() {
             pb.inc(1);
@@ -68,7 +177,20 @@ impl OBJParser {
                     let input: Vec<_> = line[2..].split_whitespace().collect();
                     let vertex =
                         Tuple::point(input[0].parse()?, input[1].parse()?, input[2].parse()?);
+                    let (vertex, welded) = welder.weld(vertex);
+                    if welded {
+                        welded_vertices += 1;
+                    }
                     verticies.push(vertex);
+                    vertex_colors.push(if input.len() >= 6 {
+                        Some(Color::new(
+                            input[3].parse()?,
+                            input[4].parse()?,
+                            input[5].parse()?,
+                        ))
+                    } else {
+                        None
+                    });
                 }
                 "vn" => {
                     let input: Vec<_> = line[3..].split_whitespace().collect();
@@ -77,26 +199,33 @@ impl OBJParser {
                     normals.push(vertex);
                 }
                 "f " => {
+                    let corners = line[2..].split_whitespace().count();
                     let mut triangles = if line.contains("/") {
-                        let (verticies, normals) = line[2..]
+                        let indices = line[2..]
                             .split_whitespace()
                             .map(|l| {
                                 l.split("/")
                                     .map(|s| s.parse::<usize>().unwrap_or_default())
                                     .collect::<Vec<_>>()
                             })
+                            .collect::<Vec<_>>();
+                        let colors = indices.iter().map(|i| vertex_colors[i[0] - 1]).collect();
+                        let (verticies, normals) = indices
+                            .into_iter()
                             .map(|i| (verticies[i[0] - 1], normals[i[2] - 1]))
                             .unzip();
 
-                        fan_triangulation(verticies, normals)
+                        fan_triangulation(verticies, normals, colors)
                     } else {
-                        let verticies: Vec<_> = line[2..]
+                        let indices: Vec<_> = line[2..]
                             .split_whitespace()
                             .map(|l| l.parse::<usize>().unwrap_or_default())
-                            .map(|i| verticies[i - 1])
                             .collect();
-                        fan_triangulation(verticies, vec![])
+                        let colors = indices.iter().map(|&i| vertex_colors[i - 1]).collect();
+                        let verticies = indices.into_iter().map(|i| verticies[i - 1]).collect();
+                        fan_triangulation(verticies, vec![], colors)
                     };
+                    degenerate_triangles += corners.saturating_sub(2) - triangles.len();
                     if let Some(ref current_group) = current_group {
                         groups
                             .entry(current_group.clone())
@@ -114,17 +243,29 @@ impl OBJParser {
                 _ => {}
             }
         }
-        pb.finish_with_message(format!(
-            "Finished importing {}",
-            path.as_ref().to_string_lossy()
-        ));
         Ok(Self {
             // verticies,
             groups,
             default_group,
+            welded_vertices,
+            degenerate_triangles,
         })
     }
 
+    /**
+       How many vertices were merged into an earlier one during import.
+    */
+    pub fn welded_vertices(&self) -> usize {
+        self.welded_vertices
+    }
+
+    /**
+       How many zero area triangles were dropped during import.
+    */
+    pub fn degenerate_triangles(&self) -> usize {
+        self.degenerate_triangles
+    }
+
     pub fn default_group(&self) -> GroupContainer {
         self.default_group.clone()
     }
@@ -133,18 +274,71 @@ impl OBJParser {
         &self.groups[&key]
     }
 
-    pub fn as_group(self) -> GroupContainer {
+    /**
+       Collect every triangle into one group. Triangles are split into nested
+       subgroups of at most `max_triangles_per_group` so rays can skip whole
+       regions of the model; smaller values render faster at the cost of more
+       groups. The OBJ groups themselves are then divided by their bounds
+       with `GroupContainer::divide`, so a model made of many small groups
+       gets a hierarchy too. `usize::MAX` keeps one group per OBJ group.
+    */
+    pub fn as_group(self, max_triangles_per_group: usize) -> GroupContainer {
+        let group = subdivide(
+            self.default_group.read().unwrap().children(),
+            max_triangles_per_group,
+        );
         for (_, triangles) in self.groups {
-            let inner_group = GroupContainer::from(Group::new());
-            for triangle in triangles {
-                inner_group.add_child(triangle.into());
-            }
-            self.default_group.add_child(inner_group.into());
+            group.add_child(subdivide(triangles, max_triangles_per_group).into());
         }
-        self.default_group
+        group.divide(max_triangles_per_group);
+        group
     }
 }
 
+/**
+   Split `shapes` in half along the longest axis of their centers until
+   each group holds no more than `max` of them.
+*/
+pub(crate) fn subdivide(shapes: Vec<ShapeContainer>, max: usize) -> GroupContainer {
+    let group = GroupContainer::from(Group::new());
+
+    if shapes.len() <= max.max(1) {
+        for shape in shapes {
+            group.add_child(shape);
+        }
+        return group;
+    }
+
+    let mut centers = shapes
+        .into_iter()
+        .map(|shape| {
+            let center = shape.read().unwrap().parent_space_bounds().center();
+            (center, shape)
+        })
+        .collect::<Vec<_>>();
+    let mut bounds = BoundedBox::empty();
+    for (center, _) in &centers {
+        bounds.add_point(*center);
+    }
+    let extent = bounds.max() - bounds.min();
+    let axis: fn(&Tuple) -> f64 = if extent.x() >= extent.y() && extent.x() >= extent.z() {
+        Tuple::x
+    } else if extent.y() >= extent.z() {
+        Tuple::y
+    } else {
+        Tuple::z
+    };
+
+    centers.sort_by(|(a, _), (b, _)| axis(a).total_cmp(&axis(b)));
+    let right = centers.split_off(centers.len() / 2);
+    for half in [centers, right] {
+        let half = half.into_iter().map(|(_, shape)| shape).collect();
+        group.add_child(subdivide(half, max).into());
+    }
+
+    group
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -199,4 +393,108 @@ mod tests {
     //     assert_eq!(t3.p2(), v4);
     //     assert_eq!(t3.p3(), v5);
     // }
+
+    use crate::shape::Shape;
+
+    use super::*;
+
+    fn largest_group(shape: &dyn Shape) -> usize {
+        let children = shape.child_shapes();
+        let leaves = children
+            .iter()
+            .filter(|c| c.read().unwrap().type_name() != "Group")
+            .count();
+
+        children
+            .iter()
+            .map(|c| largest_group(&*c.read().unwrap()))
+            .fold(leaves, usize::max)
+    }
+
+    #[test]
+    fn duplicate_vertices_are_welded_and_degenerate_triangles_dropped() {
+        let obj = "\
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 0.000001 0
+v 0 0 0
+f 1 2 3
+f 2 3 4
+f 2 5 3
+";
+        let parser = OBJParser::parse(obj, EPSILON, &ProgressBar::hidden()).unwrap();
+
+        assert_eq!(1, parser.welded_vertices());
+        assert_eq!(2, parser.degenerate_triangles());
+        assert_eq!(1, parser.default_group().read().unwrap().children().len());
+    }
+
+    #[test]
+    fn the_weld_tolerance_decides_which_vertices_merge() {
+        let obj = "\
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 0.001 0
+f 1 2 3
+f 2 3 4
+";
+        let parser = OBJParser::parse(obj, EPSILON, &ProgressBar::hidden()).unwrap();
+
+        assert_eq!(0, parser.welded_vertices());
+        assert_eq!(2, parser.default_group().read().unwrap().children().len());
+
+        let parser = OBJParser::parse(obj, 0.01, &ProgressBar::hidden()).unwrap();
+
+        assert_eq!(1, parser.welded_vertices());
+        assert_eq!(1, parser.degenerate_triangles());
+        assert_eq!(1, parser.default_group().read().unwrap().children().len());
+    }
+
+    #[test]
+    fn vertex_colors_are_read_from_extended_vertex_lines() {
+        let obj = "\
+v -1 1 0 1 0 0
+v -1 0 0 0 1 0
+v 1 0 0 0 0 1
+v 1 1 0
+f 1 2 3
+f 1 3 4
+";
+        let parser = OBJParser::parse(obj, EPSILON, &ProgressBar::hidden()).unwrap();
+        let children = parser.default_group().read().unwrap().children();
+        let colored = children[0].read().unwrap();
+        let plain = children[1].read().unwrap();
+
+        assert_eq!(
+            Some(Color::new(0.0, 1.0, 0.0)),
+            colored.vertex_color(colored.id(), 1.0, 0.0)
+        );
+        assert_eq!(None, plain.vertex_color(plain.id(), 1.0, 0.0));
+    }
+
+    #[test]
+    fn subdividing_limits_the_triangles_per_group() {
+        let triangles = (0..10)
+            .map(|i| {
+                let x = i as f64;
+                Triangle::new(
+                    Tuple::point(x, 1.0, 0.0),
+                    Tuple::point(x - 0.5, 0.0, 0.0),
+                    Tuple::point(x + 0.5, 0.0, 0.0),
+                )
+                .into()
+            })
+            .collect::<Vec<ShapeContainer>>();
+
+        let group = subdivide(triangles.clone(), 3);
+        let stats = crate::stats::SceneStats::new(&[group.clone().into()], &[]);
+
+        assert_eq!(10, stats.triangles());
+        assert!(largest_group(&*group.read().unwrap()) <= 3);
+
+        let group = subdivide(triangles, usize::MAX);
+        assert_eq!(10, group.read().unwrap().children().len());
+    }
 }
@@ -0,0 +1,88 @@
+use crate::tuple::Tuple;
+
+/**
+   An orthonormal basis built around a normal, used to turn directions
+   sampled around the local z axis into world space directions.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Onb {
+    u: Tuple,
+    v: Tuple,
+    w: Tuple,
+}
+
+impl Onb {
+    pub fn from_normal(normal: Tuple) -> Self {
+        let w = Tuple::vector(normal.x(), normal.y(), normal.z()).normalize();
+        let a = if w.x().abs() > 0.9 {
+            Tuple::vector(0.0, 1.0, 0.0)
+        } else {
+            Tuple::vector(1.0, 0.0, 0.0)
+        };
+        let v = (w ^ a).normalize();
+        let u = w ^ v;
+
+        Self { u, v, w }
+    }
+
+    pub fn u(&self) -> Tuple {
+        self.u
+    }
+
+    pub fn v(&self) -> Tuple {
+        self.v
+    }
+
+    pub fn w(&self) -> Tuple {
+        self.w
+    }
+
+    pub fn to_world(&self, local: Tuple) -> Tuple {
+        self.u * local.x() + self.v * local.y() + self.w * local.z()
+    }
+
+    pub fn to_local(&self, world: Tuple) -> Tuple {
+        Tuple::vector(world * self.u, world * self.v, world * self.w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::eq_f64;
+
+    use super::*;
+
+    #[test]
+    fn a_basis_built_from_a_normal_is_orthonormal() {
+        for normal in [
+            Tuple::vector(0.0, 1.0, 0.0),
+            Tuple::vector(1.0, 0.0, 0.0),
+            Tuple::vector(-1.0, 2.0, 3.0),
+        ] {
+            let onb = Onb::from_normal(normal);
+
+            assert_eq!(normal.normalize(), onb.w());
+            assert!(eq_f64(1.0, onb.u().magnitude()));
+            assert!(eq_f64(1.0, onb.v().magnitude()));
+            assert!(eq_f64(0.0, onb.u() * onb.v()));
+            assert!(eq_f64(0.0, onb.u() * onb.w()));
+            assert!(eq_f64(0.0, onb.v() * onb.w()));
+        }
+    }
+
+    #[test]
+    fn the_local_z_axis_maps_to_the_normal() {
+        let normal = Tuple::vector(0.0, 0.0, -1.0);
+        let onb = Onb::from_normal(normal);
+
+        assert_eq!(normal, onb.to_world(Tuple::vector(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn converting_to_world_and_back_is_lossless() {
+        let onb = Onb::from_normal(Tuple::vector(1.0, 1.0, 0.0));
+        let local = Tuple::vector(0.3, -0.4, 0.5);
+
+        assert_eq!(local, onb.to_local(onb.to_world(local)));
+    }
+}
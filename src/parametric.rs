@@ -0,0 +1,151 @@
+use std::ops::RangeInclusive;
+
+use crate::{mesh::Mesh, shape::group::GroupContainer, tuple::Tuple};
+
+/**
+   A surface traced out by a function of two parameters, such as a torus or
+   a sea shell, rendered by sampling it on a grid and joining the samples
+   into a mesh. Faces are wound so that for `(u, v) -> (u, 0, v)` they face
+   up.
+*/
+pub struct ParametricSurface<F: Fn(f64, f64) -> Tuple> {
+    function: F,
+    u_range: RangeInclusive<f64>,
+    v_range: RangeInclusive<f64>,
+    tessellation: usize,
+}
+
+impl<F: Fn(f64, f64) -> Tuple> ParametricSurface<F> {
+    /**
+       Sample `function` at `tessellation + 1` evenly spaced values across
+       each of `u_range` and `v_range`, both ends included.
+    */
+    pub fn new(
+        function: F,
+        u_range: RangeInclusive<f64>,
+        v_range: RangeInclusive<f64>,
+        tessellation: usize,
+    ) -> Self {
+        Self {
+            function,
+            u_range,
+            v_range,
+            tessellation: tessellation.max(1),
+        }
+    }
+
+    pub fn point_at(&self, u: f64, v: f64) -> Tuple {
+        (self.function)(u, v)
+    }
+
+    /**
+       Samples on the edges of the grid that land on one another, such as
+       along the seam of a torus or at the poles of a sphere, share a vertex
+       so smooth normals carry across, and faces squashed to a line by the
+       sharing are dropped.
+    */
+    pub fn to_mesh(&self) -> Mesh {
+        let side = self.tessellation + 1;
+        let step = |range: &RangeInclusive<f64>, i: usize| {
+            range.start() + (range.end() - range.start()) * i as f64 / self.tessellation as f64
+        };
+
+        let mut vertices: Vec<Tuple> = vec![];
+        let mut edge = vec![];
+        let mut indices = vec![];
+        for i in 0..side * side {
+            let (column, row) = (i % side, i / side);
+            let point = self.point_at(step(&self.u_range, column), step(&self.v_range, row));
+            let on_edge = column % self.tessellation == 0 || row % self.tessellation == 0;
+            if on_edge {
+                if let Some(&shared) = edge.iter().find(|&&j| vertices[j] == point) {
+                    indices.push(shared);
+                    continue;
+                }
+                edge.push(vertices.len());
+            }
+            indices.push(vertices.len());
+            vertices.push(point);
+        }
+
+        let faces = Mesh::grid(self.tessellation)
+            .faces()
+            .iter()
+            .map(|face| face.map(|i| indices[i]))
+            .filter(|[a, b, c]| a != b && b != c && c != a)
+            .collect();
+
+        Mesh::new(vertices, faces).unwrap()
+    }
+
+    /**
+       The tessellated surface as smooth triangles, at most
+       `max_triangles_per_group` to a group.
+    */
+    pub fn as_group(&self, max_triangles_per_group: usize) -> GroupContainer {
+        self.to_mesh().as_group(max_triangles_per_group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{intersection::ray::Ray, shape::ShapeContainer, util::approx_eq};
+
+    use super::*;
+
+    #[test]
+    fn a_flat_surface_is_sampled_on_a_grid() {
+        let surface =
+            ParametricSurface::new(|u, v| Tuple::point(u, 0.0, v), 0.0..=1.0, 0.0..=2.0, 2);
+        let mesh = surface.to_mesh();
+
+        assert_eq!(9, mesh.vertices().len());
+        assert_eq!(8, mesh.faces().len());
+        assert_eq!(Tuple::point(0.0, 0.0, 0.0), mesh.vertices()[0]);
+        assert_eq!(Tuple::point(0.5, 0.0, 1.0), mesh.vertices()[4]);
+        assert_eq!(Tuple::point(1.0, 0.0, 2.0), mesh.vertices()[8]);
+        for normal in mesh.vertex_normals() {
+            assert_eq!(Tuple::vector(0.0, 1.0, 0.0), normal);
+        }
+    }
+
+    #[test]
+    fn a_tessellated_sphere_is_close_to_a_sphere() {
+        let sphere = ParametricSurface::new(
+            |u, v| Tuple::point(v.sin() * u.cos(), v.cos(), v.sin() * u.sin()),
+            0.0..=2.0 * PI,
+            0.0..=PI,
+            32,
+        );
+        let group: ShapeContainer = sphere.as_group(64).into();
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        let hit = r
+            .intersections(&group)
+            .iter()
+            .map(|i| i.t())
+            .fold(f64::INFINITY, f64::min);
+        assert!(approx_eq(4.0, hit, 0.01));
+    }
+
+    #[test]
+    fn the_seam_and_poles_of_a_sphere_are_welded() {
+        let sphere = ParametricSurface::new(
+            |u, v| Tuple::point(v.sin() * u.cos(), v.cos(), v.sin() * u.sin()),
+            0.0..=2.0 * PI,
+            0.0..=PI,
+            4,
+        );
+        let mesh = sphere.to_mesh();
+        let normals = mesh.vertex_normals();
+
+        assert_eq!(14, mesh.vertices().len());
+        assert_eq!(24, mesh.faces().len());
+        for (vertex, normal) in mesh.vertices().iter().zip(normals) {
+            let outward = *vertex - Tuple::origin();
+            assert!((normal * outward).abs() > 0.95);
+        }
+    }
+}
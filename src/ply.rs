@@ -0,0 +1,242 @@
+use std::{fs, path::Path};
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::{
+    color::Color,
+    error::{RayTraceError, RayTraceResult},
+    obj::{fan_triangulation, subdivide},
+    shape::{group::GroupContainer, ShapeContainer},
+    tuple::Tuple,
+};
+
+/**
+   Reads ASCII PLY meshes, including per vertex normals and colors. Binary
+   PLY files are rejected.
+*/
+pub struct PLYParser {
+    triangles: Vec<ShapeContainer>,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<(String, String)>,
+}
+
+impl Element {
+    fn property(&self, name: &str) -> Option<usize> {
+        self.properties.iter().position(|(n, _)| n == name)
+    }
+
+    /**
+       Integer color channels run from 0 to 255, floating point ones from 0
+       to 1.
+    */
+    fn color_scale(&self, name: &str) -> f64 {
+        match self.property(name).map(|i| self.properties[i].1.as_str()) {
+            Some("float" | "float32" | "double" | "float64") => 1.0,
+            _ => 1.0 / 255.0,
+        }
+    }
+}
+
+fn invalid(message: &str) -> RayTraceError {
+    RayTraceError::InvalidFormat(format!("PLY: {message}"))
+}
+
+impl PLYParser {
+    pub fn parse_file<T: AsRef<Path> + Clone>(path: T) -> RayTraceResult<Self> {
+        let file_string = fs::read_to_string(path.clone())?;
+        let pb = ProgressBar::new(file_string.lines().count() as u64);
+        pb.set_style(ProgressStyle::with_template("{wide_bar} {percent}% {eta} {msg}").unwrap());
+
+        let parser = Self::parse(&file_string, &pb)?;
+        pb.finish_with_message(format!(
+            "Finished importing {}",
+            path.as_ref().to_string_lossy()
+        ));
+        Ok(parser)
+    }
+
+    fn parse(file_string: &str, pb: &ProgressBar) -> RayTraceResult<Self> {
+        let mut lines = file_string.lines().inspect(|_| pb.inc(1));
+        if lines.next().map(str::trim) != Some("ply") {
+            return Err(invalid("missing ply magic number"));
+        }
+
+        let mut elements: Vec<Element> = vec![];
+        for line in lines.by_ref() {
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            match words.as_slice() {
+                ["format", "ascii", ..] => {}
+                ["format", format, ..] => {
+                    return Err(invalid(&format!("unsupported format {format}")))
+                }
+                ["element", name, count] => elements.push(Element {
+                    name: name.to_string(),
+                    count: count.parse()?,
+                    properties: vec![],
+                }),
+                ["property", "list", .., name] | ["property", _, name] => elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("property before element"))?
+                    .properties
+                    .push((name.to_string(), words[1].to_string())),
+                ["end_header"] => break,
+                _ => {}
+            }
+        }
+
+        let mut verticies = vec![];
+        let mut normals = vec![];
+        let mut colors = vec![];
+        let mut triangles = vec![];
+
+        for element in &elements {
+            for _ in 0..element.count {
+                let line = lines
+                    .next()
+                    .ok_or_else(|| invalid(&format!("missing {} data", element.name)))?;
+                let values = line.split_whitespace().collect::<Vec<_>>();
+
+                match element.name.as_str() {
+                    "vertex" => {
+                        let get = |name: &str| -> RayTraceResult<Option<f64>> {
+                            match element.property(name).and_then(|i| values.get(i)) {
+                                Some(value) => Ok(Some(value.parse()?)),
+                                None => Ok(None),
+                            }
+                        };
+                        let point = match (get("x")?, get("y")?, get("z")?) {
+                            (Some(x), Some(y), Some(z)) => Tuple::point(x, y, z),
+                            _ => return Err(invalid("vertex without x, y and z")),
+                        };
+                        verticies.push(point);
+                        if let (Some(x), Some(y), Some(z)) = (get("nx")?, get("ny")?, get("nz")?) {
+                            normals.push(Tuple::vector(x, y, z));
+                        }
+                        colors.push(match (get("red")?, get("green")?, get("blue")?) {
+                            (Some(r), Some(g), Some(b)) => Some(Color::new(
+                                r * element.color_scale("red"),
+                                g * element.color_scale("green"),
+                                b * element.color_scale("blue"),
+                            )),
+                            _ => None,
+                        });
+                    }
+                    "face" => {
+                        let indices = values
+                            .iter()
+                            .skip(1)
+                            .map(|i| i.parse::<usize>())
+                            .collect::<Result<Vec<_>, _>>()?;
+                        if indices.len() < 3 {
+                            return Err(invalid("face has fewer than three vertices"));
+                        }
+                        if indices.iter().any(|&i| i >= verticies.len()) {
+                            return Err(invalid("face refers to a missing vertex"));
+                        }
+                        let face_normals = if normals.len() == verticies.len() {
+                            indices.iter().map(|&i| normals[i]).collect()
+                        } else {
+                            vec![]
+                        };
+                        triangles.append(&mut fan_triangulation(
+                            indices.iter().map(|&i| verticies[i]).collect(),
+                            face_normals,
+                            indices.iter().map(|&i| colors[i]).collect(),
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(Self { triangles })
+    }
+
+    pub fn triangles(&self) -> &Vec<ShapeContainer> {
+        &self.triangles
+    }
+
+    /**
+       Collect the triangles into a group, split into nested subgroups of at
+       most `max_triangles_per_group` as for OBJ files.
+    */
+    pub fn as_group(self, max_triangles_per_group: usize) -> GroupContainer {
+        subdivide(self.triangles, max_triangles_per_group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLORED_QUAD: &str = "\
+ply
+format ascii 1.0
+comment a unit quad with a color per corner
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+-1 1 0 255 0 0
+-1 0 0 0 255 0
+1 0 0 0 0 255
+1 1 0 255 255 255
+4 0 1 2 3
+";
+
+    #[test]
+    fn parsing_an_ascii_ply_with_vertex_colors() {
+        let parser = PLYParser::parse(COLORED_QUAD, &ProgressBar::hidden()).unwrap();
+        let triangles = parser.triangles();
+
+        assert_eq!(2, triangles.len());
+        let t = triangles[0].read().unwrap();
+        assert_eq!(
+            Some(Color::new(1.0, 0.0, 0.0)),
+            t.vertex_color(t.id(), 0.0, 0.0)
+        );
+        assert_eq!(
+            Some(Color::new(0.0, 0.0, 1.0)),
+            t.vertex_color(t.id(), 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn binary_ply_files_are_rejected() {
+        let ply = "ply\nformat binary_little_endian 1.0\nend_header\n";
+
+        assert!(matches!(
+            PLYParser::parse(ply, &ProgressBar::hidden()),
+            Err(RayTraceError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn faces_must_refer_to_existing_vertices() {
+        let ply = COLORED_QUAD.replace("4 0 1 2 3", "3 0 1 7");
+
+        assert!(PLYParser::parse(&ply, &ProgressBar::hidden()).is_err());
+    }
+
+    #[test]
+    fn faces_need_at_least_three_vertices() {
+        for face in ["0", "2 0 1"] {
+            let ply = COLORED_QUAD.replace("4 0 1 2 3", face);
+
+            assert!(matches!(
+                PLYParser::parse(&ply, &ProgressBar::hidden()),
+                Err(RayTraceError::InvalidFormat(_))
+            ));
+        }
+    }
+}
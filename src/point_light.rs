@@ -1,9 +1,46 @@
-use crate::{color::Color, tuple::Tuple};
+use crate::{color::Color, tuple::Tuple, world::World};
+
+/**
+   How a light dims with distance. With falloff, a light's intensity is what
+   it delivers at a distance of one unit.
+*/
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Falloff {
+    #[default]
+    None,
+    InverseSquare,
+    /**
+       `1 / (constant + linear * d + quadratic * d^2)` at distance `d`.
+    */
+    Quadratic {
+        constant: f64,
+        linear: f64,
+        quadratic: f64,
+    },
+}
+
+impl Falloff {
+    /**
+       The fraction of the light's intensity that reaches `distance` away.
+    */
+    pub fn attenuation(&self, distance: f64) -> f64 {
+        match *self {
+            Falloff::None => 1.0,
+            Falloff::InverseSquare => 1.0 / distance.powi(2),
+            Falloff::Quadratic {
+                constant,
+                linear,
+                quadratic,
+            } => 1.0 / (constant + linear * distance + quadratic * distance.powi(2)),
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct PointLight {
     position: Tuple,
     intensity: Color,
+    falloff: Falloff,
 }
 
 impl PointLight {
@@ -11,9 +48,15 @@ impl PointLight {
         Self {
             position,
             intensity,
+            falloff: Falloff::None,
         }
     }
 
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
     pub fn position(&self) -> Tuple {
         self.position
     }
@@ -21,11 +64,35 @@ impl PointLight {
     pub fn intensity(&self) -> Color {
         self.intensity
     }
+
+    pub fn falloff(&self) -> Falloff {
+        self.falloff
+    }
+
+    /**
+       The fraction of the light's intensity that reaches `point`.
+    */
+    pub fn attenuation_at(&self, point: Tuple) -> f64 {
+        self.falloff
+            .attenuation((self.position - point).magnitude())
+    }
+
+    /**
+       The fraction of the light's intensity that reaches `point` in
+       `world`: nothing if it is in shadow, otherwise its attenuation.
+    */
+    pub fn intensity_at(&self, point: Tuple, world: &World) -> f64 {
+        if world.is_shadowed_from(self, point) {
+            0.0
+        } else {
+            self.attenuation_at(point)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::color::Colors;
+    use crate::{color::Colors, testing::default_world};
 
     use super::*;
 
@@ -38,5 +105,48 @@ mod tests {
 
         assert_eq!(position, light.position());
         assert_eq!(intensity, light.intensity());
+        assert_eq!(Falloff::None, light.falloff());
+    }
+
+    #[test]
+    fn attenuation_for_each_falloff() {
+        let light = PointLight::new(Tuple::origin(), Colors::White.into());
+        let point = Tuple::point(0.0, 0.0, 2.0);
+
+        assert_eq!(1.0, light.attenuation_at(point));
+        assert_eq!(
+            0.25,
+            light
+                .with_falloff(Falloff::InverseSquare)
+                .attenuation_at(point)
+        );
+        assert_eq!(
+            0.125,
+            light
+                .with_falloff(Falloff::Quadratic {
+                    constant: 1.0,
+                    linear: 1.5,
+                    quadratic: 1.0,
+                })
+                .attenuation_at(point)
+        );
+    }
+
+    #[test]
+    fn point_lights_evaluate_the_light_intensity_at_a_given_point() {
+        let w = default_world();
+        let light = w.lights()[0];
+
+        for (point, expected) in [
+            (Tuple::point(0.0, 1.0001, 0.0), 1.0),
+            (Tuple::point(-1.0001, 0.0, 0.0), 1.0),
+            (Tuple::point(0.0, 0.0, -1.0001), 1.0),
+            (Tuple::point(0.0, 0.0, 1.0001), 0.0),
+            (Tuple::point(1.0001, 0.0, 0.0), 0.0),
+            (Tuple::point(0.0, -1.0001, 0.0), 0.0),
+            (Tuple::point(0.0, 0.0, 0.0), 0.0),
+        ] {
+            assert_eq!(expected, light.intensity_at(point, &w));
+        }
     }
 }
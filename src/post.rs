@@ -0,0 +1,123 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{canvas::Canvas, color::Color};
+
+/**
+   Effects applied to a finished image, in the spirit of a camera lens and
+   film rather than anything physically traced.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostEffect {
+    /**
+       Darken towards the corners, which lose `strength` of their brightness.
+    */
+    Vignette { strength: f64 },
+    /**
+       Spread the color channels apart towards the edges, red outwards and
+       blue inwards, by `amount` of the distance from the center.
+    */
+    ChromaticAberration { amount: f64 },
+    /**
+       Brighten or darken each pixel by up to `amount`, the same noise for
+       the same `seed`.
+    */
+    FilmGrain { amount: f64, seed: u64 },
+}
+
+impl PostEffect {
+    pub fn apply(&self, canvas: &Canvas) -> Canvas {
+        let (width, height) = (canvas.width(), canvas.height());
+        let (center_x, center_y) = (width as f64 / 2.0, height as f64 / 2.0);
+        let mut result = Canvas::new(width, height);
+
+        match *self {
+            PostEffect::Vignette { strength } => {
+                let corner = center_x.hypot(center_y);
+                for y in 0..height {
+                    for x in 0..width {
+                        let distance =
+                            (x as f64 + 0.5 - center_x).hypot(y as f64 + 0.5 - center_y) / corner;
+                        result[(x, y)] = canvas[(x, y)] * (1.0 - strength * distance * distance);
+                    }
+                }
+            }
+            PostEffect::ChromaticAberration { amount } => {
+                let sample = |x: usize, y: usize, scale: f64| {
+                    let sample_x = center_x + (x as f64 + 0.5 - center_x) * scale;
+                    let sample_y = center_y + (y as f64 + 0.5 - center_y) * scale;
+                    canvas[(
+                        (sample_x.max(0.0) as usize).min(width - 1),
+                        (sample_y.max(0.0) as usize).min(height - 1),
+                    )]
+                };
+                for y in 0..height {
+                    for x in 0..width {
+                        result[(x, y)] = Color::new(
+                            sample(x, y, 1.0 - amount).red(),
+                            canvas[(x, y)].green(),
+                            sample(x, y, 1.0 + amount).blue(),
+                        );
+                    }
+                }
+            }
+            PostEffect::FilmGrain { amount, seed } => {
+                let mut rng = StdRng::seed_from_u64(seed);
+                for y in 0..height {
+                    for x in 0..width {
+                        let grain = (rng.gen::<f64>() * 2.0 - 1.0) * amount;
+                        result[(x, y)] = canvas[(x, y)] + Color::new(grain, grain, grain);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::eq_f64;
+
+    use super::*;
+
+    fn gray() -> Canvas {
+        Canvas::fill_with(10, 10, Color::new(0.5, 0.5, 0.5))
+    }
+
+    #[test]
+    fn a_vignette_darkens_the_corners_more_than_the_center() {
+        let image = PostEffect::Vignette { strength: 0.5 }.apply(&gray());
+
+        assert!(image[(0, 0)].red() < image[(4, 4)].red());
+        assert!(image[(4, 4)].red() > 0.49);
+        assert!(eq_f64(image[(0, 0)].red(), image[(9, 9)].red()));
+    }
+
+    #[test]
+    fn chromatic_aberration_shifts_red_and_blue_apart() {
+        let mut canvas = Canvas::new(10, 10);
+        canvas[(8, 5)] = Color::new(1.0, 1.0, 1.0);
+
+        let image = PostEffect::ChromaticAberration { amount: 0.25 }.apply(&canvas);
+
+        assert_eq!(Color::new(0.0, 1.0, 0.0), image[(8, 5)]);
+        assert_eq!(1.0, image[(9, 5)].red());
+        assert_eq!(1.0, image[(7, 5)].blue());
+    }
+
+    #[test]
+    fn film_grain_is_repeatable_for_a_seed() {
+        let grain = PostEffect::FilmGrain {
+            amount: 0.1,
+            seed: 4,
+        };
+
+        let first = grain.apply(&gray()).into_iter().collect::<Vec<_>>();
+        let second = grain.apply(&gray()).into_iter().collect::<Vec<_>>();
+
+        assert_eq!(first, second);
+        assert!(first.iter().all(|c| (c.red() - 0.5).abs() <= 0.1));
+        assert!(first.iter().any(|c| c.red() != 0.5));
+    }
+}
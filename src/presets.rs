@@ -0,0 +1,225 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    shape::{convex_polytope::ConvexPolytope, material::Material, Shape},
+    tuple::Tuple,
+};
+
+/**
+   How strongly diamond bends light, measured for yellow light.
+*/
+pub const DIAMOND_REFRACTIVE_INDEX: f64 = 2.417;
+
+/**
+   The proportions of a round brilliant cut. Lengths are fractions of the
+   stone's diameter and angles are measured from the girdle plane.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cut {
+    symmetry: usize,
+    table: f64,
+    crown_angle: f64,
+    pavilion_angle: f64,
+    girdle: f64,
+}
+
+impl Cut {
+    /**
+       Tolkowsky's ideal proportions: an eightfold stone with a 53% table,
+       34.5 degree crown, 40.75 degree pavilion and a thin girdle.
+    */
+    pub fn brilliant() -> Self {
+        Self {
+            symmetry: 8,
+            table: 0.53,
+            crown_angle: 34.5f64.to_radians(),
+            pavilion_angle: 40.75f64.to_radians(),
+            girdle: 0.02,
+        }
+    }
+
+    pub fn with_symmetry(mut self, symmetry: usize) -> Self {
+        self.symmetry = symmetry.max(3);
+        self
+    }
+
+    pub fn with_table(mut self, table: f64) -> Self {
+        self.table = table;
+        self
+    }
+
+    pub fn with_crown_angle(mut self, crown_angle: f64) -> Self {
+        self.crown_angle = crown_angle;
+        self
+    }
+
+    pub fn with_pavilion_angle(mut self, pavilion_angle: f64) -> Self {
+        self.pavilion_angle = pavilion_angle;
+        self
+    }
+
+    pub fn with_girdle(mut self, girdle: f64) -> Self {
+        self.girdle = girdle;
+        self
+    }
+
+    pub fn symmetry(&self) -> usize {
+        self.symmetry
+    }
+
+    pub fn table(&self) -> f64 {
+        self.table
+    }
+
+    pub fn crown_angle(&self) -> f64 {
+        self.crown_angle
+    }
+
+    pub fn pavilion_angle(&self) -> f64 {
+        self.pavilion_angle
+    }
+
+    pub fn girdle(&self) -> f64 {
+        self.girdle
+    }
+
+    /**
+       How far the table stands above the middle of the girdle, for a stone
+       with a radius of 1.
+    */
+    pub fn crown_height(&self) -> f64 {
+        self.girdle + (1.0 - self.table) * self.crown_angle.tan()
+    }
+
+    /**
+       How far the culet sits below the middle of the girdle, for a stone
+       with a radius of 1.
+    */
+    pub fn pavilion_depth(&self) -> f64 {
+        self.girdle + self.pavilion_angle.tan()
+    }
+}
+
+impl Default for Cut {
+    fn default() -> Self {
+        Self::brilliant()
+    }
+}
+
+/**
+   A clear, strongly refracting and reflecting material for gems.
+*/
+pub fn diamond_material() -> Material {
+    Material::new()
+        .with_color(Color::new(0.0, 0.0, 0.0))
+        .with_ambient(0.0)
+        .with_diffuse(0.05)
+        .with_specular(1.0)
+        .with_shininess(300.0)
+        .with_reflective(0.9)
+        .with_transparency(0.95)
+        .with_refractive_index(DIAMOND_REFRACTIVE_INDEX)
+}
+
+/**
+   A diamond with a radius of 1, cut as `cut` describes, with the girdle
+   around the middle on the xz plane and the table facing +y. Besides the
+   table and the main crown and pavilion facets it has the star and girdle
+   facets of a brilliant, turned to sit between the main facets.
+*/
+pub fn diamond(cut: Cut) -> ConvexPolytope {
+    let step = 2.0 * PI / cut.symmetry as f64;
+    let steps = |count: usize, offset: f64| {
+        (0..count).map(move |i| i as f64 * 2.0 * PI / count as f64 + offset)
+    };
+    // The plane through the point `radius` out at `height` in the direction
+    // of `azimuth`, sloping down and out at `angle`. Negative angles slope
+    // down and in, for the pavilion.
+    let facet = |azimuth: f64, angle: f64, radius: f64, height: f64| {
+        let normal = Tuple::vector(
+            azimuth.sin() * angle.abs().sin(),
+            angle.signum() * angle.abs().cos(),
+            -azimuth.cos() * angle.abs().sin(),
+        );
+        let point = Tuple::vector(azimuth.sin() * radius, height, -azimuth.cos() * radius);
+        (normal, normal * point)
+    };
+
+    let girdle = cut.girdle;
+    let table = cut.crown_height();
+    let mut planes = vec![(Tuple::vector(0.0, 1.0, 0.0), table)];
+    planes.extend(steps(cut.symmetry * 4, 0.0).map(|azimuth| facet(azimuth, PI / 2.0, 1.0, 0.0)));
+    planes.extend(
+        steps(cut.symmetry, 0.0).map(|azimuth| facet(azimuth, cut.crown_angle, 1.0, girdle)),
+    );
+    planes.extend(
+        steps(cut.symmetry, step / 2.0)
+            .map(|azimuth| facet(azimuth, cut.crown_angle * 0.6, cut.table, table)),
+    );
+    planes.extend(
+        steps(cut.symmetry * 2, step / 4.0)
+            .map(|azimuth| facet(azimuth, cut.crown_angle * 1.15, 1.0, girdle)),
+    );
+    planes.extend(
+        steps(cut.symmetry, 0.0).map(|azimuth| facet(azimuth, -cut.pavilion_angle, 1.0, -girdle)),
+    );
+    planes.extend(
+        steps(cut.symmetry * 2, step / 4.0)
+            .map(|azimuth| facet(azimuth, -cut.pavilion_angle * 1.05, 1.0, -girdle)),
+    );
+
+    let mut diamond = ConvexPolytope::from_planes(planes);
+    diamond.set_material(diamond_material());
+    diamond
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{intersection::ray::Ray, util::eq_f64};
+
+    use super::*;
+
+    #[test]
+    fn a_brilliant_has_the_facets_of_a_brilliant() {
+        let stone = diamond(Cut::brilliant());
+
+        assert_eq!(1 + 32 + 8 + 8 + 16 + 8 + 16, stone.faces().len());
+        assert!(eq_f64(
+            DIAMOND_REFRACTIVE_INDEX,
+            stone.material(stone.id()).unwrap().refractive_index()
+        ));
+    }
+
+    #[test]
+    fn a_diamond_runs_from_table_to_culet() {
+        let cut = Cut::brilliant();
+        let stone = diamond(cut);
+        let bounds = stone.bounds();
+
+        assert!(eq_f64(cut.crown_height(), bounds.max().y()));
+        assert!(eq_f64(-cut.pavilion_depth(), bounds.min().y()));
+        assert!(eq_f64(1.0, bounds.max().z()));
+        assert!(bounds.max().x() <= 1.0 / (PI / 32.0).cos() + 1e-9);
+
+        let xs = stone.intersects(&Ray::new(
+            Tuple::point(0.0, 5.0, 0.0),
+            Tuple::vector(0.0, -1.0, 0.0),
+        ));
+        assert_eq!(2, xs.len());
+        assert!(eq_f64(
+            cut.crown_height() + cut.pavilion_depth(),
+            xs[1].t() - xs[0].t()
+        ));
+    }
+
+    #[test]
+    fn the_table_is_the_size_the_cut_asks_for() {
+        let cut = Cut::brilliant().with_table(0.6);
+        let stone = diamond(cut);
+        let top = cut.crown_height() - 1e-6;
+
+        assert!(stone.includes_point(Tuple::point(0.0, top, -0.55)));
+        assert!(!stone.includes_point(Tuple::point(0.0, top, -0.65)));
+    }
+}
@@ -0,0 +1,217 @@
+use std::{fmt, time::Duration, time::Instant};
+
+use indicatif::{
+    style::ProgressTracker, HumanDuration, ProgressBar, ProgressDrawTarget, ProgressState,
+    ProgressStyle,
+};
+
+/**
+   How a render reports its progress on the terminal. Pixels are counted a
+   tile at a time so workers touch the shared bar once per `tile_size`
+   pixels rather than once per pixel, the bar is redrawn at most once per
+   `update_interval`, and the ETA follows the rendering rate averaged over
+   about `eta_smoothing` so it does not jump around between easy and hard
+   parts of the image.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    visible: bool,
+    tile_size: usize,
+    update_interval: Duration,
+    eta_smoothing: Duration,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self {
+            visible: true,
+            tile_size: 64,
+            update_interval: Duration::from_millis(100),
+            eta_smoothing: Duration::from_secs(5),
+        }
+    }
+
+    /**
+       Report nothing, for example when rendering many small frames.
+    */
+    pub fn hidden() -> Self {
+        Self {
+            visible: false,
+            ..Self::new()
+        }
+    }
+
+    pub fn with_tile_size(mut self, tile_size: usize) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    /**
+       Redraw no more often than every `update_interval`, which is kept
+       between 1/255 of a second and one second.
+    */
+    pub fn with_update_interval(mut self, update_interval: Duration) -> Self {
+        self.update_interval =
+            update_interval.clamp(Duration::from_secs(1) / 255, Duration::from_secs(1));
+        self
+    }
+
+    pub fn with_eta_smoothing(mut self, eta_smoothing: Duration) -> Self {
+        self.eta_smoothing = eta_smoothing;
+        self
+    }
+
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn tile_size(&self) -> usize {
+        self.tile_size
+    }
+
+    pub fn update_interval(&self) -> Duration {
+        self.update_interval
+    }
+
+    pub fn eta_smoothing(&self) -> Duration {
+        self.eta_smoothing
+    }
+
+    /**
+       A bar counting up to `len`, drawn as configured.
+    */
+    pub fn bar(&self, len: u64) -> ProgressBar {
+        if !self.visible {
+            return ProgressBar::hidden();
+        }
+
+        let hz = (1.0 / self.update_interval.as_secs_f64()).round() as u8;
+        let pb = ProgressBar::with_draw_target(Some(len), ProgressDrawTarget::stderr_with_hz(hz));
+        pb.set_style(
+            ProgressStyle::with_template("{wide_bar} {percent}% {smoothed_eta} {msg}")
+                .unwrap()
+                .with_key("smoothed_eta", SmoothedEta::new(self.eta_smoothing)),
+        );
+        pb
+    }
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+   Estimates the time left from an exponential moving average of the
+   progress rate, which forgets older measurements over `smoothing`.
+*/
+#[derive(Debug, Clone)]
+struct SmoothedEta {
+    smoothing: f64,
+    rate: Option<f64>,
+    last: Option<(Instant, u64)>,
+}
+
+impl SmoothedEta {
+    fn new(smoothing: Duration) -> Self {
+        Self {
+            smoothing: smoothing.as_secs_f64(),
+            rate: None,
+            last: None,
+        }
+    }
+
+    fn record(&mut self, pos: u64, now: Instant) {
+        let Some((then, last_pos)) = self.last else {
+            self.last = Some((now, pos));
+            return;
+        };
+        let elapsed = now.duration_since(then).as_secs_f64();
+        if elapsed <= 0.0 || pos < last_pos {
+            return;
+        }
+
+        let rate = (pos - last_pos) as f64 / elapsed;
+        let weight = if self.smoothing > 0.0 {
+            1.0 - (-elapsed / self.smoothing).exp()
+        } else {
+            1.0
+        };
+        self.rate = Some(self.rate.map_or(rate, |old| old + (rate - old) * weight));
+        self.last = Some((now, pos));
+    }
+
+    fn remaining(&self, pos: u64, len: u64) -> Option<Duration> {
+        self.rate
+            .filter(|&rate| rate > 0.0)
+            .map(|rate| Duration::from_secs_f64(len.saturating_sub(pos) as f64 / rate))
+    }
+}
+
+impl ProgressTracker for SmoothedEta {
+    fn clone_box(&self) -> Box<dyn ProgressTracker> {
+        Box::new(self.clone())
+    }
+
+    fn tick(&mut self, state: &ProgressState, now: Instant) {
+        self.record(state.pos(), now);
+    }
+
+    fn reset(&mut self, _state: &ProgressState, _now: Instant) {
+        self.rate = None;
+        self.last = None;
+    }
+
+    fn write(&self, state: &ProgressState, w: &mut dyn fmt::Write) {
+        let remaining = self
+            .remaining(state.pos(), state.len().unwrap_or(0))
+            .unwrap_or_default();
+        let _ = write!(w, "{:#}", HumanDuration(remaining));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_intervals_stay_within_what_the_terminal_can_show() {
+        let progress = Progress::new().with_update_interval(Duration::ZERO);
+        assert_eq!(Duration::from_secs(1) / 255, progress.update_interval());
+
+        let progress = Progress::new().with_update_interval(Duration::from_secs(10));
+        assert_eq!(Duration::from_secs(1), progress.update_interval());
+    }
+
+    #[test]
+    fn the_smoothed_eta_settles_on_the_recent_rate() {
+        let start = Instant::now();
+        let mut eta = SmoothedEta::new(Duration::from_secs(1));
+
+        eta.record(0, start);
+        eta.record(100, start + Duration::from_secs(1));
+        assert_eq!(Some(Duration::from_secs(9)), eta.remaining(100, 1000));
+
+        eta.record(100, start + Duration::from_secs(2));
+        let slower = eta.remaining(100, 1000).unwrap();
+        assert!(slower > Duration::from_secs(9));
+
+        for second in 3..20 {
+            eta.record(100 + (second - 2) * 10, start + Duration::from_secs(second));
+        }
+        let settled = eta.remaining(270, 1000).unwrap().as_secs_f64();
+        assert!((settled - 73.0).abs() < 1.0, "{settled}");
+    }
+
+    #[test]
+    fn a_rate_of_zero_has_no_eta() {
+        let start = Instant::now();
+        let mut eta = SmoothedEta::new(Duration::ZERO);
+
+        eta.record(10, start);
+        eta.record(10, start + Duration::from_secs(1));
+
+        assert_eq!(None, eta.remaining(10, 100));
+    }
+}
@@ -0,0 +1,487 @@
+use std::{cell::RefCell, f64::consts::PI, fmt::Debug};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::tuple::Tuple;
+
+const INVERSE_GOLDEN_RATIO: f64 = 0.618_033_988_749_894_9;
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/**
+   Run `f` with this thread's random number generator. All randomness in
+   rendering goes through it so a seeded render is reproducible.
+*/
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+/**
+   Restart this thread's random number generator from `seed`.
+*/
+pub fn seed_rng(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/**
+   A new seed made from `seed` and `value`, so several seeds can be derived
+   from one without falling into step with each other (the finalizer of
+   SplitMix64).
+*/
+pub fn mix_seed(seed: u64, value: u64) -> u64 {
+    let mut z = seed.wrapping_add(value.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+    z ^ (z >> 31)
+}
+
+/**
+   A source of 2D sample points in the unit square. Features that need to
+   sample an area (pixels, lenses, area lights, hemispheres) take a sampler so
+   the distribution can be swapped without touching the feature.
+*/
+pub trait Sampler: Debug + Send + Sync {
+    fn samples(&self, count: usize) -> Vec<(f64, f64)>;
+}
+
+/**
+   Splits the unit square into a grid of cells and places one sample in each,
+   optionally jittered within its cell. Sample counts that are not perfect
+   squares use the squarest grid with exactly that many cells, so every
+   part of the square is sampled equally.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StratifiedSampler {
+    jitter: bool,
+}
+
+impl StratifiedSampler {
+    pub fn new(jitter: bool) -> Self {
+        Self { jitter }
+    }
+}
+
+impl Default for StratifiedSampler {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn samples(&self, count: usize) -> Vec<(f64, f64)> {
+        let rows = (1..=(count as f64).sqrt() as usize)
+            .rev()
+            .find(|rows| count.is_multiple_of(*rows))
+            .unwrap_or(1);
+        let columns = count / rows;
+
+        with_rng(|rng| {
+            (0..count)
+                .map(|i| {
+                    let (dx, dy) = if self.jitter {
+                        (rng.gen::<f64>(), rng.gen::<f64>())
+                    } else {
+                        (0.5, 0.5)
+                    };
+                    (
+                        ((i % columns) as f64 + dx) / columns as f64,
+                        ((i / columns) as f64 + dy) / rows as f64,
+                    )
+                })
+                .collect()
+        })
+    }
+}
+
+/**
+   The Halton sequence in bases 2 and 3, starting at `offset`. Deterministic,
+   and any prefix of the sequence is well distributed.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HaltonSampler {
+    offset: usize,
+}
+
+impl HaltonSampler {
+    pub fn new(offset: usize) -> Self {
+        Self { offset }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn samples(&self, count: usize) -> Vec<(f64, f64)> {
+        (self.offset + 1..=self.offset + count)
+            .map(|i| (radical_inverse(2, i), radical_inverse(3, i)))
+            .collect()
+    }
+}
+
+/**
+   Independent, uniformly random samples, for jittering within cells that
+   are already laid out some other way.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RandomSampler;
+
+impl Sampler for RandomSampler {
+    fn samples(&self, count: usize) -> Vec<(f64, f64)> {
+        with_rng(|rng| (0..count).map(|_| (rng.gen(), rng.gen())).collect())
+    }
+}
+
+/**
+   Approximates blue noise with Mitchell's best candidate algorithm: each new
+   sample is the farthest of `candidates` random points from the samples
+   already placed, measured on a torus so the pattern tiles.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlueNoiseSampler {
+    candidates: usize,
+}
+
+impl BlueNoiseSampler {
+    pub fn new(candidates: usize) -> Self {
+        Self {
+            candidates: candidates.max(1),
+        }
+    }
+}
+
+impl Default for BlueNoiseSampler {
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn samples(&self, count: usize) -> Vec<(f64, f64)> {
+        with_rng(|rng| {
+            let mut samples: Vec<(f64, f64)> = Vec::with_capacity(count);
+
+            for _ in 0..count {
+                let best = (0..self.candidates)
+                    .map(|_| (rng.gen::<f64>(), rng.gen::<f64>()))
+                    .map(|candidate| {
+                        let distance = samples
+                            .iter()
+                            .map(|&sample| toroidal_distance_squared(candidate, sample))
+                            .fold(f64::INFINITY, f64::min);
+                        (candidate, distance)
+                    })
+                    .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(candidate, _)| candidate)
+                    .unwrap();
+                samples.push(best);
+            }
+
+            samples
+        })
+    }
+}
+
+/**
+   The Fibonacci lattice: sample `i` of `n` sits at `(i / phi, (i + 0.5) / n)`
+   with the first coordinate wrapped into the unit square. Deterministic and
+   very evenly spread for any count, which makes it converge quickly for
+   occlusion queries with few samples.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FibonacciSampler;
+
+impl Sampler for FibonacciSampler {
+    fn samples(&self, count: usize) -> Vec<(f64, f64)> {
+        (0..count)
+            .map(|i| {
+                (
+                    (i as f64 * INVERSE_GOLDEN_RATIO).fract(),
+                    (i as f64 + 0.5) / count as f64,
+                )
+            })
+            .collect()
+    }
+}
+
+/**
+   Repeats a fixed list of values, taking two at a time for each sample.
+   Makes sampled features deterministic in tests.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceSampler {
+    values: Vec<f64>,
+}
+
+impl SequenceSampler {
+    pub fn new(values: Vec<f64>) -> Self {
+        Self { values }
+    }
+}
+
+impl Sampler for SequenceSampler {
+    fn samples(&self, count: usize) -> Vec<(f64, f64)> {
+        if self.values.is_empty() {
+            return vec![(0.5, 0.5); count];
+        }
+
+        let mut values = self.values.iter().copied().cycle();
+        (0..count)
+            .map(|_| (values.next().unwrap(), values.next().unwrap()))
+            .collect()
+    }
+}
+
+fn toroidal_distance_squared((ax, ay): (f64, f64), (bx, by): (f64, f64)) -> f64 {
+    let dx = (ax - bx).abs();
+    let dy = (ay - by).abs();
+    let dx = dx.min(1.0 - dx);
+    let dy = dy.min(1.0 - dy);
+
+    dx * dx + dy * dy
+}
+
+pub fn radical_inverse(base: usize, mut index: usize) -> f64 {
+    let mut result = 0.0;
+    let mut fraction = 1.0 / base as f64;
+
+    while index > 0 {
+        result += (index % base) as f64 * fraction;
+        index /= base;
+        fraction /= base as f64;
+    }
+
+    result
+}
+
+/**
+   Map a sample in the unit square onto the unit disk, keeping stratification
+   intact (Shirley's concentric mapping).
+*/
+pub fn square_to_disk((u, v): (f64, f64)) -> (f64, f64) {
+    let a = 2.0 * u - 1.0;
+    let b = 2.0 * v - 1.0;
+
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let (radius, theta) = if a.abs() > b.abs() {
+        (a, (PI / 4.0) * (b / a))
+    } else {
+        (b, PI / 2.0 - (PI / 4.0) * (a / b))
+    };
+
+    (radius * theta.cos(), radius * theta.sin())
+}
+
+/**
+   Map a sample in the unit square to a cosine weighted direction on the
+   hemisphere around +z. Use an `Onb` to orient it around a surface normal.
+*/
+pub fn square_to_cosine_hemisphere(sample: (f64, f64)) -> Tuple {
+    let (x, y) = square_to_disk(sample);
+    let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+
+    Tuple::vector(x, y, z)
+}
+
+/**
+   `count` points on the unit disk laid out along the golden angle spiral
+   (Vogel's method), each covering the same area.
+*/
+pub fn spiral_disk(count: usize) -> Vec<(f64, f64)> {
+    let golden_angle = 2.0 * PI * (1.0 - INVERSE_GOLDEN_RATIO);
+
+    (0..count)
+        .map(|i| {
+            let radius = ((i as f64 + 0.5) / count as f64).sqrt();
+            let theta = golden_angle * i as f64;
+            (radius * theta.cos(), radius * theta.sin())
+        })
+        .collect()
+}
+
+/**
+   `count` cosine weighted directions on the hemisphere around +z, made by
+   lifting `spiral_disk` onto it. Use an `Onb` to orient them around a
+   surface normal.
+*/
+pub fn spiral_cosine_hemisphere(count: usize) -> Vec<Tuple> {
+    spiral_disk(count)
+        .into_iter()
+        .map(|(x, y)| Tuple::vector(x, y, (1.0 - x * x - y * y).max(0.0).sqrt()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::eq_f64;
+
+    use super::*;
+
+    #[test]
+    fn reseeding_the_rng_repeats_its_sequence() {
+        seed_rng(7);
+        let first = StratifiedSampler::default().samples(4);
+        seed_rng(7);
+        let second = StratifiedSampler::default().samples(4);
+
+        assert_eq!(first, second);
+    }
+
+    fn in_unit_square(samples: &[(f64, f64)]) -> bool {
+        samples
+            .iter()
+            .all(|&(x, y)| (0.0..1.0).contains(&x) && (0.0..1.0).contains(&y))
+    }
+
+    #[test]
+    fn stratified_samples_fall_in_separate_cells() {
+        let samples = StratifiedSampler::default().samples(16);
+
+        assert_eq!(16, samples.len());
+        assert!(in_unit_square(&samples));
+        for (i, (x, y)) in samples.into_iter().enumerate() {
+            assert_eq!(i % 4, (x * 4.0) as usize);
+            assert_eq!(i / 4, (y * 4.0) as usize);
+        }
+    }
+
+    #[test]
+    fn unjittered_stratified_samples_are_cell_centers() {
+        let samples = StratifiedSampler::new(false).samples(4);
+
+        assert_eq!(
+            vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)],
+            samples
+        );
+    }
+
+    #[test]
+    fn stratified_samples_that_are_not_square_cover_the_square_evenly() {
+        let samples = StratifiedSampler::new(false).samples(6);
+
+        assert_eq!(
+            vec![
+                (1.0 / 6.0, 0.25),
+                (0.5, 0.25),
+                (5.0 / 6.0, 0.25),
+                (1.0 / 6.0, 0.75),
+                (0.5, 0.75),
+                (5.0 / 6.0, 0.75)
+            ],
+            samples
+        );
+        for count in [2, 5, 6, 7, 12] {
+            let samples = StratifiedSampler::new(false).samples(count);
+            let (x, y) = samples
+                .iter()
+                .fold((0.0, 0.0), |(x, y), sample| (x + sample.0, y + sample.1));
+
+            assert_eq!(count, samples.len());
+            assert!(eq_f64(0.5, x / count as f64), "{count}: {samples:?}");
+            assert!(eq_f64(0.5, y / count as f64), "{count}: {samples:?}");
+        }
+    }
+
+    #[test]
+    fn the_radical_inverse_mirrors_digits_around_the_point() {
+        assert!(eq_f64(0.5, radical_inverse(2, 1)));
+        assert!(eq_f64(0.25, radical_inverse(2, 2)));
+        assert!(eq_f64(0.75, radical_inverse(2, 3)));
+        assert!(eq_f64(1.0 / 3.0, radical_inverse(3, 1)));
+        assert!(eq_f64(1.0 / 9.0, radical_inverse(3, 3)));
+    }
+
+    #[test]
+    fn halton_samples_continue_from_the_offset() {
+        let all = HaltonSampler::default().samples(8);
+        let tail = HaltonSampler::new(4).samples(4);
+
+        assert_eq!(all[4..], tail[..]);
+        assert_eq!((0.5, 1.0 / 3.0), all[0]);
+    }
+
+    #[test]
+    fn blue_noise_samples_are_spread_apart() {
+        let samples = BlueNoiseSampler::new(20).samples(16);
+
+        assert_eq!(16, samples.len());
+        assert!(in_unit_square(&samples));
+        for (i, &a) in samples.iter().enumerate() {
+            for &b in &samples[i + 1..] {
+                assert!(toroidal_distance_squared(a, b) > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn a_sequence_sampler_cycles_through_its_values() {
+        let sampler = SequenceSampler::new(vec![0.1, 0.5, 1.0]);
+
+        assert_eq!(vec![(0.1, 0.5), (1.0, 0.1), (0.5, 1.0)], sampler.samples(3));
+    }
+
+    #[test]
+    fn fibonacci_samples_cover_every_row_once() {
+        let samples = FibonacciSampler.samples(16);
+
+        assert_eq!(16, samples.len());
+        assert!(in_unit_square(&samples));
+        assert_eq!(samples, FibonacciSampler.samples(16));
+        for (i, (_, y)) in samples.into_iter().enumerate() {
+            assert_eq!(i, (y * 16.0) as usize);
+        }
+    }
+
+    #[test]
+    fn spiral_disk_samples_fill_the_disk_evenly() {
+        let samples = spiral_disk(64);
+
+        assert_eq!(64, samples.len());
+        for &(x, y) in &samples {
+            assert!(x * x + y * y <= 1.0);
+        }
+        let left = samples.iter().filter(|(x, _)| *x < 0.0).count();
+        assert!((28..=36).contains(&left));
+    }
+
+    #[test]
+    fn spiral_hemisphere_samples_estimate_cosine_integrals_closely() {
+        let directions = spiral_cosine_hemisphere(16);
+
+        for d in &directions {
+            assert!(d.is_vector());
+            assert!(d.z() >= 0.0);
+            assert!(eq_f64(1.0, d.magnitude()));
+        }
+        let mean_z = directions.iter().map(|d| d.z()).sum::<f64>() / 16.0;
+        assert!((mean_z - 2.0 / 3.0).abs() < 0.01, "{mean_z}");
+    }
+
+    #[test]
+    fn mapping_the_square_to_the_disk() {
+        assert_eq!((0.0, 0.0), square_to_disk((0.5, 0.5)));
+
+        let (x, y) = square_to_disk((1.0, 0.5));
+        assert!(eq_f64(1.0, x));
+        assert!(eq_f64(0.0, y));
+
+        for sample in HaltonSampler::default().samples(32) {
+            let (x, y) = square_to_disk(sample);
+            assert!(x * x + y * y <= 1.0);
+        }
+    }
+
+    #[test]
+    fn cosine_hemisphere_samples_are_unit_vectors_above_the_plane() {
+        for sample in HaltonSampler::default().samples(32) {
+            let d = square_to_cosine_hemisphere(sample);
+
+            assert!(d.is_vector());
+            assert!(d.z() >= 0.0);
+            assert!(eq_f64(1.0, d.magnitude()));
+        }
+    }
+}
@@ -0,0 +1,2068 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    f64::consts::PI,
+    fs, mem,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use yaml_rust::{yaml::Hash, Yaml, YamlLoader};
+
+use crate::{
+    animation::{Interpolate, Keyframes, TransformStep},
+    camera::{Camera, Tile},
+    canvas::Canvas,
+    color::Color,
+    error::{RayTraceError, RayTraceResult},
+    image,
+    metadata::{frame_name, RenderMetadata},
+    obj::OBJParser,
+    ply::PLYParser,
+    point_light::PointLight,
+    sampling::mix_seed,
+    shape::{
+        cone::Cone,
+        convex_polytope::ConvexPolytope,
+        cube::Cube,
+        cylinder::Cylinder,
+        group::{Group, GroupContainer},
+        half_space::HalfSpace,
+        material::{
+            pattern::{
+                checker::CheckerPattern,
+                fade::FadePattern,
+                gradient::GradientPattern,
+                grid::GridPattern,
+                ring::RingPattern,
+                stripes::StripePattern,
+                uv::{
+                    CubeMapPattern, TextureMapPattern, UvAlignCheck, UvCheckers, UvImage, UvMap,
+                    UvPattern,
+                },
+                Pattern,
+            },
+            Material,
+        },
+        plane::Plane,
+        shell::Shell,
+        sphere::Sphere,
+        Shape, ShapeContainer, Sweep,
+    },
+    spot_light::SpotLight,
+    transformation::Transformation,
+    tuple::Tuple,
+    util::PrecisionPolicy,
+    world::World,
+};
+
+/**
+   The camera described by a scene file, kept as its parameters so they can
+   still be changed before the camera is built.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneCamera {
+    width: usize,
+    height: usize,
+    field_of_view: f64,
+    from: Tuple,
+    to: Tuple,
+    up: Tuple,
+    shutter: (f64, f64),
+    motion_samples: usize,
+}
+
+impl SceneCamera {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn field_of_view(&self) -> f64 {
+        self.field_of_view
+    }
+
+    pub fn from(&self) -> Tuple {
+        self.from
+    }
+
+    pub fn to(&self) -> Tuple {
+        self.to
+    }
+
+    pub fn up(&self) -> Tuple {
+        self.up
+    }
+
+    pub fn shutter(&self) -> (f64, f64) {
+        self.shutter
+    }
+
+    pub fn motion_samples(&self) -> usize {
+        self.motion_samples
+    }
+
+    pub fn to_camera(&self) -> Camera {
+        let mut camera = Camera::new(self.width, self.height, self.field_of_view)
+            .with_shutter(self.shutter.0, self.shutter.1)
+            .with_motion_samples(self.motion_samples);
+        camera.set_transformation(Transformation::view(self.from, self.to, self.up));
+        camera
+    }
+}
+
+/**
+   Cameras blend where they look from and to, their up vector and their
+   field of view. The image size and shutter are not animated and stay the
+   earlier camera's.
+*/
+impl Interpolate for SceneCamera {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        Self {
+            field_of_view: self.field_of_view.interpolate(&other.field_of_view, t),
+            from: self.from.interpolate(&other.from, t),
+            to: self.to.interpolate(&other.to, t),
+            up: self.up.interpolate(&other.up, t),
+            ..*self
+        }
+    }
+}
+
+impl Default for SceneCamera {
+    fn default() -> Self {
+        Self {
+            width: 100,
+            height: 100,
+            field_of_view: PI / 3.0,
+            from: Tuple::point(0.0, 0.0, -5.0),
+            to: Tuple::origin(),
+            up: Tuple::vector(0.0, 1.0, 0.0),
+            shutter: (0.0, 0.0),
+            motion_samples: 1,
+        }
+    }
+}
+
+/**
+   How many passes to average and which seed to render a scene with.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderSettings {
+    samples: usize,
+    seed: Option<u64>,
+}
+
+impl RenderSettings {
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            samples: 1,
+            seed: None,
+        }
+    }
+}
+
+/**
+   A world and camera read from a scene file in the YAML format used by the
+   book's bonus chapters: a list of `add` items for the camera, lights and
+   shapes, and `define` items naming materials, transforms and shapes for
+   reuse. A definition can `extend` an earlier one, overriding some of its
+   keys, and shapes can override the keys of a defined shape they add.
+
+   Lights with a `direction` are spot lights, with a `cone` of their inner
+   and outer angles in radians.
+
+   `include: path` reads another scene file's items in place, which suits
+   shared material libraries, and `add: file` with a `path` adds the shapes
+   of an OBJ, PLY or scene file as one group. Paths are relative to the file
+   that names them.
+
+   Shapes, groups and the camera can have an `animate` list of keyframes,
+   each with a `time` and the keys it changes: a `transform` for shapes, and
+   `from`, `to`, `up` or `field-of-view` for the camera. Transforms blend
+   step by step, so keys should list the same steps. A camera `shutter` of
+   `[open, close]`, measured from each frame's time, blurs what moves while
+   it is open, averaged over `motion-samples` times.
+
+   A `map` pattern paints a `uv_pattern` onto a shape through a `spherical`,
+   `planar`, `cylindrical`, `conical` or `cubic` `mapping`, or a `cube`
+   mapping with one for each of `left`, `front`, `right`, `back`, `up` and
+   `down`. Image files are read relative to the scene file too.
+*/
+#[derive(Debug)]
+pub struct Scene {
+    camera: Option<SceneCamera>,
+    camera_keys: Option<Keyframes<SceneCamera>>,
+    animations: Vec<(ShapeContainer, Keyframes<Vec<TransformStep>>)>,
+    render: RenderSettings,
+    world: World,
+    files: Vec<PathBuf>,
+    time: f64,
+}
+
+impl Scene {
+    pub fn parse_file<T: AsRef<Path>>(path: T) -> RayTraceResult<Self> {
+        let mut builder = SceneBuilder::new(PathBuf::new(), vec![]);
+        builder.include(path.as_ref())?;
+
+        Ok(Self::from_builder(builder))
+    }
+
+    /**
+       Parse a scene from a string, resolving paths in it against the
+       current directory.
+    */
+    pub fn parse(source: &str) -> RayTraceResult<Self> {
+        let mut builder = SceneBuilder::new(PathBuf::new(), vec![]);
+        builder.items(source)?;
+
+        Ok(Self::from_builder(builder))
+    }
+
+    fn from_builder(builder: SceneBuilder) -> Self {
+        Self {
+            camera: builder.camera,
+            camera_keys: builder.camera_keys,
+            animations: builder.animations.into_inner(),
+            render: RenderSettings::default(),
+            world: builder.world,
+            files: builder.files.into_inner(),
+            time: 0.0,
+        }
+    }
+
+    /**
+       Parse the scene at `path`, hand it to `on_change`, then check every
+       `interval` whether the scene file or any file it reads has changed,
+       parsing it and calling `on_change` again when one has. Parse errors
+       are passed on too, so a half saved file does not end the watch, and
+       the files read before the error are still watched. Stops when
+       `on_change` breaks.
+    */
+    pub fn watch<T: AsRef<Path>>(
+        path: T,
+        interval: Duration,
+        mut on_change: impl FnMut(RayTraceResult<Scene>) -> ControlFlow<()>,
+    ) {
+        let path = path.as_ref();
+        // Files a scene reads are kept canonical, so the first parse's
+        // versions are only found under the canonical path.
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let mut files = vec![path.clone()];
+
+        loop {
+            // Taken before parsing, so an edit made while parsing is seen.
+            let before = files
+                .iter()
+                .cloned()
+                .zip(file_versions(&files))
+                .collect::<HashMap<_, _>>();
+            let scene = Self::parse_file(&path);
+            // A scene that fails to parse may not get as far as reading the
+            // files it includes, so keep watching the ones it read last time.
+            if let Ok(scene) = &scene {
+                files = scene.files.clone();
+            }
+            let versions = files
+                .iter()
+                .map(|file| {
+                    before
+                        .get(file)
+                        .copied()
+                        .unwrap_or_else(|| file_version(file))
+                })
+                .collect::<Vec<_>>();
+
+            if on_change(scene).is_break() {
+                return;
+            }
+            while file_versions(&files) == versions {
+                thread::sleep(interval);
+            }
+        }
+    }
+
+    pub fn camera(&self) -> Option<&SceneCamera> {
+        self.camera.as_ref()
+    }
+
+    /**
+       Every file read to build the scene: the scene file itself, the files
+       it includes and the models it adds.
+    */
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /**
+       Shrink the camera's image by `divisor` on each side and render a
+       single pass, for quick previews.
+    */
+    pub fn preview(&mut self, divisor: usize) {
+        let divisor = divisor.max(1);
+        let camera = self.camera.get_or_insert_with(SceneCamera::default);
+        camera.width = (camera.width / divisor).max(1);
+        camera.height = (camera.height / divisor).max(1);
+        self.render.samples = 1;
+    }
+
+    pub fn render_settings(&self) -> RenderSettings {
+        self.render
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /**
+       Change a setting after the scene has been read, from a `key=value`
+       assignment such as `camera.width=1920` or `render.samples=64`. Values
+       are written as in the scene file, so `camera.from=[0, 2, -5]` works.
+       Setting up a camera this way adds one if the scene had none.
+    */
+    pub fn set(&mut self, assignment: &str) -> RayTraceResult<()> {
+        let (key, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| invalid(&format!("expected key=value but found {assignment}")))?;
+        let value = YamlLoader::load_from_str(value)
+            .map_err(|e| RayTraceError::InvalidFormat(e.to_string()))?
+            .into_iter()
+            .next()
+            .unwrap_or(Yaml::Null);
+
+        if let Some(key) = key.trim().strip_prefix("camera.") {
+            let camera = self.camera.get_or_insert_with(SceneCamera::default);
+            match key {
+                "width" => camera.width = integer(&value)?,
+                "height" => camera.height = integer(&value)?,
+                "field-of-view" => camera.field_of_view = number(&value)?,
+                "from" => camera.from = point(&value)?,
+                "to" => camera.to = point(&value)?,
+                "up" => camera.up = vector(&value)?,
+                "shutter" => camera.shutter = pair(&value)?,
+                "motion-samples" => camera.motion_samples = integer(&value)?.max(1),
+                _ => return Err(invalid(&format!("unknown camera setting {key}"))),
+            }
+            return Ok(());
+        }
+
+        match key.trim() {
+            "render.samples" => self.render.samples = integer(&value)?.max(1),
+            "render.seed" => self.render.seed = Some(integer(&value)? as u64),
+            "render.max-depth" => self.world.set_max_depth(integer(&value)?),
+            "render.precision" => self.world.set_precision(precision(&value)?),
+            key => return Err(invalid(&format!("unknown setting {key}"))),
+        }
+
+        Ok(())
+    }
+
+    /**
+       Render the scene from its camera, or a default one if it has none,
+       averaging `render.samples` passes jittered within each pixel.
+    */
+    pub fn render(&self) -> Canvas {
+        let camera = self.camera.unwrap_or_default();
+        self.render_tile(Tile::new(0, 0, camera.width, camera.height))
+    }
+
+    /**
+       Render only the pixels of `tile`, the same way `render` renders the
+       whole image.
+    */
+    pub fn render_tile(&self, tile: Tile) -> Canvas {
+        let mut shutter = self.camera.unwrap_or_default().to_camera();
+        if let Some(seed) = self.frame_seed() {
+            shutter = shutter.with_seed(seed);
+        }
+        let mut image = Canvas::new(tile.width(), tile.height());
+        let mut passes = 0;
+
+        for (sample, offset) in shutter.shutter_times().into_iter().enumerate() {
+            let camera = if offset == 0.0 {
+                self.camera.unwrap_or_default()
+            } else {
+                self.move_shapes(self.time + offset);
+                self.keyed_camera(self.time + offset)
+                    .or(self.camera)
+                    .unwrap_or_default()
+            };
+            let mut camera = camera.to_camera();
+            if let Some(seed) = shutter.motion_seed(sample) {
+                camera = camera.with_seed(seed);
+            }
+            if self.render.samples > 1 {
+                camera = camera.with_accumulation();
+            }
+
+            for _ in 0..self.render.samples {
+                passes += 1;
+                image.accumulate(&camera.render_tile(&self.world, tile), 1.0 / passes as f64);
+                camera.next_pass();
+            }
+        }
+        if shutter.shutter_close() > shutter.shutter_open() {
+            self.move_shapes(self.time);
+        }
+
+        image
+    }
+
+    /**
+       The seed to render the current frame with. Each frame of an animated
+       scene gets its own, made from `render.seed` and the frame's time, so
+       any frame can be rendered again on its own and come out the same.
+    */
+    pub fn frame_seed(&self) -> Option<u64> {
+        let seed = self.render.seed?;
+        if self.animation_range().is_some() {
+            Some(mix_seed(seed, self.time.to_bits()))
+        } else {
+            Some(seed)
+        }
+    }
+
+    /**
+       The times of the first and last keyframes in the scene, or `None` if
+       nothing in it is animated.
+    */
+    pub fn animation_range(&self) -> Option<(f64, f64)> {
+        self.animations
+            .iter()
+            .map(|(_, keys)| (keys.start(), keys.end()))
+            .chain(
+                self.camera_keys
+                    .iter()
+                    .map(|keys| (keys.start(), keys.end())),
+            )
+            .filter_map(|(start, end)| Some((start?, end?)))
+            .reduce(|(start, end), (first, last)| (start.min(first), end.max(last)))
+    }
+
+    /**
+       Move every animated shape, and the camera if it is animated, to where
+       its keyframes put it at `time`. The camera keeps its image size, so
+       a preview stays small.
+    */
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+        self.move_shapes(time);
+        if let Some(keyed) = self.keyed_camera(time) {
+            self.camera = Some(keyed);
+        }
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    fn move_shapes(&self, time: f64) {
+        for (shape, keys) in &self.animations {
+            if let Some(steps) = keys.at(time) {
+                shape
+                    .write()
+                    .unwrap()
+                    .set_transformation(TransformStep::transformation(&steps));
+                let parent = shape.read().unwrap().parent();
+                if let Some(parent) = parent {
+                    parent.refresh_bounds();
+                }
+            }
+        }
+    }
+
+    /**
+       Where the camera's keyframes put it at `time`, keeping the current
+       camera's image size and shutter.
+    */
+    fn keyed_camera(&self, time: f64) -> Option<SceneCamera> {
+        let keyed = self.camera_keys.as_ref()?.at(time)?;
+
+        Some(match self.camera {
+            Some(camera) => SceneCamera {
+                width: camera.width,
+                height: camera.height,
+                shutter: camera.shutter,
+                motion_samples: camera.motion_samples,
+                ..keyed
+            },
+            None => keyed,
+        })
+    }
+
+    /**
+       The times of `frames` frames spread evenly from the first keyframe to
+       the last.
+    */
+    pub fn frame_times(&self, frames: usize) -> Vec<f64> {
+        let (start, end) = self.animation_range().unwrap_or((0.0, 0.0));
+
+        (0..frames)
+            .map(|frame| {
+                if frames > 1 {
+                    start + (end - start) * frame as f64 / (frames - 1) as f64
+                } else {
+                    start
+                }
+            })
+            .collect()
+    }
+
+    /**
+       Render `frames` frames spread evenly from the first keyframe to the
+       last, saving each under `pattern` with its frame number filled in by
+       `frame_name`, along with how long it took to render. The scene is
+       left at the last frame's time.
+    */
+    pub fn render_animation(&mut self, frames: usize, pattern: &str) -> RayTraceResult<()> {
+        for (frame, time) in self.frame_times(frames).into_iter().enumerate() {
+            self.set_time(time);
+
+            let started = Instant::now();
+            let image = self.render();
+            let metadata = RenderMetadata::new()
+                .with_samples(self.render.samples)
+                .with_duration(started.elapsed());
+            image.save_with_metadata(&frame_name(pattern, frame), &metadata)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn into_world(self) -> World {
+        self.world
+    }
+}
+
+#[derive(Debug)]
+struct SceneBuilder {
+    camera: Option<SceneCamera>,
+    camera_keys: Option<Keyframes<SceneCamera>>,
+    animations: RefCell<Vec<(ShapeContainer, Keyframes<Vec<TransformStep>>)>>,
+    world: World,
+    defines: HashMap<String, Yaml>,
+    directory: PathBuf,
+    including: Vec<PathBuf>,
+    files: RefCell<Vec<PathBuf>>,
+}
+
+impl SceneBuilder {
+    fn new(directory: PathBuf, including: Vec<PathBuf>) -> Self {
+        Self {
+            camera: None,
+            camera_keys: None,
+            animations: RefCell::new(vec![]),
+            world: World::new(),
+            defines: HashMap::new(),
+            directory,
+            including,
+            files: RefCell::new(vec![]),
+        }
+    }
+
+    fn items(&mut self, source: &str) -> RayTraceResult<()> {
+        let documents = YamlLoader::load_from_str(source)
+            .map_err(|e| RayTraceError::InvalidFormat(e.to_string()))?;
+
+        for document in &documents {
+            let items = document
+                .as_vec()
+                .ok_or_else(|| invalid("a scene must be a list of items"))?;
+            for item in items {
+                self.item(item)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+       The full path of a file named relative to the file being read. Files
+       that are already being read are refused, as reading them again would
+       never end.
+    */
+    fn resolve(&self, path: &Path) -> RayTraceResult<PathBuf> {
+        let path = self.directory.join(path).canonicalize()?;
+        if self.including.contains(&path) {
+            return Err(invalid(&format!(
+                "{} includes itself",
+                path.to_string_lossy()
+            )));
+        }
+
+        let mut files = self.files.borrow_mut();
+        if !files.contains(&path) {
+            files.push(path.clone());
+        }
+        Ok(path)
+    }
+
+    fn include(&mut self, path: &Path) -> RayTraceResult<()> {
+        let path = self.resolve(path)?;
+        let source = fs::read_to_string(&path)?;
+        let directory = mem::replace(&mut self.directory, parent(&path));
+        self.including.push(path);
+
+        let result = self.items(&source);
+
+        self.including.pop();
+        self.directory = directory;
+        result
+    }
+
+    fn item(&mut self, item: &Yaml) -> RayTraceResult<()> {
+        if let Some(name) = item["define"].as_str() {
+            return self.define(name, item);
+        }
+        if let Some(path) = item["include"].as_str() {
+            return self.include(Path::new(path));
+        }
+
+        match item["add"].as_str() {
+            Some("camera") => {
+                let camera = self.camera(item, SceneCamera::default())?;
+                self.camera_keys = self.keyframes(item, |key| self.camera(key, camera))?;
+                self.camera = Some(camera);
+            }
+            Some("light") => {
+                let at = point(&item["at"])?;
+                let intensity =
+                    optional(&item["intensity"], color)?.unwrap_or(Color::new(1.0, 1.0, 1.0));
+                match optional(&item["direction"], vector)? {
+                    Some(direction) => {
+                        let mut light = SpotLight::new(at, direction, intensity);
+                        if let Some((inner, outer)) = optional(&item["cone"], pair)? {
+                            light = light.with_cone(inner, outer);
+                        }
+                        self.world.add_light(light);
+                    }
+                    None => self.world.add_light(PointLight::new(at, intensity)),
+                }
+            }
+            Some(_) => {
+                let shape = self.shape(item)?;
+                self.world.add_shape(shape);
+            }
+            None => return Err(invalid("each item must either add or define something")),
+        }
+
+        Ok(())
+    }
+
+    fn define(&mut self, name: &str, item: &Yaml) -> RayTraceResult<()> {
+        let value = item["value"].clone();
+        let value = match item["extend"].as_str() {
+            Some(parent) => match (self.lookup(parent)?.clone(), value) {
+                (Yaml::Hash(mut base), Yaml::Hash(overrides)) => {
+                    base.extend(overrides);
+                    Yaml::Hash(base)
+                }
+                (Yaml::Array(mut base), Yaml::Array(more)) => {
+                    base.extend(more);
+                    Yaml::Array(base)
+                }
+                _ => {
+                    return Err(invalid(&format!(
+                        "{name} must be the same kind of value as {parent}, which it extends"
+                    )))
+                }
+            },
+            None => value,
+        };
+
+        self.defines.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> RayTraceResult<&Yaml> {
+        self.defines
+            .get(name)
+            .ok_or_else(|| invalid(&format!("{name} has not been defined")))
+    }
+
+    /**
+       A camera from the keys of `item`, taking any it leaves out from
+       `default`.
+    */
+    fn camera(&self, item: &Yaml, default: SceneCamera) -> RayTraceResult<SceneCamera> {
+        Ok(SceneCamera {
+            width: optional(&item["width"], integer)?.unwrap_or(default.width),
+            height: optional(&item["height"], integer)?.unwrap_or(default.height),
+            field_of_view: optional(&item["field-of-view"], number)?
+                .unwrap_or(default.field_of_view),
+            from: optional(&item["from"], point)?.unwrap_or(default.from),
+            to: optional(&item["to"], point)?.unwrap_or(default.to),
+            up: optional(&item["up"], vector)?.unwrap_or(default.up),
+            shutter: optional(&item["shutter"], pair)?.unwrap_or(default.shutter),
+            motion_samples: optional(&item["motion-samples"], integer)?
+                .map_or(default.motion_samples, |samples| samples.max(1)),
+        })
+    }
+
+    /**
+       The item with the keys of the shape it adds merged underneath, if it
+       adds a defined shape rather than a built in one.
+    */
+    fn resolve_shape(&self, item: &Yaml) -> RayTraceResult<Hash> {
+        self.resolve_shape_within(item, &mut vec![])
+    }
+
+    /**
+       Resolves `item`, given the defined shapes already being resolved
+       further up, so that defines adding each other are refused instead of
+       followed forever.
+    */
+    fn resolve_shape_within(
+        &self,
+        item: &Yaml,
+        resolving: &mut Vec<String>,
+    ) -> RayTraceResult<Hash> {
+        let mut hash = item
+            .as_hash()
+            .cloned()
+            .ok_or_else(|| invalid("shapes must be described by a map"))?;
+        let kind = item["add"].as_str().unwrap_or_default();
+
+        if let Some(Yaml::Hash(defined)) = self.defines.get(kind) {
+            if defined.get(&Yaml::String("add".to_string())) == Some(&Yaml::String(kind.into())) {
+                return Err(invalid(&format!("{kind} cannot add itself")));
+            }
+            if resolving.iter().any(|name| name == kind) {
+                return Err(invalid(&format!(
+                    "recursive define: {} adds {kind} again",
+                    resolving.join(" adds ")
+                )));
+            }
+            resolving.push(kind.to_string());
+            let mut base = self.resolve_shape_within(&Yaml::Hash(defined.clone()), resolving)?;
+            resolving.pop();
+            hash.remove(&Yaml::String("add".to_string()));
+            base.extend(hash);
+            hash = base;
+        }
+
+        Ok(hash)
+    }
+
+    fn shape(&self, item: &Yaml) -> RayTraceResult<ShapeContainer> {
+        let item = Yaml::Hash(self.resolve_shape(item)?);
+        let kind = item["add"].as_str().unwrap_or_default();
+
+        let shape: ShapeContainer = match kind {
+            "sphere" => Sphere::new().into(),
+            "plane" => Plane::new().into(),
+            "cube" => Cube::new().into(),
+            "half-space" => HalfSpace::new().into(),
+            "prism" => {
+                ConvexPolytope::prism(optional(&item["sides"], integer)?.unwrap_or(6)).into()
+            }
+            "frustum" => ConvexPolytope::frustum(
+                optional(&item["sides"], integer)?.unwrap_or(6),
+                number(&item["top"])?,
+            )
+            .into(),
+            "shell" => Shell::new(
+                optional(&item["radius"], number)?.unwrap_or(1.0),
+                number(&item["inner-radius"])?,
+            )
+            .into(),
+            "cylinder" => {
+                let mut cylinder = Cylinder::new();
+                if let Some(minimum) = optional(&item["min"], number)? {
+                    cylinder.set_minimum(minimum);
+                }
+                if let Some(maximum) = optional(&item["max"], number)? {
+                    cylinder.set_maximum(maximum);
+                }
+                cylinder.set_closed(item["closed"].as_bool().unwrap_or(false));
+                if let Some((radius_x, radius_z)) = optional(&item["radius"], radii)? {
+                    cylinder.set_radii(radius_x, radius_z);
+                }
+                if let Some((theta_min, theta_max)) = optional(&item["sweep"], pair)? {
+                    cylinder.set_sweep(Sweep::new(theta_min, theta_max));
+                }
+                cylinder.into()
+            }
+            "cone" => {
+                let mut cone = Cone::new();
+                if let Some(minimum) = optional(&item["min"], number)? {
+                    cone.set_minimum(minimum);
+                }
+                if let Some(maximum) = optional(&item["max"], number)? {
+                    cone.set_maximum(maximum);
+                }
+                cone.set_closed(item["closed"].as_bool().unwrap_or(false));
+                if let Some((radius_x, radius_z)) = optional(&item["radius"], radii)? {
+                    cone.set_radii(radius_x, radius_z);
+                }
+                if let Some((theta_min, theta_max)) = optional(&item["sweep"], pair)? {
+                    cone.set_sweep(Sweep::new(theta_min, theta_max));
+                }
+                cone.into()
+            }
+            "group" => return self.group(&item),
+            "file" => return self.file(&item),
+            _ => return Err(invalid(&format!("unknown shape {kind}"))),
+        };
+        self.animate(&shape, &item)?;
+
+        if !item["transform"].is_badvalue() {
+            shape
+                .write()
+                .unwrap()
+                .set_transformation(self.transform(&item["transform"])?);
+        }
+        if !item["material"].is_badvalue() {
+            shape
+                .write()
+                .unwrap()
+                .set_material(self.material(&item["material"])?);
+        }
+
+        Ok(shape)
+    }
+
+    fn group(&self, item: &Yaml) -> RayTraceResult<ShapeContainer> {
+        let group = GroupContainer::from(Group::new());
+
+        for child in item["children"].as_vec().into_iter().flatten() {
+            group.add_child(self.shape(child)?);
+        }
+
+        self.place_group(group, item)
+    }
+
+    /**
+       The shapes from an OBJ, PLY or scene file as one group, chosen by the
+       file's extension. Lights and cameras in scene files are left out.
+    */
+    fn file(&self, item: &Yaml) -> RayTraceResult<ShapeContainer> {
+        let path = item["path"]
+            .as_str()
+            .ok_or_else(|| invalid("a file needs a path"))?;
+        let path = self.resolve(Path::new(path))?;
+        let group_size = optional(&item["group-size"], integer)?.unwrap_or(64);
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+
+        let group = match extension.as_deref() {
+            Some("obj") => OBJParser::parse_file(&path)?.as_group(group_size),
+            Some("ply") => PLYParser::parse_file(&path)?.as_group(group_size),
+            Some("yml" | "yaml") => {
+                let mut including = self.including.clone();
+                including.push(path.clone());
+                let mut builder = SceneBuilder::new(parent(&path), including);
+                builder.items(&fs::read_to_string(&path)?)?;
+                self.files.borrow_mut().extend(builder.files.take());
+                self.animations
+                    .borrow_mut()
+                    .extend(builder.animations.take());
+
+                let group = GroupContainer::from(Group::new());
+                for shape in builder.world.shapes() {
+                    group.add_child(shape.clone());
+                }
+                group
+            }
+            _ => {
+                return Err(invalid(&format!(
+                    "cannot add {}, only OBJ, PLY and scene files",
+                    path.to_string_lossy()
+                )))
+            }
+        };
+
+        self.place_group(group, item)
+    }
+
+    fn place_group(&self, group: GroupContainer, item: &Yaml) -> RayTraceResult<ShapeContainer> {
+        if !item["transform"].is_badvalue() {
+            group
+                .write()
+                .unwrap()
+                .set_transformation(self.transform(&item["transform"])?);
+        }
+        if !item["material"].is_badvalue() {
+            group.set_material_recursive(self.material(&item["material"])?);
+        }
+
+        let group = group.into();
+        self.animate(&group, item)?;
+        Ok(group)
+    }
+
+    /**
+       Keyframes from the `animate` list of `item`, each made by `key` from
+       one entry of the list.
+    */
+    fn keyframes<T: Interpolate>(
+        &self,
+        item: &Yaml,
+        key: impl Fn(&Yaml) -> RayTraceResult<T>,
+    ) -> RayTraceResult<Option<Keyframes<T>>> {
+        if item["animate"].is_badvalue() {
+            return Ok(None);
+        }
+        let entries = item["animate"]
+            .as_vec()
+            .ok_or_else(|| invalid("animate must be a list of keyframes"))?;
+
+        entries
+            .iter()
+            .try_fold(Keyframes::new(), |keys, entry| {
+                Ok(keys.with_key(number(&entry["time"])?, key(entry)?))
+            })
+            .map(Some)
+    }
+
+    /**
+       Keyframe the transform of `shape` if `item` animates it. Keys without
+       a transform keep the shape's own.
+    */
+    fn animate(&self, shape: &ShapeContainer, item: &Yaml) -> RayTraceResult<()> {
+        let base = if item["transform"].is_badvalue() {
+            vec![]
+        } else {
+            self.steps(&item["transform"])?
+        };
+        let keys = self.keyframes(item, |key| {
+            if key["transform"].is_badvalue() {
+                Ok(base.clone())
+            } else {
+                self.steps(&key["transform"])
+            }
+        })?;
+
+        if let Some(keys) = keys {
+            self.animations.borrow_mut().push((shape.clone(), keys));
+        }
+        Ok(())
+    }
+
+    /**
+       A transformation from a list of steps applied in order. Each step is
+       either `[operation, arguments...]` or the name of a defined list of
+       steps.
+    */
+    fn transform(&self, value: &Yaml) -> RayTraceResult<Transformation> {
+        Ok(TransformStep::transformation(&self.steps(value)?))
+    }
+
+    /**
+       The steps of a transform, with defined lists of steps expanded in
+       place.
+    */
+    fn steps(&self, value: &Yaml) -> RayTraceResult<Vec<TransformStep>> {
+        let mut steps = vec![];
+        self.collect_steps(value, &mut steps)?;
+        Ok(steps)
+    }
+
+    fn collect_steps(&self, value: &Yaml, steps: &mut Vec<TransformStep>) -> RayTraceResult<()> {
+        let items = match value {
+            Yaml::String(name) => return self.collect_steps(self.lookup(name)?, steps),
+            Yaml::Array(items) => items,
+            _ => return Err(invalid("a transform must be a list of steps")),
+        };
+
+        for step in items {
+            let Yaml::Array(step) = step else {
+                self.collect_steps(step, steps)?;
+                continue;
+            };
+            let operation = step
+                .first()
+                .and_then(Yaml::as_str)
+                .ok_or_else(|| invalid("a transform step must start with its operation"))?;
+            let args = step[1..]
+                .iter()
+                .map(number)
+                .collect::<RayTraceResult<Vec<_>>>()?;
+
+            steps.push(match (operation, args.as_slice()) {
+                ("translate", &[x, y, z]) => TransformStep::Translate(x, y, z),
+                ("scale", &[x, y, z]) => TransformStep::Scale(x, y, z),
+                ("rotate-x", &[radians]) => TransformStep::RotateX(radians),
+                ("rotate-y", &[radians]) => TransformStep::RotateY(radians),
+                ("rotate-z", &[radians]) => TransformStep::RotateZ(radians),
+                ("shear", &[xy, xz, yx, yz, zx, zy]) => {
+                    TransformStep::Shear(xy, xz, yx, yz, zx, zy)
+                }
+                _ => {
+                    return Err(invalid(&format!(
+                        "{operation} does not take {} arguments",
+                        args.len()
+                    )))
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /**
+       A material from a map of its properties, or the name of a defined
+       one. Properties that are not given keep their defaults.
+    */
+    fn material(&self, value: &Yaml) -> RayTraceResult<Material> {
+        let value = match value {
+            Yaml::String(name) => self.lookup(name)?,
+            value => value,
+        };
+        if value.as_hash().is_none() {
+            return Err(invalid("a material must be a map of its properties"));
+        }
+
+        let mut material = Material::new();
+        if let Some(color) = optional(&value["color"], color)? {
+            material = material.with_color(color);
+        }
+        if !value["pattern"].is_badvalue() {
+            material = self.pattern(material, &value["pattern"])?;
+        }
+        for (key, with) in [
+            (
+                "ambient",
+                Material::with_ambient as fn(Material, f64) -> Material,
+            ),
+            ("diffuse", Material::with_diffuse),
+            ("specular", Material::with_specular),
+            ("shininess", Material::with_shininess),
+            ("reflective", Material::with_reflective),
+            ("transparency", Material::with_transparency),
+            ("refractive-index", Material::with_refractive_index),
+        ] {
+            if let Some(value) = optional(&value[key], number)? {
+                material = with(material, value);
+            }
+        }
+
+        Ok(material)
+    }
+
+    fn pattern(&self, material: Material, value: &Yaml) -> RayTraceResult<Material> {
+        let transformation = if value["transform"].is_badvalue() {
+            Transformation::identity()
+        } else {
+            self.transform(&value["transform"])?
+        };
+
+        let fade = optional(&value["fade"], fade)?;
+
+        fn with<T: Pattern + 'static>(
+            material: Material,
+            mut pattern: T,
+            transformation: Transformation,
+            fade: Option<(Color, f64, f64, Tuple)>,
+        ) -> Material {
+            pattern.set_transformation(transformation);
+            match fade {
+                Some((base, start, end, center)) => material
+                    .with_pattern(FadePattern::new(pattern, base, start, end).with_center(center)),
+                None => material.with_pattern(pattern),
+            }
+        }
+
+        if value["type"].as_str() == Some("map") && value["mapping"].as_str() == Some("cube") {
+            let face = |name: &str| self.uv_pattern(&value[name]);
+            let pattern = CubeMapPattern::new(
+                face("left")?,
+                face("front")?,
+                face("right")?,
+                face("back")?,
+                face("up")?,
+                face("down")?,
+            );
+            return Ok(with(material, pattern, transformation, fade));
+        }
+        if value["type"].as_str() == Some("map") {
+            let pattern = TextureMapPattern::new(
+                self.uv_pattern(&value["uv_pattern"])?,
+                uv_map(&value["mapping"])?,
+            );
+            return Ok(with(material, pattern, transformation, fade));
+        }
+
+        let &[a, b] = two_colors(&value["colors"])?.as_slice() else {
+            return Err(invalid("a pattern needs two colors"));
+        };
+
+        Ok(match value["type"].as_str() {
+            Some("stripes") => with(material, StripePattern::new(a, b), transformation, fade),
+            Some("gradient") => with(material, GradientPattern::new(a, b), transformation, fade),
+            Some("rings") => with(material, RingPattern::new(a, b), transformation, fade),
+            Some("checkers") => with(material, CheckerPattern::new(a, b), transformation, fade),
+            Some("grid") => {
+                let mut grid = GridPattern::new(a, b);
+                if let Some(width) = optional(&value["width"], number)? {
+                    grid = grid.with_line_width(width);
+                }
+                with(material, grid, transformation, fade)
+            }
+            kind => return Err(invalid(&format!("unknown pattern {kind:?}"))),
+        })
+    }
+
+    /**
+       The pattern a `map` pattern paints across the unit square: `checkers`
+       with a `width` and `height`, an `align_check` with a `main` color and
+       one for each corner, or an `image` read from a PPM or PNG `file`.
+    */
+    fn uv_pattern(&self, value: &Yaml) -> RayTraceResult<Box<dyn UvPattern>> {
+        Ok(match value["type"].as_str() {
+            Some("checkers") => {
+                let &[a, b] = two_colors(&value["colors"])?.as_slice() else {
+                    return Err(invalid("uv checkers need two colors"));
+                };
+                Box::new(UvCheckers::new(
+                    number(&value["width"])?,
+                    number(&value["height"])?,
+                    a,
+                    b,
+                ))
+            }
+            Some("align_check") => {
+                let colors = &value["colors"];
+                Box::new(UvAlignCheck::new(
+                    color(&colors["main"])?,
+                    color(&colors["ul"])?,
+                    color(&colors["ur"])?,
+                    color(&colors["bl"])?,
+                    color(&colors["br"])?,
+                ))
+            }
+            Some("image") => {
+                let path = value["file"]
+                    .as_str()
+                    .ok_or_else(|| invalid("an image needs a file"))?;
+                Box::new(UvImage::new(image::load(self.resolve(Path::new(path))?)?))
+            }
+            kind => return Err(invalid(&format!("unknown uv pattern {kind:?}"))),
+        })
+    }
+}
+
+/**
+   When and how large each file was last changed, to notice edits.
+*/
+fn file_versions(files: &[PathBuf]) -> Vec<Option<(SystemTime, u64)>> {
+    files.iter().map(|file| file_version(file)).collect()
+}
+
+fn file_version(file: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(file).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn parent(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+fn invalid(message: &str) -> RayTraceError {
+    RayTraceError::InvalidFormat(message.to_string())
+}
+
+fn optional<T>(value: &Yaml, parse: fn(&Yaml) -> RayTraceResult<T>) -> RayTraceResult<Option<T>> {
+    if value.is_badvalue() {
+        Ok(None)
+    } else {
+        parse(value).map(Some)
+    }
+}
+
+fn number(value: &Yaml) -> RayTraceResult<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_i64().map(|i| i as f64))
+        .ok_or_else(|| invalid(&format!("expected a number but found {value:?}")))
+}
+
+fn integer(value: &Yaml) -> RayTraceResult<usize> {
+    value
+        .as_i64()
+        .and_then(|i| usize::try_from(i).ok())
+        .ok_or_else(|| invalid(&format!("expected a whole number but found {value:?}")))
+}
+
+fn triple(value: &Yaml) -> RayTraceResult<(f64, f64, f64)> {
+    match value.as_vec().map(Vec::as_slice) {
+        Some([x, y, z]) => Ok((number(x)?, number(y)?, number(z)?)),
+        _ => Err(invalid(&format!("expected [x, y, z] but found {value:?}"))),
+    }
+}
+
+fn pair(value: &Yaml) -> RayTraceResult<(f64, f64)> {
+    match value.as_vec().map(Vec::as_slice) {
+        Some([a, b]) => Ok((number(a)?, number(b)?)),
+        _ => Err(invalid(&format!("expected [a, b] but found {value:?}"))),
+    }
+}
+
+/**
+   A single radius, or `[x, z]` radii for an elliptical cross section.
+*/
+fn radii(value: &Yaml) -> RayTraceResult<(f64, f64)> {
+    if value.as_vec().is_some() {
+        pair(value)
+    } else {
+        number(value).map(|radius| (radius, radius))
+    }
+}
+
+fn point(value: &Yaml) -> RayTraceResult<Tuple> {
+    triple(value).map(|(x, y, z)| Tuple::point(x, y, z))
+}
+
+fn vector(value: &Yaml) -> RayTraceResult<Tuple> {
+    triple(value).map(|(x, y, z)| Tuple::vector(x, y, z))
+}
+
+fn color(value: &Yaml) -> RayTraceResult<Color> {
+    triple(value).map(|(r, g, b)| Color::new(r, g, b))
+}
+
+fn two_colors(value: &Yaml) -> RayTraceResult<Vec<Color>> {
+    Ok(value
+        .as_vec()
+        .map(|colors| colors.iter().map(color).collect::<RayTraceResult<Vec<_>>>())
+        .transpose()?
+        .unwrap_or_default())
+}
+
+fn uv_map(value: &Yaml) -> RayTraceResult<UvMap> {
+    Ok(match value.as_str() {
+        Some("spherical") => UvMap::Spherical,
+        Some("planar") => UvMap::planar(),
+        Some("cylindrical") => UvMap::Cylindrical,
+        Some("conical") => UvMap::Conical,
+        Some("cubic") => UvMap::Cubic,
+        mapping => return Err(invalid(&format!("unknown mapping {mapping:?}"))),
+    })
+}
+
+/**
+   A precision policy written as `{ absolute: 0.00001 }`, `{ relative: 1e-9 }`
+   or `{ ulps: 4 }`.
+*/
+fn precision(value: &Yaml) -> RayTraceResult<PrecisionPolicy> {
+    let entries = value.as_hash().map(|hash| hash.iter().collect::<Vec<_>>());
+    match entries.as_deref() {
+        Some([(kind, amount)]) => match kind.as_str() {
+            Some("absolute") => Ok(PrecisionPolicy::Absolute(number(amount)?)),
+            Some("relative") => Ok(PrecisionPolicy::Relative(number(amount)?)),
+            Some("ulps") => Ok(PrecisionPolicy::Ulps(integer(amount)? as u64)),
+            _ => Err(invalid(&format!("unknown precision {kind:?}"))),
+        },
+        _ => Err(invalid(&format!(
+            "expected absolute, relative or ulps precision but found {value:?}"
+        ))),
+    }
+}
+
+/**
+   The base color a pattern fades to, the distances the fade starts and
+   ends at and the point they are measured from, the origin unless given.
+*/
+fn fade(value: &Yaml) -> RayTraceResult<(Color, f64, f64, Tuple)> {
+    Ok((
+        color(&value["color"])?,
+        number(&value["start"])?,
+        number(&value["end"])?,
+        optional(&value["center"], point)?.unwrap_or(Tuple::origin()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::{intersection::ray::Ray, light::Light, util::eq_f64};
+
+    use super::*;
+
+    /**
+       Write `files` into a fresh temporary directory, returning its path.
+    */
+    fn scene_directory(files: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("scene-{}", Uuid::new_v4()));
+        for (name, contents) in files {
+            let path = directory.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        directory
+    }
+
+    const BOOK_SCENE: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [ -6, 6, -10 ]
+  to: [ 6, 0, 6 ]
+  up: [ -0.45, 1, 0 ]
+
+- add: light
+  at: [ 50, 100, -50 ]
+  intensity: [ 1, 1, 1 ]
+
+- define: white-material
+  value:
+    color: [ 1, 1, 1 ]
+    diffuse: 0.7
+    ambient: 0.1
+    specular: 0.0
+    reflective: 0.1
+
+- define: blue-material
+  extend: white-material
+  value:
+    color: [ 0.537, 0.831, 0.914 ]
+
+- define: standard-transform
+  value:
+    - [ translate, 1, -1, 1 ]
+    - [ scale, 0.5, 0.5, 0.5 ]
+
+- define: large-object
+  value:
+    - standard-transform
+    - [ scale, 3.5, 3.5, 3.5 ]
+
+- add: plane
+  material: white-material
+  transform:
+    - [ rotate-x, 1.5707963267948966 ]
+
+- add: cube
+  material: blue-material
+  transform:
+    - large-object
+    - [ translate, 8.5, 1.5, -0.5 ]
+";
+
+    #[test]
+    fn parsing_the_camera_and_lights() {
+        let scene = Scene::parse(BOOK_SCENE).unwrap();
+        let camera = scene.camera().unwrap();
+
+        assert_eq!(100, camera.width());
+        assert_eq!(50, camera.height());
+        assert!(eq_f64(0.785, camera.field_of_view()));
+        assert_eq!(Tuple::point(-6.0, 6.0, -10.0), camera.from());
+        assert_eq!(Tuple::vector(-0.45, 1.0, 0.0), camera.up());
+        assert_eq!(
+            vec![PointLight::new(
+                Tuple::point(50.0, 100.0, -50.0),
+                Color::new(1.0, 1.0, 1.0)
+            )],
+            *scene.world().lights()
+        );
+    }
+
+    #[test]
+    fn lights_with_a_direction_are_spot_lights() {
+        let scene = Scene::parse(
+            "- add: light\n  at: [0, 10, 0]\n  direction: [0, -1, 0]\n  cone: [0.2, 0.4]\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Light::from(
+                SpotLight::new(
+                    Tuple::point(0.0, 10.0, 0.0),
+                    Tuple::vector(0.0, -1.0, 0.0),
+                    Color::new(1.0, 1.0, 1.0)
+                )
+                .with_cone(0.2, 0.4)
+            ),
+            scene.world().lights()[0]
+        );
+    }
+
+    #[test]
+    fn a_malformed_light_intensity_is_an_error() {
+        assert!(Scene::parse("- add: light\n  at: [0, 10, 0]\n  intensity: bright\n").is_err());
+        assert!(Scene::parse("- add: light\n  at: [0, 10, 0]\n  intensity: [1, 1]\n").is_err());
+    }
+
+    #[test]
+    fn an_extended_material_overrides_only_the_keys_it_gives() {
+        let scene = Scene::parse(BOOK_SCENE).unwrap();
+        let cube = scene.world().shapes()[1].read().unwrap();
+        let material = cube.material(cube.id()).unwrap();
+
+        assert_eq!(
+            Color::new(0.537, 0.831, 0.914),
+            material.pattern().color_at(Tuple::origin())
+        );
+        assert!(eq_f64(0.7, material.diffuse()));
+        assert!(eq_f64(0.1, material.ambient()));
+        assert!(eq_f64(0.0, material.specular()));
+        assert!(eq_f64(0.1, material.reflective()));
+    }
+
+    #[test]
+    fn patterns_can_fade_to_a_base_color() {
+        let scene = Scene::parse(
+            "
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      colors:
+        - [ 1, 1, 1 ]
+        - [ 0, 0, 0 ]
+      fade:
+        color: [ 0.5, 0.5, 0.5 ]
+        start: 10
+        end: 20
+",
+        )
+        .unwrap();
+        let plane = scene.world().shapes()[0].read().unwrap();
+        let material = plane.material(plane.id()).unwrap();
+
+        assert_eq!(
+            Color::new(1.0, 1.0, 1.0),
+            material.pattern().color_at(Tuple::point(0.5, 0.0, 0.5))
+        );
+        assert_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            material.pattern().color_at(Tuple::point(0.5, 0.0, 30.5))
+        );
+        let scene = Scene::parse(
+            "
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      colors:
+        - [ 1, 1, 1 ]
+        - [ 0, 0, 0 ]
+      fade:
+        color: [ 0.5, 0.5, 0.5 ]
+        start: 10
+        end: 20
+        center: [ 0, 0, 30 ]
+",
+        )
+        .unwrap();
+        let plane = scene.world().shapes()[0].read().unwrap();
+        let material = plane.material(plane.id()).unwrap();
+
+        assert_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            material.pattern().color_at(Tuple::point(0.5, 0.0, 0.5))
+        );
+        assert_eq!(
+            Color::new(1.0, 1.0, 1.0),
+            material.pattern().color_at(Tuple::point(0.5, 0.0, 30.5))
+        );
+        assert!(Scene::parse(
+            "- add: plane\n  material:\n    pattern:\n      type: checkers\n      colors: [ [ 1, 1, 1 ], [ 0, 0, 0 ] ]\n      fade:\n        start: 1\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn map_patterns_paint_uv_patterns_and_images() {
+        let directory = scene_directory(&[
+            (
+                "textures/corner.ppm",
+                "P3\n2 2\n255\n255 0 0  0 0 0\n0 0 0  0 0 0\n",
+            ),
+            (
+                "scene.yml",
+                "
+- add: sphere
+  material:
+    pattern:
+      type: map
+      mapping: spherical
+      uv_pattern:
+        type: image
+        file: textures/corner.ppm
+- add: plane
+  material:
+    pattern:
+      type: map
+      mapping: planar
+      uv_pattern:
+        type: checkers
+        width: 2
+        height: 2
+        colors: [ [ 1, 1, 1 ], [ 0, 0, 0 ] ]
+",
+            ),
+        ]);
+        let scene = Scene::parse_file(directory.join("scene.yml")).unwrap();
+        let color_at = |index: usize, point: Tuple| {
+            let shape = scene.world().shapes()[index].read().unwrap();
+            shape
+                .material(shape.id())
+                .unwrap()
+                .pattern()
+                .color_at(point)
+        };
+
+        assert_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            color_at(0, Tuple::point(0.5, 0.8, -0.3))
+        );
+        assert_eq!(
+            Color::new(1.0, 1.0, 1.0),
+            color_at(1, Tuple::point(0.25, 0.0, 0.25))
+        );
+        assert_eq!(
+            Color::new(0.0, 0.0, 0.0),
+            color_at(1, Tuple::point(0.75, 0.0, 0.25))
+        );
+        assert!(scene
+            .files()
+            .iter()
+            .any(|file| file.ends_with("textures/corner.ppm")));
+        assert!(Scene::parse(
+            "- add: sphere\n  material:\n    pattern:\n      type: map\n      mapping: wrapped\n      uv_pattern: { type: checkers, width: 1, height: 1, colors: [ [ 1, 1, 1 ], [ 0, 0, 0 ] ] }\n"
+        )
+        .is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn cube_map_patterns_paint_each_face() {
+        let face = |color: &str| {
+            format!(
+                "{{ type: align_check, colors: {{ main: {color}, ul: [ 1, 0, 0 ], ur: [ 1, 0, 0 ], bl: [ 1, 0, 0 ], br: [ 1, 0, 0 ] }} }}"
+            )
+        };
+        let source = format!(
+            "
+- add: cube
+  material:
+    pattern:
+      type: map
+      mapping: cube
+      left: {}
+      front: {}
+      right: {}
+      back: {}
+      up: {}
+      down: {}
+",
+            face("[ 0, 0, 0 ]"),
+            face("[ 0, 0, 1 ]"),
+            face("[ 0, 1, 0 ]"),
+            face("[ 0, 1, 1 ]"),
+            face("[ 1, 1, 1 ]"),
+            face("[ 1, 1, 0 ]"),
+        );
+        let scene = Scene::parse(&source).unwrap();
+        let cube = scene.world().shapes()[0].read().unwrap();
+        let material = cube.material(cube.id()).unwrap();
+        let pattern = material.pattern();
+
+        assert_eq!(
+            Color::new(0.0, 1.0, 0.0),
+            pattern.color_at(Tuple::point(1.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            pattern.color_at(Tuple::point(1.0, 0.9, 0.9))
+        );
+        assert_eq!(
+            Color::new(1.0, 1.0, 1.0),
+            pattern.color_at(Tuple::point(0.0, 1.0, 0.0))
+        );
+        assert!(Scene::parse(
+            "- add: cube\n  material:\n    pattern: { type: map, mapping: cube, left: { type: checkers } }\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn defined_transforms_expand_in_place() {
+        let scene = Scene::parse(BOOK_SCENE).unwrap();
+
+        assert_eq!(
+            Transformation::identity()
+                .translation(1.0, -1.0, 1.0)
+                .scale(0.5, 0.5, 0.5)
+                .scale(3.5, 3.5, 3.5)
+                .translation(8.5, 1.5, -0.5),
+            scene.world().shapes()[1].read().unwrap().transformation()
+        );
+        assert_eq!(
+            Transformation::identity().rotate_x(PI / 2.0),
+            scene.world().shapes()[0].read().unwrap().transformation()
+        );
+    }
+
+    #[test]
+    fn adding_a_defined_shape_overrides_its_keys() {
+        let scene = Scene::parse(
+            "
+- define: pillar
+  value:
+    add: cylinder
+    min: 0
+    max: 3
+    closed: true
+    material:
+      color: [ 1, 0, 0 ]
+- add: pillar
+  max: 2
+  transform:
+    - [ translate, 1, 0, 0 ]
+",
+        )
+        .unwrap();
+
+        let shape = scene.world().shapes()[0].read().unwrap();
+
+        assert_eq!(
+            Transformation::identity().translation(1.0, 0.0, 0.0),
+            shape.transformation()
+        );
+        assert_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            shape
+                .material(shape.id())
+                .unwrap()
+                .pattern()
+                .color_at(Tuple::origin())
+        );
+        let bounds = shape.bounds();
+        assert!(eq_f64(0.0, bounds.min().y()));
+        assert!(eq_f64(2.0, bounds.max().y()));
+    }
+
+    #[test]
+    fn cylinders_and_cones_take_a_radius_or_radii() {
+        let scene = Scene::parse(
+            "
+- add: cylinder
+  radius: 2
+- add: cone
+  min: -1
+  max: 1
+  radius: [ 3, 0.5 ]
+",
+        )
+        .unwrap();
+
+        let cylinder = scene.world().shapes()[0].read().unwrap().bounds();
+        assert!(eq_f64(2.0, cylinder.max().x()));
+        assert!(eq_f64(2.0, cylinder.max().z()));
+        let cone = scene.world().shapes()[1].read().unwrap().bounds();
+        assert!(eq_f64(3.0, cone.max().x()));
+        assert!(eq_f64(0.5, cone.max().z()));
+        assert!(Scene::parse("- add: cylinder\n  radius: [ 1, 2, 3 ]\n").is_err());
+    }
+
+    #[test]
+    fn shells_take_an_inner_radius() {
+        let scene = Scene::parse("- add: shell\n  radius: 2\n  inner-radius: 1.5\n").unwrap();
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        assert_eq!(4, scene.world().intersects(&r).len());
+        assert!(Scene::parse("- add: shell\n").is_err());
+    }
+
+    #[test]
+    fn prisms_and_frustums_take_a_number_of_sides() {
+        let scene = Scene::parse(
+            "
+- add: prism
+  sides: 4
+- add: frustum
+  top: 0.5
+",
+        )
+        .unwrap();
+
+        let prism = scene.world().shapes()[0].read().unwrap().bounds();
+        assert!(eq_f64(1.0, prism.max().x()));
+        let frustum = scene.world().shapes()[1].read().unwrap().bounds();
+        assert!(eq_f64(2.0 / 3f64.sqrt(), frustum.max().x()));
+        assert!(eq_f64(1.0, frustum.max().z()));
+        assert!(Scene::parse("- add: frustum\n").is_err());
+    }
+
+    #[test]
+    fn cylinders_and_cones_take_a_sweep() {
+        let scene = Scene::parse("- add: cylinder\n  sweep: [ 0, 3.14159 ]\n").unwrap();
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        assert_eq!(1, scene.world().intersects(&r).len());
+    }
+
+    #[test]
+    fn unknown_definitions_are_errors() {
+        assert!(Scene::parse("- add: sphere\n  material: missing\n").is_err());
+        assert!(Scene::parse("- define: a\n  extend: missing\n  value: {}\n").is_err());
+        assert!(Scene::parse("- add: teapot\n").is_err());
+    }
+
+    #[test]
+    fn defines_that_add_each_other_are_errors() {
+        let scene = Scene::parse(
+            "
+- define: a
+  value:
+    add: b
+- define: b
+  value:
+    add: a
+- add: a
+",
+        );
+
+        match scene {
+            Err(RayTraceError::InvalidFormat(message)) => {
+                assert!(message.starts_with("recursive define"), "{message}")
+            }
+            _ => panic!("expected a recursive define error"),
+        }
+    }
+
+    #[test]
+    fn included_files_share_their_definitions() {
+        let directory = scene_directory(&[
+            (
+                "scene.yml",
+                "- include: lib/materials.yml\n- add: sphere\n  material: red\n",
+            ),
+            (
+                "lib/materials.yml",
+                "- define: red\n  value:\n    color: [ 1, 0, 0 ]\n",
+            ),
+        ]);
+
+        let scene = Scene::parse_file(directory.join("scene.yml"));
+        fs::remove_dir_all(&directory).unwrap();
+
+        let scene = scene.unwrap();
+        let sphere = scene.world().shapes()[0].read().unwrap();
+        assert_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            sphere
+                .material(sphere.id())
+                .unwrap()
+                .pattern()
+                .color_at(Tuple::origin())
+        );
+    }
+
+    #[test]
+    fn adding_a_scene_file_groups_its_shapes() {
+        let directory = scene_directory(&[
+            (
+                "scene.yml",
+                "- add: file\n  path: parts/pair.yml\n  transform:\n    - [ translate, 0, 1, 0 ]\n",
+            ),
+            (
+                "parts/pair.yml",
+                "- add: sphere\n- add: light\n  at: [ 0, 10, 0 ]\n- add: file\n  path: cube.yml\n",
+            ),
+            ("parts/cube.yml", "- add: cube\n"),
+        ]);
+
+        let scene = Scene::parse_file(directory.join("scene.yml"));
+        fs::remove_dir_all(&directory).unwrap();
+
+        let scene = scene.unwrap();
+        assert_eq!(1, scene.world().shapes().len());
+        assert!(scene.world().lights().is_empty());
+        let group = scene.world().shapes()[0].read().unwrap();
+        assert_eq!(2, group.child_shapes().len());
+        assert_eq!(
+            Transformation::identity().translation(0.0, 1.0, 0.0),
+            group.transformation()
+        );
+    }
+
+    #[test]
+    fn files_that_include_themselves_are_errors() {
+        let directory = scene_directory(&[
+            ("a.yml", "- include: b.yml\n"),
+            ("b.yml", "- add: file\n  path: a.yml\n"),
+        ]);
+
+        let scene = Scene::parse_file(directory.join("a.yml"));
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert!(scene.is_err());
+    }
+
+    #[test]
+    fn settings_can_be_overridden_after_parsing() {
+        let mut scene = Scene::parse(BOOK_SCENE).unwrap();
+
+        scene.set("camera.width=1920").unwrap();
+        scene.set("camera.from=[0, 2, -5]").unwrap();
+        scene.set("render.samples=64").unwrap();
+        scene.set("render.seed=7").unwrap();
+
+        let camera = scene.camera().unwrap();
+        assert_eq!(1920, camera.width());
+        assert_eq!(50, camera.height());
+        assert_eq!(Tuple::point(0.0, 2.0, -5.0), camera.from());
+        assert_eq!(64, scene.render_settings().samples());
+        assert_eq!(Some(7), scene.render_settings().seed());
+        assert!(scene.set("camera.depth=3").is_err());
+        assert!(scene.set("render.samples").is_err());
+        assert!(scene.set("render.samples=lots").is_err());
+    }
+
+    #[test]
+    fn the_precision_is_set_on_the_world() {
+        let mut scene = Scene::parse(BOOK_SCENE).unwrap();
+        assert_eq!(PrecisionPolicy::default(), scene.world().precision());
+
+        scene.set("render.precision={ relative: 1e-9 }").unwrap();
+        assert_eq!(PrecisionPolicy::Relative(1e-9), scene.world().precision());
+        scene.set("render.precision={ ulps: 4 }").unwrap();
+        assert_eq!(PrecisionPolicy::Ulps(4), scene.world().precision());
+        assert!(scene.set("render.precision=0.1").is_err());
+        assert!(scene.set("render.precision={ exact: 0 }").is_err());
+    }
+
+    #[test]
+    fn shapes_and_the_camera_follow_their_keyframes() {
+        let mut scene = Scene::parse(
+            r#"
+- add: camera
+  width: 40
+  from: [0, 0, -5]
+  animate:
+    - time: 0
+    - time: 2
+      from: [0, 0, -9]
+- add: group
+  transform: [[rotate-y, 0]]
+  animate:
+    - time: 1
+      transform: [[rotate-y, 3.141592653589793]]
+  children:
+    - add: sphere
+      transform: [[translate, 1, 0, 0]]
+      animate:
+        - time: 0
+          transform: [[translate, 1, 0, 0]]
+        - time: 4
+          transform: [[translate, 1, 4, 0]]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(Some((0.0, 4.0)), scene.animation_range());
+
+        scene.set("camera.width=20").unwrap();
+        scene.set_time(1.0);
+        let camera = scene.camera().unwrap();
+        assert_eq!(20, camera.width());
+        assert_eq!(Tuple::point(0.0, 0.0, -7.0), camera.from());
+
+        let world = scene.world();
+        world.prepare();
+        let group = world.shapes()[0].read().unwrap();
+        let sphere = &group.child_shapes()[0];
+        assert_eq!(
+            Tuple::point(-1.0, 1.0, 0.0),
+            sphere.read().unwrap().object_to_world(Tuple::origin())
+        );
+    }
+
+    #[test]
+    fn rays_hit_animated_children_outside_their_first_bounds() {
+        let mut scene = Scene::parse(
+            "
+- add: group
+  children:
+    - add: sphere
+      animate:
+        - time: 0
+          transform: [[translate, 0, 0, 0]]
+        - time: 1
+          transform: [[translate, 5, 0, 0]]
+",
+        )
+        .unwrap();
+
+        scene.set_time(1.0);
+        let world = scene.world();
+        world.prepare();
+        let ray = |x: f64| Ray::new(Tuple::point(x, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        let hit = world.nearest_hit(&ray(5.0)).unwrap();
+        assert!(eq_f64(4.0, hit.distance()));
+        assert!(world.nearest_hit(&ray(0.0)).is_none());
+    }
+
+    #[test]
+    fn an_open_shutter_blurs_moving_shapes_the_same_way_every_render() {
+        let mut scene = Scene::parse(
+            r#"
+- add: camera
+  width: 11
+  height: 11
+  field-of-view: 1.0
+  from: [0, 0, -5]
+  shutter: [0, 1]
+  motion-samples: 6
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+- add: sphere
+  material:
+    ambient: 1
+    diffuse: 0
+    specular: 0
+  animate:
+    - time: 0
+      transform: [[translate, -3, 0, 0]]
+    - time: 2
+      transform: [[translate, 3, 0, 0]]
+"#,
+        )
+        .unwrap();
+        scene.set("render.seed=9").unwrap();
+
+        assert_eq!((0.0, 1.0), scene.camera().unwrap().shutter());
+        scene.set_time(0.5);
+        let first = scene.render();
+        let seed = scene.frame_seed();
+        scene.set_time(1.5);
+        assert_ne!(seed, scene.frame_seed());
+        scene.render();
+        scene.set_time(0.5);
+        let again = scene.render();
+
+        let center = first[(5, 5)];
+        assert!(center.red() > 0.0 && center.red() < 1.0);
+        assert!(first.into_iter().eq(again));
+    }
+
+    #[test]
+    fn animations_need_a_time_for_each_key() {
+        assert!(Scene::parse(
+            r#"
+- add: sphere
+  animate:
+    - transform: [[scale, 2, 2, 2]]
+"#
+        )
+        .is_err());
+        assert!(Scene::parse("- add: sphere\n  animate: 3\n").is_err());
+    }
+
+    #[test]
+    fn rendering_a_scene_uses_its_camera() {
+        let mut scene =
+            Scene::parse("- add: sphere\n- add: light\n  at: [ -10, 10, -10 ]\n").unwrap();
+        scene.set("camera.width=11").unwrap();
+        scene.set("camera.height=11").unwrap();
+        scene.set("render.samples=2").unwrap();
+
+        let image = scene.render();
+
+        assert_eq!(11, image.width());
+        assert_ne!(Color::new(0.0, 0.0, 0.0), image[(5, 5)]);
+        assert_eq!(Color::new(0.0, 0.0, 0.0), image[(0, 0)]);
+    }
+
+    #[test]
+    fn a_scene_knows_which_files_it_read() {
+        let directory = scene_directory(&[
+            (
+                "scene.yml",
+                "- include: materials.yml\n- add: file\n  path: parts.yml\n",
+            ),
+            ("materials.yml", "[]\n"),
+            ("parts.yml", "- add: sphere\n"),
+        ]);
+
+        let scene = Scene::parse_file(directory.join("scene.yml"));
+        let directory = directory.canonicalize().unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            vec![
+                directory.join("scene.yml"),
+                directory.join("materials.yml"),
+                directory.join("parts.yml")
+            ],
+            scene.unwrap().files()
+        );
+    }
+
+    #[test]
+    fn watching_a_scene_parses_it_again_when_it_changes() {
+        let directory = scene_directory(&[("scene.yml", "- add: sphere\n")]);
+        let path = directory.join("scene.yml");
+        let mut shape_counts = vec![];
+
+        Scene::watch(&path, Duration::from_millis(5), |scene| {
+            shape_counts.push(scene.unwrap().world().shapes().len());
+            if shape_counts.len() == 2 {
+                return ControlFlow::Break(());
+            }
+            fs::write(&path, "- add: sphere\n- add: cube\n").unwrap();
+            ControlFlow::Continue(())
+        });
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(vec![1, 2], shape_counts);
+    }
+
+    #[test]
+    fn a_scene_that_fails_to_parse_still_watches_the_files_it_read() {
+        let directory = scene_directory(&[
+            ("scene.yml", "- include: shapes.yml\n"),
+            ("shapes.yml", "- add: sphere\n"),
+        ]);
+        let path = directory.join(".").join("scene.yml");
+        let mut results = vec![];
+
+        Scene::watch(&path, Duration::from_millis(5), |scene| {
+            results.push(scene.map(|scene| scene.world().shapes().len()).ok());
+            match results.len() {
+                1 => fs::write(&path, "- include: shapes.yml\n- add: [\n").unwrap(),
+                2 => fs::write(directory.join("shapes.yml"), "- add: cube\n- add: cube\n").unwrap(),
+                _ => return ControlFlow::Break(()),
+            }
+            ControlFlow::Continue(())
+        });
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(vec![Some(1), None, None], results);
+    }
+
+    #[test]
+    fn previews_are_smaller_single_pass_renders() {
+        let mut scene = Scene::parse(BOOK_SCENE).unwrap();
+        scene.set("render.samples=16").unwrap();
+
+        scene.preview(4);
+
+        assert_eq!(25, scene.camera().unwrap().width());
+        assert_eq!(12, scene.camera().unwrap().height());
+        assert_eq!(1, scene.render_settings().samples());
+    }
+}
@@ -5,7 +5,7 @@ use std::{
 
 use crate::{intersection::ray::Ray, transformation::Transformation, tuple::Tuple, util};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct BoundedBox {
     min: Tuple,
     max: Tuple,
@@ -40,7 +40,29 @@ impl BoundedBox {
         }
     }
 
-    pub(crate) fn intersects(&self, ray: Ray) -> bool {
+    /**
+       Whether the box holds no points at all, like a fresh `empty` box or
+       the overlap of two boxes that are apart.
+    */
+    pub(crate) fn is_empty(&self) -> bool {
+        self.min.x() > self.max.x() || self.min.y() > self.max.y() || self.min.z() > self.max.z()
+    }
+
+    /**
+       Whether the box holds points and reaches nowhere near infinity,
+       unlike the bounds of a plane.
+    */
+    pub(crate) fn is_finite(&self) -> bool {
+        !self.is_empty()
+            && [self.min, self.max]
+                .iter()
+                .all(|p| p.x().is_finite() && p.y().is_finite() && p.z().is_finite())
+    }
+
+    pub(crate) fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
         let (xtmin, xtmax) = check_axis(
             ray.origin().x(),
             ray.direction().x(),
@@ -70,13 +92,21 @@ impl BoundedBox {
         }
     }
 
-    // pub(crate) fn min(&self) -> Tuple {
-    //     self.min
-    // }
-    //
-    // pub(crate) fn max(&self) -> Tuple {
-    //     self.max
-    // }
+    pub(crate) fn min(&self) -> Tuple {
+        self.min
+    }
+
+    pub(crate) fn max(&self) -> Tuple {
+        self.max
+    }
+
+    pub(crate) fn center(&self) -> Tuple {
+        Tuple::point(
+            (self.min.x() + self.max.x()) / 2.0,
+            (self.min.y() + self.max.y()) / 2.0,
+            (self.min.z() + self.max.z()) / 2.0,
+        )
+    }
 
     pub(crate) fn add_point(&mut self, point: Tuple) {
         self.min = Tuple::point(
@@ -92,11 +122,14 @@ impl BoundedBox {
     }
 
     pub(crate) fn add_box(&mut self, other: Self) {
+        if other.is_empty() {
+            return;
+        }
         self.add_point(other.min);
         self.add_point(other.max);
     }
 
-    fn contains_point(&self, point: Tuple) -> bool {
+    pub(crate) fn contains_point(&self, point: Tuple) -> bool {
         self.min.x() <= point.x()
             && point.x() <= self.max.x()
             && self.min.y() <= point.y()
@@ -105,11 +138,68 @@ impl BoundedBox {
             && point.z() <= self.max.z()
     }
 
-    fn contains_box(&self, other: Self) -> bool {
+    pub(crate) fn contains_box(&self, other: Self) -> bool {
         self.contains_point(other.min) && self.contains_point(other.max)
     }
 
+    /**
+       The region inside both boxes.
+    */
+    pub(crate) fn intersection(&self, other: &Self) -> Self {
+        let overlap = Self::new(
+            Tuple::point(
+                self.min.x().max(other.min.x()),
+                self.min.y().max(other.min.y()),
+                self.min.z().max(other.min.z()),
+            ),
+            Tuple::point(
+                self.max.x().min(other.max.x()),
+                self.max.y().min(other.max.y()),
+                self.max.z().min(other.max.z()),
+            ),
+        );
+
+        if overlap.is_empty() {
+            Self::empty()
+        } else {
+            overlap
+        }
+    }
+
+    /**
+       Cut the box in half across its longest axis, returning the lower and
+       upper halves.
+    */
+    pub fn split(&self) -> (Self, Self) {
+        let dx = self.max.x() - self.min.x();
+        let dy = self.max.y() - self.min.y();
+        let dz = self.max.z() - self.min.z();
+        let greatest = dx.max(dy).max(dz);
+
+        let (mut x0, mut y0, mut z0) = (self.min.x(), self.min.y(), self.min.z());
+        let (mut x1, mut y1, mut z1) = (self.max.x(), self.max.y(), self.max.z());
+
+        if greatest == dx {
+            x0 += dx / 2.0;
+            x1 = x0;
+        } else if greatest == dy {
+            y0 += dy / 2.0;
+            y1 = y0;
+        } else {
+            z0 += dz / 2.0;
+            z1 = z0;
+        }
+
+        (
+            Self::new(self.min, Tuple::point(x1, y1, z1)),
+            Self::new(Tuple::point(x0, y0, z0), self.max),
+        )
+    }
+
     pub(crate) fn transform(&self, transformation: Transformation) -> Self {
+        if self.is_empty() {
+            return Self::empty();
+        }
         let p0 = self.min;
         let p1 = Tuple::point(self.min.x(), self.min.y(), self.max.z());
         let p2 = Tuple::point(self.min.x(), self.max.y(), self.max.z());
@@ -137,9 +227,9 @@ mod test {
     use crate::shape::{
         cone::Cone,
         cylinder::Cylinder,
-        group::{Group, GroupContainer},
+        group::{Group, GroupContainer, Operation},
         sphere::Sphere,
-        Shape,
+        Shape, ShapeContainer,
     };
 
     use super::*;
@@ -242,6 +332,41 @@ mod test {
         }
     }
 
+    #[test]
+    fn splitting_a_bounding_box_across_its_longest_axis() {
+        let exs = [
+            (
+                (Tuple::point(-1.0, -4.0, -5.0), Tuple::point(9.0, 6.0, 5.0)),
+                (Tuple::point(-1.0, -4.0, -5.0), Tuple::point(4.0, 6.0, 5.0)),
+                (Tuple::point(4.0, -4.0, -5.0), Tuple::point(9.0, 6.0, 5.0)),
+            ),
+            (
+                (Tuple::point(-1.0, -2.0, -3.0), Tuple::point(9.0, 5.5, 3.0)),
+                (Tuple::point(-1.0, -2.0, -3.0), Tuple::point(4.0, 5.5, 3.0)),
+                (Tuple::point(4.0, -2.0, -3.0), Tuple::point(9.0, 5.5, 3.0)),
+            ),
+            (
+                (Tuple::point(-1.0, -2.0, -3.0), Tuple::point(5.0, 8.0, 3.0)),
+                (Tuple::point(-1.0, -2.0, -3.0), Tuple::point(5.0, 3.0, 3.0)),
+                (Tuple::point(-1.0, 3.0, -3.0), Tuple::point(5.0, 8.0, 3.0)),
+            ),
+            (
+                (Tuple::point(-1.0, -2.0, -3.0), Tuple::point(5.0, 3.0, 7.0)),
+                (Tuple::point(-1.0, -2.0, -3.0), Tuple::point(5.0, 3.0, 2.0)),
+                (Tuple::point(-1.0, -2.0, 2.0), Tuple::point(5.0, 3.0, 7.0)),
+            ),
+        ];
+
+        for ((min, max), (left_min, left_max), (right_min, right_max)) in exs {
+            let (left, right) = BoundedBox::new(min, max).split();
+
+            assert_eq!(left_min, left.min);
+            assert_eq!(left_max, left.max);
+            assert_eq!(right_min, right.min);
+            assert_eq!(right_max, right.max);
+        }
+    }
+
     #[test]
     fn transforming_a_bounded_box() {
         let bbox = BoundedBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0));
@@ -295,6 +420,63 @@ mod test {
         assert_eq!(bounds.max, Tuple::point(4.0, 7.0, 4.5));
     }
 
+    #[test]
+    fn a_csg_shape_is_bounded_by_what_its_operation_can_keep() {
+        let left = || {
+            let mut s = Sphere::new();
+            s.set_transformation(Transformation::identity().translation(-0.5, 0.0, 0.0));
+            ShapeContainer::from(s)
+        };
+        let right = || {
+            let mut s = Sphere::new();
+            s.set_transformation(Transformation::identity().translation(1.0, 0.0, 0.0));
+            ShapeContainer::from(s)
+        };
+
+        for (operation, min, max) in [
+            (
+                Operation::Union,
+                Tuple::point(-1.5, -1.0, -1.0),
+                Tuple::point(2.0, 1.0, 1.0),
+            ),
+            (
+                Operation::Intersection,
+                Tuple::point(0.0, -1.0, -1.0),
+                Tuple::point(0.5, 1.0, 1.0),
+            ),
+            (
+                Operation::Difference,
+                Tuple::point(-1.5, -1.0, -1.0),
+                Tuple::point(0.5, 1.0, 1.0),
+            ),
+        ] {
+            let csg = Group::csg(operation, left(), right());
+
+            let bounds = csg.read().unwrap().bounds();
+
+            assert_eq!(min, bounds.min);
+            assert_eq!(max, bounds.max);
+        }
+    }
+
+    #[test]
+    fn the_intersection_of_boxes_that_are_apart_is_empty() {
+        let a = BoundedBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0));
+        let b = BoundedBox::new(Tuple::point(2.0, -1.0, -1.0), Tuple::point(3.0, 1.0, 1.0));
+
+        let overlap = a.intersection(&b);
+        let ray = Ray::new(Tuple::point(2.5, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        assert!(overlap.is_empty());
+        assert!(!overlap.intersects(&ray));
+
+        let mut union = BoundedBox::new(Tuple::point(0.0, 0.0, 0.0), Tuple::point(1.0, 1.0, 1.0));
+        union.add_box(overlap);
+
+        assert_eq!(Tuple::point(0.0, 0.0, 0.0), union.min);
+        assert_eq!(Tuple::point(1.0, 1.0, 1.0), union.max);
+    }
+
     #[test]
     fn intersecting_a_ray_with_a_bounding_box_at_the_origin() {
         let exs = vec![
@@ -370,7 +552,7 @@ mod test {
             let direction = direction.normalize();
             let r = Ray::new(origin, direction);
 
-            assert_eq!(bbox.intersects(r), result);
+            assert_eq!(bbox.intersects(&r), result);
         }
     }
 }
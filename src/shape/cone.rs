@@ -1,28 +1,37 @@
 use std::mem::swap;
 
+use smallvec::smallvec;
 use uuid::Uuid;
 
 use crate::{
-    intersection::{ray::Ray, Intersection, ShapeIntersection},
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
     transformation::Transformation,
     tuple::Tuple,
     util::{eq_f64, EPSILON},
 };
 
-use super::{group::WeakGroupContainer, material::Material, BoundedBox, Shape};
+use super::{
+    group::WeakGroupContainer, material::Material, visibility::Visibility,
+    world_transform::WorldTransform, BoundedBox, Shape, Sweep,
+};
 
 #[derive(Debug)]
 pub struct Cone {
     id: uuid::Uuid,
     transformation: Transformation,
     material: Material,
+    visibility: Visibility,
     minimum: f64,
     maximum: f64,
     closed: bool,
+    radius_x: f64,
+    radius_z: f64,
+    sweep: Sweep,
     parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
 }
 
-fn check_cap(ray: Ray, t: f64, y: f64) -> bool {
+fn check_cap(ray: &Ray, t: f64, y: f64) -> bool {
     let x = ray.origin().x() + t * ray.direction().x();
     let z = ray.origin().z() + t * ray.direction().z();
 
@@ -35,10 +44,15 @@ impl Cone {
             id: uuid::Uuid::new_v4(),
             transformation: Transformation::default(),
             material: Material::default(),
+            visibility: Visibility::default(),
             minimum: f64::NEG_INFINITY,
             maximum: f64::INFINITY,
             closed: false,
+            radius_x: 1.0,
+            radius_z: 1.0,
+            sweep: Sweep::default(),
             parent: None,
+            world_transform: None,
         }
     }
 
@@ -66,18 +80,75 @@ impl Cone {
         self.closed = closed;
     }
 
-    fn intersect_caps(&self, ray: Ray, xs: &mut Vec<Intersection>) {
+    pub fn radius_x(&self) -> f64 {
+        self.radius_x
+    }
+
+    pub fn radius_z(&self) -> f64 {
+        self.radius_z
+    }
+
+    pub fn set_radius(&mut self, radius: f64) {
+        self.set_radii(radius, radius);
+    }
+
+    /**
+       How far the cone widens along x and z for each unit of height, which
+       differ for an elliptical cone. Unlike scaling the transformation,
+       this leaves the height and caps alone.
+    */
+    pub fn set_radii(&mut self, radius_x: f64, radius_z: f64) {
+        self.radius_x = radius_x;
+        self.radius_z = radius_z;
+    }
+
+    pub fn sweep(&self) -> Sweep {
+        self.sweep
+    }
+
+    pub fn set_sweep(&mut self, sweep: Sweep) {
+        self.sweep = sweep;
+    }
+
+    fn in_sweep(&self, ray: &Ray, t: f64) -> bool {
+        self.sweep.contains(
+            (ray.origin().x() + t * ray.direction().x()) * self.radius_x,
+            (ray.origin().z() + t * ray.direction().z()) * self.radius_z,
+        )
+    }
+
+    /**
+       `ray` in the space where the cross section is a unit circle.
+       Intersection distances are the same in both spaces.
+    */
+    fn unit_ray(&self, ray: &Ray) -> Ray {
+        let (origin, direction) = (ray.origin(), ray.direction());
+        Ray::new(
+            Tuple::point(
+                origin.x() / self.radius_x,
+                origin.y(),
+                origin.z() / self.radius_z,
+            ),
+            Tuple::vector(
+                direction.x() / self.radius_x,
+                direction.y(),
+                direction.z() / self.radius_z,
+            ),
+        )
+    }
+
+    fn intersect_caps(&self, ray: &Ray, xs: &mut Intersections) {
         if !self.closed || eq_f64(ray.direction().y(), 0.0) {
             return;
         }
 
         let t = (self.minimum - ray.origin().y()) / ray.direction().y();
-        if check_cap(ray, t, self.minimum) {
+        if check_cap(ray, t, self.minimum) && self.in_sweep(ray, t) {
             xs.push(Intersection::new(t, self.id));
         }
 
         let t = (self.maximum - ray.origin().y()) / ray.direction().y();
-        if check_cap(ray, t, self.maximum) {
+        if check_cap(ray, t, self.maximum) && self.in_sweep(ray, t) {
             xs.push(Intersection::new(t, self.id));
         }
     }
@@ -88,7 +159,8 @@ impl Shape for Cone {
         self.id
     }
 
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
+        let ray = &self.unit_ray(ray);
         let a =
             ray.direction().x().powi(2) - ray.direction().y().powi(2) + ray.direction().z().powi(2);
 
@@ -100,14 +172,16 @@ impl Shape for Cone {
         let a0 = eq_f64(a, 0.0);
         let b0 = eq_f64(b, 0.0);
 
-        let mut xs = vec![];
+        let mut xs = smallvec![];
 
         if a0 && b0 {
             self.intersect_caps(ray, &mut xs);
             return xs;
         } else if a0 {
             let t = -c / (2.0 * b);
-            xs.push(Intersection::new(t, self.id));
+            if self.in_sweep(ray, t) {
+                xs.push(Intersection::new(t, self.id));
+            }
             self.intersect_caps(ray, &mut xs);
             return xs;
         }
@@ -115,7 +189,7 @@ impl Shape for Cone {
         let disc = b.powi(2) - 4.0 * a * c;
 
         if disc < 0.0 {
-            return vec![];
+            return smallvec![];
         }
 
         let mut t0 = (-b - disc.sqrt()) / (2.0 * a);
@@ -126,12 +200,12 @@ impl Shape for Cone {
         }
 
         let y0 = ray.origin().y() + t0 * ray.direction().y();
-        if self.minimum < y0 && y0 < self.maximum {
+        if self.minimum < y0 && y0 < self.maximum && self.in_sweep(ray, t0) {
             xs.push(Intersection::new(t0, self.id))
         }
 
         let y1 = ray.origin().y() + t1 * ray.direction().y();
-        if self.minimum < y1 && y1 < self.maximum {
+        if self.minimum < y1 && y1 < self.maximum && self.in_sweep(ray, t1) {
             xs.push(Intersection::new(t1, self.id));
         }
         self.intersect_caps(ray, &mut xs);
@@ -145,6 +219,7 @@ impl Shape for Cone {
 
     fn set_transformation(&mut self, transformation: Transformation) {
         self.transformation = transformation;
+        self.world_transform = None;
     }
 
     fn material(&self, id: Uuid) -> Option<Material> {
@@ -159,6 +234,18 @@ impl Shape for Cone {
         self.material = material;
     }
 
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        if self.id == id {
+            Some(self.visibility)
+        } else {
+            None
+        }
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+
     fn local_normal_at(
         &self,
         id: Uuid,
@@ -169,6 +256,11 @@ impl Shape for Cone {
             return None;
         }
 
+        let point = Tuple::point(
+            point.x() / self.radius_x,
+            point.y(),
+            point.z() / self.radius_z,
+        );
         let dist = point.x().powi(2) + point.z().powi(2);
 
         Some(if dist < 1.0 && point.y() >= self.maximum - EPSILON {
@@ -180,7 +272,7 @@ impl Shape for Cone {
             if point.y() > 0.0 {
                 y = -y;
             }
-            Tuple::vector(point.x(), y, point.z())
+            Tuple::vector(point.x() / self.radius_x, y, point.z() / self.radius_z)
         })
     }
 
@@ -189,9 +281,18 @@ impl Shape for Cone {
     }
 
     fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
         self.parent = Some(parent.clone())
     }
 
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
     fn bounds(&self) -> BoundedBox {
         let a = self.minimum.abs();
         let b = self.maximum.abs();
@@ -201,8 +302,8 @@ impl Shape for Cone {
             a.max(b)
         };
         BoundedBox::new(
-            Tuple::point(-limit, self.minimum, -limit),
-            Tuple::point(limit, self.maximum, limit),
+            Tuple::point(-limit * self.radius_x, self.minimum, -limit * self.radius_z),
+            Tuple::point(limit * self.radius_x, self.maximum, limit * self.radius_z),
         )
     }
 
@@ -213,6 +314,8 @@ impl Shape for Cone {
 
 #[cfg(test)]
 mod tests {
+    use std::f64::consts::PI;
+
     use crate::{shape::ShapeContainer, tuple::Tuple};
 
     use super::*;
@@ -245,7 +348,7 @@ mod tests {
         for (origin, direciton, t0, t1) in exs {
             let direction = direciton.normalize();
             let r = Ray::new(origin, direction);
-            let xs = shape.local_intersect(r);
+            let xs = shape.local_intersect(&r);
 
             assert_eq!(xs.len(), 2);
             assert!(eq_f64(xs[0].t(), t0));
@@ -258,7 +361,7 @@ mod tests {
         let shape = Cone::new();
         let direction = Tuple::vector(0.0, 1.0, 1.0).normalize();
         let r = Ray::new(Tuple::point(0.0, 0.0, -1.0), direction);
-        let xs = shape.local_intersect(r);
+        let xs = shape.local_intersect(&r);
 
         assert_eq!(xs.len(), 1);
         assert!(eq_f64(xs[0].t(), 0.35355));
@@ -292,7 +395,7 @@ mod tests {
         for (origin, direction, count) in exs {
             let direction = direction.normalize();
             let r = Ray::new(origin, direction);
-            let xs = shape.local_intersect(r);
+            let xs = shape.local_intersect(&r);
 
             assert_eq!(xs.len(), count);
         }
@@ -321,4 +424,36 @@ mod tests {
             assert_eq!(n, normal);
         }
     }
+
+    #[test]
+    fn a_wider_cone_uses_its_radius() {
+        let mut shape = Cone::new();
+        shape.set_radius(2.0);
+
+        let r = Ray::new(Tuple::point(0.0, 1.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let xs = shape.local_intersect(&r);
+        assert_eq!(2, xs.len());
+        assert!(eq_f64(3.0, xs[0].t()));
+        assert!(eq_f64(7.0, xs[1].t()));
+
+        let shape = ShapeContainer::from(shape);
+        let i = ShapeIntersection::new(0.0, shape.clone(), shape.id());
+        let n = shape
+            .read()
+            .unwrap()
+            .local_normal_at(shape.id(), Tuple::point(2.0, 1.0, 0.0), i)
+            .unwrap();
+        assert_eq!(Tuple::vector(0.5, -1.0, 0.0), n);
+    }
+
+    #[test]
+    fn a_swept_cone_is_open_outside_its_angles() {
+        let mut shape = Cone::new();
+        shape.set_sweep(Sweep::new(0.0, PI));
+
+        let r = Ray::new(Tuple::point(0.0, 1.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let xs = shape.local_intersect(&r);
+        assert_eq!(1, xs.len());
+        assert!(eq_f64(6.0, xs[0].t()));
+    }
 }
@@ -0,0 +1,421 @@
+use std::f64::consts::PI;
+
+use smallvec::smallvec;
+use uuid::Uuid;
+
+use crate::{
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
+    transformation::Transformation,
+    tuple::Tuple,
+    util::EPSILON,
+};
+
+use super::{
+    group::WeakGroupContainer, half_space::HalfSpace, material::Material, visibility::Visibility,
+    world_transform::WorldTransform, BoundedBox, Shape,
+};
+
+/**
+   How far out the corners of an unbounded polytope are looked for before
+   it is treated as going on forever.
+*/
+const FAR: f64 = 1e9;
+
+/**
+   The solid where a set of half spaces overlap, like a prism, a frustum or
+   a cut gem. Rays are clipped against each face in turn, the same way a
+   cube is intersected one pair of slabs at a time.
+*/
+#[derive(Debug)]
+pub struct ConvexPolytope {
+    id: Uuid,
+    faces: Vec<(Tuple, f64)>,
+    corners: (Tuple, Tuple),
+    material: Material,
+    visibility: Visibility,
+    transformation: Transformation,
+    parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
+}
+
+impl ConvexPolytope {
+    /**
+       The overlap of `half_spaces`, each placed by its own transformation.
+    */
+    pub fn new(half_spaces: &[HalfSpace]) -> Self {
+        Self::from_planes(half_spaces.iter().map(HalfSpace::plane).collect())
+    }
+
+    /**
+       The overlap of the half spaces `n * p <= d` given as `(n, d)`.
+    */
+    pub fn from_planes(planes: Vec<(Tuple, f64)>) -> Self {
+        let faces: Vec<_> = planes
+            .into_iter()
+            .map(|(normal, distance)| {
+                let length = normal.magnitude();
+                (normal / length, distance / length)
+            })
+            .collect();
+        let corners = corners(&faces);
+
+        Self {
+            id: Uuid::new_v4(),
+            faces,
+            corners,
+            material: Material::new(),
+            visibility: Visibility::default(),
+            transformation: Transformation::identity(),
+            parent: None,
+            world_transform: None,
+        }
+    }
+
+    /**
+       A frustum of a regular pyramid with `sides` sides standing on the xz
+       plane from y = 0 to y = 1. The faces are a distance of 1 from the y
+       axis at the bottom and `top` at the top.
+    */
+    pub fn frustum(sides: usize, top: f64) -> Self {
+        let sides = sides.max(3);
+        let slope = 1.0 - top;
+        let mut planes = vec![
+            (Tuple::vector(0.0, -1.0, 0.0), 0.0),
+            (Tuple::vector(0.0, 1.0, 0.0), 1.0),
+        ];
+        for side in 0..sides {
+            let angle = 2.0 * PI * side as f64 / sides as f64;
+            planes.push((Tuple::vector(angle.sin(), slope, -angle.cos()), 1.0));
+        }
+
+        Self::from_planes(planes)
+    }
+
+    /**
+       A regular prism with `sides` sides standing on the xz plane from
+       y = 0 to y = 1, with faces a distance of 1 from the y axis.
+    */
+    pub fn prism(sides: usize) -> Self {
+        Self::frustum(sides, 1.0)
+    }
+
+    /**
+       Each face as a unit outward normal and its distance from the origin.
+    */
+    pub fn faces(&self) -> &[(Tuple, f64)] {
+        &self.faces
+    }
+
+    pub fn includes_point(&self, point: Tuple) -> bool {
+        self.faces
+            .iter()
+            .all(|&(normal, distance)| normal * point <= distance + EPSILON)
+    }
+}
+
+/**
+   The box around the corners of the polytope, found where every three faces
+   meet inside all the others. Faces far out at `FAR` close off unbounded
+   polytopes and any corner on them is pushed out to infinity.
+*/
+fn corners(faces: &[(Tuple, f64)]) -> (Tuple, Tuple) {
+    let mut planes = faces.to_vec();
+    for axis in [
+        Tuple::vector(1.0, 0.0, 0.0),
+        Tuple::vector(0.0, 1.0, 0.0),
+        Tuple::vector(0.0, 0.0, 1.0),
+    ] {
+        planes.push((axis, FAR));
+        planes.push((-axis, FAR));
+    }
+
+    let mut bounds = BoundedBox::empty();
+    for i in 0..planes.len() {
+        for j in i + 1..planes.len() {
+            for k in j + 1..planes.len() {
+                let Some(corner) = meet(planes[i], planes[j], planes[k]) else {
+                    continue;
+                };
+                let tolerance = EPSILON * (1.0 + corner.magnitude());
+                if planes.iter().all(|&(n, d)| n * corner <= d + tolerance) {
+                    bounds.add_point(corner);
+                }
+            }
+        }
+    }
+
+    let unbounded = |c: f64| {
+        if c >= FAR * 0.5 {
+            f64::INFINITY
+        } else if c <= -FAR * 0.5 {
+            f64::NEG_INFINITY
+        } else {
+            c
+        }
+    };
+    let (min, max) = (bounds.min(), bounds.max());
+    (
+        Tuple::point(unbounded(min.x()), unbounded(min.y()), unbounded(min.z())),
+        Tuple::point(unbounded(max.x()), unbounded(max.y()), unbounded(max.z())),
+    )
+}
+
+/**
+   The point where three planes meet, if they meet in exactly one.
+*/
+fn meet((n1, d1): (Tuple, f64), (n2, d2): (Tuple, f64), (n3, d3): (Tuple, f64)) -> Option<Tuple> {
+    let determinant = n1 * (n2 ^ n3);
+    if determinant.abs() < EPSILON {
+        return None;
+    }
+
+    let p = ((n2 ^ n3) * d1 + (n3 ^ n1) * d2 + (n1 ^ n2) * d3) / determinant;
+    Some(Tuple::point(p.x(), p.y(), p.z()))
+}
+
+impl Shape for ConvexPolytope {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
+        let mut t_enter = f64::NEG_INFINITY;
+        let mut t_exit = f64::INFINITY;
+
+        for &(normal, distance) in &self.faces {
+            let along = normal * ray.direction();
+            let gap = distance - normal * ray.origin();
+            if along.abs() < EPSILON {
+                if gap < 0.0 {
+                    return smallvec![];
+                }
+                continue;
+            }
+
+            let t = gap / along;
+            if along < 0.0 {
+                t_enter = t_enter.max(t);
+            } else {
+                t_exit = t_exit.min(t);
+            }
+            if t_enter > t_exit {
+                return smallvec![];
+            }
+        }
+
+        [t_enter, t_exit]
+            .into_iter()
+            .filter(|t| t.is_finite())
+            .map(|t| Intersection::new(t, self.id))
+            .collect()
+    }
+
+    fn transformation(&self) -> Transformation {
+        self.transformation.clone()
+    }
+
+    fn set_transformation(&mut self, transformation: Transformation) {
+        self.transformation = transformation;
+        self.world_transform = None;
+    }
+
+    fn material(&self, id: Uuid) -> Option<Material> {
+        if self.id == id {
+            Some(self.material.clone())
+        } else {
+            None
+        }
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        if self.id == id {
+            Some(self.visibility)
+        } else {
+            None
+        }
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+
+    /**
+       The normal of the face the point lies closest to.
+    */
+    fn local_normal_at(
+        &self,
+        id: Uuid,
+        point: Tuple,
+        _intersection: ShapeIntersection,
+    ) -> Option<Tuple> {
+        if self.id != id {
+            return None;
+        }
+
+        self.faces
+            .iter()
+            .min_by(|&&(n1, d1), &&(n2, d2)| {
+                (n1 * point - d1).abs().total_cmp(&(n2 * point - d2).abs())
+            })
+            .map(|&(normal, _)| normal)
+    }
+
+    fn parent(&self) -> Option<WeakGroupContainer> {
+        self.parent.clone()
+    }
+
+    fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
+        self.parent = Some(parent);
+    }
+
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
+    fn bounds(&self) -> BoundedBox {
+        BoundedBox::new(self.corners.0, self.corners.1)
+    }
+
+    fn contains(&self, id: Uuid) -> bool {
+        self.id == id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{shape::ShapeContainer, util::eq_f64};
+
+    use super::*;
+
+    fn unit_cube() -> ConvexPolytope {
+        let half_spaces: Vec<_> = [
+            Tuple::vector(1.0, 0.0, 0.0),
+            Tuple::vector(-1.0, 0.0, 0.0),
+            Tuple::vector(0.0, 1.0, 0.0),
+            Tuple::vector(0.0, -1.0, 0.0),
+            Tuple::vector(0.0, 0.0, 1.0),
+            Tuple::vector(0.0, 0.0, -1.0),
+        ]
+        .into_iter()
+        .map(|normal| HalfSpace::facing(Tuple::point(normal.x(), normal.y(), normal.z()), normal))
+        .collect();
+
+        ConvexPolytope::new(&half_spaces)
+    }
+
+    #[test]
+    fn six_half_spaces_make_a_cube() {
+        let cube = unit_cube();
+        let bounds = cube.bounds();
+
+        assert_eq!(Tuple::point(-1.0, -1.0, -1.0), bounds.min());
+        assert_eq!(Tuple::point(1.0, 1.0, 1.0), bounds.max());
+        for (origin, direction, t1, t2) in [
+            (
+                Tuple::point(5.0, 0.5, 0.0),
+                Tuple::vector(-1.0, 0.0, 0.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Tuple::point(0.5, 0.0, -5.0),
+                Tuple::vector(0.0, 0.0, 1.0),
+                4.0,
+                6.0,
+            ),
+            (
+                Tuple::point(0.0, 0.5, 0.0),
+                Tuple::vector(0.0, 0.0, 1.0),
+                -1.0,
+                1.0,
+            ),
+        ] {
+            let xs = cube.local_intersect(&Ray::new(origin, direction));
+
+            assert_eq!(2, xs.len());
+            assert!(eq_f64(t1, xs[0].t()));
+            assert!(eq_f64(t2, xs[1].t()));
+        }
+    }
+
+    #[test]
+    fn a_ray_misses_a_polytope() {
+        let cube = unit_cube();
+
+        for (origin, direction) in [
+            (
+                Tuple::point(-2.0, 0.0, 0.0),
+                Tuple::vector(0.2673, 0.5345, 0.8018),
+            ),
+            (Tuple::point(2.0, 0.0, 2.0), Tuple::vector(0.0, 0.0, -1.0)),
+            (Tuple::point(0.0, 2.0, 0.0), Tuple::vector(1.0, 0.0, 0.0)),
+        ] {
+            assert!(cube
+                .local_intersect(&Ray::new(origin, direction))
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn the_normal_is_that_of_the_nearest_face() {
+        let cube = ShapeContainer::from(unit_cube());
+        let id = cube.id();
+        let i = ShapeIntersection::new(0.0, cube.clone(), id);
+
+        for (point, normal) in [
+            (Tuple::point(1.0, 0.5, -0.8), Tuple::vector(1.0, 0.0, 0.0)),
+            (Tuple::point(0.4, -1.0, -0.1), Tuple::vector(0.0, -1.0, 0.0)),
+            (Tuple::point(-0.6, 0.3, 1.0), Tuple::vector(0.0, 0.0, 1.0)),
+        ] {
+            assert_eq!(
+                Some(normal),
+                cube.read().unwrap().local_normal_at(id, point, i.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn a_prism_and_a_frustum_taper_as_asked() {
+        let prism = ConvexPolytope::prism(4);
+        let frustum = ConvexPolytope::frustum(4, 0.5);
+        let up = |x: f64| Ray::new(Tuple::point(x, -5.0, 0.0), Tuple::vector(0.0, 1.0, 0.0));
+
+        assert!(eq_f64(1.0, prism.bounds().max().y()));
+        assert!(eq_f64(1.0, prism.bounds().max().x()));
+        assert_eq!(2, prism.local_intersect(&up(0.9)).len());
+        assert!(prism.includes_point(Tuple::point(0.9, 0.9, 0.0)));
+        assert!(!frustum.includes_point(Tuple::point(0.9, 0.9, 0.0)));
+        assert!(frustum.includes_point(Tuple::point(0.45, 0.9, 0.0)));
+        assert!(eq_f64(0.8, frustum.local_intersect(&up(0.6))[1].t() - 5.0));
+    }
+
+    #[test]
+    fn an_open_polytope_is_unbounded_where_it_is_open() {
+        let wedge = ConvexPolytope::from_planes(vec![
+            (Tuple::vector(0.0, -1.0, 0.0), 0.0),
+            (Tuple::vector(1.0, 1.0, 0.0), 0.0),
+        ]);
+        let bounds = wedge.bounds();
+
+        assert_eq!(f64::NEG_INFINITY, bounds.min().x());
+        assert!(eq_f64(0.0, bounds.min().y()));
+        assert_eq!(f64::INFINITY, bounds.max().y());
+        assert_eq!(f64::INFINITY, bounds.max().z());
+
+        let xs = wedge.local_intersect(&Ray::new(
+            Tuple::point(-3.0, 1.0, 0.0),
+            Tuple::vector(1.0, 0.0, 0.0),
+        ));
+        assert_eq!(1, xs.len());
+        assert!(eq_f64(2.0, xs[0].t()));
+    }
+}
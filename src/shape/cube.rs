@@ -1,22 +1,28 @@
 use std::{f64::INFINITY, mem::swap};
 
+use smallvec::smallvec;
 use uuid::Uuid;
 
 use crate::{
-    intersection::{ray::Ray, Intersection, ShapeIntersection},
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
     transformation::Transformation,
     tuple::Tuple,
     util::{self, eq_f64},
 };
 
-use super::{material::Material, BoundedBox, Shape, WeakGroupContainer};
+use super::{
+    material::Material, visibility::Visibility, world_transform::WorldTransform, BoundedBox, Shape,
+    WeakGroupContainer,
+};
 
 #[derive(Debug)]
 pub struct Cube {
     id: uuid::Uuid,
     transformation: Transformation,
     material: Material,
+    visibility: Visibility,
     parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
 }
 
 impl Cube {
@@ -25,7 +31,9 @@ impl Cube {
             id: uuid::Uuid::new_v4(),
             transformation: Transformation::default(),
             material: Material::default(),
+            visibility: Visibility::default(),
             parent: None,
+            world_transform: None,
         }
     }
 }
@@ -52,7 +60,7 @@ impl Shape for Cube {
         self.id
     }
 
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
         let (xtmin, xtmax) = check_axis(ray.origin().x(), ray.direction().x());
         let (ytmin, ytmax) = check_axis(ray.origin().y(), ray.direction().y());
         let (ztmin, ztmax) = check_axis(ray.origin().z(), ray.direction().z());
@@ -61,9 +69,9 @@ impl Shape for Cube {
         let tmax = xtmax.min(ytmax).min(ztmax);
 
         if tmin > tmax {
-            vec![]
+            smallvec![]
         } else {
-            vec![
+            smallvec![
                 Intersection::new(tmin, self.id),
                 Intersection::new(tmax, self.id),
             ]
@@ -76,6 +84,7 @@ impl Shape for Cube {
 
     fn set_transformation(&mut self, transformation: Transformation) {
         self.transformation = transformation;
+        self.world_transform = None;
     }
 
     fn material(&self, id: Uuid) -> Option<Material> {
@@ -90,6 +99,18 @@ impl Shape for Cube {
         self.material = material;
     }
 
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        if self.id == id {
+            Some(self.visibility)
+        } else {
+            None
+        }
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+
     fn local_normal_at(
         &self,
         id: Uuid,
@@ -116,9 +137,18 @@ impl Shape for Cube {
     }
 
     fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
         self.parent = Some(parent.clone());
     }
 
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
     fn bounds(&self) -> BoundedBox {
         BoundedBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0))
     }
@@ -184,7 +214,7 @@ mod tests {
         let c = Cube::new();
         for (origin, direction, t1, t2) in input {
             let r = Ray::new(origin, direction);
-            let xs = c.local_intersect(r);
+            let xs = c.local_intersect(&r);
             assert_eq!(xs.len(), 2);
             assert_eq!(xs[0].t(), t1);
             assert_eq!(xs[1].t(), t2);
@@ -214,7 +244,7 @@ mod tests {
         let c = Cube::new();
         for (origin, direction) in input {
             let r = Ray::new(origin, direction);
-            let xs = c.local_intersect(r);
+            let xs = c.local_intersect(&r);
             assert!(xs.is_empty());
         }
     }
@@ -1,28 +1,37 @@
 use std::mem::swap;
 
+use smallvec::smallvec;
 use uuid::Uuid;
 
 use crate::{
-    intersection::{ray::Ray, Intersection, ShapeIntersection},
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
     transformation::Transformation,
     tuple::Tuple,
     util::{eq_f64, EPSILON},
 };
 
-use super::{material::Material, BoundedBox, Shape, WeakGroupContainer};
+use super::{
+    material::Material, visibility::Visibility, world_transform::WorldTransform, BoundedBox, Shape,
+    Sweep, WeakGroupContainer,
+};
 
 #[derive(Debug)]
 pub struct Cylinder {
     id: uuid::Uuid,
     transformation: Transformation,
     material: Material,
+    visibility: Visibility,
     minimum: f64,
     maximum: f64,
     closed: bool,
+    radius_x: f64,
+    radius_z: f64,
+    sweep: Sweep,
     parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
 }
 
-fn check_cap(ray: Ray, t: f64) -> bool {
+fn check_cap(ray: &Ray, t: f64) -> bool {
     let x = ray.origin().x() + t * ray.direction().x();
     let z = ray.origin().z() + t * ray.direction().z();
 
@@ -35,13 +44,30 @@ impl Cylinder {
             id: uuid::Uuid::new_v4(),
             transformation: Transformation::default(),
             material: Material::default(),
+            visibility: Visibility::default(),
             minimum: f64::NEG_INFINITY,
             maximum: f64::INFINITY,
             closed: false,
+            radius_x: 1.0,
+            radius_z: 1.0,
+            sweep: Sweep::default(),
             parent: None,
+            world_transform: None,
         }
     }
 
+    /**
+       An open rod of `radius` running from `p1` to `p2`.
+    */
+    pub fn between(p1: Tuple, p2: Tuple, radius: f64) -> Self {
+        let mut cylinder = Self::new();
+        cylinder.set_minimum(0.0);
+        cylinder.set_maximum(1.0);
+        cylinder.set_radius(radius);
+        cylinder.set_transformation(Transformation::between(p1, p2));
+        cylinder
+    }
+
     pub fn minimum(&self) -> f64 {
         self.minimum
     }
@@ -66,18 +92,75 @@ impl Cylinder {
         self.closed = closed;
     }
 
-    fn intersect_caps(&self, ray: Ray, xs: &mut Vec<Intersection>) {
+    pub fn radius_x(&self) -> f64 {
+        self.radius_x
+    }
+
+    pub fn radius_z(&self) -> f64 {
+        self.radius_z
+    }
+
+    pub fn set_radius(&mut self, radius: f64) {
+        self.set_radii(radius, radius);
+    }
+
+    /**
+       The radius of the cross section along x and z, which differ for an
+       elliptical cylinder. Unlike scaling the transformation, this leaves the
+       height and caps alone.
+    */
+    pub fn set_radii(&mut self, radius_x: f64, radius_z: f64) {
+        self.radius_x = radius_x;
+        self.radius_z = radius_z;
+    }
+
+    pub fn sweep(&self) -> Sweep {
+        self.sweep
+    }
+
+    pub fn set_sweep(&mut self, sweep: Sweep) {
+        self.sweep = sweep;
+    }
+
+    fn in_sweep(&self, ray: &Ray, t: f64) -> bool {
+        self.sweep.contains(
+            (ray.origin().x() + t * ray.direction().x()) * self.radius_x,
+            (ray.origin().z() + t * ray.direction().z()) * self.radius_z,
+        )
+    }
+
+    /**
+       `ray` in the space where the cross section is a unit circle.
+       Intersection distances are the same in both spaces.
+    */
+    fn unit_ray(&self, ray: &Ray) -> Ray {
+        let (origin, direction) = (ray.origin(), ray.direction());
+        Ray::new(
+            Tuple::point(
+                origin.x() / self.radius_x,
+                origin.y(),
+                origin.z() / self.radius_z,
+            ),
+            Tuple::vector(
+                direction.x() / self.radius_x,
+                direction.y(),
+                direction.z() / self.radius_z,
+            ),
+        )
+    }
+
+    fn intersect_caps(&self, ray: &Ray, xs: &mut Intersections) {
         if !self.closed || eq_f64(ray.direction().y(), 0.0) {
             return;
         }
 
         let t = (self.minimum - ray.origin().y()) / ray.direction().y();
-        if check_cap(ray, t) {
+        if check_cap(ray, t) && self.in_sweep(ray, t) {
             xs.push(Intersection::new(t, self.id));
         }
 
         let t = (self.maximum - ray.origin().y()) / ray.direction().y();
-        if check_cap(ray, t) {
+        if check_cap(ray, t) && self.in_sweep(ray, t) {
             xs.push(Intersection::new(t, self.id));
         }
     }
@@ -88,11 +171,12 @@ impl Shape for Cylinder {
         self.id
     }
 
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
+        let ray = &self.unit_ray(ray);
         let a = ray.direction().x().powi(2) + ray.direction().z().powi(2);
 
         if eq_f64(a, 0.0) {
-            let mut xs = vec![];
+            let mut xs = smallvec![];
             self.intersect_caps(ray, &mut xs);
             return xs;
         }
@@ -104,7 +188,7 @@ impl Shape for Cylinder {
         let disc = b.powi(2) - 4.0 * a * c;
 
         if disc < 0.0 {
-            return vec![];
+            return smallvec![];
         }
 
         let mut t0 = (-b - disc.sqrt()) / (2.0 * a);
@@ -114,15 +198,15 @@ impl Shape for Cylinder {
             swap(&mut t0, &mut t1);
         }
 
-        let mut xs = vec![];
+        let mut xs = smallvec![];
 
         let y0 = ray.origin().y() + t0 * ray.direction().y();
-        if self.minimum < y0 && y0 < self.maximum {
+        if self.minimum < y0 && y0 < self.maximum && self.in_sweep(ray, t0) {
             xs.push(Intersection::new(t0, self.id))
         }
 
         let y1 = ray.origin().y() + t1 * ray.direction().y();
-        if self.minimum < y1 && y1 < self.maximum {
+        if self.minimum < y1 && y1 < self.maximum && self.in_sweep(ray, t1) {
             xs.push(Intersection::new(t1, self.id));
         }
         self.intersect_caps(ray, &mut xs);
@@ -136,6 +220,7 @@ impl Shape for Cylinder {
 
     fn set_transformation(&mut self, transformation: Transformation) {
         self.transformation = transformation;
+        self.world_transform = None;
     }
 
     fn material(&self, id: Uuid) -> Option<Material> {
@@ -150,6 +235,18 @@ impl Shape for Cylinder {
         self.material = material;
     }
 
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        if self.id == id {
+            Some(self.visibility)
+        } else {
+            None
+        }
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+
     fn local_normal_at(
         &self,
         id: Uuid,
@@ -160,6 +257,11 @@ impl Shape for Cylinder {
             return None;
         }
 
+        let point = Tuple::point(
+            point.x() / self.radius_x,
+            point.y(),
+            point.z() / self.radius_z,
+        );
         let dist = point.x().powi(2) + point.z().powi(2);
 
         Some(if dist < 1.0 && point.y() >= self.maximum - EPSILON {
@@ -167,7 +269,7 @@ impl Shape for Cylinder {
         } else if dist < 1.0 && point.y() < self.minimum + EPSILON {
             Tuple::vector(0.0, -1.0, 0.0)
         } else {
-            Tuple::vector(point.x(), 0.0, point.z())
+            Tuple::vector(point.x() / self.radius_x, 0.0, point.z() / self.radius_z)
         })
     }
 
@@ -176,13 +278,22 @@ impl Shape for Cylinder {
     }
 
     fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
         self.parent = Some(parent.clone());
     }
 
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
     fn bounds(&self) -> BoundedBox {
         BoundedBox::new(
-            Tuple::point(-1.0, self.minimum, -1.0),
-            Tuple::point(1.0, self.maximum, 1.0),
+            Tuple::point(-self.radius_x, self.minimum, -self.radius_z),
+            Tuple::point(self.radius_x, self.maximum, self.radius_z),
         )
     }
 
@@ -193,6 +304,8 @@ impl Shape for Cylinder {
 
 #[cfg(test)]
 mod tests {
+    use std::f64::consts::PI;
+
     use crate::{intersection::ray::Ray, shape::ShapeContainer, tuple::Tuple};
 
     use super::*;
@@ -209,7 +322,7 @@ mod tests {
         for (origin, direction) in exs {
             let direction = direction.normalize();
             let r = Ray::new(origin, direction);
-            let xs = cyl.local_intersect(r);
+            let xs = cyl.local_intersect(&r);
 
             assert_eq!(xs.len(), 0);
         }
@@ -242,7 +355,7 @@ mod tests {
         for (origin, direction, t0, t1) in exs {
             let direction = direction.normalize();
             let r = Ray::new(origin, direction);
-            let xs = cyl.local_intersect(r);
+            let xs = cyl.local_intersect(&r);
             assert_eq!(xs.len(), 2);
             assert!(eq_f64(xs[0].t(), t0));
             assert!(eq_f64(xs[1].t(), t1));
@@ -315,7 +428,7 @@ mod tests {
         for (point, direction, count) in exs {
             let direction = direction.normalize();
             let r = Ray::new(point, direction);
-            let xs = cyl.local_intersect(r);
+            let xs = cyl.local_intersect(&r);
 
             assert_eq!(xs.len(), count);
         }
@@ -344,7 +457,7 @@ mod tests {
         for (point, direction) in exs {
             let direction = direction.normalize();
             let r = Ray::new(point, direction);
-            let xs = cyl.local_intersect(r);
+            let xs = cyl.local_intersect(&r);
             assert_eq!(xs.len(), 2);
         }
     }
@@ -376,4 +489,85 @@ mod tests {
             assert_eq!(n, normal);
         }
     }
+
+    #[test]
+    fn an_elliptical_cylinder_uses_its_radii() {
+        let mut cyl = Cylinder::new();
+        cyl.set_radii(2.0, 0.5);
+
+        let along_z = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let xs = cyl.local_intersect(&along_z);
+        assert_eq!(4.5, xs[0].t());
+        assert_eq!(5.5, xs[1].t());
+
+        let along_x = Ray::new(Tuple::point(-5.0, 0.0, 0.0), Tuple::vector(1.0, 0.0, 0.0));
+        let xs = cyl.local_intersect(&along_x);
+        assert_eq!(3.0, xs[0].t());
+        assert_eq!(7.0, xs[1].t());
+
+        let cyl = ShapeContainer::from(cyl);
+        let i = ShapeIntersection::new(0.0, cyl.clone(), cyl.id());
+        let normal_at = |point| {
+            cyl.read()
+                .unwrap()
+                .local_normal_at(cyl.id(), point, i.clone())
+                .unwrap()
+        };
+        assert_eq!(
+            Tuple::vector(0.5, 0.0, 0.0),
+            normal_at(Tuple::point(2.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            Tuple::vector(0.0, 0.0, 2.0),
+            normal_at(Tuple::point(0.0, 0.0, 0.5))
+        );
+        let bounds = cyl.read().unwrap().bounds();
+        assert_eq!((-2.0, -0.5), (bounds.min().x(), bounds.min().z()));
+        assert_eq!((2.0, 0.5), (bounds.max().x(), bounds.max().z()));
+    }
+
+    #[test]
+    fn a_swept_cylinder_is_open_outside_its_angles() {
+        let mut cyl = Cylinder::new();
+        cyl.set_minimum(-1.0);
+        cyl.set_maximum(1.0);
+        cyl.set_closed(true);
+        cyl.set_sweep(Sweep::new(0.0, PI));
+
+        let through_the_gap = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let xs = cyl.local_intersect(&through_the_gap);
+        assert_eq!(1, xs.len());
+        assert_eq!(6.0, xs[0].t());
+
+        let along_the_edge = Ray::new(Tuple::point(-5.0, 0.0, 0.5), Tuple::vector(1.0, 0.0, 0.0));
+        assert_eq!(2, cyl.local_intersect(&along_the_edge).len());
+
+        let down_the_missing_half =
+            Ray::new(Tuple::point(0.0, 5.0, -0.5), Tuple::vector(0.0, -1.0, 0.0));
+        assert!(cyl.local_intersect(&down_the_missing_half).is_empty());
+    }
+
+    #[test]
+    fn a_cylinder_between_two_points() {
+        let p1 = Tuple::point(1.0, 0.0, 0.0);
+        let p2 = Tuple::point(1.0, 0.0, 4.0);
+        let cyl = ShapeContainer::from(Cylinder::between(p1, p2, 0.5));
+
+        let across = Ray::new(Tuple::point(-5.0, 0.0, 2.0), Tuple::vector(1.0, 0.0, 0.0));
+        let xs = cyl.read().unwrap().intersects(&across);
+        assert_eq!(2, xs.len());
+        assert!(eq_f64(5.5, xs[0].t()));
+        assert!(eq_f64(6.5, xs[1].t()));
+
+        let past_the_end = Ray::new(Tuple::point(-5.0, 0.0, 4.5), Tuple::vector(1.0, 0.0, 0.0));
+        assert!(cyl.read().unwrap().intersects(&past_the_end).is_empty());
+
+        let i = ShapeIntersection::new(0.0, cyl.clone(), cyl.id());
+        let normal = cyl
+            .read()
+            .unwrap()
+            .normal_at(cyl.id(), Tuple::point(1.0, 0.5, 2.0), i)
+            .unwrap();
+        assert_eq!(Tuple::vector(0.0, 1.0, 0.0), normal);
+    }
 }
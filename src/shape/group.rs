@@ -1,17 +1,24 @@
 use std::{
+    collections::HashSet,
     ops::Deref,
     sync::{Arc, RwLock, Weak},
 };
 
+use smallvec::smallvec;
 use uuid::Uuid;
 
 use crate::{
-    intersection::{ray::Ray, Intersection, ShapeIntersection},
+    color::Color,
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
     transformation::Transformation,
     tuple::Tuple,
+    util::PrecisionPolicy,
 };
 
-use super::{material::Material, BoundedBox, Shape, ShapeContainer};
+use super::{
+    material::Material, smooth_triangle::SmoothTriangle, triangle::Triangle,
+    visibility::Visibility, world_transform::WorldTransform, BoundedBox, Shape, ShapeContainer,
+};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Operation {
@@ -38,8 +45,10 @@ pub struct Group {
     shapes: Vec<ShapeContainer>,
     transformation: Transformation,
     parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
     bounding_box: BoundedBox,
     operation: Operation,
+    left_ids: HashSet<Uuid>,
 }
 
 impl Group {
@@ -50,8 +59,10 @@ impl Group {
             shapes: vec![],
             transformation: Transformation::default(),
             parent: None,
+            world_transform: None,
             bounding_box: BoundedBox::empty(),
             operation: Operation::Group,
+            left_ids: HashSet::new(),
         }
     }
 
@@ -69,16 +80,54 @@ impl Group {
             shapes: vec![],
             transformation: Transformation::default(),
             parent: None,
+            world_transform: None,
             bounding_box: BoundedBox::empty(),
             operation: Operation::Group,
+            left_ids: HashSet::new(),
         };
         let g = GroupContainer::from(group);
+        let left_ids = descendant_ids(&left);
         g.add_child(left);
         g.add_child(right);
-        g.write().unwrap().operation = operation;
+        {
+            let mut group = g.write().unwrap();
+            group.operation = operation;
+            group.bounding_box = group.bounds();
+            group.left_ids = left_ids;
+        }
         g
     }
 
+    /**
+       Triangles fanning out from the first of `points` to each neighbouring
+       pair of the rest, all wound the same way. With `smooth`, each point's
+       normal is the average of the faces around it, weighted by the angle
+       each face has at that point.
+    */
+    pub fn from_triangle_fan(points: &[Tuple], smooth: bool) -> GroupContainer {
+        let faces = (1..points.len().saturating_sub(1))
+            .map(|i| [0, i, i + 1])
+            .collect::<Vec<_>>();
+        triangle_group(points, &faces, smooth)
+    }
+
+    /**
+       Triangles from every run of three consecutive `points`, flipping
+       every other one so they all face the same way as the first.
+    */
+    pub fn from_strip(points: &[Tuple], smooth: bool) -> GroupContainer {
+        let faces = (0..points.len().saturating_sub(2))
+            .map(|i| {
+                if i % 2 == 0 {
+                    [i, i + 1, i + 2]
+                } else {
+                    [i + 1, i, i + 2]
+                }
+            })
+            .collect::<Vec<_>>();
+        triangle_group(points, &faces, smooth)
+    }
+
     pub fn children(&self) -> Vec<ShapeContainer> {
         self.shapes.clone()
     }
@@ -99,14 +148,53 @@ impl Group {
         }
     }
 
-    pub fn filter_intersections(&self, xs: &Vec<Intersection>) -> Vec<Intersection> {
+    /**
+       Take out the children that fit entirely within either half of this
+       group's bounds, returning those in the lower half and those in the
+       upper half. Children that straddle the middle stay, and so does
+       everyone if one half would take them all, since splitting them off
+       would only nest the same children deeper.
+    */
+    fn partition_children(&mut self) -> (Vec<ShapeContainer>, Vec<ShapeContainer>) {
+        let (lower, upper) = self.bounding_box.split();
+        let side = |shape: &ShapeContainer| {
+            let bounds = shape.read().unwrap().parent_space_bounds();
+            if lower.contains_box(bounds) {
+                Some(true)
+            } else if upper.contains_box(bounds) {
+                Some(false)
+            } else {
+                None
+            }
+        };
+        let sides = self.shapes.iter().map(side).collect::<Vec<_>>();
+        if sides.iter().all(|side| *side == Some(true))
+            || sides.iter().all(|side| *side == Some(false))
+        {
+            return (vec![], vec![]);
+        }
+
+        let (mut left, mut right, mut kept) = (vec![], vec![], vec![]);
+        for (shape, side) in self.shapes.drain(..).zip(sides) {
+            match side {
+                Some(true) => left.push(shape),
+                Some(false) => right.push(shape),
+                None => kept.push(shape),
+            }
+        }
+        self.shapes = kept;
+
+        (left, right)
+    }
+
+    pub fn filter_intersections(&self, xs: &[Intersection]) -> Intersections {
         let mut inl = false;
         let mut inr = false;
 
-        let mut result = vec![];
+        let mut result = smallvec![];
 
         for intersection in xs.iter() {
-            let lhit = self.left().includes(intersection.object());
+            let lhit = self.left_ids.contains(&intersection.object());
 
             if self.operation.intersection_allowed(lhit, inl, inr) {
                 result.push(intersection.clone());
@@ -123,16 +211,57 @@ impl Group {
     }
 }
 
+fn triangle_group(points: &[Tuple], faces: &[[usize; 3]], smooth: bool) -> GroupContainer {
+    let group = GroupContainer::default();
+
+    if !smooth {
+        for [a, b, c] in faces {
+            group.add_child(Triangle::new(points[*a], points[*b], points[*c]).into());
+        }
+        return group;
+    }
+
+    let mut normals = vec![Tuple::vector(0.0, 0.0, 0.0); points.len()];
+    for [a, b, c] in faces {
+        let normal = ((points[*c] - points[*a]) ^ (points[*b] - points[*a])).normalize();
+        for (vertex, others) in [(a, [b, c]), (b, [c, a]), (c, [a, b])] {
+            let to_first = (points[*others[0]] - points[*vertex]).normalize();
+            let to_second = (points[*others[1]] - points[*vertex]).normalize();
+            let angle = (to_first * to_second).clamp(-1.0, 1.0).acos();
+            normals[*vertex] = normals[*vertex] + normal * angle;
+        }
+    }
+    let normals = normals
+        .into_iter()
+        .map(|normal| normal.normalize())
+        .collect::<Vec<_>>();
+
+    for [a, b, c] in faces {
+        group.add_child(
+            SmoothTriangle::new(
+                points[*a],
+                points[*b],
+                points[*c],
+                normals[*a],
+                normals[*b],
+                normals[*c],
+            )
+            .into(),
+        );
+    }
+    group
+}
+
 impl Shape for Group {
     fn id(&self) -> uuid::Uuid {
         self.id
     }
 
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
         if !self.bounding_box.intersects(ray) {
-            return vec![];
+            return smallvec![];
         }
-        let mut xs: Vec<_> = self
+        let mut xs: Intersections = self
             .shapes
             .iter()
             .flat_map(|s| s.read().unwrap().intersects(ray))
@@ -154,6 +283,7 @@ impl Shape for Group {
 
     fn set_transformation(&mut self, transformation: Transformation) {
         self.transformation = transformation;
+        self.clear_world_transform();
     }
 
     fn material(&self, id: Uuid) -> Option<Material> {
@@ -167,6 +297,81 @@ impl Shape for Group {
         panic!("Group cannot have material")
     }
 
+    /**
+       CSG shapes are groups underneath but report themselves as `Csg`, so
+       they aren't taken for nodes of the bounding volume hierarchy.
+    */
+    fn type_name(&self) -> &'static str {
+        if self.operation == Operation::Group {
+            "Group"
+        } else {
+            "Csg"
+        }
+    }
+
+    fn child_shapes(&self) -> Vec<ShapeContainer> {
+        self.children()
+    }
+
+    fn update_material(&mut self, f: &dyn Fn(Material) -> Material) {
+        for s in &self.shapes {
+            s.write().unwrap().update_material(f);
+        }
+    }
+
+    fn set_backface_culling(&mut self, backface_culling: bool) {
+        for s in &self.shapes {
+            s.write().unwrap().set_backface_culling(backface_culling);
+        }
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        if self.shapes.is_empty() {
+            return;
+        }
+        let threshold = threshold.max(1);
+        if self.operation == Operation::Group && threshold <= self.shapes.len() {
+            // Every child was added through `add_child`, so any of them
+            // knows this group's container for the subgroups to point at.
+            let this = self.shapes[0].read().unwrap().parent();
+            let (left, right) = self.partition_children();
+            for half in [left, right].into_iter().filter(|half| !half.is_empty()) {
+                let subgroup = GroupContainer::from(Group::new());
+                for shape in half {
+                    subgroup.add_child(shape);
+                }
+                if let Some(this) = this.clone() {
+                    subgroup.write().unwrap().set_parent(this);
+                }
+                self.shapes.push(subgroup.into());
+            }
+        }
+
+        for child in &self.shapes {
+            child.write().unwrap().divide(threshold);
+        }
+    }
+
+    fn vertex_color(&self, id: Uuid, u: f64, v: f64) -> Option<Color> {
+        self.shapes
+            .iter()
+            .filter_map(|s| s.read().unwrap().vertex_color(id, u, v))
+            .next()
+    }
+
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        self.shapes
+            .iter()
+            .filter_map(|s| s.read().unwrap().visibility(id))
+            .next()
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        for s in &self.shapes {
+            s.write().unwrap().set_visibility(visibility);
+        }
+    }
+
     fn local_normal_at(
         &self,
         id: Uuid,
@@ -188,15 +393,57 @@ impl Shape for Group {
     }
 
     fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
         self.parent = Some(parent.clone());
     }
 
-    fn bounds(&self) -> BoundedBox {
-        let mut bbox = BoundedBox::empty();
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn prepare(&mut self, parent: &Transformation, precision: PrecisionPolicy) {
+        let world_transform = WorldTransform::new(parent, &self.transformation, precision);
         for child in &self.shapes {
-            bbox.add_box(child.read().unwrap().parent_space_bounds());
+            child
+                .write()
+                .unwrap()
+                .prepare(world_transform.transformation(), precision);
+        }
+        self.world_transform = Some(world_transform);
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
+    fn teleport(&self, id: Uuid, point: Tuple, direction: Tuple) -> Option<Ray> {
+        self.shapes
+            .iter()
+            .filter_map(|s| s.read().unwrap().teleport(id, point, direction))
+            .next()
+    }
+
+    /**
+       Everything an intersection keeps is inside both operands, and a
+       difference keeps nothing outside its left operand, so those are
+       bounded tighter than the union of the children.
+    */
+    fn bounds(&self) -> BoundedBox {
+        let bounds = |child: &ShapeContainer| child.read().unwrap().parent_space_bounds();
+
+        match self.operation {
+            Operation::Intersection => {
+                bounds(&self.shapes[0]).intersection(&bounds(&self.shapes[1]))
+            }
+            Operation::Difference => bounds(&self.shapes[0]),
+            Operation::Group | Operation::Union => {
+                let mut bbox = BoundedBox::empty();
+                for child in &self.shapes {
+                    bbox.add_box(bounds(child));
+                }
+                bbox
+            }
         }
-        bbox
     }
 
     fn contains(&self, id: Uuid) -> bool {
@@ -222,7 +469,132 @@ impl GroupContainer {
             .set_parent(WeakGroupContainer(weak_container));
 
         group.shapes.push(shape);
-        group.bounding_box = group.bounds()
+        group.bounding_box = group.bounds();
+        drop(group);
+
+        self.refresh_ancestors();
+    }
+
+    /**
+       Recompute this group's cached bounds and those of every group above
+       it, after a child beneath it has moved.
+    */
+    pub fn refresh_bounds(&self) {
+        {
+            let mut group = self.write().unwrap();
+            group.bounding_box = group.bounds();
+        }
+        self.refresh_ancestors();
+    }
+
+    /**
+       Groups above this one cache their bounds, and CSG operations remember
+       which shapes are on their left so filtering intersections doesn't
+       search the operand for every hit. Both go stale when a child is added
+       somewhere beneath them.
+    */
+    fn refresh_ancestors(&self) {
+        let mut child: ShapeContainer = self.clone().into();
+        let mut parent = self.read().unwrap().parent();
+
+        while let Some(group) = parent.and_then(|p| p.upgrade()).map(GroupContainer) {
+            let (bounds, left) = {
+                let group = group.read().unwrap();
+                let left = (group.operation != Operation::Group).then(|| group.left());
+                (group.bounds(), left)
+            };
+            let left_ids = left
+                .filter(|left| left.id() == child.id())
+                .map(|left| descendant_ids(&left));
+            {
+                let mut group = group.write().unwrap();
+                group.bounding_box = bounds;
+                if let Some(left_ids) = left_ids {
+                    group.left_ids = left_ids;
+                }
+            }
+
+            parent = group.read().unwrap().parent();
+            child = group.into();
+        }
+    }
+
+    /**
+       Build a bounding volume hierarchy: while a group has at least
+       `threshold` children, the ones that fit in either half of its bounds
+       move into a new subgroup for that half, and every group beneath it,
+       including those inside CSG operands, is divided the same way.
+    */
+    pub fn divide(&self, threshold: usize) {
+        self.write().unwrap().divide(threshold);
+    }
+
+    /**
+       Give every child, including those of nested groups and CSG operands,
+       a copy of `material`.
+    */
+    pub fn set_material_recursive(&self, material: Material) {
+        self.write().unwrap().update_material(&|_| material.clone());
+    }
+
+    /**
+       Call `f` with this group and then every shape beneath it, depth
+       first, along with how many levels down each is. The group itself is
+       at depth 0 and CSG operands count as children.
+    */
+    pub fn visit(&self, f: &mut dyn FnMut(&dyn Shape, usize)) {
+        visit_shape(&*self.read().unwrap(), 0, f);
+    }
+
+    /**
+       The same traversal as `visit` as an iterator of containers, so shapes
+       can be collected or edited along the way.
+    */
+    pub fn descendants(&self) -> Descendants {
+        Descendants::new(self.clone().into())
+    }
+}
+
+fn descendant_ids(shape: &ShapeContainer) -> HashSet<Uuid> {
+    Descendants::new(shape.clone())
+        .map(|(shape, _)| shape.id())
+        .collect()
+}
+
+fn visit_shape(shape: &dyn Shape, depth: usize, f: &mut dyn FnMut(&dyn Shape, usize)) {
+    f(shape, depth);
+    for child in shape.child_shapes() {
+        visit_shape(&*child.read().unwrap(), depth + 1, f);
+    }
+}
+
+/**
+   A depth first walk over a shape and everything beneath it, yielding each
+   shape with its depth below the root.
+*/
+#[derive(Debug)]
+pub struct Descendants {
+    stack: Vec<(ShapeContainer, usize)>,
+}
+
+impl Descendants {
+    pub fn new(root: ShapeContainer) -> Self {
+        Self {
+            stack: vec![(root, 0)],
+        }
+    }
+}
+
+impl Iterator for Descendants {
+    type Item = (ShapeContainer, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (shape, depth) = self.stack.pop()?;
+        let children = shape.read().unwrap().child_shapes();
+        self.stack
+            .extend(children.into_iter().rev().map(|child| (child, depth + 1)));
+
+        Some((shape, depth))
     }
 }
 
@@ -261,6 +633,19 @@ impl From<GroupContainer> for WeakGroupContainer {
     }
 }
 
+impl WeakGroupContainer {
+    /**
+       Refresh the bounds of this group and those above it, if it is still
+       alive. Call it on a shape's parent after changing the shape's
+       transformation, or rays outside its old bounds will miss it.
+    */
+    pub fn refresh_bounds(&self) {
+        if let Some(group) = self.upgrade() {
+            GroupContainer(group).refresh_bounds();
+        }
+    }
+}
+
 impl Deref for WeakGroupContainer {
     type Target = Weak<RwLock<Group>>;
 
@@ -276,6 +661,7 @@ mod tests {
         intersection::ray::Ray,
         shape::{cube::Cube, sphere::Sphere},
         tuple::Tuple,
+        util::eq_f64,
     };
 
     use super::*;
@@ -325,7 +711,7 @@ mod tests {
         let g = Group::new();
         let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
 
-        let xs = g.local_intersect(r);
+        let xs = g.local_intersect(&r);
 
         assert!(xs.is_empty());
     }
@@ -344,7 +730,7 @@ mod tests {
         g.add_child(s3.into());
         let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
 
-        let xs = g.read().unwrap().local_intersect(r);
+        let xs = g.read().unwrap().local_intersect(&r);
 
         assert_eq!(xs.len(), 4);
     }
@@ -359,11 +745,186 @@ mod tests {
         g.add_child(s.into());
         let r = Ray::new(Tuple::point(10.0, 0.0, -10.0), Tuple::vector(0.0, 0.0, 1.0));
 
-        let xs = g.read().unwrap().intersects(r);
+        let xs = g.read().unwrap().intersects(&r);
 
         assert_eq!(xs.len(), 2);
     }
 
+    fn sphere_at(x: f64, y: f64, z: f64) -> ShapeContainer {
+        let mut s = Sphere::new();
+        s.set_transformation(Transformation::identity().translation(x, y, z));
+        s.into()
+    }
+
+    fn ids(shapes: &[ShapeContainer]) -> Vec<Uuid> {
+        shapes.iter().map(|shape| shape.id()).collect()
+    }
+
+    #[test]
+    fn partitioning_a_groups_children() {
+        let (s1, s2, s3) = (
+            sphere_at(-2.0, 0.0, 0.0),
+            sphere_at(2.0, 0.0, 0.0),
+            ShapeContainer::from(Sphere::new()),
+        );
+        let g = GroupContainer::from(Group::new());
+        for s in [&s1, &s2, &s3] {
+            g.add_child(s.clone());
+        }
+
+        let (left, right) = g.write().unwrap().partition_children();
+
+        assert_eq!(vec![s3.id()], ids(&g.read().unwrap().shapes));
+        assert_eq!(vec![s1.id()], ids(&left));
+        assert_eq!(vec![s2.id()], ids(&right));
+    }
+
+    #[test]
+    fn subdividing_a_group_partitions_its_children() {
+        let (s1, s2) = (sphere_at(-2.0, -2.0, 0.0), sphere_at(-2.0, 2.0, 0.0));
+        let mut s3 = Sphere::new();
+        s3.set_transformation(Transformation::identity().scale(4.0, 4.0, 4.0));
+        let s3 = ShapeContainer::from(s3);
+        let g = GroupContainer::from(Group::new());
+        for s in [&s1, &s2, &s3] {
+            g.add_child(s.clone());
+        }
+
+        g.divide(1);
+
+        let children = g.read().unwrap().children();
+        assert_eq!(2, children.len());
+        assert_eq!(s3.id(), children[0].id());
+        let subgroup = children[1].read().unwrap().child_shapes();
+        assert_eq!(2, subgroup.len());
+        assert_eq!(
+            vec![s1.id()],
+            ids(&subgroup[0].read().unwrap().child_shapes())
+        );
+        assert_eq!(
+            vec![s2.id()],
+            ids(&subgroup[1].read().unwrap().child_shapes())
+        );
+        assert_eq!(
+            children[1].id(),
+            subgroup[0]
+                .read()
+                .unwrap()
+                .parent()
+                .unwrap()
+                .upgrade()
+                .unwrap()
+                .read()
+                .unwrap()
+                .id()
+        );
+        assert_eq!(
+            g.read().unwrap().id(),
+            children[1]
+                .read()
+                .unwrap()
+                .parent()
+                .unwrap()
+                .upgrade()
+                .unwrap()
+                .read()
+                .unwrap()
+                .id()
+        );
+    }
+
+    #[test]
+    fn subdividing_a_group_with_too_few_children() {
+        let (s1, s2, s3, s4) = (
+            sphere_at(-2.0, 0.0, 0.0),
+            sphere_at(2.0, 1.0, 0.0),
+            sphere_at(2.0, -1.0, 0.0),
+            ShapeContainer::from(Sphere::new()),
+        );
+        let subgroup = GroupContainer::from(Group::new());
+        for s in [&s1, &s2, &s3] {
+            subgroup.add_child(s.clone());
+        }
+        let g = GroupContainer::from(Group::new());
+        g.add_child(subgroup.clone().into());
+        g.add_child(s4.clone());
+
+        g.divide(3);
+
+        assert_eq!(
+            vec![subgroup.read().unwrap().id(), s4.id()],
+            ids(&g.read().unwrap().children())
+        );
+        let children = subgroup.read().unwrap().children();
+        assert_eq!(2, children.len());
+        assert_eq!(
+            vec![s1.id()],
+            ids(&children[0].read().unwrap().child_shapes())
+        );
+        assert_eq!(
+            vec![s2.id(), s3.id()],
+            ids(&children[1].read().unwrap().child_shapes())
+        );
+    }
+
+    #[test]
+    fn subdividing_an_empty_group_or_with_no_threshold() {
+        let empty = GroupContainer::from(Group::new());
+        empty.divide(0);
+        assert!(empty.read().unwrap().children().is_empty());
+
+        let s = sphere_at(2.0, 0.0, 0.0);
+        let g = GroupContainer::from(Group::new());
+        g.add_child(sphere_at(-2.0, 0.0, 0.0));
+        g.add_child(s.clone());
+        g.divide(0);
+
+        let children = g.read().unwrap().children();
+        assert_eq!(2, children.len());
+        assert_eq!(
+            vec![s.id()],
+            ids(&children[1].read().unwrap().child_shapes())
+        );
+    }
+
+    #[test]
+    fn subdividing_a_csg_shape_subdivides_its_children() {
+        let left = GroupContainer::from(Group::new());
+        let (s1, s2) = (sphere_at(-1.5, 0.0, 0.0), sphere_at(1.5, 0.0, 0.0));
+        left.add_child(s1.clone());
+        left.add_child(s2.clone());
+        let right = GroupContainer::from(Group::new());
+        let (s3, s4) = (sphere_at(0.0, 0.0, -1.5), sphere_at(0.0, 0.0, 1.5));
+        right.add_child(s3.clone());
+        right.add_child(s4.clone());
+        let csg = Group::csg(
+            Operation::Difference,
+            left.clone().into(),
+            right.clone().into(),
+        );
+
+        csg.divide(1);
+
+        let left_children = left.read().unwrap().children();
+        assert_eq!(
+            vec![s1.id()],
+            ids(&left_children[0].read().unwrap().child_shapes())
+        );
+        assert_eq!(
+            vec![s2.id()],
+            ids(&left_children[1].read().unwrap().child_shapes())
+        );
+        let right_children = right.read().unwrap().children();
+        assert_eq!(
+            vec![s3.id()],
+            ids(&right_children[0].read().unwrap().child_shapes())
+        );
+        assert_eq!(
+            vec![s4.id()],
+            ids(&right_children[1].read().unwrap().child_shapes())
+        );
+    }
+
     #[test]
     fn csg_is_create_with_an_operation_and_two_shapes() {
         let s1 = Sphere::new();
@@ -475,7 +1036,7 @@ mod tests {
     fn a_ray_misses_a_csg_object() {
         let c = Group::csg(Operation::Union, Sphere::new().into(), Cube::new().into());
         let r = Ray::new(Tuple::point(0.0, 2.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
-        let xs = c.read().unwrap().local_intersect(r);
+        let xs = c.read().unwrap().local_intersect(&r);
 
         assert!(xs.is_empty());
     }
@@ -489,7 +1050,7 @@ mod tests {
         s2.set_transformation(Transformation::identity().translation(0.0, 0.0, 0.5));
         let c = Group::csg(Operation::Union, s1.into(), s2.into());
         let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
-        let xs = c.read().unwrap().local_intersect(r);
+        let xs = c.read().unwrap().local_intersect(&r);
 
         assert_eq!(xs.len(), 2);
         assert_eq!(xs[0].t(), 4.0);
@@ -497,4 +1058,207 @@ mod tests {
         assert_eq!(xs[1].t(), 6.5);
         assert_eq!(xs[1].object(), s2_id);
     }
+
+    #[test]
+    fn children_added_to_a_csg_operand_later_count_as_that_side() {
+        let left = GroupContainer::default();
+        let mut s2 = Sphere::new();
+        let s2_id = s2.id();
+        s2.set_transformation(Transformation::identity().translation(0.0, 0.0, 0.5));
+        let c = Group::csg(Operation::Difference, left.clone().into(), s2.into());
+        let s1 = Sphere::new();
+        let s1_id = s1.id();
+
+        left.add_child(s1.into());
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let xs = c.read().unwrap().local_intersect(&r);
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t(), 4.0);
+        assert_eq!(xs[0].object(), s1_id);
+        assert_eq!(xs[1].t(), 4.5);
+        assert_eq!(xs[1].object(), s2_id);
+    }
+
+    #[test]
+    fn setting_a_material_recursively_reaches_nested_children() {
+        let s1 = ShapeContainer::from(Sphere::new());
+        let s2 = ShapeContainer::from(Cube::new());
+        let s3 = ShapeContainer::from(Sphere::new());
+        let inner = GroupContainer::default();
+        inner.add_child(s2.clone());
+        let outer = GroupContainer::default();
+        outer.add_child(s1.clone());
+        outer.add_child(inner.into());
+        outer.add_child(Group::csg(Operation::Union, s3.clone(), Cube::new().into()).into());
+        let material = Material::new().with_ambient(0.5).with_reflective(0.2);
+
+        outer.set_material_recursive(material.clone());
+
+        for s in [s1, s2, s3] {
+            assert_eq!(s.read().unwrap().material(s.id()).unwrap(), material);
+        }
+    }
+
+    #[test]
+    fn backface_culling_a_group_culls_its_triangles() {
+        let inner = GroupContainer::default();
+        inner.add_child(
+            Triangle::new(
+                Tuple::point(0.0, 1.0, 0.0),
+                Tuple::point(-1.0, 0.0, 0.0),
+                Tuple::point(1.0, 0.0, 0.0),
+            )
+            .into(),
+        );
+        let g = GroupContainer::default();
+        g.add_child(inner.into());
+        let r = Ray::new(Tuple::point(0.0, 0.5, 2.0), Tuple::vector(0.0, 0.0, -1.0));
+
+        assert_eq!(1, g.read().unwrap().intersects(&r).len());
+
+        g.write().unwrap().set_backface_culling(true);
+
+        assert!(g.read().unwrap().intersects(&r).is_empty());
+    }
+
+    #[test]
+    fn a_triangle_fan_shares_its_first_point() {
+        let points = [
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::point(1.0, 0.0, 0.0),
+            Tuple::point(0.0, 0.0, 1.0),
+            Tuple::point(-1.0, 0.0, 0.0),
+            Tuple::point(0.0, 0.0, -1.0),
+        ];
+        let fan = Group::from_triangle_fan(&points, false);
+        let children = fan.read().unwrap().children();
+
+        assert_eq!(3, children.len());
+        for child in children {
+            let i = ShapeIntersection::new(0.0, child.clone(), child.id());
+            let normal = child
+                .read()
+                .unwrap()
+                .local_normal_at(child.id(), Tuple::origin(), i)
+                .unwrap();
+            assert_eq!(Tuple::vector(0.0, 1.0, 0.0), normal);
+        }
+    }
+
+    #[test]
+    fn a_triangle_strip_keeps_one_winding() {
+        let points = [
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::point(0.0, 0.0, 1.0),
+            Tuple::point(1.0, 0.0, 0.0),
+            Tuple::point(1.0, 0.0, 1.0),
+            Tuple::point(2.0, 0.0, 0.0),
+        ];
+        let strip = Group::from_strip(&points, false);
+        let children = strip.read().unwrap().children();
+
+        assert_eq!(3, children.len());
+        for child in children {
+            let i = ShapeIntersection::new(0.0, child.clone(), child.id());
+            let normal = child
+                .read()
+                .unwrap()
+                .local_normal_at(child.id(), Tuple::origin(), i)
+                .unwrap();
+            assert_eq!(Tuple::vector(0.0, -1.0, 0.0), normal);
+        }
+    }
+
+    #[test]
+    fn a_smooth_strip_averages_normals_across_a_ridge() {
+        let points = [
+            Tuple::point(-1.0, 0.0, 0.0),
+            Tuple::point(-1.0, 0.0, 1.0),
+            Tuple::point(0.0, 1.0, 0.0),
+            Tuple::point(0.0, 1.0, 1.0),
+            Tuple::point(1.0, 0.0, 0.0),
+            Tuple::point(1.0, 0.0, 1.0),
+        ];
+        let strip = Group::from_strip(&points, true);
+        let children = strip.read().unwrap().children();
+        let ridge = &children[2];
+
+        assert_eq!("SmoothTriangle", ridge.read().unwrap().type_name());
+        let i =
+            ShapeIntersection::new_with_uv(0.0, ridge.clone(), ridge.id(), Some(0.0), Some(0.0));
+        let normal = ridge
+            .read()
+            .unwrap()
+            .local_normal_at(ridge.id(), points[2], i)
+            .unwrap();
+        assert!(eq_f64(0.0, normal.x()));
+        assert!(eq_f64(0.0, normal.z()));
+    }
+
+    fn nested_scene() -> GroupContainer {
+        let outer = GroupContainer::default();
+        let inner = GroupContainer::default();
+        inner.add_child(Sphere::new().into());
+        inner.add_child(
+            Group::csg(Operation::Union, Cube::new().into(), Sphere::new().into()).into(),
+        );
+        outer.add_child(inner.into());
+        outer.add_child(Cube::new().into());
+        outer
+    }
+
+    #[test]
+    fn visiting_a_group_walks_every_shape_depth_first() {
+        let mut visited = vec![];
+
+        nested_scene().visit(&mut |shape, depth| visited.push((shape.type_name(), depth)));
+
+        assert_eq!(
+            vec![
+                ("Group", 0),
+                ("Group", 1),
+                ("Sphere", 2),
+                ("Csg", 2),
+                ("Cube", 3),
+                ("Sphere", 3),
+                ("Cube", 1),
+            ],
+            visited
+        );
+    }
+
+    #[test]
+    fn iterating_descendants_matches_the_visitor() {
+        let group = nested_scene();
+        let mut visited = vec![];
+        group.visit(&mut |shape, depth| visited.push((shape.id(), depth)));
+
+        let iterated = group
+            .descendants()
+            .map(|(shape, depth)| (shape.id(), depth))
+            .collect::<Vec<_>>();
+
+        assert_eq!(visited, iterated);
+    }
+
+    #[test]
+    fn editing_shapes_found_by_iterating_descendants() {
+        let group = nested_scene();
+
+        for (shape, _) in group.descendants() {
+            let mut shape = shape.write().unwrap();
+            if shape.type_name() == "Cube" {
+                shape.set_material(Material::new().with_ambient(1.0));
+            }
+        }
+
+        let mut ambient = vec![];
+        group.visit(&mut |shape, _| {
+            if shape.type_name() == "Cube" {
+                ambient.push(shape.material(shape.id()).unwrap().ambient());
+            }
+        });
+        assert_eq!(vec![1.0, 1.0], ambient);
+    }
 }
@@ -0,0 +1,207 @@
+use smallvec::smallvec;
+use uuid::Uuid;
+
+use crate::{
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
+    transformation::Transformation,
+    tuple::Tuple,
+    util::EPSILON,
+};
+
+use super::{
+    group::WeakGroupContainer, material::Material, visibility::Visibility,
+    world_transform::WorldTransform, BoundedBox, Shape,
+};
+
+/**
+   Everything on one side of a plane. Untransformed, the boundary is the xz
+   plane and the inside is below it, so the normal points up and out. On
+   its own it renders like a `Plane`; several together make a
+   `ConvexPolytope`.
+*/
+#[derive(Debug)]
+pub struct HalfSpace {
+    id: Uuid,
+    material: Material,
+    visibility: Visibility,
+    transformation: Transformation,
+    parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
+}
+
+impl HalfSpace {
+    pub fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            material: Material::new(),
+            visibility: Visibility::default(),
+            transformation: Transformation::identity(),
+            parent: None,
+            world_transform: None,
+        }
+    }
+
+    /**
+       The half space bounded by the plane through `point` whose outward
+       normal is `normal`.
+    */
+    pub fn facing(point: Tuple, normal: Tuple) -> Self {
+        let mut half_space = Self::new();
+        half_space.set_transformation(Transformation::between(point, point + normal.normalize()));
+        half_space
+    }
+
+    /**
+       The boundary as a unit outward normal `n` and distance `d` in the
+       half space's parent space, so the inside is where `n * p <= d`.
+    */
+    pub fn plane(&self) -> (Tuple, f64) {
+        let inverse = self.transformation.inverse().unwrap();
+        let mut normal = inverse.transpose() * Tuple::vector(0.0, 1.0, 0.0);
+        normal.as_vector();
+        let normal = normal.normalize();
+
+        (
+            normal,
+            normal * (self.transformation.clone() * Tuple::origin()),
+        )
+    }
+
+    /**
+       Whether `point`, in the half space's parent space, is inside or on
+       the boundary.
+    */
+    pub fn includes_point(&self, point: Tuple) -> bool {
+        let (normal, distance) = self.plane();
+        normal * point <= distance + EPSILON
+    }
+}
+
+impl Default for HalfSpace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for HalfSpace {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
+        if ray.direction().y().abs() < EPSILON {
+            smallvec![]
+        } else {
+            smallvec![Intersection::new(
+                -ray.origin().y() / ray.direction().y(),
+                self.id,
+            )]
+        }
+    }
+
+    fn transformation(&self) -> Transformation {
+        self.transformation.clone()
+    }
+
+    fn set_transformation(&mut self, transformation: Transformation) {
+        self.transformation = transformation;
+        self.world_transform = None;
+    }
+
+    fn material(&self, id: Uuid) -> Option<Material> {
+        if self.id == id {
+            Some(self.material.clone())
+        } else {
+            None
+        }
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        if self.id == id {
+            Some(self.visibility)
+        } else {
+            None
+        }
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+
+    fn local_normal_at(
+        &self,
+        id: Uuid,
+        _point: Tuple,
+        _intersection: ShapeIntersection,
+    ) -> Option<Tuple> {
+        if self.id == id {
+            Some(Tuple::vector(0.0, 1.0, 0.0))
+        } else {
+            None
+        }
+    }
+
+    fn parent(&self) -> Option<WeakGroupContainer> {
+        self.parent.clone()
+    }
+
+    fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
+        self.parent = Some(parent);
+    }
+
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
+    fn bounds(&self) -> BoundedBox {
+        BoundedBox::new(
+            Tuple::point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            Tuple::point(f64::INFINITY, 0.0, f64::INFINITY),
+        )
+    }
+
+    fn contains(&self, id: Uuid) -> bool {
+        self.id == id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::eq_f64;
+
+    use super::*;
+
+    #[test]
+    fn a_half_space_facing_a_direction_keeps_the_other_side() {
+        let half_space =
+            HalfSpace::facing(Tuple::point(0.0, 0.0, 2.0), Tuple::vector(0.0, 0.0, 3.0));
+        let (normal, distance) = half_space.plane();
+
+        assert_eq!(Tuple::vector(0.0, 0.0, 1.0), normal);
+        assert!(eq_f64(2.0, distance));
+        assert!(half_space.includes_point(Tuple::point(5.0, -3.0, 1.0)));
+        assert!(half_space.includes_point(Tuple::point(0.0, 0.0, 2.0)));
+        assert!(!half_space.includes_point(Tuple::point(0.0, 0.0, 2.5)));
+    }
+
+    #[test]
+    fn a_ray_meets_the_boundary_of_a_half_space() {
+        let half_space =
+            HalfSpace::facing(Tuple::point(0.0, 0.0, 2.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        let xs = half_space.intersects(&r);
+
+        assert_eq!(1, xs.len());
+        assert!(eq_f64(7.0, xs[0].t()));
+    }
+}
@@ -1,20 +1,26 @@
-use std::{borrow::BorrowMut, sync::Arc};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
 use crate::{
     color::{Color, Colors},
-    point_light::PointLight,
+    light::Light,
     tuple::Tuple,
     util::eq_f64,
 };
 
 use self::pattern::{solid::SolidPattern, Pattern};
 
-use super::ShapeContainer;
+use super::Shape;
 
 pub mod pattern;
 
+static NEXT_MATERIAL_ID: AtomicU64 = AtomicU64::new(1);
+
 #[derive(Debug, Clone)]
 pub struct Material {
+    id: u64,
     ambient: f64,
     diffuse: f64,
     specular: f64,
@@ -22,7 +28,10 @@ pub struct Material {
     reflective: f64,
     transparency: f64,
     refractive_index: f64,
-    pattern: Arc<dyn Pattern + Send + Sync>,
+    subsurface: f64,
+    scatter_distance: f64,
+    subsurface_color: Color,
+    pattern: Arc<dyn Pattern>,
 }
 
 impl Material {
@@ -30,6 +39,14 @@ impl Material {
         Self::default()
     }
 
+    /**
+       Shared by a material and its copies, and only unique within one run.
+       Ids only grow, so ID passes number materials by their order.
+    */
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
     pub fn pattern(&self) -> &dyn Pattern {
         self.pattern.as_ref()
     }
@@ -62,6 +79,28 @@ impl Material {
         self.refractive_index
     }
 
+    /**
+       Whether light passes straight through the material, neither bending
+       nor reflecting, so rays can carry on past it without shading it.
+    */
+    pub fn is_clear(&self) -> bool {
+        eq_f64(self.transparency, 1.0)
+            && self.reflective <= 0.0
+            && eq_f64(self.refractive_index, 1.0)
+    }
+
+    pub fn subsurface(&self) -> f64 {
+        self.subsurface
+    }
+
+    pub fn scatter_distance(&self) -> f64 {
+        self.scatter_distance
+    }
+
+    pub fn subsurface_color(&self) -> Color {
+        self.subsurface_color
+    }
+
     pub fn with_color(mut self, color: Color) -> Self {
         self.pattern = Arc::new(SolidPattern::new(color));
         self
@@ -102,7 +141,22 @@ impl Material {
         self
     }
 
-    pub fn with_pattern<T: Pattern + Send + Sync + 'static >(mut self, pattern: T) -> Self {
+    pub fn with_subsurface(mut self, subsurface: f64) -> Self {
+        self.subsurface = subsurface;
+        self
+    }
+
+    pub fn with_scatter_distance(mut self, scatter_distance: f64) -> Self {
+        self.scatter_distance = scatter_distance;
+        self
+    }
+
+    pub fn with_subsurface_color(mut self, subsurface_color: Color) -> Self {
+        self.subsurface_color = subsurface_color;
+        self
+    }
+
+    pub fn with_pattern<T: Pattern + 'static>(mut self, pattern: T) -> Self {
         self.pattern = Arc::new(pattern);
         self
     }
@@ -126,53 +180,125 @@ impl Material {
 
        Compute the specular contribution.
 
-       Add the three contributions together to get the final shading.
+       Compute the subsurface contribution, which takes over part of the
+       diffuse term and wraps it around the terminator. `in_shadow` does not
+       say what casts the shadow, so it is taken to be `shape` itself and
+       only the light's attenuation dims this term.
+
+       Add the contributions together to get the final shading.
     */
     pub fn lighting(
         &self,
-        shape: ShapeContainer,
-        light: PointLight,
+        shape: &dyn Shape,
+        light: impl Into<Light>,
         point: Tuple,
         eye_v: Tuple,
         normal_v: Tuple,
         in_shadow: bool,
     ) -> Color {
-        let effective_color =
-            self.pattern().borrow_mut().color_at_object(shape, point) * light.intensity();
+        let light = light.into();
+        self.lighting_with_samples(
+            shape,
+            light.intensity(),
+            &light.points(),
+            point,
+            eye_v,
+            normal_v,
+            if in_shadow {
+                0.0
+            } else {
+                light.attenuation_at(point)
+            },
+            light.attenuation_at(point),
+        )
+    }
 
-        let light_v = (light.position() - point).normalize();
+    /**
+       Shade a point lit from several sample positions on one light. Diffuse
+       and specular terms are averaged over the samples and scaled by
+       `intensity`, the fraction of the light that reaches the point; ambient
+       is unaffected by either. Subsurface light scatters through the object
+       itself, so it is scaled by `transmission` instead, the fraction that
+       reaches the point when the object is the only thing in the way. It
+       still reaches a point facing away from a light the object blocks.
+    */
+    #[allow(clippy::too_many_arguments)]
+    pub fn lighting_with_samples(
+        &self,
+        shape: &dyn Shape,
+        light_intensity: Color,
+        samples: &[Tuple],
+        point: Tuple,
+        eye_v: Tuple,
+        normal_v: Tuple,
+        intensity: f64,
+        transmission: f64,
+    ) -> Color {
+        let effective_color =
+            self.pattern().color_at_surface(shape, point, normal_v) * light_intensity;
 
         let ambient = effective_color * self.ambient();
 
-        if in_shadow {
+        if samples.is_empty() {
             return ambient;
         }
 
-        let light_dot_normal = light_v * normal_v;
+        let mut scattered: Color = Colors::Black.into();
+        for sample in samples {
+            let light_dot_normal = (*sample - point).normalize() * normal_v;
+            scattered += self.subsurface_lighting(effective_color, light_dot_normal);
+        }
+        let scattered = scattered * (transmission / samples.len() as f64);
+
+        if eq_f64(intensity, 0.0) {
+            return ambient + scattered;
+        }
+
+        let mut sum: Color = Colors::Black.into();
 
-        let (diffuse, specular) = if light_dot_normal < 0.0 {
-            (Colors::Black.into(), Colors::Black.into())
-        } else {
-            let diffuse = effective_color * self.diffuse() * light_dot_normal;
+        for sample in samples {
+            let light_v = (*sample - point).normalize();
+            let light_dot_normal = light_v * normal_v;
+
+            if light_dot_normal < 0.0 {
+                continue;
+            }
+
+            sum += effective_color * self.diffuse() * light_dot_normal * (1.0 - self.subsurface());
 
             let reflect_v = -light_v.reflect(normal_v);
             let reflect_dot_eye = reflect_v * eye_v;
 
-            if eq_f64(0.0, reflect_dot_eye) || reflect_dot_eye < 0.0 {
-                (diffuse, Colors::Black.into())
-            } else {
+            if !(eq_f64(0.0, reflect_dot_eye) || reflect_dot_eye < 0.0) {
                 let factor = reflect_dot_eye.powf(self.shininess());
-                (diffuse, light.intensity() * self.specular() * factor)
+                sum += light_intensity * self.specular() * factor;
             }
-        };
+        }
 
-        return ambient + diffuse + specular;
+        ambient + scattered + sum * (intensity / samples.len() as f64)
+    }
+
+    /**
+       Approximate light scattering beneath the surface with wrap lighting.
+       The further light scatters, the further the diffuse falloff wraps past
+       the terminator, letting thin or waxy objects glow when back lit.
+    */
+    fn subsurface_lighting(&self, effective_color: Color, light_dot_normal: f64) -> Color {
+        if eq_f64(self.subsurface(), 0.0) {
+            return Colors::Black.into();
+        }
+
+        let wrap = 1.0 - (-self.scatter_distance().max(0.0)).exp();
+        let scatter = ((light_dot_normal + wrap) / (1.0 + wrap)).max(0.0);
+
+        effective_color * self.subsurface_color() * self.diffuse() * self.subsurface() * scatter
     }
 }
 
 impl Default for Material {
     fn default() -> Self {
         Self {
+            id: NEXT_MATERIAL_ID.fetch_add(1, Ordering::Relaxed),
             pattern: Arc::new(SolidPattern::new(Colors::White.into())),
             ambient: 0.1,
             diffuse: 0.9,
@@ -181,6 +307,9 @@ impl Default for Material {
             reflective: 0.0,
             transparency: 0.0,
             refractive_index: 1.0,
+            subsurface: 0.0,
+            scatter_distance: 0.0,
+            subsurface_color: Colors::White.into(),
         }
     }
 }
@@ -196,7 +325,11 @@ impl PartialEq for Material {
 
 #[cfg(test)]
 mod tests {
-    use crate::shape::sphere::Sphere;
+    use crate::{
+        point_light::{Falloff, PointLight},
+        shape::sphere::Sphere,
+        spot_light::SpotLight,
+    };
 
     use super::{pattern::stripes::StripePattern, *};
 
@@ -215,6 +348,28 @@ mod tests {
         assert_eq!(0.0, m.reflective());
         assert_eq!(0.0, m.transparency());
         assert_eq!(1.0, m.refractive_index());
+        assert_eq!(0.0, m.subsurface());
+        assert_eq!(0.0, m.scatter_distance());
+        assert_eq!(Color::from(Colors::White), m.subsurface_color());
+    }
+
+    #[test]
+    fn materials_can_be_shared_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Material>();
+        assert_send_sync::<crate::world::World>();
+    }
+
+    #[test]
+    fn materials_keep_their_id_when_modified_or_cloned() {
+        let m = Material::new();
+        let id = m.id();
+        let m = m.with_ambient(0.5);
+
+        assert_eq!(id, m.id());
+        assert_eq!(id, m.clone().id());
+        assert_ne!(id, Material::new().id());
     }
 
     #[test]
@@ -227,7 +382,7 @@ mod tests {
         let normal_v = Tuple::vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Colors::White.into());
 
-        let result = m.lighting(sphere.into(), light, position, eye_v, normal_v, false);
+        let result = m.lighting(&sphere, light, position, eye_v, normal_v, false);
 
         assert_eq!(Color::new(1.9, 1.9, 1.9), result);
     }
@@ -242,7 +397,7 @@ mod tests {
         let normal_v = Tuple::vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Colors::White.into());
 
-        let result = m.lighting(sphere.into(), light, position, eye_v, normal_v, false);
+        let result = m.lighting(&sphere, light, position, eye_v, normal_v, false);
 
         assert_eq!(Color::new(1.0, 1.0, 1.0), result);
     }
@@ -257,7 +412,7 @@ mod tests {
         let normal_v = Tuple::vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Colors::White.into());
 
-        let result = m.lighting(sphere.into(), light, position, eye_v, normal_v, false);
+        let result = m.lighting(&sphere, light, position, eye_v, normal_v, false);
 
         assert_eq!(Color::new(0.7364, 0.7364, 0.7364), result);
     }
@@ -272,7 +427,7 @@ mod tests {
         let normal_v = Tuple::vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::point(0.0, 0.0, 10.0), Colors::White.into());
 
-        let result = m.lighting(sphere.into(), light, position, eye_v, normal_v, false);
+        let result = m.lighting(&sphere, light, position, eye_v, normal_v, false);
 
         assert_eq!(Color::new(0.1, 0.1, 0.1), result);
     }
@@ -289,14 +444,14 @@ mod tests {
 
         let in_shadow = true;
 
-        let result = m.lighting(sphere.into(), light, position, eye_v, normal_v, in_shadow);
+        let result = m.lighting(&sphere, light, position, eye_v, normal_v, in_shadow);
 
         assert_eq!(Color::new(0.1, 0.1, 0.1), result);
     }
 
     #[test]
     fn lighting_with_a_pattern_applied() {
-        let sphere = ShapeContainer::from(Sphere::new());
+        let sphere = Sphere::new();
         let material = Material::new()
             .with_ambient(1.0)
             .with_diffuse(0.0)
@@ -309,7 +464,7 @@ mod tests {
         let normal_v = Tuple::vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::point(0.0, 0.0, 10.0), Colors::White.into());
         let c1 = material.lighting(
-            sphere.clone(),
+            &sphere,
             light,
             Tuple::point(0.9, 0.0, 0.0),
             eye_v,
@@ -317,7 +472,7 @@ mod tests {
             false,
         );
         let c2 = material.lighting(
-            sphere,
+            &sphere,
             light,
             Tuple::point(1.0, 0.0, 0.0),
             eye_v,
@@ -328,4 +483,170 @@ mod tests {
         assert_eq!(c1, Colors::White.into());
         assert_eq!(c2, Colors::Black.into());
     }
+
+    #[test]
+    fn subsurface_without_scatter_distance_matches_plain_diffuse() {
+        let m = Material::new().with_subsurface(1.0);
+        let position = Tuple::origin();
+        let sphere = Sphere::new();
+
+        let eye_v = Tuple::vector(0.0, 0.0, -1.0);
+        let normal_v = Tuple::vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Colors::White.into());
+
+        let result = m.lighting(&sphere, light, position, eye_v, normal_v, false);
+
+        assert_eq!(Color::new(0.7364, 0.7364, 0.7364), result);
+    }
+
+    #[test]
+    fn subsurface_scattering_lights_a_surface_facing_away_from_the_light() {
+        let m = Material::new()
+            .with_subsurface(1.0)
+            .with_scatter_distance(2f64.ln())
+            .with_subsurface_color(Color::new(1.0, 0.5, 0.5));
+        let position = Tuple::origin();
+        let sphere = Sphere::new();
+
+        let eye_v = Tuple::vector(0.0, 0.0, -1.0);
+        let normal_v = Tuple::vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::point(10.0, 0.0, 0.0), Colors::White.into());
+
+        let result = m.lighting(&sphere, light, position, eye_v, normal_v, false);
+
+        assert_eq!(Color::new(0.4, 0.25, 0.25), result);
+    }
+
+    #[test]
+    fn subsurface_scattering_is_scaled_by_the_transmission() {
+        let m = Material::new()
+            .with_subsurface(1.0)
+            .with_scatter_distance(2f64.ln())
+            .with_subsurface_color(Color::new(1.0, 0.5, 0.5));
+        let sphere = Sphere::new();
+        let eye_v = Tuple::vector(0.0, 0.0, -1.0);
+        let normal_v = Tuple::vector(0.0, 0.0, -1.0);
+        let samples = [Tuple::point(10.0, 0.0, 0.0)];
+
+        let shade = |transmission: f64| {
+            m.lighting_with_samples(
+                &sphere,
+                Colors::White.into(),
+                &samples,
+                Tuple::origin(),
+                eye_v,
+                normal_v,
+                0.0,
+                transmission,
+            )
+        };
+
+        assert_eq!(Color::new(0.4, 0.25, 0.25), shade(1.0));
+        assert_eq!(Color::new(0.25, 0.175, 0.175), shade(0.5));
+        assert_eq!(Color::new(0.1, 0.1, 0.1), shade(0.0));
+    }
+
+    #[test]
+    fn subsurface_scattering_does_not_reach_behind_the_wrap() {
+        let m = Material::new()
+            .with_subsurface(1.0)
+            .with_scatter_distance(2f64.ln());
+        let position = Tuple::origin();
+        let sphere = Sphere::new();
+
+        let eye_v = Tuple::vector(0.0, 0.0, -1.0);
+        let normal_v = Tuple::vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::point(0.0, 0.0, 10.0), Colors::White.into());
+
+        let result = m.lighting(&sphere, light, position, eye_v, normal_v, false);
+
+        assert_eq!(Color::new(0.1, 0.1, 0.1), result);
+    }
+
+    #[test]
+    fn lighting_uses_the_intensity_to_attenuate_the_color() {
+        let m = Material::new().with_specular(0.0);
+        let shape = Sphere::new();
+        let position = Tuple::point(0.0, 0.0, -1.0);
+        let eye_v = Tuple::vector(0.0, 0.0, -1.0);
+        let normal_v = Tuple::vector(0.0, 0.0, -1.0);
+        let samples = [Tuple::point(0.0, 0.0, -10.0)];
+
+        for (intensity, expected) in [(1.0, 1.0), (0.5, 0.55), (0.0, 0.1)] {
+            let result = m.lighting_with_samples(
+                &shape,
+                Colors::White.into(),
+                &samples,
+                position,
+                eye_v,
+                normal_v,
+                intensity,
+                1.0,
+            );
+
+            assert_eq!(Color::new(expected, expected, expected), result);
+        }
+    }
+
+    #[test]
+    fn lighting_averages_the_light_samples() {
+        let m = Material::new().with_specular(0.0);
+        let shape = Sphere::new();
+        let position = Tuple::point(0.0, 0.0, -1.0);
+        let eye_v = Tuple::vector(0.0, 0.0, -1.0);
+        let normal_v = Tuple::vector(0.0, 0.0, -1.0);
+        let samples = [Tuple::point(0.0, 0.0, -10.0), Tuple::point(0.0, 0.0, 10.0)];
+
+        let result = m.lighting_with_samples(
+            &shape,
+            Colors::White.into(),
+            &samples,
+            position,
+            eye_v,
+            normal_v,
+            1.0,
+            1.0,
+        );
+
+        assert_eq!(Color::new(0.55, 0.55, 0.55), result);
+    }
+
+    #[test]
+    fn light_falloff_dims_diffuse_and_specular_but_not_ambient() {
+        let m = Material::new();
+        let sphere = Sphere::new();
+        let eye_v = Tuple::vector(0.0, 0.0, -1.0);
+        let normal_v = Tuple::vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::point(0.0, 0.0, -2.0), Colors::White.into())
+            .with_falloff(Falloff::InverseSquare);
+
+        let result = m.lighting(&sphere, light, Tuple::origin(), eye_v, normal_v, false);
+
+        assert_eq!(Color::new(0.55, 0.55, 0.55), result);
+    }
+
+    #[test]
+    fn points_outside_a_spot_lights_cone_get_only_ambient_light() {
+        let m = Material::new();
+        let sphere = Sphere::new();
+        let eye_v = Tuple::vector(0.0, 0.0, -1.0);
+        let normal_v = Tuple::vector(0.0, 0.0, -1.0);
+        let position = Tuple::point(0.0, 0.0, -10.0);
+
+        let facing = SpotLight::aimed_at(position, Tuple::origin(), Colors::White.into());
+        let away = SpotLight::aimed_at(
+            position,
+            Tuple::point(0.0, 10.0, -10.0),
+            Colors::White.into(),
+        );
+
+        assert_eq!(
+            Color::new(1.9, 1.9, 1.9),
+            m.lighting(&sphere, facing, Tuple::origin(), eye_v, normal_v, false)
+        );
+        assert_eq!(
+            Color::new(0.1, 0.1, 0.1),
+            m.lighting(&sphere, away, Tuple::origin(), eye_v, normal_v, false)
+        );
+    }
 }
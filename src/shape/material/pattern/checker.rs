@@ -1,4 +1,10 @@
-use crate::{color::Color, transformation::Transformation, tuple::Tuple, util::eq_f64};
+use crate::{
+    color::Color,
+    shape::Shape,
+    transformation::Transformation,
+    tuple::Tuple,
+    util::{eq_f64, PrecisionPolicy},
+};
 
 use super::Pattern;
 
@@ -6,6 +12,8 @@ use super::Pattern;
 pub struct CheckerPattern {
     color_a: Color,
     color_b: Color,
+    softness: f64,
+    precision: PrecisionPolicy,
     transformation: Transformation,
 }
 
@@ -14,20 +22,123 @@ impl CheckerPattern {
         Self {
             color_a,
             color_b,
+            softness: 0.0,
+            precision: PrecisionPolicy::default(),
             transformation: Transformation::identity(),
         }
     }
+
+    /**
+       Blend between the two colors within `softness` of a cell boundary,
+       rounding off the cells instead of switching abruptly. Softness is a
+       fraction of a cell and is limited to half of one.
+    */
+    pub fn with_softness(mut self, softness: f64) -> Self {
+        self.softness = softness.clamp(0.0, 0.5);
+        self
+    }
+
+    pub fn softness(&self) -> f64 {
+        self.softness
+    }
+
+    /**
+       How close to a boundary a point must be to count as past it.
+    */
+    pub fn with_precision(mut self, precision: PrecisionPolicy) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn precision(&self) -> PrecisionPolicy {
+        self.precision
+    }
+
+    /**
+       A square wave along one axis, +1 in even cells and -1 in odd ones,
+       eased to zero at the boundaries when the checker is soft.
+    */
+    fn wave(&self, value: f64) -> f64 {
+        let cell = self.precision.floor(value);
+        let fraction = (value - cell).max(0.0);
+        let distance = fraction.min(1.0 - fraction);
+        let t = (distance / self.softness).clamp(0.0, 1.0);
+
+        self.sign(value) * t * t * (3.0 - 2.0 * t)
+    }
+
+    fn sign(&self, value: f64) -> f64 {
+        if eq_f64(self.precision.floor(value).rem_euclid(2.0), 0.0) {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+
+    /**
+       The soft blend at `point`. The surface doesn't run along the axis its
+       `normal` points down, so that axis only picks the cell and is not
+       eased, or a floor lying on a cell boundary would be gray all over.
+    */
+    fn soft_color(&self, point: Tuple, normal: Option<Tuple>) -> Color {
+        let across = normal.map(|normal| {
+            let (x, y, z) = (normal.x().abs(), normal.y().abs(), normal.z().abs());
+            if x >= y && x >= z {
+                0
+            } else if y >= z {
+                1
+            } else {
+                2
+            }
+        });
+        let product = [point.x(), point.y(), point.z()]
+            .into_iter()
+            .enumerate()
+            .map(|(axis, value)| {
+                if across == Some(axis) {
+                    self.sign(value)
+                } else {
+                    self.wave(value)
+                }
+            })
+            .product::<f64>();
+
+        self.color_b + (self.color_a - self.color_b) * ((product + 1.0) / 2.0)
+    }
 }
 
 impl Pattern for CheckerPattern {
     fn color_at(&self, point: Tuple) -> Color {
-        if eq_f64((point.x().floor() + point.y().floor() + point.z().floor()) % 2.0, 0.0) {
+        if self.softness > 0.0 {
+            return self.soft_color(point, None);
+        }
+
+        let floor = |value| self.precision.floor(value);
+        if eq_f64(
+            (floor(point.x()) + floor(point.y()) + floor(point.z())) % 2.0,
+            0.0,
+        ) {
             self.color_a
         } else {
             self.color_b
         }
     }
 
+    /**
+       Soft checkers need the normal, carried into pattern space, to know
+       which axes the surface runs along.
+    */
+    fn color_at_surface(&self, shape: &dyn Shape, point: Tuple, normal: Tuple) -> Color {
+        if self.softness <= 0.0 {
+            return self.color_at_object(shape, point);
+        }
+        let inverse = self.transformation.inverse().unwrap();
+        let pattern_point = inverse.clone() * shape.world_to_object(point);
+        let pattern_normal = inverse * shape.world_to_object(point + normal) - pattern_point;
+
+        self.soft_color(pattern_point, Some(pattern_normal))
+    }
+
     fn set_transformation(&mut self, transformation: Transformation) {
         self.transformation = transformation;
     }
@@ -39,7 +150,7 @@ impl Pattern for CheckerPattern {
 
 #[cfg(test)]
 mod tests {
-    use crate::color::Colors;
+    use crate::{color::Colors, shape::plane::Plane};
 
     use super::*;
 
@@ -96,4 +207,71 @@ mod tests {
             Colors::Black.into()
         );
     }
+
+    #[test]
+    fn soft_checkers_keep_solid_cell_centers() {
+        let pattern =
+            CheckerPattern::new(Colors::White.into(), Colors::Black.into()).with_softness(0.1);
+
+        assert_eq!(
+            pattern.color_at(Tuple::point(0.5, 0.5, 0.5)),
+            Colors::White.into()
+        );
+        assert_eq!(
+            pattern.color_at(Tuple::point(1.5, 0.5, 0.5)),
+            Colors::Black.into()
+        );
+    }
+
+    #[test]
+    fn soft_checkers_blend_across_cell_boundaries() {
+        let pattern =
+            CheckerPattern::new(Colors::White.into(), Colors::Black.into()).with_softness(0.1);
+
+        assert_eq!(
+            pattern.color_at(Tuple::point(1.0, 0.5, 0.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            pattern.color_at(Tuple::point(0.95, 0.5, 0.5)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+        assert_eq!(
+            pattern.color_at(Tuple::point(1.05, 0.5, 0.5)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn soft_checkers_on_a_floor_at_a_cell_boundary_keep_their_cells() {
+        let pattern =
+            CheckerPattern::new(Colors::White.into(), Colors::Black.into()).with_softness(0.1);
+        let floor = Plane::new();
+        let up = Tuple::vector(0.0, 1.0, 0.0);
+
+        assert_eq!(
+            Color::from(Colors::White),
+            pattern.color_at_surface(&floor, Tuple::point(0.5, 0.0, 0.5), up)
+        );
+        assert_eq!(
+            Color::from(Colors::Black),
+            pattern.color_at_surface(&floor, Tuple::point(1.5, 0.0, 0.5), up)
+        );
+        assert_eq!(
+            Color::from(Colors::Black),
+            pattern.color_at_surface(&floor, Tuple::point(0.5, -1e-9, -0.5), up)
+        );
+        assert_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            pattern.color_at_surface(&floor, Tuple::point(1.0, 0.0, 0.5), up)
+        );
+    }
+
+    #[test]
+    fn softness_is_limited_to_half_a_cell() {
+        let pattern =
+            CheckerPattern::new(Colors::White.into(), Colors::Black.into()).with_softness(2.0);
+
+        assert_eq!(0.5, pattern.softness());
+    }
 }
@@ -0,0 +1,179 @@
+use crate::{color::Color, shape::Shape, transformation::Transformation, tuple::Tuple};
+
+use super::Pattern;
+
+/**
+   Blends another pattern into a plain `base` color with distance from a
+   center, the origin unless set, from none of the base at `start` to all
+   of it at `end`. Fading a checkered floor out before the horizon keeps its
+   far away cells, which are smaller than a pixel, from turning into noise;
+   center the fade under the camera so it follows what the eye sees.
+*/
+#[derive(Debug)]
+pub struct FadePattern {
+    pattern: Box<dyn Pattern>,
+    base: Color,
+    start: f64,
+    end: f64,
+    center: Tuple,
+    transformation: Transformation,
+}
+
+impl FadePattern {
+    pub fn new<T: Pattern + 'static>(pattern: T, base: Color, start: f64, end: f64) -> Self {
+        Self {
+            pattern: Box::new(pattern),
+            base,
+            start,
+            end: end.max(start),
+            center: Tuple::origin(),
+            transformation: Transformation::identity(),
+        }
+    }
+
+    pub fn base(&self) -> Color {
+        self.base
+    }
+
+    pub fn start(&self) -> f64 {
+        self.start
+    }
+
+    pub fn end(&self) -> f64 {
+        self.end
+    }
+
+    /**
+       Measure distances from `center`, in the fade's space.
+    */
+    pub fn with_center(mut self, center: Tuple) -> Self {
+        self.center = center;
+        self
+    }
+
+    pub fn center(&self) -> Tuple {
+        self.center
+    }
+
+    /**
+       How much of the base color shows at `point`, in the fade's space,
+       easing in and out between `start` and `end` away from the center.
+    */
+    pub fn fade_at(&self, point: Tuple) -> f64 {
+        let distance = (point - self.center).magnitude();
+        if self.end <= self.start {
+            return if distance < self.start { 0.0 } else { 1.0 };
+        }
+        let t = ((distance - self.start) / (self.end - self.start)).clamp(0.0, 1.0);
+
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    fn blend(&self, color: Color, point: Tuple) -> Color {
+        color + (self.base - color) * self.fade_at(point)
+    }
+}
+
+impl Pattern for FadePattern {
+    fn color_at(&self, point: Tuple) -> Color {
+        self.blend(self.pattern.color_at_world(point), point)
+    }
+
+    fn set_transformation(&mut self, transformation: Transformation) {
+        self.transformation = transformation;
+    }
+
+    fn transformation(&self) -> Transformation {
+        self.transformation.clone()
+    }
+
+    /**
+       Patterns that depend on the surface normal still get it.
+    */
+    fn color_at_surface(&self, shape: &dyn Shape, point: Tuple, normal: Tuple) -> Color {
+        let object_point = shape.world_to_object(point);
+        let fade_point = self.transformation.inverse().unwrap() * object_point;
+
+        self.blend(
+            self.pattern.color_at_surface(shape, point, normal),
+            fade_point,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::Colors,
+        shape::material::pattern::{checker::CheckerPattern, stripes::StripePattern},
+    };
+
+    use super::*;
+
+    #[test]
+    fn a_fade_shows_the_pattern_up_close_and_the_base_far_away() {
+        let pattern = FadePattern::new(
+            StripePattern::new(Colors::White.into(), Colors::Black.into()),
+            Color::new(0.5, 0.5, 0.5),
+            10.0,
+            20.0,
+        );
+
+        assert_eq!(
+            Color::from(Colors::White),
+            pattern.color_at(Tuple::point(0.5, 0.0, 3.0))
+        );
+        assert_eq!(
+            Color::from(Colors::Black),
+            pattern.color_at(Tuple::point(1.5, 0.0, 9.0))
+        );
+        assert_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            pattern.color_at(Tuple::point(0.5, 0.0, 25.0))
+        );
+        assert_eq!(
+            Color::new(0.75, 0.75, 0.75),
+            pattern.color_at(Tuple::point(0.0, 0.0, 15.0))
+        );
+    }
+
+    #[test]
+    fn a_fade_is_measured_from_its_center() {
+        let pattern = FadePattern::new(
+            StripePattern::new(Colors::White.into(), Colors::Black.into()),
+            Color::new(0.5, 0.5, 0.5),
+            10.0,
+            20.0,
+        )
+        .with_center(Tuple::point(0.0, 0.0, 100.0));
+
+        assert_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            pattern.color_at(Tuple::point(0.5, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::from(Colors::White),
+            pattern.color_at(Tuple::point(0.5, 0.0, 95.0))
+        );
+    }
+
+    #[test]
+    fn the_faded_pattern_keeps_its_own_transformation() {
+        let mut checkers = CheckerPattern::new(Colors::White.into(), Colors::Black.into());
+        checkers.set_transformation(Transformation::identity().scale(10.0, 10.0, 10.0));
+        let pattern = FadePattern::new(checkers, Colors::Red.into(), 100.0, 200.0);
+
+        assert_eq!(
+            Color::from(Colors::White),
+            pattern.color_at(Tuple::point(5.0, 0.0, 5.0))
+        );
+        assert_eq!(
+            Color::from(Colors::Black),
+            pattern.color_at(Tuple::point(15.0, 0.0, 5.0))
+        );
+        assert_eq!(
+            Color::from(Colors::Red),
+            pattern.color_at(Tuple::point(0.0, 0.0, 300.0))
+        );
+    }
+}
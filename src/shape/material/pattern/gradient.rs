@@ -2,10 +2,46 @@ use crate::{color::Color, transformation::Transformation, tuple::Tuple};
 
 use super::Pattern;
 
+/**
+   How a gradient carries on past the unit it spans, by folding its
+   coordinate back into the unit interval.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tiling {
+    /// Wrap around, starting over at every whole unit.
+    #[default]
+    Repeat,
+    /// Run back and forth, reversing direction at every whole unit.
+    Mirror,
+    /// Hold the value at the nearest edge of the unit interval.
+    Clamp,
+    /// Draw the gradient once and nothing outside it.
+    Once,
+}
+
+impl Tiling {
+    /**
+       Map a coordinate onto the unit interval, or `None` when it falls
+       outside the single unit drawn by `Tiling::Once`.
+    */
+    pub fn apply(self, value: f64) -> Option<f64> {
+        match self {
+            Tiling::Repeat => Some(value - value.floor()),
+            Tiling::Mirror => {
+                let value = value.rem_euclid(2.0);
+                Some(if value > 1.0 { 2.0 - value } else { value })
+            }
+            Tiling::Clamp => Some(value.clamp(0.0, 1.0)),
+            Tiling::Once => (0.0..=1.0).contains(&value).then_some(value),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct GradientPattern {
     color_a: Color,
     color_b: Color,
+    tiling: Tiling,
     transformation: Transformation,
 }
 
@@ -14,17 +50,34 @@ impl GradientPattern {
         Self {
             color_a,
             color_b,
+            tiling: Tiling::default(),
             transformation: Transformation::identity(),
         }
     }
+
+    /**
+       Mirroring avoids the hard seam where a repeating gradient jumps back
+       to its first color. Outside a gradient drawn `Once` the first color is
+       used.
+    */
+    pub fn with_tiling(mut self, tiling: Tiling) -> Self {
+        self.tiling = tiling;
+        self
+    }
+
+    pub fn tiling(&self) -> Tiling {
+        self.tiling
+    }
 }
 
 impl Pattern for GradientPattern {
     fn color_at(&self, point: Tuple) -> Color {
         let distance = self.color_b - self.color_a;
-        let fraction = point.x() - point.x().floor();
 
-        self.color_a + distance * fraction
+        match self.tiling.apply(point.x()) {
+            Some(fraction) => self.color_a + distance * fraction,
+            None => self.color_a,
+        }
     }
 
     fn set_transformation(&mut self, transformation: Transformation) {
@@ -38,7 +91,7 @@ impl Pattern for GradientPattern {
 
 #[cfg(test)]
 mod tests {
-    use crate::color::Colors;
+    use crate::{color::Colors, util::eq_f64};
 
     use super::*;
 
@@ -51,4 +104,62 @@ mod tests {
         assert_eq!(pattern.color_at(Tuple::point(0.5, 0.0, 0.0)), Color::new(0.5, 0.5, 0.5));
         assert_eq!(pattern.color_at(Tuple::point(0.75, 0.0, 0.0)), Color::new(0.25, 0.25, 0.25));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn a_mirrored_gradient_runs_back_without_a_seam() {
+        let pattern = GradientPattern::new(Colors::White.into(), Colors::Black.into())
+            .with_tiling(Tiling::Mirror);
+
+        assert_eq!(
+            pattern.color_at(Tuple::point(0.75, 0.0, 0.0)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+        assert_eq!(
+            pattern.color_at(Tuple::point(1.25, 0.0, 0.0)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn a_gradient_drawn_once_uses_the_first_color_outside_its_tile() {
+        let pattern = GradientPattern::new(Colors::White.into(), Colors::Black.into())
+            .with_tiling(Tiling::Once);
+
+        assert_eq!(
+            pattern.color_at(Tuple::point(0.5, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            pattern.color_at(Tuple::point(1.5, 0.0, 0.0)),
+            Colors::White.into()
+        );
+    }
+
+    #[test]
+    fn repeating_wraps_around_each_unit() {
+        assert!(eq_f64(0.25, Tiling::Repeat.apply(1.25).unwrap()));
+        assert!(eq_f64(0.75, Tiling::Repeat.apply(-0.25).unwrap()));
+    }
+
+    #[test]
+    fn mirroring_reverses_every_other_unit() {
+        assert!(eq_f64(0.25, Tiling::Mirror.apply(0.25).unwrap()));
+        assert!(eq_f64(0.75, Tiling::Mirror.apply(1.25).unwrap()));
+        assert!(eq_f64(0.25, Tiling::Mirror.apply(2.25).unwrap()));
+        assert!(eq_f64(0.25, Tiling::Mirror.apply(-0.25).unwrap()));
+    }
+
+    #[test]
+    fn clamping_holds_the_edges() {
+        assert!(eq_f64(0.0, Tiling::Clamp.apply(-3.0).unwrap()));
+        assert!(eq_f64(0.5, Tiling::Clamp.apply(0.5).unwrap()));
+        assert!(eq_f64(1.0, Tiling::Clamp.apply(3.0).unwrap()));
+    }
+
+    #[test]
+    fn once_only_covers_the_first_unit() {
+        assert!(eq_f64(0.5, Tiling::Once.apply(0.5).unwrap()));
+        assert_eq!(None, Tiling::Once.apply(1.5));
+        assert_eq!(None, Tiling::Once.apply(-0.5));
+    }
+}
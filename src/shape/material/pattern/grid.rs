@@ -0,0 +1,165 @@
+use crate::{color::Color, shape::Shape, transformation::Transformation, tuple::Tuple};
+
+use super::Pattern;
+
+/**
+   Thin lines at every whole unit across a surface over a background, so a
+   ground plane at any height gets a line at each whole x and z. On a
+   shape the lines follow the two axes the surface runs along, so walls
+   are lined too. Scale the pattern to space the lines differently.
+*/
+#[derive(Debug, Clone)]
+pub struct GridPattern {
+    line_color: Color,
+    background: Color,
+    line_width: f64,
+    transformation: Transformation,
+}
+
+impl GridPattern {
+    pub fn new(line_color: Color, background: Color) -> Self {
+        Self {
+            line_color,
+            background,
+            line_width: 0.05,
+            transformation: Transformation::identity(),
+        }
+    }
+
+    /**
+       How wide the lines are as a fraction of a cell, 0.05 by default.
+    */
+    pub fn with_line_width(mut self, line_width: f64) -> Self {
+        self.line_width = line_width.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn line_width(&self) -> f64 {
+        self.line_width
+    }
+
+    fn on_line(&self, value: f64) -> bool {
+        (value - value.round()).abs() <= self.line_width / 2.0
+    }
+
+    fn grid_color(&self, u: f64, v: f64) -> Color {
+        if self.on_line(u) || self.on_line(v) {
+            self.line_color
+        } else {
+            self.background
+        }
+    }
+}
+
+impl Pattern for GridPattern {
+    /**
+       Without a normal the surface is taken to be level, lined across x
+       and z.
+    */
+    fn color_at(&self, point: Tuple) -> Color {
+        self.grid_color(point.x(), point.z())
+    }
+
+    fn color_at_surface(&self, shape: &dyn Shape, point: Tuple, normal: Tuple) -> Color {
+        let inverse = self.transformation.inverse().unwrap();
+        let pattern_point = inverse.clone() * shape.world_to_object(point);
+        let pattern_normal = inverse * shape.world_to_object(point + normal) - pattern_point;
+        let (x, y, z) = (
+            pattern_normal.x().abs(),
+            pattern_normal.y().abs(),
+            pattern_normal.z().abs(),
+        );
+
+        if x >= y && x >= z {
+            self.grid_color(pattern_point.y(), pattern_point.z())
+        } else if y >= z {
+            self.grid_color(pattern_point.x(), pattern_point.z())
+        } else {
+            self.grid_color(pattern_point.x(), pattern_point.y())
+        }
+    }
+
+    fn set_transformation(&mut self, transformation: Transformation) {
+        self.transformation = transformation;
+    }
+
+    fn transformation(&self) -> Transformation {
+        self.transformation.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{color::Colors, shape::plane::Plane};
+
+    use super::*;
+
+    #[test]
+    fn a_grid_on_the_ground_draws_lines_at_whole_x_and_z() {
+        let pattern = GridPattern::new(Colors::Black.into(), Colors::White.into());
+
+        assert_eq!(
+            Color::from(Colors::Black),
+            pattern.color_at(Tuple::point(3.0, 0.0, 0.4))
+        );
+        assert_eq!(
+            Color::from(Colors::Black),
+            pattern.color_at(Tuple::point(0.6, 0.0, -2.01))
+        );
+        assert_eq!(
+            Color::from(Colors::White),
+            pattern.color_at(Tuple::point(0.5, 0.0, 0.5))
+        );
+        assert_eq!(
+            Color::from(Colors::White),
+            pattern.color_at(Tuple::point(3.4, 0.5, 0.4))
+        );
+    }
+
+    #[test]
+    fn floors_and_walls_away_from_whole_units_are_lined() {
+        let pattern = GridPattern::new(Colors::Black.into(), Colors::White.into());
+        let mut floor = Plane::new();
+        floor.set_transformation(Transformation::identity().translation(0.0, 0.37, 0.0));
+        let mut wall = Plane::new();
+        wall.set_transformation(
+            Transformation::identity()
+                .rotate_x(std::f64::consts::FRAC_PI_2)
+                .translation(0.0, 0.0, 4.6),
+        );
+        let up = Tuple::vector(0.0, 1.0, 0.0);
+        let back = Tuple::vector(0.0, 0.0, -1.0);
+
+        assert_eq!(
+            Color::from(Colors::Black),
+            pattern.color_at_surface(&floor, Tuple::point(2.0, 0.37, 0.4), up)
+        );
+        assert_eq!(
+            Color::from(Colors::White),
+            pattern.color_at_surface(&floor, Tuple::point(2.5, 0.37, 0.4), up)
+        );
+        assert_eq!(
+            Color::from(Colors::Black),
+            pattern.color_at_surface(&wall, Tuple::point(0.3, 1.0, 4.6), back)
+        );
+        assert_eq!(
+            Color::from(Colors::White),
+            pattern.color_at_surface(&wall, Tuple::point(0.3, 1.5, 4.6), back)
+        );
+    }
+
+    #[test]
+    fn wider_lines_cover_more_of_each_cell() {
+        let pattern =
+            GridPattern::new(Colors::Black.into(), Colors::White.into()).with_line_width(0.5);
+
+        assert_eq!(
+            Color::from(Colors::Black),
+            pattern.color_at(Tuple::point(0.2, 0.0, 0.5))
+        );
+        assert_eq!(
+            Color::from(Colors::White),
+            pattern.color_at(Tuple::point(0.3, 0.0, 0.5))
+        );
+    }
+}
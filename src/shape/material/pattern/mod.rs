@@ -1,20 +1,44 @@
-use crate::{color::Color, shape::ShapeContainer, transformation::Transformation, tuple::Tuple};
+use crate::{color::Color, shape::Shape, transformation::Transformation, tuple::Tuple};
 use std::fmt::Debug;
 
 pub mod checker;
+pub mod fade;
 pub mod gradient;
+pub mod grid;
 pub mod ring;
 pub mod solid;
 pub mod stripes;
-
-pub trait Pattern: Debug {
+pub mod triplanar;
+pub mod uv;
+
+/**
+   Patterns are shared between materials and read from every render thread,
+   so they must be `Send + Sync`.
+*/
+pub trait Pattern: Debug + Send + Sync {
     fn color_at(&self, point: Tuple) -> Color;
     fn set_transformation(&mut self, transformation: Transformation);
     fn transformation(&self) -> Transformation;
 
-    fn color_at_object(&self, shape: ShapeContainer, point: Tuple) -> Color {
-        let object_point = shape.read().unwrap().transformation().inverse().unwrap() * point;
-        let pattern_point = self.transformation().inverse().unwrap() * object_point;
+    fn color_at_object(&self, shape: &dyn Shape, point: Tuple) -> Color {
+        self.color_at_world(shape.world_to_object(point))
+    }
+
+    /**
+       The color at `point` on `shape`, where the surface faces `normal`,
+       both in world space. Only patterns that depend on which way the
+       surface faces need more than the point.
+    */
+    fn color_at_surface(&self, shape: &dyn Shape, point: Tuple, _normal: Tuple) -> Color {
+        self.color_at_object(shape, point)
+    }
+
+    /**
+       The color at a point given in the space the pattern is attached to,
+       without going through a shape's transformation.
+    */
+    fn color_at_world(&self, point: Tuple) -> Color {
+        let pattern_point = self.transformation().inverse().unwrap() * point;
         self.color_at(pattern_point)
     }
 }
@@ -41,7 +65,7 @@ impl Pattern for TestPattern {
 
 #[cfg(test)]
 mod tests {
-    use crate::shape::{sphere::Sphere, Shape};
+    use crate::shape::sphere::Sphere;
 
     use super::*;
 
@@ -69,7 +93,7 @@ mod tests {
         let mut object = Sphere::new();
         object.set_transformation(Transformation::identity().scale(2.0, 2.0, 2.0));
         let pattern = TestPattern::default();
-        let c = pattern.color_at_object(object.into(), Tuple::point(2.0, 3.0, 4.0));
+        let c = pattern.color_at_object(&object, Tuple::point(2.0, 3.0, 4.0));
 
         assert_eq!(c, Color::new(1.0, 1.5, 2.0));
     }
@@ -79,7 +103,7 @@ mod tests {
         let object = Sphere::new();
         let mut pattern = TestPattern::default();
         pattern.set_transformation(Transformation::identity().scale(2.0, 2.0, 2.0));
-        let c = pattern.color_at_object(object.into(), Tuple::point(2.0, 3.0, 4.0));
+        let c = pattern.color_at_object(&object, Tuple::point(2.0, 3.0, 4.0));
 
         assert_eq!(c, Color::new(1.0, 1.5, 2.0));
     }
@@ -90,8 +114,17 @@ mod tests {
         object.set_transformation(Transformation::identity().scale(2.0, 2.0, 2.0));
         let mut pattern = TestPattern::default();
         pattern.set_transformation(Transformation::identity().translation(0.5, 1.0, 1.5));
-        let c = pattern.color_at_object(object.into(), Tuple::point(2.5, 3.0, 3.5));
+        let c = pattern.color_at_object(&object, Tuple::point(2.5, 3.0, 3.5));
 
         assert_eq!(c, Color::new(0.75, 0.5, 0.25));
     }
+
+    #[test]
+    fn a_pattern_at_a_world_point_ignores_objects() {
+        let mut pattern = TestPattern::default();
+        pattern.set_transformation(Transformation::identity().translation(0.5, 1.0, 1.5));
+        let c = pattern.color_at_world(Tuple::point(2.5, 3.0, 3.5));
+
+        assert_eq!(c, Color::new(2.0, 2.0, 2.0));
+    }
 }
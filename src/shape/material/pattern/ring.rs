@@ -1,4 +1,9 @@
-use crate::{color::Color, transformation::Transformation, tuple::Tuple, util::eq_f64};
+use crate::{
+    color::Color,
+    transformation::Transformation,
+    tuple::Tuple,
+    util::{eq_f64, PrecisionPolicy},
+};
 
 use super::Pattern;
 
@@ -6,6 +11,7 @@ use super::Pattern;
 pub struct RingPattern {
     color_a: Color,
     color_b: Color,
+    precision: PrecisionPolicy,
     transformation: Transformation,
 }
 
@@ -14,14 +20,30 @@ impl RingPattern {
         Self {
             color_a,
             color_b,
+            precision: PrecisionPolicy::default(),
             transformation: Transformation::identity(),
         }
     }
+
+    /**
+       How close to a boundary a point must be to count as past it.
+    */
+    pub fn with_precision(mut self, precision: PrecisionPolicy) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn precision(&self) -> PrecisionPolicy {
+        self.precision
+    }
 }
 
 impl Pattern for RingPattern {
     fn color_at(&self, point: Tuple) -> Color {
-        if eq_f64((point.x().powi(2) + point.z().powi(2)).sqrt().floor() % 2.0, 0.0) {
+        let ring = self
+            .precision
+            .floor((point.x().powi(2) + point.z().powi(2)).sqrt());
+        if eq_f64(ring % 2.0, 0.0) {
             self.color_a
         } else {
             self.color_b
@@ -1,4 +1,9 @@
-use crate::{color::Color, transformation::Transformation, tuple::Tuple, util::eq_f64};
+use crate::{
+    color::Color,
+    transformation::Transformation,
+    tuple::Tuple,
+    util::{eq_f64, PrecisionPolicy},
+};
 
 use super::Pattern;
 
@@ -6,6 +11,7 @@ use super::Pattern;
 pub struct StripePattern {
     color_a: Color,
     color_b: Color,
+    precision: PrecisionPolicy,
     transformation: Transformation,
 }
 
@@ -14,6 +20,7 @@ impl StripePattern {
         Self {
             color_a,
             color_b,
+            precision: PrecisionPolicy::default(),
             transformation: Transformation::identity(),
         }
     }
@@ -25,11 +32,23 @@ impl StripePattern {
     pub fn color_b(&self) -> Color {
         self.color_b
     }
+
+    /**
+       How close to a boundary a point must be to count as past it.
+    */
+    pub fn with_precision(mut self, precision: PrecisionPolicy) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn precision(&self) -> PrecisionPolicy {
+        self.precision
+    }
 }
 
 impl Pattern for StripePattern {
     fn color_at(&self, point: Tuple) -> Color {
-        if eq_f64(point.x().floor() % 2.0, 0.0) {
+        if eq_f64(self.precision.floor(point.x()) % 2.0, 0.0) {
             self.color_a
         } else {
             self.color_b
@@ -133,7 +152,7 @@ mod tests {
         let mut object = Sphere::new();
         object.set_transformation(Transformation::identity().scale(2.0, 2.0, 2.0));
         let pattern = StripePattern::new(Colors::White.into(), Colors::Black.into());
-        let c = pattern.color_at_object(object.into(), Tuple::point(1.5, 0.0, 0.0));
+        let c = pattern.color_at_object(&object, Tuple::point(1.5, 0.0, 0.0));
 
         assert_eq!(c, Colors::White.into());
     }
@@ -143,7 +162,7 @@ mod tests {
         let object = Sphere::new();
         let mut pattern = StripePattern::new(Colors::White.into(), Colors::Black.into());
         pattern.set_transformation(Transformation::identity().scale(2.0, 2.0, 2.0));
-        let c = pattern.color_at_object(object.into(), Tuple::point(1.5, 0.0, 0.0));
+        let c = pattern.color_at_object(&object, Tuple::point(1.5, 0.0, 0.0));
 
         assert_eq!(c, Colors::White.into());
     }
@@ -154,8 +173,22 @@ mod tests {
         object.set_transformation(Transformation::identity().scale(2.0, 2.0, 2.0));
         let mut pattern = StripePattern::new(Colors::White.into(), Colors::Black.into());
         pattern.set_transformation(Transformation::identity().translation(0.5, 0.0, 0.0));
-        let c = pattern.color_at_object(object.into(), Tuple::point(2.5, 0.0, 0.0));
+        let c = pattern.color_at_object(&object, Tuple::point(2.5, 0.0, 0.0));
 
         assert_eq!(c, Colors::White.into());
     }
+
+    #[test]
+    fn a_precision_policy_decides_which_stripe_a_boundary_falls_in() {
+        let pattern = StripePattern::new(Colors::White.into(), Colors::Black.into());
+        let point = Tuple::point(0.9999999, 0.0, 0.0);
+
+        assert_eq!(Color::from(Colors::Black), pattern.color_at(point));
+        assert_eq!(
+            Color::from(Colors::White),
+            pattern
+                .with_precision(PrecisionPolicy::Ulps(4))
+                .color_at(point)
+        );
+    }
 }
@@ -0,0 +1,155 @@
+use crate::{color::Color, shape::Shape, transformation::Transformation, tuple::Tuple};
+
+use super::{uv::UvPattern, Pattern};
+
+/**
+   Projects a `UvPattern` along each of the x, y and z axes and blends the
+   three by how squarely the surface faces each axis, so shapes without
+   texture coordinates, like meshes, can be textured without the stretching
+   a single planar projection gives on steep sides. Each projection repeats
+   once per unit.
+*/
+#[derive(Debug)]
+pub struct TriplanarPattern {
+    uv_pattern: Box<dyn UvPattern>,
+    sharpness: f64,
+    transformation: Transformation,
+}
+
+impl TriplanarPattern {
+    pub fn new<T: UvPattern + 'static>(uv_pattern: T) -> Self {
+        Self {
+            uv_pattern: Box::new(uv_pattern),
+            sharpness: 4.0,
+            transformation: Transformation::identity(),
+        }
+    }
+
+    /**
+       The power the normal's components are raised to for the blend
+       weights, 4 by default. Higher values narrow the seams where the
+       projections mix.
+    */
+    pub fn with_sharpness(mut self, sharpness: f64) -> Self {
+        self.sharpness = sharpness.max(0.0);
+        self
+    }
+
+    pub fn sharpness(&self) -> f64 {
+        self.sharpness
+    }
+
+    /**
+       The blended color at `point` on a surface facing `normal`, both in
+       pattern space.
+    */
+    pub fn color_facing(&self, point: Tuple, normal: Tuple) -> Color {
+        let weights = [normal.x(), normal.y(), normal.z()].map(|n| n.abs().powf(self.sharpness));
+        let total = weights.iter().sum::<f64>();
+        if total == 0.0 {
+            return self.uv_color_at(point.x(), point.z());
+        }
+
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        (self.uv_color_at(z, y) * weights[0]
+            + self.uv_color_at(x, z) * weights[1]
+            + self.uv_color_at(x, y) * weights[2])
+            * (1.0 / total)
+    }
+
+    fn uv_color_at(&self, u: f64, v: f64) -> Color {
+        self.uv_pattern
+            .uv_color_at(u.rem_euclid(1.0), v.rem_euclid(1.0))
+    }
+}
+
+impl Pattern for TriplanarPattern {
+    /**
+       Without the surface normal, the direction from the origin stands in
+       for it, which is exact for spheres.
+    */
+    fn color_at(&self, point: Tuple) -> Color {
+        self.color_facing(point, point - Tuple::origin())
+    }
+
+    /**
+       The normal is carried into pattern space as a direction, so the blend
+       is exact for rotations and uniform scales.
+    */
+    fn color_at_surface(&self, shape: &dyn Shape, point: Tuple, normal: Tuple) -> Color {
+        let inverse = self.transformation.inverse().unwrap();
+        let pattern_point = inverse.clone() * shape.world_to_object(point);
+        let pattern_normal = inverse * shape.world_to_object(point + normal) - pattern_point;
+
+        self.color_facing(pattern_point, pattern_normal)
+    }
+
+    fn set_transformation(&mut self, transformation: Transformation) {
+        self.transformation = transformation;
+    }
+
+    fn transformation(&self) -> Transformation {
+        self.transformation.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shape::sphere::Sphere;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct UvColor;
+
+    impl UvPattern for UvColor {
+        fn uv_color_at(&self, u: f64, v: f64) -> Color {
+            Color::new(u, v, 0.0)
+        }
+    }
+
+    #[test]
+    fn a_surface_facing_an_axis_uses_only_that_projection() {
+        let pattern = TriplanarPattern::new(UvColor);
+        let point = Tuple::point(0.25, 1.0, 0.5);
+
+        assert_eq!(
+            Color::new(0.25, 0.5, 0.0),
+            pattern.color_facing(point, Tuple::vector(0.0, 1.0, 0.0))
+        );
+        assert_eq!(
+            Color::new(0.5, 0.0, 0.0),
+            pattern.color_facing(point, Tuple::vector(-1.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            Color::new(0.25, 0.0, 0.0),
+            pattern.color_facing(point, Tuple::vector(0.0, 0.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn a_diagonal_surface_blends_the_projections_evenly() {
+        let pattern = TriplanarPattern::new(UvColor);
+        let normal = Tuple::vector(1.0, 1.0, 0.0).normalize();
+
+        assert_eq!(
+            Color::new(0.375, 0.25, 0.0),
+            pattern.color_facing(Tuple::point(0.25, 0.0, 0.5), normal)
+        );
+    }
+
+    #[test]
+    fn the_surface_normal_is_moved_into_pattern_space() {
+        let mut pattern = TriplanarPattern::new(UvColor);
+        pattern.set_transformation(Transformation::identity().rotate_z(std::f64::consts::PI / 2.0));
+        let shape = Sphere::from(Transformation::identity().translation(5.0, 0.0, 0.0));
+
+        let color = pattern.color_at_surface(
+            &shape,
+            Tuple::point(5.0, 0.25, 0.5),
+            Tuple::vector(1.0, 0.0, 0.0),
+        );
+
+        assert_eq!(Color::new(0.25, 0.5, 0.0), color);
+    }
+}
@@ -0,0 +1,729 @@
+use std::{f64::consts::PI, fmt::Debug};
+
+use crate::{
+    canvas::Canvas, color::Color, transformation::Transformation, tuple::Tuple, util::EPSILON,
+};
+
+use super::Pattern;
+
+/**
+   A pattern over the unit square, painted onto a surface by a `UvMap`.
+*/
+pub trait UvPattern: Debug + Send + Sync {
+    fn uv_color_at(&self, u: f64, v: f64) -> Color;
+}
+
+impl<T: UvPattern + ?Sized> UvPattern for Box<T> {
+    fn uv_color_at(&self, u: f64, v: f64) -> Color {
+        self.as_ref().uv_color_at(u, v)
+    }
+}
+
+/**
+   `width` by `height` alternating squares across the unit square.
+*/
+#[derive(Debug, Clone)]
+pub struct UvCheckers {
+    width: f64,
+    height: f64,
+    color_a: Color,
+    color_b: Color,
+}
+
+impl UvCheckers {
+    pub fn new(width: f64, height: f64, color_a: Color, color_b: Color) -> Self {
+        Self {
+            width,
+            height,
+            color_a,
+            color_b,
+        }
+    }
+}
+
+impl UvPattern for UvCheckers {
+    fn uv_color_at(&self, u: f64, v: f64) -> Color {
+        let u2 = (u * self.width).floor();
+        let v2 = (v * self.height).floor();
+
+        if (u2 + v2).rem_euclid(2.0) == 0.0 {
+            self.color_a
+        } else {
+            self.color_b
+        }
+    }
+}
+
+/**
+   A solid color with a different color in each corner, for checking that
+   a texture is the right way up and round.
+*/
+#[derive(Debug, Clone)]
+pub struct UvAlignCheck {
+    main: Color,
+    upper_left: Color,
+    upper_right: Color,
+    bottom_left: Color,
+    bottom_right: Color,
+}
+
+impl UvAlignCheck {
+    pub fn new(
+        main: Color,
+        upper_left: Color,
+        upper_right: Color,
+        bottom_left: Color,
+        bottom_right: Color,
+    ) -> Self {
+        Self {
+            main,
+            upper_left,
+            upper_right,
+            bottom_left,
+            bottom_right,
+        }
+    }
+}
+
+impl UvPattern for UvAlignCheck {
+    fn uv_color_at(&self, u: f64, v: f64) -> Color {
+        match (u, v) {
+            (u, v) if v > 0.8 && u < 0.2 => self.upper_left,
+            (u, v) if v > 0.8 && u > 0.8 => self.upper_right,
+            (u, v) if v < 0.2 && u < 0.2 => self.bottom_left,
+            (u, v) if v < 0.2 && u > 0.8 => self.bottom_right,
+            _ => self.main,
+        }
+    }
+}
+
+/**
+   An image stretched once across the unit square, with its top row at
+   `v = 1`. Each point takes the color of the nearest pixel.
+*/
+pub struct UvImage {
+    canvas: Canvas,
+}
+
+impl UvImage {
+    pub fn new(canvas: Canvas) -> Self {
+        Self { canvas }
+    }
+
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+}
+
+impl Debug for UvImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UvImage")
+            .field("width", &self.canvas.width())
+            .field("height", &self.canvas.height())
+            .finish()
+    }
+}
+
+impl UvPattern for UvImage {
+    fn uv_color_at(&self, u: f64, v: f64) -> Color {
+        let (width, height) = (self.canvas.width(), self.canvas.height());
+        let x = (u.clamp(0.0, 1.0) * (width - 1) as f64).round() as usize;
+        let y = ((1.0 - v.clamp(0.0, 1.0)) * (height - 1) as f64).round() as usize;
+
+        self.canvas[(x, y)]
+    }
+}
+
+/**
+   The faces of a unit cube, each unwrapped as seen from outside with up
+   pointing towards +y, or towards -z and +z for the top and bottom.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    Left,
+    Front,
+    Right,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    /**
+       The face whose axis the point is furthest along.
+    */
+    pub fn from_point(point: Tuple) -> Self {
+        let coord = point.x().abs().max(point.y().abs()).max(point.z().abs());
+
+        if coord == point.x() {
+            CubeFace::Right
+        } else if coord == -point.x() {
+            CubeFace::Left
+        } else if coord == point.y() {
+            CubeFace::Up
+        } else if coord == -point.y() {
+            CubeFace::Down
+        } else if coord == point.z() {
+            CubeFace::Front
+        } else {
+            CubeFace::Back
+        }
+    }
+
+    pub fn uv(&self, point: Tuple) -> (f64, f64) {
+        let wrap = |n: f64| n.rem_euclid(2.0) / 2.0;
+        let (x, y, z) = (point.x(), point.y(), point.z());
+
+        match self {
+            CubeFace::Front => (wrap(x + 1.0), wrap(y + 1.0)),
+            CubeFace::Back => (wrap(1.0 - x), wrap(y + 1.0)),
+            CubeFace::Left => (wrap(z + 1.0), wrap(y + 1.0)),
+            CubeFace::Right => (wrap(1.0 - z), wrap(y + 1.0)),
+            CubeFace::Up => (wrap(x + 1.0), wrap(1.0 - z)),
+            CubeFace::Down => (wrap(x + 1.0), wrap(z + 1.0)),
+        }
+    }
+}
+
+/**
+   Ways of unwrapping a point on a shape onto the unit square. Each expects
+   the shape's default, untransformed size in pattern space.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UvMap {
+    /**
+       Around a unit sphere, with `u` as the longitude and `v` running from
+       the south pole to the north.
+    */
+    Spherical,
+    /**
+       Around the y axis of a unit cylinder, one unit of height per repeat.
+       Points inside the radius are on a cap and are mapped flat across it.
+    */
+    Cylindrical,
+    /**
+       Around the y axis of a double cone, one unit of height per repeat.
+       Points inside the cone are on a cap and are mapped flat across it.
+    */
+    Conical,
+    /**
+       Across the xz plane, repeating `scale` times per unit along x and z
+       and shifted by `offset` after scaling.
+    */
+    Planar {
+        scale: (f64, f64),
+        offset: (f64, f64),
+    },
+    /**
+       Each face of a unit cube onto the whole square. Use `CubeMapPattern`
+       for a different pattern on each face.
+    */
+    Cubic,
+}
+
+impl UvMap {
+    /**
+       A planar map repeating once per unit, as in the book.
+    */
+    pub fn planar() -> Self {
+        UvMap::Planar {
+            scale: (1.0, 1.0),
+            offset: (0.0, 0.0),
+        }
+    }
+
+    pub fn map(&self, point: Tuple) -> (f64, f64) {
+        match self {
+            UvMap::Spherical => {
+                let radius = Tuple::vector(point.x(), point.y(), point.z()).magnitude();
+                let phi = (point.y() / radius).acos();
+
+                (azimuth(point), 1.0 - phi / PI)
+            }
+            UvMap::Cylindrical => around_y(point, 1.0),
+            UvMap::Conical => around_y(point, point.y().abs()),
+            UvMap::Planar { scale, offset } => (
+                (point.x() * scale.0 + offset.0).rem_euclid(1.0),
+                (point.z() * scale.1 + offset.1).rem_euclid(1.0),
+            ),
+            UvMap::Cubic => CubeFace::from_point(point).uv(point),
+        }
+    }
+}
+
+/**
+   Wraps around the side of a surface of revolution of the given `radius`
+   at the point's height, or flat across a cap for points inside it. Where
+   the radius shrinks to nothing, as at the tip of a cone, there is no way
+   round and `u` is 0.
+*/
+fn around_y(point: Tuple, radius: f64) -> (f64, f64) {
+    if radius < EPSILON {
+        return (0.0, point.y().rem_euclid(1.0));
+    }
+    if point.x().powi(2) + point.z().powi(2) < (radius - EPSILON).powi(2) {
+        cap_map(point, radius)
+    } else {
+        (azimuth(point), point.y().rem_euclid(1.0))
+    }
+}
+
+/**
+   The angle around the y axis as a fraction of a turn, starting at -z and
+   increasing towards +x.
+*/
+fn azimuth(point: Tuple) -> f64 {
+    let theta = point.x().atan2(point.z());
+    let raw_u = theta / (2.0 * PI);
+
+    1.0 - (raw_u + 0.5)
+}
+
+fn cap_map(point: Tuple, radius: f64) -> (f64, f64) {
+    (
+        (point.x() / radius + 1.0) / 2.0,
+        (point.z() / radius + 1.0) / 2.0,
+    )
+}
+
+/**
+   Paints a `UvPattern` onto a shape through a `UvMap`.
+*/
+#[derive(Debug)]
+pub struct TextureMapPattern {
+    uv_pattern: Box<dyn UvPattern>,
+    uv_map: UvMap,
+    transformation: Transformation,
+}
+
+impl TextureMapPattern {
+    pub fn new<T: UvPattern + 'static>(uv_pattern: T, uv_map: UvMap) -> Self {
+        Self {
+            uv_pattern: Box::new(uv_pattern),
+            uv_map,
+            transformation: Transformation::identity(),
+        }
+    }
+
+    pub fn uv_map(&self) -> UvMap {
+        self.uv_map
+    }
+}
+
+impl Pattern for TextureMapPattern {
+    fn color_at(&self, point: Tuple) -> Color {
+        let (u, v) = self.uv_map.map(point);
+        self.uv_pattern.uv_color_at(u, v)
+    }
+
+    fn set_transformation(&mut self, transformation: Transformation) {
+        self.transformation = transformation;
+    }
+
+    fn transformation(&self) -> Transformation {
+        self.transformation.clone()
+    }
+}
+
+/**
+   A separate `UvPattern` on each face of a cube, as for skyboxes and dice.
+*/
+#[derive(Debug)]
+pub struct CubeMapPattern {
+    left: Box<dyn UvPattern>,
+    front: Box<dyn UvPattern>,
+    right: Box<dyn UvPattern>,
+    back: Box<dyn UvPattern>,
+    up: Box<dyn UvPattern>,
+    down: Box<dyn UvPattern>,
+    transformation: Transformation,
+}
+
+impl CubeMapPattern {
+    pub fn new(
+        left: Box<dyn UvPattern>,
+        front: Box<dyn UvPattern>,
+        right: Box<dyn UvPattern>,
+        back: Box<dyn UvPattern>,
+        up: Box<dyn UvPattern>,
+        down: Box<dyn UvPattern>,
+    ) -> Self {
+        Self {
+            left,
+            front,
+            right,
+            back,
+            up,
+            down,
+            transformation: Transformation::identity(),
+        }
+    }
+
+    fn face(&self, face: CubeFace) -> &dyn UvPattern {
+        match face {
+            CubeFace::Left => self.left.as_ref(),
+            CubeFace::Front => self.front.as_ref(),
+            CubeFace::Right => self.right.as_ref(),
+            CubeFace::Back => self.back.as_ref(),
+            CubeFace::Up => self.up.as_ref(),
+            CubeFace::Down => self.down.as_ref(),
+        }
+    }
+}
+
+impl Pattern for CubeMapPattern {
+    fn color_at(&self, point: Tuple) -> Color {
+        let face = CubeFace::from_point(point);
+        let (u, v) = face.uv(point);
+
+        self.face(face).uv_color_at(u, v)
+    }
+
+    fn set_transformation(&mut self, transformation: Transformation) {
+        self.transformation = transformation;
+    }
+
+    fn transformation(&self) -> Transformation {
+        self.transformation.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{color::Colors, util::eq_f64};
+
+    use super::{super::checker, *};
+
+    fn assert_uv(expected: (f64, f64), actual: (f64, f64)) {
+        assert!(
+            eq_f64(expected.0, actual.0) && eq_f64(expected.1, actual.1),
+            "expected {expected:?}, got {actual:?}"
+        );
+    }
+
+    #[test]
+    fn checker_pattern_in_2d() {
+        let black: Color = Colors::Black.into();
+        let white: Color = Colors::White.into();
+        let checkers = UvCheckers::new(2.0, 2.0, black, white);
+
+        assert_eq!(black, checkers.uv_color_at(0.0, 0.0));
+        assert_eq!(white, checkers.uv_color_at(0.5, 0.0));
+        assert_eq!(white, checkers.uv_color_at(0.0, 0.5));
+        assert_eq!(black, checkers.uv_color_at(0.5, 0.5));
+        assert_eq!(black, checkers.uv_color_at(1.0, 1.0));
+    }
+
+    #[test]
+    fn cube_faces_on_integer_boundaries_map_without_acne() {
+        let black: Color = Colors::Black.into();
+        let white: Color = Colors::White.into();
+        let mapped = TextureMapPattern::new(UvCheckers::new(2.0, 2.0, black, white), UvMap::Cubic);
+        let solid = checker::CheckerPattern::new(black, white);
+        let (inside, outside) = (
+            Tuple::point(1.0 - 1e-4, 0.25, 0.5),
+            Tuple::point(1.0 + 1e-4, 0.25, 0.5),
+        );
+
+        assert_ne!(solid.color_at(inside), solid.color_at(outside));
+        assert_eq!(mapped.color_at(inside), mapped.color_at(outside));
+    }
+
+    #[test]
+    fn using_a_cylindrical_mapping_on_a_3d_point() {
+        let r = 2f64.sqrt() / 2.0;
+        let cases = [
+            (Tuple::point(0.0, 0.0, -1.0), (0.0, 0.0)),
+            (Tuple::point(0.0, 0.5, -1.0), (0.0, 0.5)),
+            (Tuple::point(0.0, 1.0, -1.0), (0.0, 0.0)),
+            (Tuple::point(r, 0.5, -r), (0.125, 0.5)),
+            (Tuple::point(1.0, 0.5, 0.0), (0.25, 0.5)),
+            (Tuple::point(r, 0.5, r), (0.375, 0.5)),
+            (Tuple::point(0.0, -0.25, 1.0), (0.5, 0.75)),
+            (Tuple::point(-r, 0.5, r), (0.625, 0.5)),
+            (Tuple::point(-1.0, 1.25, 0.0), (0.75, 0.25)),
+            (Tuple::point(-r, 0.5, -r), (0.875, 0.5)),
+        ];
+
+        for (point, uv) in cases {
+            assert_uv(uv, UvMap::Cylindrical.map(point));
+        }
+    }
+
+    #[test]
+    fn cylinder_caps_are_mapped_flat() {
+        assert_uv(
+            (0.5, 0.5),
+            UvMap::Cylindrical.map(Tuple::point(0.0, 1.0, 0.0)),
+        );
+        assert_uv(
+            (0.75, 0.25),
+            UvMap::Cylindrical.map(Tuple::point(0.5, 0.0, -0.5)),
+        );
+    }
+
+    #[test]
+    fn using_a_conical_mapping_on_a_3d_point() {
+        assert_uv((0.0, 0.5), UvMap::Conical.map(Tuple::point(0.0, 0.5, -0.5)));
+        assert_uv((0.25, 0.5), UvMap::Conical.map(Tuple::point(1.5, 1.5, 0.0)));
+        assert_uv(
+            (0.5, 0.75),
+            UvMap::Conical.map(Tuple::point(0.0, -0.25, 0.25)),
+        );
+    }
+
+    #[test]
+    fn cone_caps_are_mapped_flat_across_their_radius() {
+        assert_uv((0.5, 0.5), UvMap::Conical.map(Tuple::point(0.0, 2.0, 0.0)));
+        assert_uv(
+            (0.75, 0.5),
+            UvMap::Conical.map(Tuple::point(1.0, -2.0, 0.0)),
+        );
+    }
+
+    #[test]
+    fn the_apex_of_a_cone_maps_to_the_start_of_the_seam() {
+        assert_uv((0.0, 0.0), UvMap::Conical.map(Tuple::point(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn using_a_spherical_mapping_on_a_3d_point() {
+        let half = 2f64.sqrt() / 2.0;
+        let cases = [
+            (Tuple::point(0.0, 0.0, -1.0), (0.0, 0.5)),
+            (Tuple::point(1.0, 0.0, 0.0), (0.25, 0.5)),
+            (Tuple::point(0.0, 0.0, 1.0), (0.5, 0.5)),
+            (Tuple::point(-1.0, 0.0, 0.0), (0.75, 0.5)),
+            (Tuple::point(0.0, 1.0, 0.0), (0.5, 1.0)),
+            (Tuple::point(0.0, -1.0, 0.0), (0.5, 0.0)),
+            (Tuple::point(half, half, 0.0), (0.25, 0.75)),
+        ];
+
+        for (point, uv) in cases {
+            assert_uv(uv, UvMap::Spherical.map(point));
+        }
+    }
+
+    #[test]
+    fn using_a_planar_mapping_on_a_3d_point() {
+        let cases = [
+            (Tuple::point(0.25, 0.0, 0.5), (0.25, 0.5)),
+            (Tuple::point(0.25, 0.0, -0.25), (0.25, 0.75)),
+            (Tuple::point(0.25, 0.5, -0.25), (0.25, 0.75)),
+            (Tuple::point(1.25, 0.0, 0.5), (0.25, 0.5)),
+            (Tuple::point(0.25, 0.0, -1.75), (0.25, 0.25)),
+            (Tuple::point(1.0, 0.0, -1.0), (0.0, 0.0)),
+            (Tuple::point(0.0, 0.0, 0.0), (0.0, 0.0)),
+        ];
+
+        for (point, uv) in cases {
+            assert_uv(uv, UvMap::planar().map(point));
+        }
+    }
+
+    #[test]
+    fn a_planar_mapping_can_be_scaled_and_offset() {
+        let map = UvMap::Planar {
+            scale: (0.25, 0.5),
+            offset: (0.5, 0.25),
+        };
+
+        assert_uv((0.5, 0.25), map.map(Tuple::point(0.0, 0.0, 0.0)));
+        assert_uv((0.75, 0.75), map.map(Tuple::point(1.0, 0.0, 1.0)));
+        assert_uv((0.5, 0.25), map.map(Tuple::point(4.0, 3.0, -2.0)));
+    }
+
+    const RED: Color = Color::new(1.0, 0.0, 0.0);
+    const YELLOW: Color = Color::new(1.0, 1.0, 0.0);
+    const BROWN: Color = Color::new(1.0, 0.5, 0.0);
+    const GREEN: Color = Color::new(0.0, 1.0, 0.0);
+    const CYAN: Color = Color::new(0.0, 1.0, 1.0);
+    const BLUE: Color = Color::new(0.0, 0.0, 1.0);
+    const PURPLE: Color = Color::new(1.0, 0.0, 1.0);
+    const WHITE: Color = Color::new(1.0, 1.0, 1.0);
+
+    #[test]
+    fn layout_of_the_align_check_pattern() {
+        let pattern = UvAlignCheck::new(WHITE, RED, YELLOW, GREEN, CYAN);
+
+        assert_eq!(WHITE, pattern.uv_color_at(0.5, 0.5));
+        assert_eq!(RED, pattern.uv_color_at(0.1, 0.9));
+        assert_eq!(YELLOW, pattern.uv_color_at(0.9, 0.9));
+        assert_eq!(GREEN, pattern.uv_color_at(0.1, 0.1));
+        assert_eq!(CYAN, pattern.uv_color_at(0.9, 0.1));
+    }
+
+    #[test]
+    fn identifying_the_face_of_a_cube_from_a_point() {
+        let cases = [
+            (Tuple::point(-1.0, 0.5, -0.25), CubeFace::Left),
+            (Tuple::point(1.1, -0.75, 0.8), CubeFace::Right),
+            (Tuple::point(0.1, 0.6, 0.9), CubeFace::Front),
+            (Tuple::point(-0.7, 0.0, -2.0), CubeFace::Back),
+            (Tuple::point(0.5, 1.0, 0.9), CubeFace::Up),
+            (Tuple::point(-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+
+        for (point, face) in cases {
+            assert_eq!(face, CubeFace::from_point(point));
+        }
+    }
+
+    #[test]
+    fn uv_mapping_each_face_of_a_cube() {
+        let cases = [
+            (CubeFace::Front, Tuple::point(-0.5, 0.5, 1.0), (0.25, 0.75)),
+            (CubeFace::Front, Tuple::point(0.5, -0.5, 1.0), (0.75, 0.25)),
+            (CubeFace::Back, Tuple::point(0.5, 0.5, -1.0), (0.25, 0.75)),
+            (CubeFace::Back, Tuple::point(-0.5, -0.5, -1.0), (0.75, 0.25)),
+            (CubeFace::Left, Tuple::point(-1.0, 0.5, -0.5), (0.25, 0.75)),
+            (CubeFace::Left, Tuple::point(-1.0, -0.5, 0.5), (0.75, 0.25)),
+            (CubeFace::Right, Tuple::point(1.0, 0.5, 0.5), (0.25, 0.75)),
+            (CubeFace::Right, Tuple::point(1.0, -0.5, -0.5), (0.75, 0.25)),
+            (CubeFace::Up, Tuple::point(-0.5, 1.0, -0.5), (0.25, 0.75)),
+            (CubeFace::Up, Tuple::point(0.5, 1.0, 0.5), (0.75, 0.25)),
+            (CubeFace::Down, Tuple::point(-0.5, -1.0, 0.5), (0.25, 0.75)),
+            (CubeFace::Down, Tuple::point(0.5, -1.0, -0.5), (0.75, 0.25)),
+        ];
+
+        for (face, point, uv) in cases {
+            assert_uv(uv, face.uv(point));
+        }
+    }
+
+    #[test]
+    fn finding_the_colors_on_a_mapped_cube() {
+        let pattern = CubeMapPattern::new(
+            Box::new(UvAlignCheck::new(YELLOW, CYAN, RED, BLUE, BROWN)),
+            Box::new(UvAlignCheck::new(CYAN, RED, YELLOW, BROWN, GREEN)),
+            Box::new(UvAlignCheck::new(RED, YELLOW, PURPLE, GREEN, WHITE)),
+            Box::new(UvAlignCheck::new(GREEN, PURPLE, CYAN, WHITE, BLUE)),
+            Box::new(UvAlignCheck::new(BROWN, CYAN, PURPLE, RED, YELLOW)),
+            Box::new(UvAlignCheck::new(PURPLE, BROWN, GREEN, BLUE, WHITE)),
+        );
+        let cases = [
+            (Tuple::point(-1.0, 0.0, 0.0), YELLOW),
+            (Tuple::point(-1.0, 0.9, -0.9), CYAN),
+            (Tuple::point(-1.0, 0.9, 0.9), RED),
+            (Tuple::point(-1.0, -0.9, -0.9), BLUE),
+            (Tuple::point(-1.0, -0.9, 0.9), BROWN),
+            (Tuple::point(0.0, 0.0, 1.0), CYAN),
+            (Tuple::point(-0.9, 0.9, 1.0), RED),
+            (Tuple::point(0.9, 0.9, 1.0), YELLOW),
+            (Tuple::point(-0.9, -0.9, 1.0), BROWN),
+            (Tuple::point(0.9, -0.9, 1.0), GREEN),
+            (Tuple::point(1.0, 0.0, 0.0), RED),
+            (Tuple::point(1.0, 0.9, 0.9), YELLOW),
+            (Tuple::point(1.0, 0.9, -0.9), PURPLE),
+            (Tuple::point(1.0, -0.9, 0.9), GREEN),
+            (Tuple::point(1.0, -0.9, -0.9), WHITE),
+            (Tuple::point(0.0, 0.0, -1.0), GREEN),
+            (Tuple::point(0.9, 0.9, -1.0), PURPLE),
+            (Tuple::point(-0.9, 0.9, -1.0), CYAN),
+            (Tuple::point(0.9, -0.9, -1.0), WHITE),
+            (Tuple::point(-0.9, -0.9, -1.0), BLUE),
+            (Tuple::point(0.0, 1.0, 0.0), BROWN),
+            (Tuple::point(-0.9, 1.0, -0.9), CYAN),
+            (Tuple::point(0.9, 1.0, -0.9), PURPLE),
+            (Tuple::point(-0.9, 1.0, 0.9), RED),
+            (Tuple::point(0.9, 1.0, 0.9), YELLOW),
+            (Tuple::point(0.0, -1.0, 0.0), PURPLE),
+            (Tuple::point(-0.9, -1.0, 0.9), BROWN),
+            (Tuple::point(0.9, -1.0, 0.9), GREEN),
+            (Tuple::point(-0.9, -1.0, -0.9), BLUE),
+            (Tuple::point(0.9, -1.0, -0.9), WHITE),
+        ];
+
+        for (point, color) in cases {
+            assert_eq!(color, pattern.color_at(point), "at {point:?}");
+        }
+    }
+
+    #[test]
+    fn a_cubic_texture_map_repeats_one_pattern_on_every_face() {
+        let pattern = TextureMapPattern::new(
+            UvAlignCheck::new(WHITE, RED, YELLOW, GREEN, CYAN),
+            UvMap::Cubic,
+        );
+
+        assert_eq!(RED, pattern.color_at(Tuple::point(-0.9, 0.9, 1.0)));
+        assert_eq!(RED, pattern.color_at(Tuple::point(1.0, 0.9, 0.9)));
+        assert_eq!(WHITE, pattern.color_at(Tuple::point(0.0, -1.0, 0.0)));
+    }
+
+    #[test]
+    fn a_texture_map_paints_a_uv_pattern_around_a_cylinder() {
+        let black: Color = Colors::Black.into();
+        let white: Color = Colors::White.into();
+        let pattern =
+            TextureMapPattern::new(UvCheckers::new(4.0, 2.0, black, white), UvMap::Cylindrical);
+
+        assert_eq!(black, pattern.color_at(Tuple::point(0.0, 0.25, -1.0)));
+        assert_eq!(white, pattern.color_at(Tuple::point(1.0, 0.25, 0.0)));
+        assert_eq!(white, pattern.color_at(Tuple::point(0.0, 0.75, -1.0)));
+    }
+
+    #[test]
+    fn an_image_is_sampled_by_its_nearest_pixel() {
+        let mut canvas = Canvas::new(10, 10);
+        for y in 0..10 {
+            for x in 0..10 {
+                let shade = (x + y) as f64 / 18.0;
+                canvas[(x, y)] = Color::new(shade, 0.0, 1.0 - shade);
+            }
+        }
+        let image = UvImage::new(canvas);
+        let cases = [
+            (0.0, 0.0, 9),
+            (0.3, 0.0, 12),
+            (0.6, 0.3, 11),
+            (1.0, 1.0, 9),
+            (0.0, 1.0, 0),
+        ];
+
+        for (u, v, sum) in cases {
+            let shade = sum as f64 / 18.0;
+            assert_eq!(Color::new(shade, 0.0, 1.0 - shade), image.uv_color_at(u, v));
+        }
+    }
+
+    #[test]
+    fn using_a_texture_map_pattern_with_a_spherical_map() {
+        let black: Color = Colors::Black.into();
+        let white: Color = Colors::White.into();
+        let pattern =
+            TextureMapPattern::new(UvCheckers::new(16.0, 8.0, black, white), UvMap::Spherical);
+        let cases = [
+            (Tuple::point(0.4315, 0.4670, 0.7719), white),
+            (Tuple::point(-0.9654, 0.2552, -0.0534), black),
+            (Tuple::point(0.1039, 0.7090, 0.6975), white),
+            (Tuple::point(-0.4986, -0.7856, -0.3663), black),
+            (Tuple::point(-0.0317, -0.9395, 0.3411), black),
+            (Tuple::point(0.4809, -0.7721, 0.4154), black),
+            (Tuple::point(0.0285, -0.9612, -0.2745), black),
+            (Tuple::point(-0.5734, -0.2162, -0.7903), white),
+            (Tuple::point(0.7688, -0.1470, 0.6223), black),
+            (Tuple::point(-0.7652, 0.2175, 0.6060), black),
+        ];
+
+        for (point, color) in cases {
+            assert_eq!(color, pattern.color_at(point), "{point:?}");
+        }
+    }
+
+    #[test]
+    fn a_texture_map_wraps_an_image_around_a_sphere() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas[(0, 0)] = Colors::White.into();
+        let pattern = TextureMapPattern::new(UvImage::new(canvas), UvMap::Spherical);
+
+        assert_eq!(
+            Color::from(Colors::White),
+            pattern.color_at(Tuple::point(0.5, 0.8, -0.3))
+        );
+        assert_eq!(
+            Color::from(Colors::Black),
+            pattern.color_at(Tuple::point(-0.5, -0.8, 0.3))
+        );
+    }
+}
@@ -3,31 +3,41 @@ use group::WeakGroupContainer;
 use uuid::Uuid;
 
 use std::{
+    f64::consts::PI,
     fmt::Debug,
     ops::Deref,
     sync::{Arc, RwLock},
 };
 
 use crate::{
-    intersection::{Intersection, ShapeIntersection},
+    color::Color,
+    intersection::{Intersections, ShapeIntersection},
     transformation::Transformation,
     tuple::Tuple,
+    util::PrecisionPolicy,
 };
 
-use self::material::Material;
+use self::{material::Material, visibility::Visibility, world_transform::WorldTransform};
 
 use crate::intersection::ray::Ray;
 
 pub mod bounded_box;
 pub mod cone;
+pub mod convex_polytope;
 pub mod cube;
 pub mod cylinder;
 pub mod group;
+pub mod half_space;
 pub mod material;
 pub mod plane;
+pub mod point_cloud;
+pub mod portal;
+pub mod shell;
 pub mod smooth_triangle;
 pub mod sphere;
 pub mod triangle;
+pub mod visibility;
+pub mod world_transform;
 
 #[derive(Debug, Clone)]
 pub struct ShapeContainer(Arc<RwLock<dyn Shape + Sync + Send>>);
@@ -37,8 +47,12 @@ impl ShapeContainer {
         self.read().unwrap().id()
     }
 
-    fn includes(&self, id: Uuid) -> bool {
-        self.read().unwrap().contains(id)
+    /**
+       Replace the material with `f` applied to a copy of the current one,
+       so existing settings are kept. Groups update every child.
+    */
+    pub fn update_material<F: Fn(Material) -> Material>(&self, f: F) {
+        self.write().unwrap().update_material(&f);
     }
 }
 
@@ -64,11 +78,13 @@ impl PartialEq for ShapeContainer {
 
 pub trait Shape: Debug {
     fn id(&self) -> Uuid;
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection>;
+    fn local_intersect(&self, ray: &Ray) -> Intersections;
     fn transformation(&self) -> Transformation;
     fn set_transformation(&mut self, transformation: Transformation);
     fn material(&self, id: Uuid) -> Option<Material>;
     fn set_material(&mut self, material: Material);
+    fn visibility(&self, id: Uuid) -> Option<Visibility>;
+    fn set_visibility(&mut self, visibility: Visibility);
     fn local_normal_at(
         &self,
         id: uuid::Uuid,
@@ -80,9 +96,138 @@ pub trait Shape: Debug {
     fn bounds(&self) -> BoundedBox;
     fn contains(&self, id: Uuid) -> bool;
 
-    fn intersects(&self, ray: Ray) -> Vec<Intersection> {
-        let ray = self.transformation().inverse().unwrap() * ray;
-        self.local_intersect(ray)
+    /**
+       Set the transformation while building a shape, so it can be made and
+       added to a world in one expression.
+    */
+    fn with_transformation(mut self, transformation: Transformation) -> Self
+    where
+        Self: Sized,
+    {
+        self.set_transformation(transformation);
+        self
+    }
+
+    /**
+       Set the material while building a shape. Groups keep no material of
+       their own, so use `GroupContainer::set_material_recursive` for them.
+    */
+    fn with_material(mut self, material: Material) -> Self
+    where
+        Self: Sized,
+    {
+        self.set_material(material);
+        self
+    }
+
+    fn teleport(&self, _id: Uuid, _point: Tuple, _direction: Tuple) -> Option<Ray> {
+        None
+    }
+
+    /**
+       The name of the concrete shape type, e.g. `Sphere`.
+    */
+    fn type_name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
+
+    /**
+       The shapes directly inside this one. Only groups have any.
+    */
+    fn child_shapes(&self) -> Vec<ShapeContainer> {
+        vec![]
+    }
+
+    /**
+       Skip intersections on the back side of the shape. Only triangles
+       support culling; groups pass the setting on to their children.
+    */
+    fn set_backface_culling(&mut self, _backface_culling: bool) {}
+
+    /**
+       Split groups with at least `threshold` children into subgroups, see
+       `GroupContainer::divide`. Other shapes have nothing to split.
+    */
+    fn divide(&mut self, _threshold: usize) {}
+
+    /**
+       The color blended from per vertex colors at the surface coordinates
+       `u` and `v`, for shapes that have them.
+    */
+    fn vertex_color(&self, _id: Uuid, _u: f64, _v: f64) -> Option<Color> {
+        None
+    }
+
+    /**
+       The transformation to world space cached by `prepare`, if it is still
+       current.
+    */
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        None
+    }
+
+    fn set_world_transform(&mut self, _world_transform: Option<WorldTransform>) {}
+
+    /**
+       The transformation to world space of the innermost shape holding
+       `id`, from the cache `prepare` fills when it is current and otherwise
+       by walking up through the groups.
+    */
+    fn world_transformation(&self, id: Uuid) -> Transformation {
+        let child = self
+            .child_shapes()
+            .into_iter()
+            .find(|child| child.read().unwrap().contains(id));
+        if let Some(child) = child {
+            return child.read().unwrap().world_transformation(id);
+        }
+
+        if let Some(world_transform) = self.world_transform() {
+            return world_transform.transformation().clone();
+        }
+        match self.parent().and_then(|parent| parent.upgrade()) {
+            Some(parent) => {
+                let parent = parent.read().unwrap();
+                &parent.world_transformation(parent.id()) * &self.transformation()
+            }
+            None => self.transformation(),
+        }
+    }
+
+    /**
+       Drop the cached world transformation of this shape and of every
+       shape beneath it, which all move with it.
+    */
+    fn clear_world_transform(&mut self) {
+        self.set_world_transform(None);
+        for child in self.child_shapes() {
+            child.write().unwrap().clear_world_transform();
+        }
+    }
+
+    /**
+       Cache this shape's transformation to world space, given its parent's
+       world transformation, so points and normals can be converted without
+       walking up through the groups. Transformations are inverted under
+       `precision`. Groups prepare their children too.
+    */
+    fn prepare(&mut self, parent: &Transformation, precision: PrecisionPolicy) {
+        let world_transform = WorldTransform::new(parent, &self.transformation(), precision);
+        self.set_world_transform(Some(world_transform));
+    }
+
+    fn update_material(&mut self, f: &dyn Fn(Material) -> Material) {
+        let material = self.material(self.id()).unwrap_or_default();
+        self.set_material(f(material));
+    }
+
+    fn intersects(&self, ray: &Ray) -> Intersections {
+        let ray = match self.world_transform() {
+            Some(world_transform) => world_transform.local_inverse() * ray,
+            None => &self.transformation().inverse().unwrap() * ray,
+        };
+        self.local_intersect(&ray)
     }
 
     fn normal_at(
@@ -97,6 +242,10 @@ pub trait Shape: Debug {
     }
 
     fn world_to_object(&self, point: Tuple) -> Tuple {
+        if let Some(world_transform) = self.world_transform() {
+            return world_transform.inverse() * point;
+        }
+
         let mut point = point;
         if let Some(parent) = self.parent() {
             point = parent
@@ -113,7 +262,32 @@ pub trait Shape: Debug {
             * point
     }
 
+    fn object_to_world(&self, point: Tuple) -> Tuple {
+        if let Some(world_transform) = self.world_transform() {
+            return world_transform.transformation() * point;
+        }
+
+        let point = self.transformation() * point;
+
+        if let Some(parent) = self.parent() {
+            parent
+                .upgrade()
+                .unwrap()
+                .read()
+                .unwrap()
+                .object_to_world(point)
+        } else {
+            point
+        }
+    }
+
     fn normal_to_world(&self, normal: Tuple) -> Tuple {
+        if let Some(world_transform) = self.world_transform() {
+            let mut normal = world_transform.inverse_transpose() * normal;
+            normal.as_vector();
+            return normal.normalize();
+        }
+
         let mut normal = self
             .transformation()
             .inverse()
@@ -142,13 +316,62 @@ impl PartialEq for &dyn Shape {
     }
 }
 
+/**
+   The part of a cylinder or cone kept between `theta_min` and `theta_max`
+   radians around the y axis, measured from +x towards +z, leaving the rest
+   open. Caps are cut the same way.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sweep {
+    theta_min: f64,
+    theta_max: f64,
+}
+
+impl Sweep {
+    pub fn new(theta_min: f64, theta_max: f64) -> Self {
+        Self {
+            theta_min,
+            theta_max,
+        }
+    }
+
+    pub fn theta_min(&self) -> f64 {
+        self.theta_min
+    }
+
+    pub fn theta_max(&self) -> f64 {
+        self.theta_max
+    }
+
+    /**
+       Whether the direction (`x`, `z`) from the y axis is kept.
+    */
+    pub fn contains(&self, x: f64, z: f64) -> bool {
+        if self.theta_max - self.theta_min >= 2.0 * PI {
+            return true;
+        }
+
+        let theta = (z.atan2(x) - self.theta_min).rem_euclid(2.0 * PI);
+        theta <= self.theta_max - self.theta_min
+    }
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self::new(0.0, 2.0 * PI)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use core::f64;
 
     use group::{Group, GroupContainer};
+    use smallvec::smallvec;
     use sphere::Sphere;
 
+    use crate::intersection::Intersection;
+
     use super::*;
 
     #[derive(Debug)]
@@ -175,8 +398,8 @@ mod tests {
             self.id
         }
 
-        fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
-            vec![
+        fn local_intersect(&self, ray: &Ray) -> Intersections {
+            smallvec![
                 Intersection::new(ray.origin().x(), self.id),
                 Intersection::new(ray.origin().y(), self.id),
                 Intersection::new(ray.origin().z(), self.id),
@@ -203,6 +426,16 @@ mod tests {
             self.material = material;
         }
 
+        fn visibility(&self, id: Uuid) -> Option<Visibility> {
+            if id == self.id {
+                Some(Visibility::default())
+            } else {
+                None
+            }
+        }
+
+        fn set_visibility(&mut self, _visibility: Visibility) {}
+
         fn local_normal_at(
             &self,
             id: Uuid,
@@ -267,12 +500,54 @@ mod tests {
         assert_eq!(shape.material(shape.id()).unwrap(), material);
     }
 
+    #[test]
+    fn updating_a_material_keeps_its_other_settings() {
+        let mut shape = TestShape::new();
+        shape.set_material(Material::new().with_ambient(1.0));
+        let shape = ShapeContainer::from(shape);
+
+        shape.update_material(|m| m.with_reflective(0.3));
+
+        let material = shape.read().unwrap().material(shape.id()).unwrap();
+        assert_eq!(material.ambient(), 1.0);
+        assert_eq!(material.reflective(), 0.3);
+    }
+
+    #[test]
+    fn updating_the_material_of_a_group_updates_its_children() {
+        let s1 = ShapeContainer::from(Sphere::new());
+        let mut s2 = Sphere::new();
+        s2.set_material(Material::new().with_ambient(1.0));
+        let s2 = ShapeContainer::from(s2);
+        let g1 = GroupContainer::default();
+        let g2 = GroupContainer::default();
+        g2.add_child(s2.clone());
+        g1.add_child(s1.clone());
+        g1.add_child(g2.into());
+        let g1: ShapeContainer = g1.into();
+
+        g1.update_material(|m| m.with_reflective(0.3));
+
+        let m1 = s1.read().unwrap().material(s1.id()).unwrap();
+        let m2 = s2.read().unwrap().material(s2.id()).unwrap();
+        assert_eq!(m1.reflective(), 0.3);
+        assert_eq!(m2.reflective(), 0.3);
+        assert_eq!(m2.ambient(), 1.0);
+    }
+
+    #[test]
+    fn a_shape_knows_its_type_name() {
+        assert_eq!(TestShape::new().type_name(), "TestShape");
+        assert_eq!(Sphere::new().type_name(), "Sphere");
+        assert_eq!(GroupContainer::default().read().unwrap().type_name(), "Group");
+    }
+
     #[test]
     fn intersects_scaled_shape_with_ray() {
         let ray = Ray::new(Tuple::point(1.0, 2.0, 3.0), Tuple::vector(0.0, 1.0, 0.0));
         let mut shape = TestShape::new();
         shape.set_transformation(Transformation::identity().scale(2.0, 2.0, 2.0).clone());
-        let xs = shape.intersects(ray);
+        let xs = shape.intersects(&ray);
 
         assert_eq!(xs.len(), 3);
         assert_eq!(xs[0].t(), 0.5);
@@ -332,6 +607,28 @@ mod tests {
         assert_eq!(p, Tuple::point(0.0, 0.0, -1.0));
     }
 
+    #[test]
+    fn converting_a_point_from_object_to_world_space() {
+        let mut g1 = Group::new();
+        g1.set_transformation(Transformation::identity().rotate_y(f64::consts::PI / 2.0));
+        let mut g2 = Group::new();
+        g2.set_transformation(Transformation::identity().scale(2.0, 2.0, 2.0));
+        let mut s = Sphere::new();
+        s.set_transformation(Transformation::identity().translation(5.0, 0.0, 0.0));
+        let s = ShapeContainer::from(s);
+        let g2 = GroupContainer::from(g2);
+        g2.add_child(s.clone());
+        let g1 = GroupContainer::from(g1);
+        g1.add_child(g2.into());
+
+        let p = s
+            .read()
+            .unwrap()
+            .object_to_world(Tuple::point(0.0, 0.0, -1.0));
+
+        assert_eq!(p, Tuple::point(-2.0, 0.0, -10.0));
+    }
+
     #[test]
     fn converting_a_normal_from_object_to_world_space() {
         let mut g1 = Group::new();
@@ -378,4 +675,90 @@ mod tests {
 
         assert_eq!(n, Tuple::vector(0.28570, 0.42854, -0.85716));
     }
+
+    #[test]
+    fn a_prepared_child_uses_its_cached_world_transform() {
+        let mut g1 = Group::new();
+        g1.set_transformation(Transformation::identity().rotate_y(f64::consts::PI / 2.0));
+        let mut g2 = Group::new();
+        g2.set_transformation(Transformation::identity().scale(1.0, 2.0, 3.0));
+        let g1 = GroupContainer::from(g1);
+        let mut s = Sphere::new();
+        s.set_transformation(Transformation::identity().translation(5.0, 0.0, 0.0));
+        let s = ShapeContainer::from(s);
+        let g2 = GroupContainer::from(g2);
+        g2.add_child(s.clone());
+        g1.add_child(g2.into());
+        let point = Tuple::point(-2.0, 0.0, -10.0);
+        let walked = s.read().unwrap().world_to_object(point);
+
+        g1.write()
+            .unwrap()
+            .prepare(&Transformation::identity(), PrecisionPolicy::default());
+
+        assert!(s.read().unwrap().world_transform().is_some());
+        assert_eq!(walked, s.read().unwrap().world_to_object(point));
+        let i = ShapeIntersection::new(0.0, s.clone(), g1.read().unwrap().id());
+        let n = s
+            .read()
+            .unwrap()
+            .normal_at(s.id(), Tuple::point(1.7321, 1.1547, -5.5774), i)
+            .unwrap();
+        assert_eq!(n, Tuple::vector(0.28570, 0.42854, -0.85716));
+    }
+
+    #[test]
+    fn building_a_shape_with_a_transformation_and_material() {
+        let transformation = Transformation::identity().translation(2.0, 3.0, 4.0);
+        let material = Material::new().with_ambient(1.0);
+        let s = ShapeContainer::from(
+            Sphere::new()
+                .with_transformation(transformation.clone())
+                .with_material(material.clone()),
+        );
+        let cube = cube::Cube::new().with_material(material.clone());
+
+        let s = s.read().unwrap();
+        assert_eq!(transformation, s.transformation());
+        assert_eq!(Some(material.clone()), s.material(s.id()));
+        assert_eq!(Transformation::identity(), cube.transformation());
+        assert_eq!(Some(material), cube.material(cube.id()));
+    }
+
+    #[test]
+    fn changing_the_transformation_clears_the_cache() {
+        let mut s = Sphere::new();
+        s.prepare(&Transformation::identity(), PrecisionPolicy::default());
+        assert!(s.world_transform().is_some());
+
+        s.set_transformation(Transformation::identity().scale(2.0, 2.0, 2.0));
+
+        assert!(s.world_transform().is_none());
+    }
+
+    #[test]
+    fn moving_a_group_clears_the_cache_of_everything_inside_it() {
+        let outer = GroupContainer::from(Group::new());
+        let inner = GroupContainer::from(Group::new());
+        let s = ShapeContainer::from(Sphere::new());
+        inner.add_child(s.clone());
+        outer.add_child(inner.clone().into());
+        outer
+            .write()
+            .unwrap()
+            .prepare(&Transformation::identity(), PrecisionPolicy::default());
+        assert!(s.read().unwrap().world_transform().is_some());
+
+        outer
+            .write()
+            .unwrap()
+            .set_transformation(Transformation::identity().translation(5.0, 0.0, 0.0));
+
+        assert!(inner.read().unwrap().world_transform().is_none());
+        assert!(s.read().unwrap().world_transform().is_none());
+        assert_eq!(
+            Tuple::point(5.0, 0.0, 0.0),
+            s.read().unwrap().object_to_world(Tuple::origin())
+        );
+    }
 }
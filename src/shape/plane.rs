@@ -1,22 +1,28 @@
 use core::f64;
 
+use smallvec::smallvec;
 use uuid::Uuid;
 
 use crate::{
-    intersection::{ray::Ray, Intersection, ShapeIntersection},
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
     transformation::Transformation,
     tuple::Tuple,
     util::EPSILON,
 };
 
-use super::{group::WeakGroupContainer, material::Material, BoundedBox, Shape};
+use super::{
+    group::WeakGroupContainer, material::Material, visibility::Visibility,
+    world_transform::WorldTransform, BoundedBox, Shape,
+};
 
 #[derive(Debug)]
 pub struct Plane {
     id: Uuid,
     material: Material,
+    visibility: Visibility,
     transformation: Transformation,
     parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
 }
 
 impl Plane {
@@ -24,8 +30,10 @@ impl Plane {
         Self {
             id: Uuid::new_v4(),
             material: Material::new(),
+            visibility: Visibility::default(),
             transformation: Transformation::identity(),
             parent: None,
+            world_transform: None,
         }
     }
 }
@@ -35,11 +43,11 @@ impl Shape for Plane {
         self.id
     }
 
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
         if ray.direction().y().abs() < EPSILON {
-            vec![]
+            smallvec![]
         } else {
-            vec![Intersection::new(
+            smallvec![Intersection::new(
                 -ray.origin().y() / ray.direction().y(),
                 self.id,
             )]
@@ -52,6 +60,7 @@ impl Shape for Plane {
 
     fn set_transformation(&mut self, transformation: Transformation) {
         self.transformation = transformation;
+        self.world_transform = None;
     }
 
     fn material(&self, id: Uuid) -> Option<Material> {
@@ -66,6 +75,18 @@ impl Shape for Plane {
         self.material = material;
     }
 
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        if self.id == id {
+            Some(self.visibility)
+        } else {
+            None
+        }
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+
     fn local_normal_at(
         &self,
         id: Uuid,
@@ -84,9 +105,18 @@ impl Shape for Plane {
     }
 
     fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
         self.parent = Some(parent);
     }
 
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
     fn bounds(&self) -> BoundedBox {
         BoundedBox::new(
             Tuple::point(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
@@ -145,7 +175,7 @@ mod tests {
     fn intersecting_a_ray_parallel_to_the_plane() {
         let p = Plane::new();
         let r = Ray::new(Tuple::point(0.0, 10.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
-        let xs = p.local_intersect(r);
+        let xs = p.local_intersect(&r);
 
         assert_eq!(xs.len(), 0);
     }
@@ -154,7 +184,7 @@ mod tests {
     fn intersecting_a_ray_coplanar_with_the_plane() {
         let p = Plane::new();
         let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
-        let xs = p.local_intersect(r);
+        let xs = p.local_intersect(&r);
 
         assert_eq!(xs.len(), 0);
     }
@@ -163,7 +193,7 @@ mod tests {
     fn a_ray_intersecting_a_plane_from_above() {
         let p = Plane::new();
         let r = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
-        let xs = p.local_intersect(r);
+        let xs = p.local_intersect(&r);
 
         assert_eq!(xs.len(), 1);
         assert_eq!(xs[0].t(), 1.0);
@@ -173,7 +203,7 @@ mod tests {
     fn a_ray_intersecting_a_plane_from_below() {
         let p = Plane::new();
         let r = Ray::new(Tuple::point(0.0, -1.0, 0.0), Tuple::vector(0.0, 1.0, 0.0));
-        let xs = p.local_intersect(r);
+        let xs = p.local_intersect(&r);
 
         assert_eq!(xs.len(), 1);
         assert_eq!(xs[0].t(), 1.0);
@@ -0,0 +1,343 @@
+use std::ops::Range;
+
+use smallvec::smallvec;
+use uuid::Uuid;
+
+use crate::{
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
+    transformation::Transformation,
+    tuple::Tuple,
+};
+
+use super::{
+    group::WeakGroupContainer, material::Material, visibility::Visibility,
+    world_transform::WorldTransform, BoundedBox, Shape,
+};
+
+/**
+   The most points kept in one leaf of a point cloud's hierarchy.
+*/
+const LEAF_SIZE: usize = 4;
+
+#[derive(Debug)]
+enum Node {
+    Leaf {
+        bounds: BoundedBox,
+        points: Range<usize>,
+    },
+    Branch {
+        bounds: BoundedBox,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl Node {
+    fn bounds(&self) -> &BoundedBox {
+        match self {
+            Node::Leaf { bounds, .. } | Node::Branch { bounds, .. } => bounds,
+        }
+    }
+}
+
+/**
+   A set of points drawn as small spheres of one radius, for scans and
+   particle systems. The points live in the shape itself, under a bounding
+   volume hierarchy of their own, rather than as a group of spheres, so
+   large clouds stay cheap to build and to trace.
+*/
+#[derive(Debug)]
+pub struct PointCloud {
+    id: Uuid,
+    points: Vec<Tuple>,
+    radius: f64,
+    nodes: Vec<Node>,
+    transformation: Transformation,
+    material: Material,
+    visibility: Visibility,
+    parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
+}
+
+impl PointCloud {
+    pub fn new(mut points: Vec<Tuple>, radius: f64) -> Self {
+        let mut nodes = vec![];
+        if !points.is_empty() {
+            build(&mut points, 0, radius, &mut nodes);
+        }
+
+        Self {
+            id: Uuid::new_v4(),
+            points,
+            radius,
+            nodes,
+            transformation: Transformation::identity(),
+            material: Material::new(),
+            visibility: Visibility::default(),
+            parent: None,
+            world_transform: None,
+        }
+    }
+
+    /**
+       The points of the cloud, reordered as they are stored in the
+       hierarchy.
+    */
+    pub fn points(&self) -> &[Tuple] {
+        &self.points
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /**
+       Indexes of the leaves whose bounds `accept`, found by descending only
+       into nodes it accepts.
+    */
+    fn leaves<F: Fn(&BoundedBox) -> bool>(&self, accept: F) -> Vec<Range<usize>> {
+        let mut leaves = vec![];
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !accept(node.bounds()) {
+                continue;
+            }
+            match node {
+                Node::Leaf { points, .. } => leaves.push(points.clone()),
+                Node::Branch { left, right, .. } => stack.extend([*left, *right]),
+            }
+        }
+
+        leaves
+    }
+}
+
+/**
+   Build the node for `points`, which start at `offset` in the cloud, and
+   everything under it, returning its index. Points are sorted in place
+   along the longest axis of their bounds and split at the median.
+*/
+fn build(points: &mut [Tuple], offset: usize, radius: f64, nodes: &mut Vec<Node>) -> usize {
+    let mut bounds = BoundedBox::empty();
+    for point in points.iter() {
+        bounds.add_point(*point - Tuple::vector(radius, radius, radius));
+        bounds.add_point(*point + Tuple::vector(radius, radius, radius));
+    }
+
+    let index = nodes.len();
+    if points.len() <= LEAF_SIZE {
+        nodes.push(Node::Leaf {
+            bounds,
+            points: offset..offset + points.len(),
+        });
+        return index;
+    }
+
+    let extent = bounds.max() - bounds.min();
+    let axis = |point: &Tuple| {
+        if extent.x() >= extent.y() && extent.x() >= extent.z() {
+            point.x()
+        } else if extent.y() >= extent.z() {
+            point.y()
+        } else {
+            point.z()
+        }
+    };
+    points.sort_by(|a, b| axis(a).total_cmp(&axis(b)));
+
+    nodes.push(Node::Branch {
+        bounds,
+        left: 0,
+        right: 0,
+    });
+    let middle = points.len() / 2;
+    let (left_points, right_points) = points.split_at_mut(middle);
+    let left = build(left_points, offset, radius, nodes);
+    let right = build(right_points, offset + middle, radius, nodes);
+    if let Node::Branch {
+        left: l, right: r, ..
+    } = &mut nodes[index]
+    {
+        *l = left;
+        *r = right;
+    }
+
+    index
+}
+
+impl Shape for PointCloud {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
+        let mut xs = smallvec![];
+
+        for leaf in self.leaves(|bounds| bounds.intersects(ray)) {
+            for center in &self.points[leaf] {
+                let sphere_to_ray = ray.origin() - *center;
+
+                let a = ray.direction() * ray.direction();
+                let b = (ray.direction() * sphere_to_ray) * 2.0;
+                let c = sphere_to_ray * sphere_to_ray - self.radius.powi(2);
+
+                let discriminant = b.powi(2) - 4.0 * a * c;
+                if discriminant >= 0.0 {
+                    xs.push(Intersection::new(
+                        (-b - discriminant.sqrt()) / (2.0 * a),
+                        self.id,
+                    ));
+                    xs.push(Intersection::new(
+                        (-b + discriminant.sqrt()) / (2.0 * a),
+                        self.id,
+                    ));
+                }
+            }
+        }
+
+        xs
+    }
+
+    fn transformation(&self) -> Transformation {
+        self.transformation.clone()
+    }
+
+    fn set_transformation(&mut self, transformation: Transformation) {
+        self.transformation = transformation;
+        self.world_transform = None;
+    }
+
+    fn material(&self, id: Uuid) -> Option<Material> {
+        if self.id == id {
+            Some(self.material.clone())
+        } else {
+            None
+        }
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        if self.id == id {
+            Some(self.visibility)
+        } else {
+            None
+        }
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+
+    /**
+       The normal of the sphere whose center is closest to `point`.
+    */
+    fn local_normal_at(
+        &self,
+        id: Uuid,
+        point: Tuple,
+        _intersection: ShapeIntersection,
+    ) -> Option<Tuple> {
+        if id != self.id {
+            return None;
+        }
+
+        self.leaves(|bounds| bounds.contains_point(point))
+            .into_iter()
+            .flat_map(|leaf| &self.points[leaf])
+            .map(|center| point - *center)
+            .min_by(|a, b| a.magnitude().total_cmp(&b.magnitude()))
+    }
+
+    fn parent(&self) -> Option<WeakGroupContainer> {
+        self.parent.clone()
+    }
+
+    fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
+        self.parent = Some(parent);
+    }
+
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
+    fn bounds(&self) -> BoundedBox {
+        match self.nodes.first() {
+            Some(root) => BoundedBox::new(root.bounds().min(), root.bounds().max()),
+            None => BoundedBox::empty(),
+        }
+    }
+
+    fn contains(&self, id: Uuid) -> bool {
+        self.id == id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shape::ShapeContainer;
+
+    use super::*;
+
+    fn line(count: usize) -> Vec<Tuple> {
+        (0..count)
+            .map(|i| Tuple::point(i as f64, 0.0, 0.0))
+            .collect()
+    }
+
+    #[test]
+    fn a_ray_along_a_line_of_points_hits_every_splat() {
+        let cloud = PointCloud::new(line(50), 0.25);
+        let r = Ray::new(Tuple::point(-5.0, 0.0, 0.0), Tuple::vector(1.0, 0.0, 0.0));
+
+        let xs = cloud.local_intersect(&r);
+
+        assert_eq!(100, xs.len());
+        assert!(xs.iter().any(|i| i.t() == 4.75));
+        assert!(xs.iter().any(|i| i.t() == 54.25));
+    }
+
+    #[test]
+    fn a_ray_between_splats_misses() {
+        let cloud = PointCloud::new(line(50), 0.25);
+        let r = Ray::new(Tuple::point(10.5, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        assert!(cloud.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn the_normal_faces_away_from_the_nearest_point() {
+        let cloud = ShapeContainer::from(PointCloud::new(line(50), 0.25));
+        let i = ShapeIntersection::new(0.0, cloud.clone(), cloud.id());
+
+        let normal = cloud
+            .read()
+            .unwrap()
+            .local_normal_at(cloud.id(), Tuple::point(17.0, 0.25, 0.0), i)
+            .unwrap();
+
+        assert_eq!(Tuple::vector(0.0, 0.25, 0.0), normal);
+    }
+
+    #[test]
+    fn a_point_cloud_is_bounded_by_its_splats() {
+        let cloud = PointCloud::new(line(10), 0.5);
+        let bounds = cloud.bounds();
+
+        assert_eq!(Tuple::point(-0.5, -0.5, -0.5), bounds.min());
+        assert_eq!(Tuple::point(9.5, 0.5, 0.5), bounds.max());
+    }
+}
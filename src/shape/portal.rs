@@ -0,0 +1,274 @@
+use std::sync::{Arc, RwLock, Weak};
+
+use smallvec::smallvec;
+use uuid::Uuid;
+
+use crate::{
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
+    transformation::Transformation,
+    tuple::Tuple,
+    util::EPSILON,
+};
+
+use super::{
+    group::WeakGroupContainer, material::Material, visibility::Visibility,
+    world_transform::WorldTransform, BoundedBox, Shape, ShapeContainer,
+};
+
+#[derive(Debug)]
+pub struct Portal {
+    id: Uuid,
+    material: Material,
+    visibility: Visibility,
+    transformation: Transformation,
+    parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
+    twin: Option<Weak<RwLock<Portal>>>,
+}
+
+impl Portal {
+    pub fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            material: Material::new(),
+            visibility: Visibility::default(),
+            transformation: Transformation::identity(),
+            parent: None,
+            world_transform: None,
+            twin: None,
+        }
+    }
+
+    /**
+       Create two linked portals. A ray hitting either portal continues from
+       the same local position and direction on its twin.
+    */
+    pub fn pair(
+        transformation_a: Transformation,
+        transformation_b: Transformation,
+    ) -> (ShapeContainer, ShapeContainer) {
+        let mut a = Portal::new();
+        a.set_transformation(transformation_a);
+        let a = Arc::new(RwLock::new(a));
+
+        let mut b = Portal::new();
+        b.set_transformation(transformation_b);
+        let b = Arc::new(RwLock::new(b));
+
+        a.write().unwrap().twin = Some(Arc::downgrade(&b));
+        b.write().unwrap().twin = Some(Arc::downgrade(&a));
+
+        (ShapeContainer(a), ShapeContainer(b))
+    }
+
+    pub fn twin(&self) -> Option<ShapeContainer> {
+        self.twin
+            .as_ref()
+            .and_then(|twin| twin.upgrade())
+            .map(|twin| ShapeContainer(twin))
+    }
+}
+
+impl Shape for Portal {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
+        if ray.direction().y().abs() < EPSILON {
+            return smallvec![];
+        }
+
+        let t = -ray.origin().y() / ray.direction().y();
+        let point = ray.position(t);
+
+        if point.x().abs() <= 1.0 && point.z().abs() <= 1.0 {
+            smallvec![Intersection::new(t, self.id)]
+        } else {
+            smallvec![]
+        }
+    }
+
+    fn transformation(&self) -> Transformation {
+        self.transformation.clone()
+    }
+
+    fn set_transformation(&mut self, transformation: Transformation) {
+        self.transformation = transformation;
+        self.world_transform = None;
+    }
+
+    fn material(&self, id: Uuid) -> Option<Material> {
+        if self.id == id {
+            Some(self.material.clone())
+        } else {
+            None
+        }
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        if self.id == id {
+            Some(self.visibility)
+        } else {
+            None
+        }
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+
+    fn local_normal_at(
+        &self,
+        id: Uuid,
+        _point: Tuple,
+        _intersection: ShapeIntersection,
+    ) -> Option<Tuple> {
+        if self.id == id {
+            Some(Tuple::vector(0.0, 1.0, 0.0))
+        } else {
+            None
+        }
+    }
+
+    fn parent(&self) -> Option<WeakGroupContainer> {
+        self.parent.clone()
+    }
+
+    fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
+        self.parent = Some(parent);
+    }
+
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
+    fn bounds(&self) -> BoundedBox {
+        BoundedBox::new(Tuple::point(-1.0, 0.0, -1.0), Tuple::point(1.0, 0.0, 1.0))
+    }
+
+    fn contains(&self, id: Uuid) -> bool {
+        self.id == id
+    }
+
+    fn teleport(&self, id: Uuid, point: Tuple, direction: Tuple) -> Option<Ray> {
+        if self.id != id {
+            return None;
+        }
+
+        let twin = self.twin()?;
+        let twin = twin.read().unwrap();
+
+        let point = twin.object_to_world(self.world_to_object(point));
+        let direction = twin
+            .object_to_world(self.world_to_object(direction))
+            .normalize();
+
+        Some(Ray::new(point + direction * EPSILON, direction))
+    }
+}
+
+impl Default for Portal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    #[test]
+    fn a_ray_intersects_a_portal_inside_its_bounds() {
+        let p = Portal::new();
+        let r = Ray::new(Tuple::point(0.5, 1.0, -0.5), Tuple::vector(0.0, -1.0, 0.0));
+        let xs = p.local_intersect(&r);
+
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t(), 1.0);
+    }
+
+    #[test]
+    fn a_ray_misses_a_portal_outside_its_bounds() {
+        let p = Portal::new();
+        let r = Ray::new(Tuple::point(1.5, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        let xs = p.local_intersect(&r);
+
+        assert!(xs.is_empty());
+    }
+
+    #[test]
+    fn paired_portals_reference_each_other() {
+        let (a, b) = Portal::pair(Transformation::identity(), Transformation::identity());
+
+        let a_twin =
+            a.read()
+                .unwrap()
+                .teleport(a.id(), Tuple::origin(), Tuple::vector(0.0, -1.0, 0.0));
+        let b_twin =
+            b.read()
+                .unwrap()
+                .teleport(b.id(), Tuple::origin(), Tuple::vector(0.0, -1.0, 0.0));
+
+        assert!(a_twin.is_some());
+        assert!(b_twin.is_some());
+    }
+
+    #[test]
+    fn an_unpaired_portal_does_not_teleport() {
+        let p = Portal::new();
+
+        assert!(p
+            .teleport(p.id(), Tuple::origin(), Tuple::vector(0.0, -1.0, 0.0))
+            .is_none());
+    }
+
+    #[test]
+    fn a_portal_does_not_teleport_other_shapes() {
+        let (a, _b) = Portal::pair(Transformation::identity(), Transformation::identity());
+
+        assert!(a
+            .read()
+            .unwrap()
+            .teleport(
+                Uuid::new_v4(),
+                Tuple::origin(),
+                Tuple::vector(0.0, -1.0, 0.0)
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn a_portal_re_emits_the_ray_from_its_twin() {
+        let (a, _b) = Portal::pair(
+            Transformation::identity(),
+            Transformation::identity()
+                .rotate_z(PI / 2.0)
+                .translation(10.0, 0.0, 0.0),
+        );
+
+        let r = a
+            .read()
+            .unwrap()
+            .teleport(
+                a.id(),
+                Tuple::point(0.5, 0.0, 0.25),
+                Tuple::vector(0.0, -1.0, 0.0),
+            )
+            .unwrap();
+
+        assert_eq!(r.origin(), Tuple::point(10.0 + EPSILON, 0.5, 0.25));
+        assert_eq!(r.direction(), Tuple::vector(1.0, 0.0, 0.0));
+    }
+}
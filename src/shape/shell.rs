@@ -0,0 +1,251 @@
+use smallvec::smallvec;
+use uuid::Uuid;
+
+use crate::{
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
+    transformation::Transformation,
+    tuple::Tuple,
+};
+
+use super::{
+    group::WeakGroupContainer, material::Material, visibility::Visibility,
+    world_transform::WorldTransform, BoundedBox, Shape,
+};
+
+/**
+   A hollow sphere: the space between two spheres centered on the origin.
+   Rays report all four surfaces, and since every one belongs to the same
+   shape, a transparent shell's material is entered and left twice with air
+   in the cavity between.
+*/
+#[derive(Debug)]
+pub struct Shell {
+    id: Uuid,
+    outer_radius: f64,
+    inner_radius: f64,
+    transformation: Transformation,
+    material: Material,
+    visibility: Visibility,
+    parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
+}
+
+impl Shell {
+    pub fn new(outer_radius: f64, inner_radius: f64) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            outer_radius,
+            inner_radius,
+            transformation: Transformation::identity(),
+            material: Material::new(),
+            visibility: Visibility::default(),
+            parent: None,
+            world_transform: None,
+        }
+    }
+
+    pub fn outer_radius(&self) -> f64 {
+        self.outer_radius
+    }
+
+    pub fn inner_radius(&self) -> f64 {
+        self.inner_radius
+    }
+}
+
+fn intersect_sphere(ray: &Ray, radius: f64, id: Uuid, xs: &mut Intersections) {
+    let sphere_to_ray = ray.origin() - Tuple::origin();
+
+    let a = ray.direction() * ray.direction();
+    let b = (ray.direction() * sphere_to_ray) * 2.0;
+    let c = sphere_to_ray * sphere_to_ray - radius.powi(2);
+
+    let discriminant = b.powi(2) - 4.0 * a * c;
+    if discriminant >= 0.0 {
+        xs.push(Intersection::new(
+            (-b - discriminant.sqrt()) / (2.0 * a),
+            id,
+        ));
+        xs.push(Intersection::new(
+            (-b + discriminant.sqrt()) / (2.0 * a),
+            id,
+        ));
+    }
+}
+
+impl Shape for Shell {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
+        let mut xs = smallvec![];
+        intersect_sphere(ray, self.outer_radius, self.id, &mut xs);
+        if !xs.is_empty() {
+            intersect_sphere(ray, self.inner_radius, self.id, &mut xs);
+        }
+        xs
+    }
+
+    fn transformation(&self) -> Transformation {
+        self.transformation.clone()
+    }
+
+    fn set_transformation(&mut self, transformation: Transformation) {
+        self.transformation = transformation;
+        self.world_transform = None;
+    }
+
+    fn material(&self, id: Uuid) -> Option<Material> {
+        if self.id == id {
+            Some(self.material.clone())
+        } else {
+            None
+        }
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        if self.id == id {
+            Some(self.visibility)
+        } else {
+            None
+        }
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+
+    /**
+       Normals point out of the solid part, so away from the center on the
+       outer surface and towards it on the inner one.
+    */
+    fn local_normal_at(
+        &self,
+        id: Uuid,
+        point: Tuple,
+        _intersection: ShapeIntersection,
+    ) -> Option<Tuple> {
+        if id != self.id {
+            return None;
+        }
+
+        let normal = point - Tuple::origin();
+        let middle = (self.outer_radius + self.inner_radius) / 2.0;
+        Some(if normal.magnitude() < middle {
+            -normal
+        } else {
+            normal
+        })
+    }
+
+    fn parent(&self) -> Option<WeakGroupContainer> {
+        self.parent.clone()
+    }
+
+    fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
+        self.parent = Some(parent);
+    }
+
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
+    fn bounds(&self) -> BoundedBox {
+        let r = self.outer_radius;
+        BoundedBox::new(Tuple::point(-r, -r, -r), Tuple::point(r, r, r))
+    }
+
+    fn contains(&self, id: Uuid) -> bool {
+        self.id == id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        intersection::{prepcomputation::PrepComputations, IntersectionHeap},
+        shape::ShapeContainer,
+        util::eq_f64,
+    };
+
+    use super::*;
+
+    #[test]
+    fn a_ray_through_a_shell_crosses_four_surfaces() {
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let shell = Shell::new(1.0, 0.5);
+
+        let mut ts = shell
+            .local_intersect(&r)
+            .iter()
+            .map(|i| i.t())
+            .collect::<Vec<_>>();
+        ts.sort_by(f64::total_cmp);
+
+        assert_eq!(vec![4.0, 4.5, 5.5, 6.0], ts);
+    }
+
+    #[test]
+    fn a_ray_that_only_grazes_the_outside_misses_the_cavity() {
+        let r = Ray::new(Tuple::point(0.0, 0.75, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let shell = Shell::new(1.0, 0.5);
+
+        assert_eq!(2, shell.local_intersect(&r).len());
+    }
+
+    #[test]
+    fn normals_point_out_of_the_solid_part() {
+        let shell = ShapeContainer::from(Shell::new(1.0, 0.5));
+        let i = ShapeIntersection::new(0.0, shell.clone(), shell.id());
+        let normal_at = |point| {
+            shell
+                .read()
+                .unwrap()
+                .local_normal_at(shell.id(), point, i.clone())
+                .unwrap()
+        };
+
+        assert_eq!(
+            Tuple::vector(0.0, 0.0, -1.0),
+            normal_at(Tuple::point(0.0, 0.0, -1.0))
+        );
+        assert_eq!(
+            Tuple::vector(0.0, 0.0, 0.5),
+            normal_at(Tuple::point(0.0, 0.0, -0.5))
+        );
+    }
+
+    #[test]
+    fn the_cavity_of_a_glass_shell_is_air() {
+        let mut shell = Shell::new(1.0, 0.5);
+        shell.set_material(
+            Material::new()
+                .with_transparency(1.0)
+                .with_refractive_index(1.5),
+        );
+        let shell = ShapeContainer::from(shell);
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let xs = r
+            .intersections(&shell)
+            .into_iter()
+            .collect::<IntersectionHeap>();
+
+        let expected = [(1.0, 1.5), (1.5, 1.0), (1.0, 1.5), (1.5, 1.0)];
+        for (index, (n1, n2)) in expected.into_iter().enumerate() {
+            let comps = PrepComputations::new(xs[index].clone(), r, &xs);
+            assert!(eq_f64(n1, comps.n1()));
+            assert!(eq_f64(n2, comps.n2()));
+            assert_eq!(index % 2 == 1, comps.inside());
+        }
+    }
+}
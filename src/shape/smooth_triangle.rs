@@ -1,14 +1,16 @@
+use smallvec::smallvec;
 use uuid::Uuid;
 
 use crate::{
-    intersection::{ray::Ray, Intersection, ShapeIntersection},
+    color::Color,
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
     transformation::Transformation,
     tuple::Tuple,
 };
 
 use super::{
     bounded_box::BoundedBox, group::WeakGroupContainer, material::Material, triangle::Triangle,
-    Shape,
+    visibility::Visibility, world_transform::WorldTransform, Shape,
 };
 
 #[derive(Debug)]
@@ -28,6 +30,20 @@ impl SmoothTriangle {
             n3,
         }
     }
+
+    pub fn with_backface_culling(mut self, backface_culling: bool) -> Self {
+        self.triangle = self.triangle.with_backface_culling(backface_culling);
+        self
+    }
+
+    pub fn backface_culling(&self) -> bool {
+        self.triangle.backface_culling()
+    }
+
+    pub fn with_vertex_colors(mut self, c1: Color, c2: Color, c3: Color) -> Self {
+        self.triangle = self.triangle.with_vertex_colors(c1, c2, c3);
+        self
+    }
 }
 
 impl Shape for SmoothTriangle {
@@ -35,10 +51,10 @@ impl Shape for SmoothTriangle {
         self.triangle.id()
     }
 
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
         self.triangle
             .local_intersect_with_uv(ray)
-            .map(|(i, u, v)| vec![Intersection::new_with_uv(i.t(), i.object(), u, v)])
+            .map(|(i, u, v)| smallvec![Intersection::new_with_uv(i.t(), i.object(), u, v)])
             .unwrap_or_default()
     }
 
@@ -58,6 +74,22 @@ impl Shape for SmoothTriangle {
         self.triangle.set_material(material);
     }
 
+    fn set_backface_culling(&mut self, backface_culling: bool) {
+        self.triangle.set_backface_culling(backface_culling);
+    }
+
+    fn vertex_color(&self, id: Uuid, u: f64, v: f64) -> Option<Color> {
+        self.triangle.vertex_color(id, u, v)
+    }
+
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        self.triangle.visibility(id)
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.triangle.set_visibility(visibility);
+    }
+
     fn local_normal_at(
         &self,
         id: Uuid,
@@ -83,6 +115,14 @@ impl Shape for SmoothTriangle {
         self.triangle.set_parent(parent);
     }
 
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.triangle.world_transform()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.triangle.set_world_transform(world_transform);
+    }
+
     fn bounds(&self) -> BoundedBox {
         self.triangle.bounds()
     }
@@ -132,7 +172,7 @@ mod tests {
         let t: SmoothTriangle = SmoothTriangle::new(p1, p2, p3, n1, n2, n3);
 
         let r = Ray::new(Tuple::point(-0.2, 0.3, -2.0), Tuple::vector(0.0, 0.0, 1.0));
-        let xs = t.local_intersect(r);
+        let xs = t.local_intersect(&r);
         assert!(eq_f64(xs[0].u().unwrap(), 0.45));
         assert!(eq_f64(xs[0].v().unwrap(), 0.25));
     }
@@ -1,11 +1,15 @@
 use crate::{
-    intersection::{ray::Ray, Intersection, ShapeIntersection},
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
     transformation::Transformation,
     tuple::Tuple,
 };
+use smallvec::smallvec;
 use uuid::Uuid;
 
-use super::{group::WeakGroupContainer, material::Material, BoundedBox, Shape};
+use super::{
+    group::WeakGroupContainer, material::Material, visibility::Visibility,
+    world_transform::WorldTransform, BoundedBox, Shape,
+};
 
 #[derive(Debug)]
 pub struct Sphere {
@@ -13,7 +17,9 @@ pub struct Sphere {
     center: Tuple,
     transformation: Transformation,
     material: Material,
+    visibility: Visibility,
     parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
 }
 
 impl Sphere {
@@ -23,7 +29,9 @@ impl Sphere {
             center: Tuple::origin(),
             transformation: Transformation::identity(),
             material: Material::new(),
+            visibility: Visibility::default(),
             parent: None,
+            world_transform: None,
         }
     }
 
@@ -42,23 +50,40 @@ impl Shape for Sphere {
         self.id
     }
 
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
+    /**
+       The quadratic is solved in a form that avoids cancellation, so rays
+       starting far from the sphere still find it accurately. The
+       discriminant is measured from the point on the ray closest to the
+       center rather than from the ray's origin, and the smaller root comes
+       from the larger one instead of from subtracting nearly equal numbers.
+    */
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
         let sphere_to_ray = ray.origin() - self.center;
+        let direction = ray.direction();
 
-        let a = ray.direction() * ray.direction();
-        let b = (ray.direction() * sphere_to_ray) * 2.0;
+        let a = direction * direction;
+        let half_b = direction * sphere_to_ray;
         let c = sphere_to_ray * sphere_to_ray - 1.0;
 
-        let discriminant = b.powf(2.0) - 4.0 * a * c;
+        let closest = sphere_to_ray - direction * (half_b / a);
+        let discriminant = a * (1.0 - closest * closest);
 
         if discriminant < 0.0 {
-            vec![]
-        } else {
-            vec![
-                Intersection::new((-b - discriminant.sqrt()) / (2.0 * a), self.id),
-                Intersection::new((-b + discriminant.sqrt()) / (2.0 * a), self.id),
-            ]
+            return smallvec![];
         }
+
+        let q = -(half_b + half_b.signum() * discriminant.sqrt());
+        let (t1, t2) = if q == 0.0 {
+            (0.0, 0.0)
+        } else {
+            let (near, far) = (q / a, c / q);
+            (near.min(far), near.max(far))
+        };
+
+        smallvec![
+            Intersection::new(t1, self.id),
+            Intersection::new(t2, self.id),
+        ]
     }
 
     fn transformation(&self) -> Transformation {
@@ -67,6 +92,7 @@ impl Shape for Sphere {
 
     fn set_transformation(&mut self, transformation: Transformation) {
         self.transformation = transformation;
+        self.world_transform = None;
     }
 
     fn material(&self, id: Uuid) -> Option<Material> {
@@ -81,6 +107,18 @@ impl Shape for Sphere {
         self.material = material;
     }
 
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        if self.id == id {
+            Some(self.visibility)
+        } else {
+            None
+        }
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+
     fn local_normal_at(
         &self,
         id: Uuid,
@@ -99,9 +137,18 @@ impl Shape for Sphere {
     }
 
     fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
         self.parent = Some(parent);
     }
 
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
     fn bounds(&self) -> BoundedBox {
         BoundedBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0))
     }
@@ -132,11 +179,12 @@ mod tests {
         let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
         let s = Sphere::new();
 
-        let xs = s.intersects(r);
+        let xs = s.intersects(&r);
 
         assert_eq!(2, xs.len());
         assert_eq!(4.0, xs[0].t());
         assert_eq!(6.0, xs[1].t());
+        assert!(!xs.spilled());
     }
 
     #[test]
@@ -144,7 +192,7 @@ mod tests {
         let r = Ray::new(Tuple::point(0.0, 1.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
         let s = Sphere::new();
 
-        let xs = s.intersects(r);
+        let xs = s.intersects(&r);
 
         assert_eq!(2, xs.len());
         assert_eq!(5.0, xs[0].t());
@@ -156,7 +204,7 @@ mod tests {
         let r = Ray::new(Tuple::point(0.0, 2.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
         let s = Sphere::new();
 
-        let xs = s.intersects(r);
+        let xs = s.intersects(&r);
 
         assert_eq!(0, xs.len());
     }
@@ -166,7 +214,7 @@ mod tests {
         let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
         let s = Sphere::new();
 
-        let xs = s.intersects(r);
+        let xs = s.intersects(&r);
 
         assert_eq!(2, xs.len());
         assert_eq!(-1.0, xs[0].t());
@@ -178,13 +226,25 @@ mod tests {
         let r = Ray::new(Tuple::point(0.0, 0.0, 5.0), Tuple::vector(0.0, 0.0, 1.0));
         let s = Sphere::new();
 
-        let xs = s.intersects(r);
+        let xs = s.intersects(&r);
 
         assert_eq!(2, xs.len());
         assert_eq!(-6.0, xs[0].t());
         assert_eq!(-4.0, xs[1].t());
     }
 
+    #[test]
+    fn a_ray_from_far_away_hits_a_sphere_accurately() {
+        let r = Ray::new(Tuple::point(0.6, 0.0, -1e7), Tuple::vector(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+
+        let xs = s.intersects(&r);
+
+        assert_eq!(2, xs.len());
+        assert!((xs[0].t() - (1e7 - 0.8)).abs() < 1e-6);
+        assert!((xs[1].t() - (1e7 + 0.8)).abs() < 1e-6);
+    }
+
     #[test]
     fn intersecting_a_scaled_sphere_with_a_ray() {
         let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
@@ -192,7 +252,7 @@ mod tests {
 
         s.set_transformation(Transformation::identity().scale(2.0, 2.0, 2.0));
 
-        let xs = s.intersects(r);
+        let xs = s.intersects(&r);
 
         assert_eq!(2, xs.len());
         assert_eq!(3.0, xs[0].t());
@@ -206,7 +266,7 @@ mod tests {
 
         s.set_transformation(Transformation::identity().translation(5.0, 0.0, 0.0));
 
-        let xs = s.intersects(r);
+        let xs = s.intersects(&r);
 
         assert_eq!(0, xs.len());
     }
@@ -1,26 +1,35 @@
+use smallvec::smallvec;
 use uuid::Uuid;
 
 use crate::{
-    intersection::{ray::Ray, Intersection, ShapeIntersection},
+    color::Color,
+    intersection::{ray::Ray, Intersection, Intersections, ShapeIntersection},
     transformation::Transformation,
     tuple::Tuple,
     util,
 };
 
-use super::{bounded_box::BoundedBox, group::WeakGroupContainer, material::Material, Shape};
+use super::{
+    bounded_box::BoundedBox, group::WeakGroupContainer, material::Material, visibility::Visibility,
+    world_transform::WorldTransform, Shape,
+};
 
 #[derive(Debug, Clone)]
 pub struct Triangle {
     id: Uuid,
     transformation: Transformation,
     material: Material,
+    visibility: Visibility,
     parent: Option<WeakGroupContainer>,
+    world_transform: Option<WorldTransform>,
     p1: Tuple,
     p2: Tuple,
     p3: Tuple,
     e1: Tuple,
     e2: Tuple,
     normal: Tuple,
+    backface_culling: bool,
+    vertex_colors: Option<[Color; 3]>,
 }
 
 impl Triangle {
@@ -31,16 +40,52 @@ impl Triangle {
             id: Uuid::new_v4(),
             transformation: Transformation::identity(),
             material: Material::new(),
+            visibility: Visibility::default(),
             parent: None,
+            world_transform: None,
             p1,
             p2,
             p3,
             e1,
             e2,
             normal: (e2 ^ e1).normalize(),
+            backface_culling: false,
+            vertex_colors: None,
         }
     }
 
+    /**
+       Colors for `p1`, `p2` and `p3`, blended across the face in place of
+       the material's own color.
+    */
+    pub fn with_vertex_colors(mut self, c1: Color, c2: Color, c3: Color) -> Self {
+        self.vertex_colors = Some([c1, c2, c3]);
+        self
+    }
+
+    pub fn vertex_colors(&self) -> Option<[Color; 3]> {
+        self.vertex_colors
+    }
+
+    pub(crate) fn interpolate_color(&self, u: f64, v: f64) -> Option<Color> {
+        self.vertex_colors
+            .map(|[c1, c2, c3]| c1 * (1.0 - u - v) + c2 * u + c3 * v)
+    }
+
+    /**
+       Ignore rays that reach the triangle from behind, the side its normal
+       points away from. Faster for closed meshes, and needed for single
+       sided geometry.
+    */
+    pub fn with_backface_culling(mut self, backface_culling: bool) -> Self {
+        self.backface_culling = backface_culling;
+        self
+    }
+
+    pub fn backface_culling(&self) -> bool {
+        self.backface_culling
+    }
+
     #[allow(unused)]
     pub(crate) fn p1(&self) -> Tuple {
         self.p1
@@ -56,11 +101,12 @@ impl Triangle {
         self.p3
     }
 
-    pub(crate) fn local_intersect_with_uv(&self, ray: Ray) -> Option<(Intersection, f64, f64)> {
+    pub(crate) fn local_intersect_with_uv(&self, ray: &Ray) -> Option<(Intersection, f64, f64)> {
         let dir_cross_e2 = ray.direction() ^ self.e2;
         let det = self.e1 * dir_cross_e2;
 
-        if det.abs() < util::EPSILON {
+        // det has the sign of the ray direction along the normal
+        if det.abs() < util::EPSILON || (self.backface_culling && det > 0.0) {
             return None;
         }
 
@@ -92,11 +138,13 @@ impl Shape for Triangle {
         self.id
     }
 
-    fn local_intersect(&self, ray: Ray) -> Vec<Intersection> {
-        if let Some((intersection, _, _)) = self.local_intersect_with_uv(ray) {
-            vec![intersection]
-        } else {
-            vec![]
+    fn local_intersect(&self, ray: &Ray) -> Intersections {
+        match self.local_intersect_with_uv(ray) {
+            Some((i, u, v)) if self.vertex_colors.is_some() => {
+                smallvec![Intersection::new_with_uv(i.t(), i.object(), u, v)]
+            }
+            Some((i, _, _)) => smallvec![i],
+            None => smallvec![],
         }
     }
 
@@ -106,6 +154,7 @@ impl Shape for Triangle {
 
     fn set_transformation(&mut self, transformation: Transformation) {
         self.transformation = transformation;
+        self.world_transform = None;
     }
 
     fn material(&self, id: uuid::Uuid) -> Option<Material> {
@@ -120,6 +169,30 @@ impl Shape for Triangle {
         self.material = material;
     }
 
+    fn visibility(&self, id: Uuid) -> Option<Visibility> {
+        if self.id == id {
+            Some(self.visibility)
+        } else {
+            None
+        }
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.visibility = visibility;
+    }
+
+    fn set_backface_culling(&mut self, backface_culling: bool) {
+        self.backface_culling = backface_culling;
+    }
+
+    fn vertex_color(&self, id: Uuid, u: f64, v: f64) -> Option<Color> {
+        if self.id == id {
+            self.interpolate_color(u, v)
+        } else {
+            None
+        }
+    }
+
     fn local_normal_at(
         &self,
         id: uuid::Uuid,
@@ -138,9 +211,18 @@ impl Shape for Triangle {
     }
 
     fn set_parent(&mut self, parent: WeakGroupContainer) {
+        self.world_transform = None;
         self.parent = Some(parent);
     }
 
+    fn world_transform(&self) -> Option<&WorldTransform> {
+        self.world_transform.as_ref()
+    }
+
+    fn set_world_transform(&mut self, world_transform: Option<WorldTransform>) {
+        self.world_transform = world_transform;
+    }
+
     fn bounds(&self) -> BoundedBox {
         let mut bbox = BoundedBox::empty();
         bbox.add_point(self.p1);
@@ -157,7 +239,7 @@ impl Shape for Triangle {
 #[cfg(test)]
 mod tests {
 
-    use crate::shape::ShapeContainer;
+    use crate::{shape::ShapeContainer, util::eq_f64};
 
     use super::*;
 
@@ -224,7 +306,7 @@ mod tests {
     fn intersecting_a_ray_parallel_to_the_triangle() {
         let t = test_triangle();
         let r = Ray::new(Tuple::point(0.0, -1.0, -2.0), Tuple::vector(0.0, 1.0, 0.0));
-        let xs = t.local_intersect(r);
+        let xs = t.local_intersect(&r);
 
         assert!(xs.is_empty());
     }
@@ -233,7 +315,7 @@ mod tests {
     fn a_ray_misses_the_p1_p3_edge() {
         let t = test_triangle();
         let r = Ray::new(Tuple::point(1.0, 1.0, -2.0), Tuple::vector(0.0, 0.0, 1.0));
-        let xs = t.local_intersect(r);
+        let xs = t.local_intersect(&r);
         assert!(xs.is_empty());
     }
 
@@ -241,7 +323,7 @@ mod tests {
     fn a_ray_misses_the_p1_p2_edge() {
         let t = test_triangle();
         let r = Ray::new(Tuple::point(-1.0, 1.0, -2.0), Tuple::vector(0.0, 0.0, 1.0));
-        let xs = t.local_intersect(r);
+        let xs = t.local_intersect(&r);
 
         assert!(xs.is_empty());
     }
@@ -250,7 +332,7 @@ mod tests {
     fn a_ray_misses_the_p2_p3_edge() {
         let t = test_triangle();
         let r = Ray::new(Tuple::point(0.0, -1.0, -2.0), Tuple::vector(0.0, 0.0, 1.0));
-        let xs = t.local_intersect(r);
+        let xs = t.local_intersect(&r);
 
         assert!(xs.is_empty());
     }
@@ -259,9 +341,47 @@ mod tests {
     fn a_ray_strikes_the_triangle() {
         let t = test_triangle();
         let r = Ray::new(Tuple::point(0.0, 0.5, -2.0), Tuple::vector(0.0, 0.0, 1.0));
-        let xs = t.local_intersect(r);
+        let xs = t.local_intersect(&r);
 
         assert_eq!(xs.len(), 1);
         assert_eq!(xs[0].t(), 2.0);
     }
+
+    #[test]
+    fn a_culled_triangle_ignores_rays_from_behind() {
+        let t = test_triangle().with_backface_culling(true);
+        let front = Ray::new(Tuple::point(0.0, 0.5, -2.0), Tuple::vector(0.0, 0.0, 1.0));
+        let back = Ray::new(Tuple::point(0.0, 0.5, 2.0), Tuple::vector(0.0, 0.0, -1.0));
+
+        assert_eq!(1, t.local_intersect(&front).len());
+        assert!(t.local_intersect(&back).is_empty());
+        assert_eq!(1, test_triangle().local_intersect(&back).len());
+    }
+
+    #[test]
+    fn vertex_colors_are_blended_with_barycentric_coordinates() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let green = Color::new(0.0, 1.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        let t = test_triangle().with_vertex_colors(red, green, blue);
+
+        assert_eq!(Some(red), t.vertex_color(t.id(), 0.0, 0.0));
+        assert_eq!(Some(blue), t.vertex_color(t.id(), 0.0, 1.0));
+        assert_eq!(
+            Some(Color::new(0.5, 0.25, 0.25)),
+            t.vertex_color(t.id(), 0.25, 0.25)
+        );
+        assert_eq!(None, test_triangle().vertex_color(t.id(), 0.25, 0.25));
+    }
+
+    #[test]
+    fn intersecting_a_colored_triangle_keeps_the_barycentric_coordinates() {
+        let c = Color::new(1.0, 1.0, 1.0);
+        let t = test_triangle().with_vertex_colors(c, c, c);
+        let r = Ray::new(Tuple::point(-0.2, 0.3, -2.0), Tuple::vector(0.0, 0.0, 1.0));
+        let xs = t.local_intersect(&r);
+
+        assert!(eq_f64(0.45, xs[0].u().unwrap()));
+        assert!(eq_f64(0.25, xs[0].v().unwrap()));
+    }
 }
@@ -0,0 +1,82 @@
+use crate::intersection::ray::RayKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Visibility {
+    camera: bool,
+    shadow: bool,
+    secondary: bool,
+}
+
+impl Visibility {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn camera(&self) -> bool {
+        self.camera
+    }
+
+    pub fn shadow(&self) -> bool {
+        self.shadow
+    }
+
+    pub fn secondary(&self) -> bool {
+        self.secondary
+    }
+
+    pub fn with_camera(mut self, camera: bool) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    pub fn with_shadow(mut self, shadow: bool) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    pub fn with_secondary(mut self, secondary: bool) -> Self {
+        self.secondary = secondary;
+        self
+    }
+
+    pub fn is_visible_to(&self, kind: RayKind) -> bool {
+        match kind {
+            RayKind::Camera => self.camera,
+            RayKind::Shadow => self.shadow,
+            RayKind::Secondary => self.secondary,
+        }
+    }
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Self {
+            camera: true,
+            shadow: true,
+            secondary: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_are_visible_to_every_ray_by_default() {
+        let v = Visibility::default();
+
+        assert!(v.is_visible_to(RayKind::Camera));
+        assert!(v.is_visible_to(RayKind::Shadow));
+        assert!(v.is_visible_to(RayKind::Secondary));
+    }
+
+    #[test]
+    fn hiding_a_shape_from_the_camera_keeps_it_in_shadows_and_reflections() {
+        let v = Visibility::new().with_camera(false);
+
+        assert!(!v.is_visible_to(RayKind::Camera));
+        assert!(v.is_visible_to(RayKind::Shadow));
+        assert!(v.is_visible_to(RayKind::Secondary));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{transformation::Transformation, util::PrecisionPolicy};
+
+/**
+   A shape's transformation composed with all of its parents', along with
+   the inverse and inverse transpose, computed once by `World::prepare`.
+   The inverse of the shape's own transformation is kept too, for taking
+   rays from its parent's space into its own.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldTransform {
+    transformation: Transformation,
+    inverse: Transformation,
+    inverse_transpose: Transformation,
+    local_inverse: Transformation,
+}
+
+impl WorldTransform {
+    /**
+       The world transform of a shape transformed by `local` inside a parent
+       transformed by `parent`, inverted under `precision`.
+    */
+    pub fn new(
+        parent: &Transformation,
+        local: &Transformation,
+        precision: PrecisionPolicy,
+    ) -> Self {
+        let transformation = parent * local;
+        let inverse = transformation
+            .inverse_with(precision)
+            .expect("Could not get inverse");
+        let inverse_transpose = inverse.transpose();
+        let local_inverse = local
+            .inverse_with(precision)
+            .expect("Could not get inverse");
+
+        Self {
+            transformation,
+            inverse,
+            inverse_transpose,
+            local_inverse,
+        }
+    }
+
+    pub fn transformation(&self) -> &Transformation {
+        &self.transformation
+    }
+
+    pub fn inverse(&self) -> &Transformation {
+        &self.inverse
+    }
+
+    pub fn inverse_transpose(&self) -> &Transformation {
+        &self.inverse_transpose
+    }
+
+    pub fn local_inverse(&self) -> &Transformation {
+        &self.local_inverse
+    }
+}
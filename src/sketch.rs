@@ -0,0 +1,145 @@
+use std::f64::consts::PI;
+
+use crate::{canvas::Canvas, color::Color, transformation::Transformation, tuple::Tuple};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Projectile {
+    position: Tuple,
+    velocity: Tuple,
+}
+
+impl Projectile {
+    pub fn new(position: Tuple, velocity: Tuple) -> Self {
+        Self { position, velocity }
+    }
+
+    pub fn position(&self) -> Tuple {
+        self.position
+    }
+
+    pub fn velocity(&self) -> Tuple {
+        self.velocity
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Environment {
+    gravity: Tuple,
+    wind: Tuple,
+}
+
+impl Environment {
+    pub fn new(gravity: Tuple, wind: Tuple) -> Self {
+        Self { gravity, wind }
+    }
+
+    pub fn gravity(&self) -> Tuple {
+        self.gravity
+    }
+
+    pub fn wind(&self) -> Tuple {
+        self.wind
+    }
+
+    pub fn tick(&self, projectile: Projectile) -> Projectile {
+        Projectile {
+            position: projectile.position + projectile.velocity,
+            velocity: projectile.velocity + self.gravity + self.wind,
+        }
+    }
+}
+
+/**
+   Plot a point on the canvas with y pointing up, so the origin is the
+   bottom left pixel. Returns false when the point falls outside the canvas.
+*/
+pub fn plot(canvas: &mut Canvas, point: Tuple, color: Color) -> bool {
+    let x = point.x().round();
+    let y = canvas.height() as f64 - 1.0 - point.y().round();
+
+    if x < 0.0 || y < 0.0 || x >= canvas.width() as f64 || y >= canvas.height() as f64 {
+        return false;
+    }
+
+    canvas[(x as usize, y as usize)] = color;
+    true
+}
+
+/**
+   The twelve hour marks of a clock face centered on the canvas.
+*/
+pub fn clock_hours(canvas: &Canvas, radius: f64) -> Vec<Tuple> {
+    let center_x = (canvas.width() as f64 - 1.0) / 2.0;
+    let center_y = (canvas.height() as f64 - 1.0) / 2.0;
+
+    (0..12)
+        .map(|hour| {
+            Transformation::identity()
+                .rotate_z(-(hour as f64) * PI / 6.0)
+                .translation(center_x, center_y, 0.0)
+                * Tuple::point(0.0, radius, 0.0)
+        })
+        .collect()
+}
+
+pub fn draw_clock_face(canvas: &mut Canvas, radius: f64, color: Color) {
+    for hour in clock_hours(canvas, radius) {
+        plot(canvas, hour, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Colors;
+
+    use super::*;
+
+    #[test]
+    fn ticking_a_projectile_applies_gravity_and_wind() {
+        let env = Environment::new(
+            Tuple::vector(0.0, -0.1, 0.0),
+            Tuple::vector(-0.01, 0.0, 0.0),
+        );
+        let p = Projectile::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(1.0, 1.0, 0.0));
+
+        let p = env.tick(p);
+
+        assert_eq!(Tuple::point(1.0, 2.0, 0.0), p.position());
+        assert_eq!(Tuple::vector(0.99, 0.9, 0.0), p.velocity());
+    }
+
+    #[test]
+    fn plotting_flips_the_y_axis() {
+        let mut c = Canvas::new(10, 20);
+        let red: Color = Colors::Red.into();
+
+        assert!(plot(&mut c, Tuple::point(2.0, 0.0, 0.0), red));
+        assert!(plot(&mut c, Tuple::point(3.2, 18.8, 0.0), red));
+
+        assert_eq!(red, c[(2, 19)]);
+        assert_eq!(red, c[(3, 0)]);
+    }
+
+    #[test]
+    fn plotting_outside_the_canvas_is_ignored() {
+        let mut c = Canvas::new(10, 20);
+        let red: Color = Colors::Red.into();
+
+        assert!(!plot(&mut c, Tuple::point(-1.0, 0.0, 0.0), red));
+        assert!(!plot(&mut c, Tuple::point(10.0, 0.0, 0.0), red));
+        assert!(!plot(&mut c, Tuple::point(0.0, 20.0, 0.0), red));
+        assert!(!plot(&mut c, Tuple::point(0.0, -0.6, 0.0), red));
+    }
+
+    #[test]
+    fn the_clock_hours_run_clockwise_from_twelve() {
+        let c = Canvas::new(101, 101);
+        let hours = clock_hours(&c, 40.0);
+
+        assert_eq!(12, hours.len());
+        assert_eq!(Tuple::point(50.0, 90.0, 0.0), hours[0]);
+        assert_eq!(Tuple::point(90.0, 50.0, 0.0), hours[3]);
+        assert_eq!(Tuple::point(50.0, 10.0, 0.0), hours[6]);
+        assert_eq!(Tuple::point(10.0, 50.0, 0.0), hours[9]);
+    }
+}
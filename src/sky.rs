@@ -0,0 +1,420 @@
+use std::{f64::consts::PI, fmt::Debug};
+
+use crate::{
+    color::Color, point_light::PointLight, shape::bounded_box::BoundedBox, tuple::Tuple,
+    util::EPSILON,
+};
+
+/**
+   What a ray sees when it leaves the scene without hitting anything, looked
+   up by the ray's direction.
+*/
+pub trait Sky: Debug + Send + Sync {
+    fn color_at(&self, direction: Tuple) -> Color;
+
+    /**
+       The sun drawn in the sky, if it has one, for lighting the scene from
+       the same direction.
+    */
+    fn sun(&self) -> Option<Sun> {
+        None
+    }
+}
+
+/**
+   How many times the radius of the scene the sun's light is placed away
+   from its center, far enough that its rays are all but parallel.
+*/
+const SUN_DISTANCE: f64 = 1000.0;
+
+/**
+   A sun infinitely far away, drawn as a disk `angular_radius` radians wide
+   around `direction`.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sun {
+    direction: Tuple,
+    color: Color,
+    angular_radius: f64,
+}
+
+impl Sun {
+    pub fn new(direction: Tuple, color: Color) -> Self {
+        Self {
+            direction: direction.normalize(),
+            color,
+            angular_radius: 0.02,
+        }
+    }
+
+    pub fn with_angular_radius(mut self, angular_radius: f64) -> Self {
+        self.angular_radius = angular_radius;
+        self
+    }
+
+    pub fn direction(&self) -> Tuple {
+        self.direction
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn angular_radius(&self) -> f64 {
+        self.angular_radius
+    }
+
+    pub fn covers(&self, direction: Tuple) -> bool {
+        direction.normalize() * self.direction >= self.angular_radius.cos()
+    }
+
+    /**
+       A point light far beyond `bounds` towards the sun, so the shapes
+       inside them are lit and shadowed from the direction the disk is drawn
+       in. Empty or unbounded boxes are taken as a unit sphere around the
+       origin.
+    */
+    pub fn light(&self, bounds: &BoundedBox) -> PointLight {
+        let (center, radius) = if bounds.is_finite() {
+            let radius = (bounds.max() - bounds.min()).magnitude() / 2.0;
+            (bounds.center(), radius.max(1.0))
+        } else {
+            (Tuple::origin(), 1.0)
+        };
+
+        PointLight::new(
+            center + self.direction * (radius * SUN_DISTANCE),
+            self.color,
+        )
+    }
+}
+
+/**
+   Blends from `horizon` straight out to `zenith` straight up, with a flat
+   `ground` color below the horizon and an optional sun disk.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientSky {
+    zenith: Color,
+    horizon: Color,
+    ground: Color,
+    sun: Option<Sun>,
+}
+
+impl GradientSky {
+    pub fn new(zenith: Color, horizon: Color) -> Self {
+        Self {
+            zenith,
+            horizon,
+            ground: horizon,
+            sun: None,
+        }
+    }
+
+    pub fn with_ground(mut self, ground: Color) -> Self {
+        self.ground = ground;
+        self
+    }
+
+    pub fn with_sun(mut self, sun: Sun) -> Self {
+        self.sun = Some(sun);
+        self
+    }
+}
+
+impl Sky for GradientSky {
+    fn sun(&self) -> Option<Sun> {
+        self.sun
+    }
+
+    fn color_at(&self, direction: Tuple) -> Color {
+        if let Some(sun) = self.sun.filter(|sun| sun.covers(direction)) {
+            return sun.color();
+        }
+
+        let height = direction.normalize().y();
+        if height < 0.0 {
+            self.ground
+        } else {
+            self.horizon + (self.zenith - self.horizon) * height
+        }
+    }
+}
+
+/**
+   The Preetham, Shirley and Smits (1999) daylight model: a clear sky whose
+   brightness and color follow the sun's elevation (in radians above the
+   horizon) and the haziness of the air, given as turbidity from 2 (very
+   clear) to about 10 (hazy). Luminance is scaled by `exposure` to suit the
+   renderer's 0 to 1 color range.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreethamSky {
+    sun_direction: Tuple,
+    turbidity: f64,
+    exposure: f64,
+    zenith: (f64, f64, f64),
+    coefficients: [[f64; 5]; 3],
+}
+
+impl PreethamSky {
+    pub fn new(sun_elevation: f64, turbidity: f64) -> Self {
+        Self::with_sun(sun_elevation, 0.0, turbidity)
+    }
+
+    /**
+       Like `new`, with the sun turned `sun_azimuth` radians from +z towards
+       +x.
+    */
+    pub fn with_sun(sun_elevation: f64, sun_azimuth: f64, turbidity: f64) -> Self {
+        let sun_elevation = sun_elevation.clamp(0.0, PI / 2.0);
+        let sun_direction = Tuple::vector(
+            sun_azimuth.sin() * sun_elevation.cos(),
+            sun_elevation.sin(),
+            sun_azimuth.cos() * sun_elevation.cos(),
+        );
+        let t = turbidity;
+        let theta = PI / 2.0 - sun_elevation;
+        let (theta2, theta3) = (theta * theta, theta * theta * theta);
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta);
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let x = t * t * (0.00166 * theta3 - 0.00375 * theta2 + 0.00209 * theta)
+            + t * (-0.02903 * theta3 + 0.06377 * theta2 - 0.03202 * theta + 0.00394)
+            + (0.11693 * theta3 - 0.21196 * theta2 + 0.06052 * theta + 0.25886);
+        let y = t * t * (0.00275 * theta3 - 0.00610 * theta2 + 0.00317 * theta)
+            + t * (-0.04214 * theta3 + 0.08970 * theta2 - 0.04153 * theta + 0.00516)
+            + (0.15346 * theta3 - 0.26756 * theta2 + 0.06670 * theta + 0.26688);
+
+        Self {
+            sun_direction,
+            turbidity,
+            exposure: 0.05,
+            zenith: (luminance, x, y),
+            coefficients: [
+                [
+                    0.1787 * t - 1.4630,
+                    -0.3554 * t + 0.4275,
+                    -0.0227 * t + 5.3251,
+                    0.1206 * t - 2.5771,
+                    -0.0670 * t + 0.3703,
+                ],
+                [
+                    -0.0193 * t - 0.2592,
+                    -0.0665 * t + 0.0008,
+                    -0.0004 * t + 0.2125,
+                    -0.0641 * t - 0.8989,
+                    -0.0033 * t + 0.0452,
+                ],
+                [
+                    -0.0167 * t - 0.2608,
+                    -0.0950 * t + 0.0092,
+                    -0.0079 * t + 0.2102,
+                    -0.0441 * t - 1.6537,
+                    -0.0109 * t + 0.0529,
+                ],
+            ],
+        }
+    }
+
+    pub fn with_exposure(mut self, exposure: f64) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    pub fn sun_direction(&self) -> Tuple {
+        self.sun_direction
+    }
+
+    pub fn turbidity(&self) -> f64 {
+        self.turbidity
+    }
+
+    pub fn exposure(&self) -> f64 {
+        self.exposure
+    }
+
+    /**
+       The Perez distribution for one of the model's channels, at a view
+       angle `theta` from the zenith and `gamma` from the sun.
+    */
+    fn perez(&self, channel: usize, theta: f64, gamma: f64) -> f64 {
+        let [a, b, c, d, e] = self.coefficients[channel];
+
+        (1.0 + a * (b / theta.cos()).exp())
+            * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+    }
+}
+
+/**
+   How far above the horizon the Perez distribution is evaluated at lowest,
+   as it grows without bound towards the horizon itself.
+*/
+const HORIZON: f64 = 0.01;
+
+impl Sky for PreethamSky {
+    /**
+       Directions below the horizon see the sky just above it, straight down
+       seeing the horizon below the sun.
+    */
+    fn color_at(&self, direction: Tuple) -> Color {
+        let mut direction = direction.normalize();
+        if direction.y() < HORIZON {
+            let (x, z) = match (direction.x(), direction.z()) {
+                (x, z) if x.abs() < EPSILON && z.abs() < EPSILON => {
+                    (self.sun_direction.x(), self.sun_direction.z() + EPSILON)
+                }
+                xz => xz,
+            };
+            direction = Tuple::vector(x, 0.0, z).normalize();
+            direction = Tuple::vector(direction.x(), HORIZON, direction.z()).normalize();
+        }
+        let theta = direction.y().acos();
+        let gamma = (direction * self.sun_direction).clamp(-1.0, 1.0).acos();
+        let sun_theta = self.sun_direction.y().clamp(-1.0, 1.0).acos();
+
+        let (zenith_luminance, zenith_x, zenith_y) = self.zenith;
+        let channel = |channel: usize, zenith: f64| {
+            zenith * self.perez(channel, theta, gamma) / self.perez(channel, 0.0, sun_theta)
+        };
+        let luminance = channel(0, zenith_luminance);
+        let x = channel(1, zenith_x);
+        let y = channel(2, zenith_y);
+
+        let luminance = luminance * self.exposure;
+        let big_x = x / y * luminance;
+        let big_z = (1.0 - x - y) / y * luminance;
+
+        Color::new(
+            (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+            (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+            (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+        )
+    }
+
+    /**
+       The sun as a light, tinted by the sky beside it so a low sun lights
+       the scene orange. Its brightest channel is 1, unless the sky beside
+       it is black, when the sun is too.
+    */
+    fn sun(&self) -> Option<Sun> {
+        let color = self.color_at(self.sun_direction);
+        let brightest = color.red().max(color.green()).max(color.blue());
+        if brightest < EPSILON {
+            return Some(Sun::new(self.sun_direction, Color::new(0.0, 0.0, 0.0)));
+        }
+
+        Some(Sun::new(self.sun_direction, color * (1.0 / brightest)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::eq_f64;
+
+    use super::*;
+
+    fn sky() -> GradientSky {
+        GradientSky::new(Color::new(0.2, 0.4, 1.0), Color::new(1.0, 1.0, 1.0))
+            .with_ground(Color::new(0.3, 0.2, 0.1))
+    }
+
+    #[test]
+    fn a_gradient_sky_blends_from_horizon_to_zenith() {
+        let sky = sky();
+
+        assert_eq!(
+            Color::new(0.2, 0.4, 1.0),
+            sky.color_at(Tuple::vector(0.0, 1.0, 0.0))
+        );
+        assert_eq!(
+            Color::new(1.0, 1.0, 1.0),
+            sky.color_at(Tuple::vector(0.0, 0.0, 1.0))
+        );
+        assert_eq!(
+            Color::new(0.6, 0.7, 1.0),
+            sky.color_at(Tuple::vector(0.0, 1.0, 3f64.sqrt()))
+        );
+        assert_eq!(
+            Color::new(0.3, 0.2, 0.1),
+            sky.color_at(Tuple::vector(1.0, -1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn the_sun_disk_covers_the_sky_around_its_direction() {
+        let sun = Sun::new(Tuple::vector(0.0, 1.0, 1.0), Color::new(5.0, 5.0, 4.0))
+            .with_angular_radius(0.1);
+        let sky = sky().with_sun(sun);
+
+        assert_eq!(
+            Color::new(5.0, 5.0, 4.0),
+            sky.color_at(Tuple::vector(0.0, 1.1, 1.0))
+        );
+        assert_ne!(
+            Color::new(5.0, 5.0, 4.0),
+            sky.color_at(Tuple::vector(0.0, 1.5, 1.0))
+        );
+    }
+
+    #[test]
+    fn the_sun_light_sits_far_beyond_the_scene_towards_the_sun() {
+        let sun = Sun::new(Tuple::vector(0.0, 1.0, 1.0), Color::new(5.0, 5.0, 4.0));
+        let bounds = BoundedBox::new(Tuple::point(9.0, 4.0, -1.0), Tuple::point(11.0, 6.0, 1.0));
+        let distance = 3f64.sqrt() * SUN_DISTANCE;
+
+        let light = sun.light(&bounds);
+
+        assert_eq!(
+            Tuple::point(10.0, 5.0, 0.0) + Tuple::vector(0.0, 1.0, 1.0).normalize() * distance,
+            light.position()
+        );
+        assert_eq!(Color::new(5.0, 5.0, 4.0), light.intensity());
+        assert_eq!(
+            Tuple::vector(0.0, 1.0, 1.0).normalize() * SUN_DISTANCE,
+            sun.light(&BoundedBox::empty()).position() - Tuple::origin()
+        );
+    }
+
+    #[test]
+    fn a_high_clear_sun_gives_a_blue_sky() {
+        let sky = PreethamSky::new(1.2, 2.5);
+        let zenith = sky.color_at(Tuple::vector(0.0, 1.0, 0.0));
+
+        assert!(zenith.blue() > zenith.green() && zenith.green() > zenith.red());
+        assert!(zenith.blue() < 1.0);
+    }
+
+    #[test]
+    fn a_low_sun_turns_the_sky_and_its_light_orange() {
+        let sky = PreethamSky::with_sun(0.1, PI / 2.0, 2.5);
+        let near_sun = sky.color_at(Tuple::vector(1.0, 0.1, 0.0));
+        let sun = sky.sun().unwrap();
+
+        assert!(near_sun.red() > near_sun.blue());
+        assert_eq!(sky.sun_direction(), sun.direction());
+        assert!(eq_f64(1.0, sun.color().red()));
+        assert!(sun.color().blue() < 0.5);
+    }
+
+    #[test]
+    fn a_sun_beside_a_black_sky_is_black() {
+        let sun = PreethamSky::new(0.0, 4.0).with_exposure(0.0).sun().unwrap();
+
+        assert_eq!(Color::new(0.0, 0.0, 0.0), sun.color());
+    }
+
+    #[test]
+    fn below_the_horizon_the_preetham_sky_repeats_the_horizon() {
+        let sky = PreethamSky::new(0.8, 4.0);
+
+        assert_eq!(
+            sky.color_at(Tuple::vector(1.0, 0.0, 0.0)),
+            sky.color_at(Tuple::vector(1.0, -0.5, 0.0))
+        );
+        assert_eq!(
+            sky.color_at(Tuple::vector(0.0, 0.0, 1.0)),
+            sky.color_at(Tuple::vector(0.0, -1.0, 0.0))
+        );
+    }
+}
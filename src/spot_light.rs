@@ -0,0 +1,168 @@
+use crate::{color::Color, point_light::Falloff, tuple::Tuple, world::World};
+
+/**
+   A point light that only shines within a cone around `direction`. Points
+   within `inner_angle` of the direction get the full light, which fades
+   away smoothly towards `outer_angle`, past which there is none. Both
+   angles are measured from the direction to the edge of the cone.
+*/
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SpotLight {
+    position: Tuple,
+    direction: Tuple,
+    intensity: Color,
+    inner_angle: f64,
+    outer_angle: f64,
+    falloff: Falloff,
+}
+
+impl SpotLight {
+    /**
+       A spot light with a cone of 30 degrees, fading out over its last five.
+    */
+    pub fn new(position: Tuple, direction: Tuple, intensity: Color) -> Self {
+        Self {
+            position,
+            direction: direction.normalize(),
+            intensity,
+            inner_angle: 25f64.to_radians(),
+            outer_angle: 30f64.to_radians(),
+            falloff: Falloff::None,
+        }
+    }
+
+    /**
+       A spot light at `position` pointing at `target`.
+    */
+    pub fn aimed_at(position: Tuple, target: Tuple, intensity: Color) -> Self {
+        Self::new(position, target - position, intensity)
+    }
+
+    pub fn with_cone(mut self, inner_angle: f64, outer_angle: f64) -> Self {
+        self.inner_angle = inner_angle.min(outer_angle);
+        self.outer_angle = outer_angle;
+        self
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    pub fn position(&self) -> Tuple {
+        self.position
+    }
+
+    pub fn direction(&self) -> Tuple {
+        self.direction
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    pub fn inner_angle(&self) -> f64 {
+        self.inner_angle
+    }
+
+    pub fn outer_angle(&self) -> f64 {
+        self.outer_angle
+    }
+
+    pub fn falloff(&self) -> Falloff {
+        self.falloff
+    }
+
+    /**
+       How much of the light the cone lets through towards `point`, easing
+       from all of it at the inner angle to none at the outer angle.
+    */
+    pub fn cone_at(&self, point: Tuple) -> f64 {
+        let cos_angle = (point - self.position).normalize() * self.direction;
+        let (inner, outer) = (self.inner_angle.cos(), self.outer_angle.cos());
+        if cos_angle >= inner {
+            return 1.0;
+        }
+        if cos_angle <= outer {
+            return 0.0;
+        }
+        let t = (cos_angle - outer) / (inner - outer);
+
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    /**
+       The fraction of the light's intensity that reaches `point`, dimmed
+       by both the cone and the falloff.
+    */
+    pub fn attenuation_at(&self, point: Tuple) -> f64 {
+        self.cone_at(point)
+            * self
+                .falloff
+                .attenuation((self.position - point).magnitude())
+    }
+
+    /**
+       The fraction of the light's intensity that reaches `point` in
+       `world`: nothing if it is in shadow, otherwise its attenuation.
+    */
+    pub fn intensity_at(&self, point: Tuple, world: &World) -> f64 {
+        if world.is_shadowed_from(self, point) {
+            0.0
+        } else {
+            self.attenuation_at(point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{color::Colors, util::eq_f64};
+
+    use super::*;
+
+    #[test]
+    fn a_spot_light_lights_only_inside_its_cone() {
+        let light = SpotLight::new(
+            Tuple::point(0.0, 10.0, 0.0),
+            Tuple::vector(0.0, -2.0, 0.0),
+            Colors::White.into(),
+        )
+        .with_cone(PI / 8.0, PI / 4.0);
+        let at_angle = |angle: f64| Tuple::point(10.0 * angle.tan(), 0.0, 0.0);
+
+        assert_eq!(Tuple::vector(0.0, -1.0, 0.0), light.direction());
+        assert!(eq_f64(1.0, light.attenuation_at(Tuple::origin())));
+        assert!(eq_f64(1.0, light.attenuation_at(at_angle(PI / 9.0))));
+        assert!(eq_f64(0.0, light.attenuation_at(at_angle(PI / 3.0))));
+        assert!(eq_f64(
+            0.0,
+            light.attenuation_at(Tuple::point(0.0, 20.0, 0.0))
+        ));
+
+        let edge = light.attenuation_at(at_angle(3.0 * PI / 16.0));
+        assert!(edge > 0.0 && edge < 1.0);
+        assert!(light.attenuation_at(at_angle(PI / 5.0)) < edge);
+    }
+
+    #[test]
+    fn the_cone_and_falloff_both_dim_a_spot_light() {
+        let light = SpotLight::aimed_at(
+            Tuple::origin(),
+            Tuple::point(0.0, 0.0, 5.0),
+            Colors::White.into(),
+        )
+        .with_falloff(Falloff::InverseSquare);
+
+        assert!(eq_f64(
+            0.25,
+            light.attenuation_at(Tuple::point(0.0, 0.0, 2.0))
+        ));
+        assert!(eq_f64(
+            0.0,
+            light.attenuation_at(Tuple::point(0.0, 2.0, 0.0))
+        ));
+    }
+}
@@ -0,0 +1,163 @@
+use std::{collections::BTreeMap, mem};
+
+use crate::{
+    light::Light,
+    shape::{group::Descendants, Shape, ShapeContainer},
+};
+
+/**
+   A summary of what a world is made of, for tracking down scenes that take
+   far longer to render than expected.
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SceneStats {
+    shapes_by_type: BTreeMap<&'static str, usize>,
+    max_group_depth: usize,
+    lights: usize,
+    approximate_bytes: usize,
+}
+
+impl SceneStats {
+    pub fn new(shapes: &[ShapeContainer], lights: &[Light]) -> Self {
+        let mut stats = Self {
+            lights: lights.len(),
+            approximate_bytes: mem::size_of_val(lights),
+            ..Self::default()
+        };
+
+        for (shape, depth) in shapes.iter().cloned().flat_map(Descendants::new) {
+            stats.add_shape(&*shape.read().unwrap(), depth);
+        }
+
+        stats
+    }
+
+    fn add_shape(&mut self, shape: &dyn Shape, depth: usize) {
+        *self.shapes_by_type.entry(shape.type_name()).or_default() += 1;
+        self.approximate_bytes += mem::size_of_val(shape) + mem::size_of::<ShapeContainer>();
+
+        if shape.type_name() == "Group" {
+            self.max_group_depth = self.max_group_depth.max(depth + 1);
+        }
+    }
+
+    pub fn shapes_by_type(&self) -> &BTreeMap<&'static str, usize> {
+        &self.shapes_by_type
+    }
+
+    pub fn count(&self, type_name: &str) -> usize {
+        self.shapes_by_type.get(type_name).copied().unwrap_or(0)
+    }
+
+    /**
+       Every shape including groups and the shapes nested inside them.
+    */
+    pub fn shapes(&self) -> usize {
+        self.shapes_by_type.values().sum()
+    }
+
+    /**
+       Flat and smooth triangles, including the faces of meshes, which become
+       smooth triangles when they are turned into groups.
+    */
+    pub fn triangles(&self) -> usize {
+        self.count("Triangle") + self.count("SmoothTriangle")
+    }
+
+    /**
+       Groups are the nodes of the bounding volume hierarchy. CSG shapes
+       are counted as `Csg` instead.
+    */
+    pub fn bvh_nodes(&self) -> usize {
+        self.count("Group")
+    }
+
+    /**
+       How deeply groups are nested; zero when the world has no groups.
+    */
+    pub fn max_group_depth(&self) -> usize {
+        self.max_group_depth
+    }
+
+    pub fn lights(&self) -> usize {
+        self.lights
+    }
+
+    /**
+       The size of the shapes and lights themselves. Heap allocations owned
+       by shapes, such as a group's child list, are not included.
+    */
+    pub fn approximate_bytes(&self) -> usize {
+        self.approximate_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::Colors,
+        mesh::Mesh,
+        point_light::PointLight,
+        shape::{
+            cube::Cube,
+            group::{Group, GroupContainer, Operation},
+            sphere::Sphere,
+            triangle::Triangle,
+        },
+        tuple::Tuple,
+    };
+
+    use super::*;
+
+    fn triangle() -> ShapeContainer {
+        Triangle::new(
+            Tuple::point(0.0, 1.0, 0.0),
+            Tuple::point(-1.0, 0.0, 0.0),
+            Tuple::point(1.0, 0.0, 0.0),
+        )
+        .into()
+    }
+
+    #[test]
+    fn stats_for_an_empty_scene() {
+        let stats = SceneStats::new(&[], &[]);
+
+        assert_eq!(stats.shapes(), 0);
+        assert_eq!(stats.max_group_depth(), 0);
+        assert_eq!(stats.approximate_bytes(), 0);
+    }
+
+    #[test]
+    fn stats_count_nested_shapes_by_type() {
+        let inner = GroupContainer::default();
+        inner.add_child(triangle());
+        inner.add_child(triangle());
+        let outer = GroupContainer::default();
+        outer.add_child(inner.into());
+        outer.add_child(Sphere::new().into());
+        let light = PointLight::new(Tuple::origin(), Colors::White.into());
+
+        let stats = SceneStats::new(&[outer.into(), Sphere::new().into()], &[light.into()]);
+
+        assert_eq!(stats.shapes(), 6);
+        assert_eq!(stats.count("Sphere"), 2);
+        assert_eq!(stats.triangles(), 2);
+        assert_eq!(stats.bvh_nodes(), 2);
+        assert_eq!(stats.max_group_depth(), 2);
+        assert_eq!(stats.lights(), 1);
+        assert!(stats.approximate_bytes() > 6 * mem::size_of::<ShapeContainer>());
+    }
+
+    #[test]
+    fn csg_shapes_are_not_bvh_nodes_and_mesh_faces_are_triangles() {
+        let csg = Group::csg(Operation::Union, Sphere::new().into(), Cube::new().into());
+        let mesh = Mesh::grid(2).as_group(2);
+
+        let stats = SceneStats::new(&[csg.into(), mesh.into()], &[]);
+
+        assert_eq!(stats.count("Csg"), 1);
+        assert_eq!(stats.triangles(), 8);
+        assert_eq!(stats.bvh_nodes(), stats.count("Group"));
+        assert!(stats.bvh_nodes() > 1);
+    }
+}
@@ -0,0 +1,819 @@
+use std::f64::consts::PI;
+
+use crate::{
+    camera::Camera,
+    color::{Color, Colors},
+    error::RayTraceResult,
+    obj::OBJParser,
+    point_light::PointLight,
+    shape::{
+        cube::Cube,
+        cylinder::Cylinder,
+        group::GroupContainer,
+        material::{
+            pattern::{
+                checker::CheckerPattern, ring::RingPattern, stripes::StripePattern, Pattern,
+            },
+            Material,
+        },
+        plane::Plane,
+        sphere::Sphere,
+        Shape, ShapeContainer,
+    },
+    transformation::Transformation,
+    tuple::Tuple,
+    world::World,
+};
+
+/**
+   The book prints its expected values to five decimal places, so results
+   are compared to them with a little more slack than `EPSILON`.
+*/
+pub const BOOK_TOLERANCE: f64 = 0.0001;
+
+pub fn colors_match(expected: Color, actual: Color) -> bool {
+    expected.approx_eq(&actual, BOOK_TOLERANCE)
+}
+
+pub fn tuples_match(expected: Tuple, actual: Tuple) -> bool {
+    expected.approx_eq(&actual, BOOK_TOLERANCE)
+}
+
+/**
+   The two concentric spheres and single light used throughout the book's
+   world, shading and shadow tests.
+*/
+pub fn default_world() -> World {
+    World::default()
+}
+
+/**
+   The default world with a plane at y = -1, as used by the book's
+   reflection and refraction tests. The floor is returned so intersections
+   with it can be built by hand.
+*/
+pub fn default_world_with_floor(material: Material) -> (World, ShapeContainer) {
+    let mut world = default_world();
+    let mut floor = Plane::new();
+    floor.set_transformation(Transformation::identity().translation(0.0, -1.0, 0.0));
+    floor.set_material(material);
+    let floor = ShapeContainer::from(floor);
+    world.add_shape(floor.clone());
+
+    (world, floor)
+}
+
+/**
+   The camera both chapter scenes are viewed from.
+*/
+pub fn book_camera(h_size: usize, v_size: usize) -> Camera {
+    let mut camera = Camera::new(h_size, v_size, PI / 3.0);
+    camera.set_transformation(Transformation::view(
+        Tuple::point(0.0, 1.5, -5.0),
+        Tuple::point(0.0, 1.0, 0.0),
+        Tuple::vector(0.0, 1.0, 0.0),
+    ));
+
+    camera
+}
+
+fn chapter_spheres() -> Vec<ShapeContainer> {
+    let mut middle = Sphere::new();
+    middle.set_transformation(Transformation::identity().translation(-0.5, 1.0, 0.5));
+    middle.set_material(
+        Material::new()
+            .with_color(Color::new(0.1, 1.0, 0.5))
+            .with_diffuse(0.7)
+            .with_specular(0.3),
+    );
+
+    let mut right = Sphere::new();
+    right.set_transformation(
+        Transformation::identity()
+            .scale(0.5, 0.5, 0.5)
+            .translation(1.5, 0.5, -0.5),
+    );
+    right.set_material(
+        Material::new()
+            .with_color(Color::new(0.5, 1.0, 0.1))
+            .with_diffuse(0.7)
+            .with_specular(0.3),
+    );
+
+    let mut left = Sphere::new();
+    left.set_transformation(
+        Transformation::identity()
+            .scale(0.33, 0.33, 0.33)
+            .translation(-1.5, 0.33, -0.75),
+    );
+    left.set_material(
+        Material::new()
+            .with_color(Color::new(1.0, 0.8, 0.1))
+            .with_diffuse(0.7)
+            .with_specular(0.3),
+    );
+
+    vec![middle.into(), right.into(), left.into()]
+}
+
+fn chapter_world(walls: Vec<ShapeContainer>) -> World {
+    let mut world = World::new();
+    for shape in walls.into_iter().chain(chapter_spheres()) {
+        world.add_shape(shape);
+    }
+    world.add_light(PointLight::new(
+        Tuple::point(-10.0, 10.0, -10.0),
+        Colors::White.into(),
+    ));
+
+    world
+}
+
+fn wall_material() -> Material {
+    Material::new()
+        .with_color(Color::new(1.0, 0.9, 0.9))
+        .with_specular(0.0)
+}
+
+/**
+   The end of chapter 7 scene: three spheres in a room whose floor and walls
+   are flattened spheres.
+*/
+pub fn chapter_7_world() -> World {
+    let mut floor = Sphere::new();
+    floor.set_transformation(Transformation::identity().scale(10.0, 0.01, 10.0));
+    floor.set_material(wall_material());
+
+    let mut left_wall = Sphere::new();
+    left_wall.set_transformation(
+        Transformation::identity()
+            .scale(10.0, 0.01, 10.0)
+            .rotate_x(PI / 2.0)
+            .rotate_y(-PI / 4.0)
+            .translation(0.0, 0.0, 5.0),
+    );
+    left_wall.set_material(wall_material());
+
+    let mut right_wall = Sphere::new();
+    right_wall.set_transformation(
+        Transformation::identity()
+            .scale(10.0, 0.01, 10.0)
+            .rotate_x(PI / 2.0)
+            .rotate_y(PI / 4.0)
+            .translation(0.0, 0.0, 5.0),
+    );
+    right_wall.set_material(wall_material());
+
+    chapter_world(vec![floor.into(), left_wall.into(), right_wall.into()])
+}
+
+/**
+   The chapter 7 scene rebuilt in chapter 9 with planes for the floor and
+   back wall.
+*/
+pub fn chapter_9_world() -> World {
+    let mut floor = Plane::new();
+    floor.set_material(wall_material());
+
+    let mut back_wall = Plane::new();
+    back_wall.set_transformation(
+        Transformation::identity()
+            .rotate_x(PI / 2.0)
+            .translation(0.0, 0.0, 5.0),
+    );
+    back_wall.set_material(wall_material());
+
+    chapter_world(vec![floor.into(), back_wall.into()])
+}
+
+/**
+   The chapter 9 scene with the middle sphere ringed, following the floor
+   and back wall.
+*/
+pub fn ringed_chapter_9_world() -> World {
+    let world = chapter_9_world();
+
+    let mut pattern = RingPattern::new(Colors::Red.into(), Colors::White.into());
+    pattern.set_transformation(
+        Transformation::identity()
+            .scale(0.1, 0.1, 0.1)
+            .rotate_x(PI / 2.0),
+    );
+    world.shapes()[2].update_material(|material| material.with_pattern(pattern.clone()));
+
+    world
+}
+
+/**
+   The sphere chapter 5 casts rays at by hand. It only asks whether each
+   ray hits, so the sphere is a flat red silhouette.
+*/
+pub fn chapter_5_world() -> World {
+    sphere_shadow_world(
+        Material::new()
+            .with_color(Colors::Red.into())
+            .with_ambient(1.0)
+            .with_diffuse(0.0)
+            .with_specular(0.0),
+    )
+}
+
+/**
+   The same sphere shaded by hand in chapter 6.
+*/
+pub fn chapter_6_world() -> World {
+    sphere_shadow_world(Material::new().with_color(Color::new(1.0, 0.2, 1.0)))
+}
+
+fn sphere_shadow_world(material: Material) -> World {
+    let mut sphere = Sphere::new();
+    sphere.set_transformation(
+        Transformation::identity()
+            .scale(50.0, 50.0, 50.0)
+            .translation(200.0, 200.0, -300.0),
+    );
+    sphere.set_material(material);
+
+    let mut world = World::new();
+    world.add_shape(sphere.into());
+    world.add_light(PointLight::new(
+        Tuple::point(-100.0, -100.0, -600.0),
+        Colors::White.into(),
+    ));
+
+    world
+}
+
+/**
+   The view of chapters 5 and 6 from where their rays start, with canvas
+   rows running up the y axis as theirs do.
+*/
+pub fn sphere_shadow_camera(h_size: usize, v_size: usize) -> Camera {
+    let mut camera = Camera::new(h_size, v_size, 2.0 * 0.4f64.atan());
+    camera.set_transformation(Transformation::view(
+        Tuple::point(200.0, 200.0, -500.0),
+        Tuple::point(200.0, 200.0, 0.0),
+        Tuple::vector(0.0, -1.0, 0.0),
+    ));
+    camera
+}
+
+/**
+   A glass sphere with a bubble of air inside, in front of a checkered
+   wall, showing off the Fresnel effect.
+*/
+pub fn fresnel_world() -> World {
+    let mut world = World::new();
+    world.add_light(PointLight::new(
+        Tuple::point(2.0, 10.0, -5.0),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+
+    let mut plane = Plane::new();
+    plane.set_transformation(
+        Transformation::identity()
+            .rotate_x(PI / 2.0)
+            .translation(0.0, 0.0, 10.0),
+    );
+    plane.set_material(
+        Material::new()
+            .with_pattern(CheckerPattern::new(
+                Color::new(0.15, 0.15, 0.15),
+                Color::new(0.85, 0.85, 0.85),
+            ))
+            .with_ambient(0.8)
+            .with_diffuse(0.2)
+            .with_specular(0.0),
+    );
+    world.add_shape(plane.into());
+
+    let glass = Material::new()
+        .with_color(Colors::White.into())
+        .with_ambient(0.0)
+        .with_diffuse(0.0)
+        .with_specular(0.9)
+        .with_shininess(300.0)
+        .with_reflective(0.9)
+        .with_transparency(0.9);
+
+    let mut sphere = Sphere::new();
+    sphere.set_material(glass.clone().with_refractive_index(1.5));
+    world.add_shape(sphere.into());
+
+    let mut sphere = Sphere::new();
+    sphere.set_transformation(Transformation::identity().scale(0.5, 0.5, 0.5));
+    sphere.set_material(glass.with_refractive_index(1.0000034));
+    world.add_shape(sphere.into());
+
+    world
+}
+
+pub fn fresnel_camera(h_size: usize, v_size: usize) -> Camera {
+    let mut camera = Camera::new(h_size, v_size, 0.45);
+    camera.set_transformation(Transformation::view(
+        Tuple::point(0.0, 0.0, -5.0),
+        Tuple::point(0.0, 0.0, 0.0),
+        Tuple::vector(0.0, 1.0, 0.0),
+    ));
+    camera
+}
+
+/**
+   The chapter 11 room of striped walls with matte, mirrored and glass
+   spheres.
+*/
+pub fn reflect_refract_world() -> World {
+    let mut world = World::new();
+    world.add_light(PointLight::new(
+        Tuple::point(-4.9, 4.9, -1.0),
+        Colors::White.into(),
+    ));
+
+    let mut stripes =
+        StripePattern::new(Color::new(0.45, 0.45, 0.45), Color::new(0.55, 0.55, 0.55));
+    stripes.set_transformation(
+        Transformation::identity()
+            .scale(0.25, 0.25, 0.25)
+            .rotate_y(PI / 2.0),
+    );
+
+    let wall_material = Material::new()
+        .with_pattern(stripes)
+        .with_ambient(0.0)
+        .with_diffuse(0.4)
+        .with_specular(0.0)
+        .with_reflective(0.3);
+
+    let mut floor = Plane::new();
+    floor.set_transformation(Transformation::identity().rotate_y(0.31415));
+    floor.set_material(
+        Material::new()
+            .with_pattern(CheckerPattern::new(
+                Color::new(0.35, 0.35, 0.35),
+                Color::new(0.65, 0.65, 0.65),
+            ))
+            .with_specular(0.0)
+            .with_reflective(0.4),
+    );
+    world.add_shape(floor.into());
+
+    let mut ceiling = Plane::new();
+    ceiling.set_transformation(Transformation::identity().translation(0.0, 5.0, 0.0));
+    ceiling.set_material(
+        Material::new()
+            .with_color(Color::new(0.8, 0.8, 0.8))
+            .with_ambient(0.3)
+            .with_specular(0.0),
+    );
+    world.add_shape(ceiling.into());
+
+    let walls = [
+        Transformation::identity()
+            .rotate_y(PI / 2.0)
+            .rotate_z(PI / 2.0)
+            .translation(-5.0, 0.0, 0.0),
+        Transformation::identity()
+            .rotate_y(PI / 2.0)
+            .rotate_z(PI / 2.0)
+            .translation(5.0, 0.0, 0.0),
+        Transformation::identity()
+            .rotate_x(PI / 2.0)
+            .translation(0.0, 0.0, 5.0),
+        Transformation::identity()
+            .rotate_x(PI / 2.0)
+            .translation(0.0, 0.0, -5.0),
+    ];
+    for transformation in walls {
+        let mut wall = Plane::new();
+        wall.set_transformation(transformation);
+        wall.set_material(wall_material.clone());
+        world.add_shape(wall.into());
+    }
+
+    let background = [
+        (0.4, (4.6, 0.4, 1.0), Color::new(0.8, 0.5, 0.3)),
+        (0.3, (4.7, 0.3, 0.4), Color::new(0.9, 0.4, 0.5)),
+        (0.5, (-1.0, 0.5, 4.5), Color::new(0.4, 0.9, 0.6)),
+        (0.3, (-1.7, 0.3, 4.7), Color::new(0.4, 0.6, 0.9)),
+    ];
+    for (radius, (x, y, z), color) in background {
+        let mut sphere = Sphere::new();
+        sphere.set_transformation(
+            Transformation::identity()
+                .scale(radius, radius, radius)
+                .translation(x, y, z),
+        );
+        sphere.set_material(Material::new().with_color(color).with_shininess(50.0));
+        world.add_shape(sphere.into());
+    }
+
+    let mut sphere = Sphere::new();
+    sphere.set_transformation(Transformation::identity().translation(-0.6, 1.0, 0.6));
+    sphere.set_material(
+        Material::new()
+            .with_color(Color::new(1.0, 0.3, 0.2))
+            .with_specular(0.4)
+            .with_shininess(5.0),
+    );
+    world.add_shape(sphere.into());
+
+    let glass = Material::new()
+        .with_ambient(0.0)
+        .with_diffuse(0.4)
+        .with_specular(0.9)
+        .with_shininess(300.0)
+        .with_reflective(0.9)
+        .with_transparency(0.9)
+        .with_refractive_index(1.5);
+
+    let mut sphere = Sphere::new();
+    sphere.set_transformation(
+        Transformation::identity()
+            .scale(0.7, 0.7, 0.7)
+            .translation(0.7, 0.7, -0.6),
+    );
+    sphere.set_material(glass.clone().with_color(Color::new(0.0, 0.0, 0.2)));
+    world.add_shape(sphere.into());
+
+    let mut sphere = Sphere::new();
+    sphere.set_transformation(
+        Transformation::identity()
+            .scale(0.5, 0.5, 0.5)
+            .translation(-0.7, 0.5, -0.8),
+    );
+    sphere.set_material(glass.with_color(Color::new(0.0, 0.2, 0.0)));
+    world.add_shape(sphere.into());
+
+    world
+}
+
+pub fn reflect_refract_camera(h_size: usize, v_size: usize) -> Camera {
+    let mut camera = Camera::new(h_size, v_size, 1.52);
+    camera.set_transformation(Transformation::view(
+        Tuple::point(-2.6, 1.5, -3.9),
+        Tuple::point(-0.6, 1.0, -0.8),
+        Tuple::vector(0.0, 1.0, 0.0),
+    ));
+    camera
+}
+
+/**
+   A clear glass sphere in front of checkered walls and colored spheres.
+*/
+pub fn refraction_world() -> World {
+    let mut world = World::new();
+    world.add_light(PointLight::new(
+        Tuple::point(-5.0, 10.0, -10.0),
+        Color::new(1.0, 1.0, 1.0),
+    ));
+
+    let wall_material = Material::new()
+        .with_pattern(CheckerPattern::new(
+            Color::new(1.0, 1.0, 1.0),
+            Color::new(0.5, 0.5, 0.5),
+        ))
+        .with_reflective(0.0);
+
+    let mut left_wall = Plane::new();
+    left_wall.set_material(wall_material.clone());
+    left_wall.set_transformation(
+        Transformation::identity()
+            .rotate_z(PI / 2.0)
+            .translation(-15.0, 0.0, 0.0),
+    );
+    world.add_shape(left_wall.into());
+
+    let mut right_wall = Plane::new();
+    right_wall.set_material(wall_material);
+    right_wall.set_transformation(
+        Transformation::identity()
+            .rotate_x(PI / 2.0)
+            .translation(0.0, 0.0, 15.0),
+    );
+    world.add_shape(right_wall.into());
+
+    let mut sphere = Sphere::new();
+    sphere.set_material(
+        Material::new()
+            .with_color(Color::new(0.1, 0.1, 0.1))
+            .with_transparency(1.0)
+            .with_refractive_index(1.5),
+    );
+    sphere.set_transformation(Transformation::identity().translation(0.0, 1.5, 0.0));
+    world.add_shape(sphere.into());
+
+    let colored = [
+        (1.0, (-8.0, 1.0, 5.0), Color::new(0.0, 0.0, 1.0)),
+        (0.5, (-1.0, 0.5, 5.0), Color::new(1.0, 0.0, 0.0)),
+        (0.5, (-2.3, 0.5, 0.77), Color::new(0.0, 1.0, 0.0)),
+    ];
+    for (radius, (x, y, z), color) in colored {
+        let mut sphere = Sphere::new();
+        sphere.set_material(
+            Material::new()
+                .with_color(color)
+                .with_diffuse(0.7)
+                .with_specular(0.3)
+                .with_reflective(0.2),
+        );
+        sphere.set_transformation(
+            Transformation::identity()
+                .scale(radius, radius, radius)
+                .translation(x, y, z),
+        );
+        world.add_shape(sphere.into());
+    }
+
+    world
+}
+
+pub fn refraction_camera(h_size: usize, v_size: usize) -> Camera {
+    let mut camera = Camera::new(h_size, v_size, 1.0471966);
+    camera.set_transformation(Transformation::view(
+        Tuple::point(5.0, 1.5, -5.5),
+        Tuple::point(0.0, 0.7, 0.0),
+        Tuple::vector(0.0, 1.0, 0.0),
+    ));
+    camera
+}
+
+fn hexagon_side(material: Material) -> GroupContainer {
+    let mut corner = Sphere::new();
+    corner.set_transformation(
+        Transformation::identity()
+            .scale(0.25, 0.25, 0.25)
+            .translation(0.0, 0.0, -1.0),
+    );
+    corner.set_material(material.clone());
+
+    let mut edge = Cylinder::between(
+        Tuple::point(0.0, 0.0, -1.0),
+        Tuple::point(3f64.sqrt() / 2.0, 0.0, -0.5),
+        0.25,
+    );
+    edge.set_material(material);
+
+    let side = GroupContainer::default();
+    side.add_child(corner.into());
+    side.add_child(edge.into());
+
+    side
+}
+
+/**
+   A glass hexagon of spheres and cylinders built from nested groups, in
+   front of a checkered wall.
+*/
+pub fn hexagon_world() -> World {
+    let material = Material::default()
+        .with_transparency(1.0)
+        .with_reflective(1.0)
+        .with_refractive_index(1.52);
+    let hexagon = GroupContainer::default();
+    for n in 0..=5 {
+        let side = hexagon_side(material.clone());
+        side.write().unwrap().set_transformation(
+            Transformation::identity()
+                .rotate_y((n as f64) * PI / 3.0)
+                .translation(0.0, 0.5, 0.0),
+        );
+        hexagon.add_child(side.into());
+    }
+
+    group_world(hexagon)
+}
+
+/**
+   The low resolution teapot from the examples directory, on show in front
+   of a checkered wall.
+*/
+pub fn teapot_world() -> RayTraceResult<World> {
+    let teapot = OBJParser::parse_file(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/examples/objs/15_teapot_low_res.obj"
+    ))?
+    .as_group(64);
+    let scale = 1.0 / 5.0;
+    teapot.write().unwrap().set_transformation(
+        Transformation::identity()
+            .scale(scale, scale, scale)
+            .rotate_x(-PI / 2.0),
+    );
+    teapot.set_material_recursive(
+        Material::new()
+            .with_color(Color::new(0.8, 0.5, 0.3))
+            .with_reflective(0.1),
+    );
+
+    Ok(group_world(teapot))
+}
+
+fn group_world(group: GroupContainer) -> World {
+    let mut world = World::new();
+    world.add_shape(group.into());
+    world.add_light(PointLight::new(
+        Tuple::point(-10.0, 10.0, -10.0),
+        Colors::White.into(),
+    ));
+
+    let mut back_wall = Plane::new();
+    back_wall.set_transformation(
+        Transformation::identity()
+            .rotate_x(PI / 2.0)
+            .translation(0.0, 0.0, 5.0),
+    );
+    back_wall.set_material(Material::new().with_pattern(CheckerPattern::new(
+        Colors::Black.into(),
+        Colors::Purple.into(),
+    )));
+    world.add_shape(back_wall.into());
+
+    world
+}
+
+/**
+   The camera the hexagon and teapot are viewed from.
+*/
+pub fn group_camera(h_size: usize, v_size: usize) -> Camera {
+    let mut camera = Camera::new(h_size, v_size, PI / 3.0);
+    camera.set_transformation(Transformation::view(
+        Tuple::point(0.0, 3.0, -5.0),
+        Tuple::point(0.0, 1.0, 0.0),
+        Tuple::vector(0.0, 1.0, 0.0),
+    ));
+    camera
+}
+
+fn cover_material(color: Color) -> Material {
+    Material::new()
+        .with_color(color)
+        .with_diffuse(0.7)
+        .with_ambient(0.1)
+        .with_specular(0.0)
+        .with_reflective(0.1)
+}
+
+/**
+   The book's cover: a glass sphere among white, blue, red and purple
+   cubes in front of a white backdrop.
+*/
+pub fn cover_world() -> World {
+    let white = cover_material(Colors::White.into());
+    let blue = cover_material(Color::new(0.537, 0.832, 0.914));
+    let red = cover_material(Color::new(0.941, 0.322, 0.388));
+    let purple = cover_material(Color::new(0.373, 0.404, 0.55));
+
+    let standard = Transformation::identity()
+        .translation(1.0, -1.0, 1.0)
+        .scale(0.5, 0.5, 0.5);
+    let large = standard.clone().scale(3.5, 3.5, 3.5);
+    let medium = standard.clone().scale(3.0, 3.0, 3.0);
+    let small = standard.scale(2.0, 2.0, 2.0);
+
+    let mut world = World::new();
+    world.add_light(PointLight::new(
+        Tuple::point(50.0, 100.0, -50.0),
+        Colors::White.into(),
+    ));
+    world.add_light(PointLight::new(
+        Tuple::point(-400.0, 50.0, -10.0),
+        Color::new(0.2, 0.2, 0.2),
+    ));
+
+    let mut backdrop = Plane::new();
+    backdrop.set_material(
+        Material::new()
+            .with_color(Colors::White.into())
+            .with_ambient(1.0)
+            .with_diffuse(0.0)
+            .with_specular(0.0),
+    );
+    backdrop.set_transformation(
+        Transformation::identity()
+            .rotate_x(PI / 2.0)
+            .translation(0.0, 0.0, 500.0),
+    );
+    world.add_shape(backdrop.into());
+
+    let mut sphere = Sphere::new();
+    sphere.set_material(
+        Material::new()
+            .with_color(Color::new(0.373, 0.404, 0.550))
+            .with_diffuse(0.2)
+            .with_ambient(0.0)
+            .with_specular(1.0)
+            .with_shininess(200.0)
+            .with_reflective(0.7)
+            .with_transparency(0.7)
+            .with_refractive_index(1.5),
+    );
+    sphere.set_transformation(large.clone());
+    world.add_shape(sphere.into());
+
+    let cubes = [
+        (&medium, (4.0, 0.0, 0.0), &white),
+        (&large, (8.5, 1.5, -0.5), &blue),
+        (&large, (0.0, 0.0, 4.0), &red),
+        (&large, (4.0, 0.0, 4.0), &white),
+        (&medium, (7.5, 0.5, 4.0), &purple),
+        (&medium, (-0.25, 0.25, 8.0), &white),
+        (&large, (4.0, 1.0, 7.5), &blue),
+        (&medium, (10.0, 2.0, 7.5), &red),
+        (&small, (8.0, 2.0, 12.0), &white),
+        (&small, (20.0, 1.0, 9.0), &white),
+        (&large, (-0.5, -5.0, 0.25), &blue),
+        (&large, (4.0, -4.0, 0.0), &red),
+        (&large, (8.5, -4.0, 0.0), &white),
+        (&large, (0.0, -4.0, 4.0), &white),
+        (&large, (-0.5, -4.6, 8.0), &purple),
+        (&large, (0.0, -8.0, 4.0), &white),
+        (&large, (-0.5, -8.5, 8.0), &white),
+    ];
+    for (size, (x, y, z), material) in cubes {
+        let mut cube = Cube::new();
+        cube.set_material(material.clone());
+        cube.set_transformation(size.clone().translation(x, y, z));
+        world.add_shape(cube.into());
+    }
+
+    world
+}
+
+pub fn cover_camera(h_size: usize, v_size: usize) -> Camera {
+    let mut camera = Camera::new(h_size, v_size, 0.785);
+    camera.set_transformation(Transformation::view(
+        Tuple::point(-6.0, 6.0, -10.0),
+        Tuple::point(6.0, 0.0, 6.0),
+        Tuple::vector(-0.45, 1.0, 0.0),
+    ));
+    camera
+}
+
+pub fn random_spheres_world() -> World {
+    World::random_spheres(400, 42)
+}
+
+pub fn random_spheres_camera(h_size: usize, v_size: usize) -> Camera {
+    let mut camera = Camera::new(h_size, v_size, 0.35);
+    camera.set_transformation(Transformation::view(
+        Tuple::point(13.0, 2.0, 3.0),
+        Tuple::point(0.0, 0.0, 0.0),
+        Tuple::vector(0.0, 1.0, 0.0),
+    ));
+    camera
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intersection::ray::Ray;
+
+    use super::*;
+
+    #[test]
+    fn matching_allows_for_the_books_rounding() {
+        assert!(colors_match(
+            Color::new(0.38066, 0.47583, 0.2855),
+            Color::new(0.380661, 0.475826, 0.285496)
+        ));
+        assert!(!colors_match(
+            Color::new(0.38066, 0.47583, 0.2855),
+            Color::new(0.3808, 0.47583, 0.2855)
+        ));
+        assert!(tuples_match(
+            Tuple::point(-0.5, 0.33333, 1.0),
+            Tuple::point(-0.5, 1.0 / 3.0, 1.0)
+        ));
+    }
+
+    #[test]
+    fn the_default_world_matches_the_book() {
+        let w = default_world();
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        assert!(colors_match(
+            Color::new(0.38066, 0.47583, 0.2855),
+            w.color_at(r)
+        ));
+    }
+
+    #[test]
+    fn the_floor_is_added_to_the_default_world() {
+        let (w, floor) = default_world_with_floor(Material::new().with_reflective(0.5));
+
+        assert_eq!(3, w.shapes().len());
+        let material = floor.read().unwrap().material(floor.id()).unwrap();
+        assert_eq!(0.5, material.reflective());
+    }
+
+    #[test]
+    fn the_chapter_scenes_are_lit_and_visible() {
+        let camera = book_camera(11, 11);
+
+        for world in [chapter_7_world(), chapter_9_world()] {
+            let image = camera.render(&world);
+
+            assert_eq!(1, world.lights().len());
+            assert_ne!(Color::from(Colors::Black), image[(5, 5)]);
+        }
+    }
+}
@@ -0,0 +1,542 @@
+use crate::{
+    obj::subdivide,
+    shape::{group::GroupContainer, triangle::Triangle, ShapeContainer},
+    tuple::Tuple,
+    util::EPSILON,
+};
+
+/**
+   A closed outline in the xy plane. The last point joins back to the
+   first.
+*/
+pub type Contour = Vec<(f64, f64)>;
+
+type Point = (f64, f64);
+
+const TRIANGLES_PER_GROUP: usize = 8;
+
+/**
+   Below this, corners are treated as straight. Outlines are measured in
+   ems and curves are split into short segments, so the areas compared
+   against it are far smaller than `EPSILON`.
+*/
+const AREA_TOLERANCE: f64 = 1e-12;
+
+/**
+   Cut `contours` out of a board running from z = 0 back to z = `depth`,
+   the way letters are extruded. Contours nested inside an odd number of
+   others are holes, so either winding works. The front faces -z, towards
+   the default camera.
+*/
+pub fn extrude(contours: &[Contour], depth: f64) -> GroupContainer {
+    let contours = contours
+        .iter()
+        .map(|contour| clean(contour))
+        .filter(|contour| contour.len() >= 3 && signed_area(contour).abs() > AREA_TOLERANCE)
+        .collect::<Vec<_>>();
+    let nesting = contours
+        .iter()
+        .enumerate()
+        .map(|(i, contour)| {
+            contours
+                .iter()
+                .enumerate()
+                .filter(|&(j, other)| i != j && contains(other, contour[0]))
+                .count()
+        })
+        .collect::<Vec<_>>();
+    let contours = contours
+        .into_iter()
+        .zip(&nesting)
+        .map(|(mut contour, nesting)| {
+            if (signed_area(&contour) > 0.0) != (nesting % 2 == 0) {
+                contour.reverse();
+            }
+            contour
+        })
+        .collect::<Vec<_>>();
+
+    let mut triangles: Vec<ShapeContainer> = vec![];
+    let front = |(x, y): Point| Tuple::point(x, y, 0.0);
+    let back = |(x, y): Point| Tuple::point(x, y, depth);
+
+    for contour in &contours {
+        for (i, &a) in contour.iter().enumerate() {
+            let b = contour[(i + 1) % contour.len()];
+            triangles.push(Triangle::new(front(a), back(b), front(b)).into());
+            triangles.push(Triangle::new(front(a), back(a), back(b)).into());
+        }
+    }
+
+    for (i, outer) in contours.iter().enumerate() {
+        if nesting[i] % 2 == 1 {
+            continue;
+        }
+        let holes = contours
+            .iter()
+            .enumerate()
+            .filter(|&(j, hole)| nesting[j] == nesting[i] + 1 && contains(outer, hole[0]))
+            .map(|(_, hole)| hole.clone())
+            .collect::<Vec<_>>();
+
+        for [a, b, c] in triangulate(&bridge_holes(outer.clone(), holes)) {
+            triangles.push(Triangle::new(front(a), front(b), front(c)).into());
+            triangles.push(Triangle::new(back(a), back(c), back(b)).into());
+        }
+    }
+
+    subdivide(triangles, TRIANGLES_PER_GROUP)
+}
+
+fn clean(contour: &[Point]) -> Contour {
+    let mut cleaned: Contour = vec![];
+    for &point in contour {
+        if cleaned.last().is_none_or(|&last| !same(last, point)) {
+            cleaned.push(point);
+        }
+    }
+    while cleaned.len() > 1 && same(cleaned[0], *cleaned.last().unwrap()) {
+        cleaned.pop();
+    }
+    cleaned
+}
+
+fn same(a: Point, b: Point) -> bool {
+    (a.0 - b.0).abs() < EPSILON && (a.1 - b.1).abs() < EPSILON
+}
+
+/**
+   Positive when the contour winds counterclockwise.
+*/
+fn signed_area(contour: &[Point]) -> f64 {
+    contour
+        .iter()
+        .zip(contour.iter().cycle().skip(1))
+        .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+        .sum::<f64>()
+        / 2.0
+}
+
+fn contains(contour: &[Point], point: Point) -> bool {
+    let mut inside = false;
+    for (i, &a) in contour.iter().enumerate() {
+        let b = contour[(i + 1) % contour.len()];
+        if (a.1 > point.1) != (b.1 > point.1)
+            && point.0 < a.0 + (point.1 - a.1) * (b.0 - a.0) / (b.1 - a.1)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn cross(a: Point, b: Point, c: Point) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+fn segments_cross(a: Point, b: Point, c: Point, d: Point) -> bool {
+    if [c, d].iter().any(|&p| same(p, a) || same(p, b)) {
+        return false;
+    }
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+
+    (d1 > 0.0) != (d2 > 0.0) && (d3 > 0.0) != (d4 > 0.0)
+}
+
+fn edges(contour: &[Point]) -> impl Iterator<Item = (Point, Point)> + '_ {
+    contour
+        .iter()
+        .enumerate()
+        .map(|(i, &a)| (a, contour[(i + 1) % contour.len()]))
+}
+
+/**
+   Join each hole to the outline with a pair of coincident edges so the
+   result is one polygon that ear clipping can fill. Holes are joined right
+   to left, each from its rightmost point to the nearest outline point it
+   can see.
+*/
+fn bridge_holes(outer: Contour, mut holes: Vec<Contour>) -> Contour {
+    let rightmost = |hole: &Contour| {
+        (0..hole.len())
+            .max_by(|&a, &b| hole[a].0.total_cmp(&hole[b].0))
+            .unwrap()
+    };
+    holes.sort_by(|a, b| b[rightmost(b)].0.total_cmp(&a[rightmost(a)].0));
+
+    let mut polygon = outer;
+    for (h, hole) in holes.iter().enumerate() {
+        let m = rightmost(hole);
+        let from = hole[m];
+        let visible = |&i: &usize| {
+            let to = polygon[i];
+            !edges(&polygon)
+                .chain(holes[h..].iter().flat_map(|hole| edges(hole)))
+                .any(|(c, d)| segments_cross(from, to, c, d))
+        };
+        let mut candidates = (0..polygon.len()).collect::<Vec<_>>();
+        candidates
+            .sort_by(|&a, &b| distance(from, polygon[a]).total_cmp(&distance(from, polygon[b])));
+        let Some(p) = candidates.into_iter().find(visible) else {
+            continue;
+        };
+
+        let mut joined = polygon[..=p].to_vec();
+        joined.extend(hole[m..].iter().chain(&hole[..=m]));
+        joined.extend_from_slice(&polygon[p..]);
+        polygon = joined;
+    }
+
+    polygon
+}
+
+fn distance(a: Point, b: Point) -> f64 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+}
+
+fn in_triangle(p: Point, a: Point, b: Point, c: Point) -> bool {
+    cross(a, b, p) >= -AREA_TOLERANCE
+        && cross(b, c, p) >= -AREA_TOLERANCE
+        && cross(c, a, p) >= -AREA_TOLERANCE
+}
+
+/**
+   Fill a counterclockwise polygon by repeatedly cutting off a corner that
+   has no other point inside it.
+*/
+fn triangulate(polygon: &[Point]) -> Vec<[Point; 3]> {
+    let mut remaining = (0..polygon.len()).collect::<Vec<_>>();
+    let mut triangles = vec![];
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| {
+            (
+                polygon[remaining[(i + n - 1) % n]],
+                polygon[remaining[i]],
+                polygon[remaining[(i + 1) % n]],
+            )
+        };
+        let turn = |i: usize| {
+            let (a, b, c) = corner(i);
+            cross(a, b, c)
+        };
+        let is_ear = |i: usize| {
+            let (a, b, c) = corner(i);
+            turn(i) > AREA_TOLERANCE
+                && !remaining.iter().any(|&j| {
+                    let p = polygon[j];
+                    !same(p, a) && !same(p, b) && !same(p, c) && in_triangle(p, a, b, c)
+                })
+        };
+
+        if let Some(i) = (0..n).find(|&i| turn(i).abs() <= AREA_TOLERANCE) {
+            remaining.remove(i);
+            continue;
+        }
+        let Some(i) = (0..n)
+            .find(|&i| is_ear(i))
+            .or_else(|| (0..n).find(|&i| turn(i) > 0.0))
+        else {
+            break;
+        };
+        let (a, b, c) = corner(i);
+        triangles.push([a, b, c]);
+        remaining.remove(i);
+    }
+
+    if let [a, b, c] = remaining[..] {
+        if cross(polygon[a], polygon[b], polygon[c]) > AREA_TOLERANCE {
+            triangles.push([polygon[a], polygon[b], polygon[c]]);
+        }
+    }
+
+    triangles
+}
+
+#[cfg(feature = "text")]
+pub use font::Font;
+
+#[cfg(feature = "text")]
+mod font {
+    use std::{fs, path::Path};
+
+    use ttf_parser::{Face, OutlineBuilder};
+
+    use crate::{
+        error::{RayTraceError, RayTraceResult},
+        shape::group::GroupContainer,
+    };
+
+    use super::{extrude, Contour};
+
+    /**
+       How many straight segments each curve in a glyph outline becomes.
+    */
+    const CURVE_STEPS: usize = 8;
+
+    /**
+       A TrueType or OpenType font that lays out text as contours measured
+       in ems: one unit is the font's full height, the first line's
+       baseline is at y = 0 and text runs along +x.
+    */
+    #[derive(Debug, Clone)]
+    pub struct Font {
+        data: Vec<u8>,
+    }
+
+    impl Font {
+        pub fn parse_file<T: AsRef<Path>>(path: T) -> RayTraceResult<Self> {
+            Self::from_bytes(fs::read(path)?)
+        }
+
+        pub fn from_bytes(data: Vec<u8>) -> RayTraceResult<Self> {
+            Face::parse(&data, 0)
+                .map_err(|e| RayTraceError::InvalidFormat(format!("font: {e}")))?;
+            Ok(Self { data })
+        }
+
+        fn face(&self) -> Face<'_> {
+            Face::parse(&self.data, 0).expect("font was checked when it was loaded")
+        }
+
+        /**
+           The outlines of every glyph in `text`. Each newline starts a new
+           line one line height further down.
+        */
+        pub fn contours(&self, text: &str) -> Vec<Contour> {
+            let face = self.face();
+            let scale = 1.0 / face.units_per_em() as f64;
+            let line_height =
+                (face.ascender() as f64 - face.descender() as f64 + face.line_gap() as f64) * scale;
+
+            let mut outline = Outline::new(scale);
+            for (line, text) in text.lines().enumerate() {
+                outline.origin = (0.0, -(line as f64) * line_height);
+                for c in text.chars() {
+                    let Some(glyph) = face.glyph_index(c) else {
+                        continue;
+                    };
+                    face.outline_glyph(glyph, &mut outline);
+                    outline.origin.0 += face.glyph_hor_advance(glyph).unwrap_or(0) as f64 * scale;
+                }
+            }
+
+            outline.contours
+        }
+
+        /**
+           `text` extruded `depth` ems deep. See `extrude`.
+        */
+        pub fn text(&self, text: &str, depth: f64) -> GroupContainer {
+            extrude(&self.contours(text), depth)
+        }
+    }
+
+    struct Outline {
+        scale: f64,
+        origin: (f64, f64),
+        contours: Vec<Contour>,
+        current: Contour,
+        last: (f64, f64),
+    }
+
+    impl Outline {
+        fn new(scale: f64) -> Self {
+            Self {
+                scale,
+                origin: (0.0, 0.0),
+                contours: vec![],
+                current: vec![],
+                last: (0.0, 0.0),
+            }
+        }
+
+        fn point(&self, x: f32, y: f32) -> (f64, f64) {
+            (
+                self.origin.0 + x as f64 * self.scale,
+                self.origin.1 + y as f64 * self.scale,
+            )
+        }
+
+        fn curve(&mut self, to: (f64, f64), at: impl Fn((f64, f64), f64) -> (f64, f64)) {
+            let from = self.last;
+            for step in 1..=CURVE_STEPS {
+                self.current
+                    .push(at(from, step as f64 / CURVE_STEPS as f64));
+            }
+            self.last = to;
+        }
+    }
+
+    impl OutlineBuilder for Outline {
+        fn move_to(&mut self, x: f32, y: f32) {
+            self.close();
+            self.last = self.point(x, y);
+            self.current.push(self.last);
+        }
+
+        fn line_to(&mut self, x: f32, y: f32) {
+            self.last = self.point(x, y);
+            self.current.push(self.last);
+        }
+
+        fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+            let (c, to) = (self.point(x1, y1), self.point(x, y));
+            self.curve(to, |from, t| {
+                let s = 1.0 - t;
+                (
+                    s * s * from.0 + 2.0 * s * t * c.0 + t * t * to.0,
+                    s * s * from.1 + 2.0 * s * t * c.1 + t * t * to.1,
+                )
+            });
+        }
+
+        fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+            let (c1, c2, to) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+            self.curve(to, |from, t| {
+                let s = 1.0 - t;
+                (
+                    s * s * s * from.0
+                        + 3.0 * s * s * t * c1.0
+                        + 3.0 * s * t * t * c2.0
+                        + t * t * t * to.0,
+                    s * s * s * from.1
+                        + 3.0 * s * s * t * c1.1
+                        + 3.0 * s * t * t * c2.1
+                        + t * t * t * to.1,
+                )
+            });
+        }
+
+        fn close(&mut self) {
+            if !self.current.is_empty() {
+                self.contours.push(std::mem::take(&mut self.current));
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn fonts_that_cannot_be_read_are_reported() {
+            assert!(matches!(
+                Font::from_bytes(b"not a font".to_vec()),
+                Err(RayTraceError::InvalidFormat(_))
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        intersection::{ray::Ray, ShapeIntersection},
+        shape::Shape,
+    };
+
+    use super::*;
+
+    fn square(min: f64, max: f64) -> Contour {
+        vec![(min, min), (max, min), (max, max), (min, max)]
+    }
+
+    fn hits(group: &GroupContainer, origin: Tuple, direction: Tuple) -> usize {
+        group
+            .read()
+            .unwrap()
+            .intersects(&Ray::new(origin, direction))
+            .len()
+    }
+
+    #[test]
+    fn ear_clipping_fills_a_concave_polygon() {
+        let arrow = vec![(0.0, 0.0), (2.0, 1.0), (4.0, 0.0), (2.0, 3.0)];
+
+        let triangles = triangulate(&arrow);
+
+        assert_eq!(2, triangles.len());
+        let area = triangles
+            .iter()
+            .map(|&[a, b, c]| cross(a, b, c) / 2.0)
+            .sum::<f64>();
+        assert!((area - signed_area(&arrow)).abs() < EPSILON);
+    }
+
+    #[test]
+    fn extruding_a_square_makes_a_closed_box() {
+        let group = extrude(&[square(0.0, 1.0)], 0.5);
+
+        assert_eq!(
+            12,
+            group
+                .descendants()
+                .filter(|(s, _)| s.read().unwrap().type_name() == "Triangle")
+                .count()
+        );
+        let down_z = Tuple::vector(0.0, 0.0, 1.0);
+        assert_eq!(2, hits(&group, Tuple::point(0.3, 0.6, -5.0), down_z));
+        assert_eq!(0, hits(&group, Tuple::point(1.5, 0.5, -5.0), down_z));
+        assert_eq!(
+            2,
+            hits(
+                &group,
+                Tuple::point(-5.0, 0.3, 0.1),
+                Tuple::vector(1.0, 0.0, 0.0)
+            )
+        );
+    }
+
+    #[test]
+    fn contours_inside_others_are_cut_out() {
+        let mut hole = square(1.0, 2.0);
+        hole.reverse();
+        let group = extrude(&[square(0.0, 3.0), hole], 1.0);
+        let down_z = Tuple::vector(0.0, 0.0, 1.0);
+
+        assert_eq!(0, hits(&group, Tuple::point(1.4, 1.7, -5.0), down_z));
+        assert_eq!(2, hits(&group, Tuple::point(0.4, 1.3, -5.0), down_z));
+        assert_eq!(2, hits(&group, Tuple::point(2.6, 2.3, -5.0), down_z));
+        assert_eq!(
+            4,
+            hits(
+                &group,
+                Tuple::point(-5.0, 1.3, 0.3),
+                Tuple::vector(1.0, 0.0, 0.0)
+            )
+        );
+    }
+
+    #[test]
+    fn the_front_of_extruded_outlines_faces_the_camera() {
+        let group = extrude(&[square(0.0, 1.0)], 1.0);
+        let ray = Ray::new(Tuple::point(0.3, 0.6, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        let hit = group
+            .descendants()
+            .map(|(shape, _)| shape)
+            .filter(|shape| shape.read().unwrap().type_name() == "Triangle")
+            .find(|shape| {
+                shape
+                    .read()
+                    .unwrap()
+                    .intersects(&ray)
+                    .iter()
+                    .any(|i| (i.t() - 5.0).abs() < EPSILON)
+            })
+            .unwrap();
+        let i = ShapeIntersection::new(5.0, hit.clone(), hit.id());
+        let normal = hit
+            .read()
+            .unwrap()
+            .normal_at(hit.id(), Tuple::point(0.3, 0.6, 0.0), i)
+            .unwrap();
+
+        assert_eq!(Tuple::vector(0.0, 0.0, -1.0), normal);
+    }
+}
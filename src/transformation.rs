@@ -1,6 +1,6 @@
-use std::ops::Mul;
+use std::{fmt::Display, ops::Mul};
 
-use crate::{intersection::ray::Ray, matrix::Matrix, tuple::Tuple};
+use crate::{intersection::ray::Ray, matrix::Matrix, tuple::Tuple, util::PrecisionPolicy};
 
 #[derive(Debug, PartialEq, Default, Clone)]
 pub struct Transformation {
@@ -8,16 +8,26 @@ pub struct Transformation {
 }
 
 impl Transformation {
-    pub fn identity() -> Self {
+    pub const fn identity() -> Self {
         Self {
             matrix: Matrix::identity(4),
         }
     }
 
+    pub fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
     pub fn inverse(&self) -> Option<Self> {
         self.matrix.inverse().map(|matrix| Self { matrix })
     }
 
+    pub fn inverse_with(&self, precision: PrecisionPolicy) -> Option<Self> {
+        self.matrix
+            .inverse_with(precision)
+            .map(|matrix| Self { matrix })
+    }
+
     pub fn translation(&self, x: f64, y: f64, z: f64) -> Self {
         let mut m = Matrix::identity(4);
         m[(0, 3)] = x;
@@ -112,6 +122,76 @@ impl Transformation {
             matrix: &orientation * &Self::identity().translation(-from.x(), -from.y(), -from.z()).matrix,
         }
     }
+
+    /**
+       Place an object at `from` turned to face `target` the way a camera
+       there would, down its -z axis, with +y kept as close to up as it can
+       be.
+    */
+    pub fn look_at_object(from: Tuple, target: Tuple) -> Self {
+        let forward = (target - from).normalize();
+        let up = if (forward ^ Tuple::vector(0.0, 1.0, 0.0)).magnitude() < 1e-6 {
+            Tuple::vector(0.0, 0.0, 1.0)
+        } else {
+            Tuple::vector(0.0, 1.0, 0.0)
+        };
+
+        Self::view(from, target, up)
+            .inverse()
+            .expect("Could not get inverse")
+    }
+
+    /**
+       Stand an object on the xz plane at `(x, z)`, turned `rotation`
+       radians about the y axis.
+    */
+    pub fn place_on_plane(x: f64, z: f64, rotation: f64) -> Self {
+        Self::identity().rotate_y(rotation).translation(x, 0.0, z)
+    }
+
+    /**
+       Stretch the segment from the origin to one unit up the y axis so it
+       runs from `a` to `b`, without scaling the other axes. A cylinder
+       truncated to 0..1 becomes a rod of its own radius between the points.
+    */
+    pub fn between(a: Tuple, b: Tuple) -> Self {
+        let axis = b - a;
+        let direction = axis.normalize();
+        let side = if (direction ^ Tuple::vector(0.0, 0.0, 1.0)).magnitude() < 1e-6 {
+            Tuple::vector(1.0, 0.0, 0.0)
+        } else {
+            (direction ^ Tuple::vector(0.0, 0.0, 1.0)).normalize()
+        };
+        let front = side ^ direction;
+
+        Self {
+            matrix: Matrix::from(vec![
+                vec![side.x(), axis.x(), front.x(), a.x()],
+                vec![side.y(), axis.y(), front.y(), a.y()],
+                vec![side.z(), axis.z(), front.z(), a.z()],
+                vec![0.0, 0.0, 0.0, 1.0],
+            ]),
+        }
+    }
+}
+
+impl Display for Transformation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.matrix.fmt(f)
+    }
+}
+
+/**
+   Compose two transformations, applying `rhs` first.
+*/
+impl Mul<&Transformation> for &Transformation {
+    type Output = Transformation;
+
+    fn mul(self, rhs: &Transformation) -> Self::Output {
+        Transformation {
+            matrix: &self.matrix * &rhs.matrix,
+        }
+    }
 }
 
 impl Mul<Tuple> for &Transformation {
@@ -130,11 +210,19 @@ impl Mul<Tuple> for Transformation {
     }
 }
 
+impl Mul<&Ray> for &Transformation {
+    type Output = Ray;
+
+    fn mul(self, rhs: &Ray) -> Self::Output {
+        Ray::new(self * rhs.origin(), self * rhs.direction())
+    }
+}
+
 impl Mul<Ray> for Transformation {
     type Output = Ray;
 
     fn mul(self, rhs: Ray) -> Self::Output {
-        Ray::new(&self * rhs.origin(), &self * rhs.direction())
+        &self * &rhs
     }
 }
 
@@ -142,7 +230,7 @@ impl Mul<Ray> for Transformation {
 mod tests {
     use std::{f64::consts::PI, vec};
 
-    use crate::tuple::Tuple;
+    use crate::{tuple::Tuple, util::eq_f64};
 
     use super::*;
 
@@ -411,4 +499,87 @@ mod tests {
 
         assert_eq!(expected, t.matrix);
     }
+
+    #[test]
+    fn looking_at_a_target_from_an_object() {
+        let from = Tuple::point(1.0, 2.0, 3.0);
+        let target = Tuple::point(4.0, 2.0, 7.0);
+
+        let t = Transformation::look_at_object(from, target);
+
+        assert_eq!(from, &t * Tuple::origin());
+        assert_eq!(
+            Tuple::vector(0.6, 0.0, 0.8),
+            &t * Tuple::vector(0.0, 0.0, -1.0)
+        );
+        assert_eq!(
+            Tuple::vector(0.0, 1.0, 0.0),
+            &t * Tuple::vector(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn looking_straight_down_at_a_target() {
+        let t = Transformation::look_at_object(Tuple::point(0.0, 5.0, 0.0), Tuple::origin());
+
+        assert_eq!(
+            Tuple::vector(0.0, -1.0, 0.0),
+            &t * Tuple::vector(0.0, 0.0, -1.0)
+        );
+    }
+
+    #[test]
+    fn placing_an_object_on_the_plane() {
+        let t = Transformation::place_on_plane(2.0, -3.0, PI / 2.0);
+
+        assert_eq!(Tuple::point(2.0, 0.0, -3.0), &t * Tuple::origin());
+        assert_eq!(
+            Tuple::point(2.0, 0.0, -4.0),
+            &t * Tuple::point(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn stretching_the_unit_y_segment_between_two_points() {
+        for (a, b) in [
+            (Tuple::point(1.0, 2.0, 3.0), Tuple::point(-2.0, 6.0, 3.0)),
+            (Tuple::point(0.0, 0.0, 0.0), Tuple::point(0.0, 0.0, 5.0)),
+            (Tuple::point(0.0, 1.0, 0.0), Tuple::point(0.0, -1.0, 0.0)),
+        ] {
+            let t = Transformation::between(a, b);
+            let direction = (b - a).normalize();
+
+            assert_eq!(a, &t * Tuple::origin());
+            assert_eq!(b, &t * Tuple::point(0.0, 1.0, 0.0));
+            for radial in [Tuple::vector(1.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0)] {
+                let radial = &t * radial;
+                assert!(eq_f64(1.0, radial.magnitude()));
+                assert!(eq_f64(0.0, radial * direction));
+            }
+        }
+
+        assert_eq!(
+            Transformation::identity().scale(1.0, 2.0, 1.0),
+            Transformation::between(Tuple::origin(), Tuple::point(0.0, 2.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn the_identity_can_be_built_at_compile_time() {
+        static IDENTITY: Transformation = Transformation::identity();
+        let p = Tuple::point(1.0, 2.0, 3.0);
+
+        assert_eq!(p, &IDENTITY * p);
+        assert_eq!(Some(Transformation::identity()), IDENTITY.inverse());
+    }
+
+    #[test]
+    fn displaying_a_transformation_shows_its_matrix() {
+        let transform = Transformation::identity().translation(5.0, -3.0, 2.0);
+
+        assert_eq!(
+            "| 1 0 0  5 |\n| 0 1 0 -3 |\n| 0 0 1  2 |\n| 0 0 0  1 |",
+            transform.to_string()
+        );
+    }
 }
@@ -1,6 +1,12 @@
-use std::ops::{Add, BitXor, Div, Mul, Neg, Sub};
+use std::{
+    fmt::Display,
+    ops::{Add, BitXor, Div, Mul, Neg, Sub},
+};
 
-use crate::{color::Color, util::eq_f64};
+use crate::{
+    color::Color,
+    util::{approx_eq, eq_f64, format_number},
+};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Tuple {
@@ -11,38 +17,45 @@ pub struct Tuple {
 }
 
 impl Tuple {
-    pub fn new(x: f64, y: f64, z: f64, w: f64) -> Self {
+    pub const fn new(x: f64, y: f64, z: f64, w: f64) -> Self {
         Tuple { x, y, z, w }
     }
 
-    pub fn x(&self) -> f64 {
+    pub const fn x(&self) -> f64 {
         self.x
     }
 
-    pub fn y(&self) -> f64 {
+    pub const fn y(&self) -> f64 {
         self.y
     }
 
-    pub fn z(&self) -> f64 {
+    pub const fn z(&self) -> f64 {
         self.z
     }
 
-    pub fn w(&self) -> f64 {
+    pub const fn w(&self) -> f64 {
         self.w
     }
 
-    pub fn point(x: f64, y: f64, z: f64) -> Self {
+    pub const fn point(x: f64, y: f64, z: f64) -> Self {
         Tuple::new(x, y, z, 1.0)
     }
 
-    pub fn origin() -> Self {
+    pub const fn origin() -> Self {
         Self::point(0.0, 0.0, 0.0)
     }
 
-    pub fn vector(x: f64, y: f64, z: f64) -> Self {
+    pub const fn vector(x: f64, y: f64, z: f64) -> Self {
         Tuple::new(x, y, z, 0.0)
     }
 
+    pub fn approx_eq(&self, other: &Tuple, tolerance: f64) -> bool {
+        approx_eq(self.x, other.x, tolerance)
+            && approx_eq(self.y, other.y, tolerance)
+            && approx_eq(self.z, other.z, tolerance)
+            && approx_eq(self.w, other.w, tolerance)
+    }
+
     pub fn is_point(&self) -> bool {
         self.w == 1.0
     }
@@ -66,6 +79,30 @@ impl Tuple {
     pub fn as_vector(&mut self) {
         self.w = 0.0;
     }
+
+    /**
+       Apply `f` to the x, y and z components, leaving w untouched so points
+       stay points and vectors stay vectors.
+    */
+    pub fn map<F: Fn(f64) -> f64>(&self, f: F) -> Tuple {
+        Tuple::new(f(self.x), f(self.y), f(self.z), self.w)
+    }
+
+    pub fn xyz(&self) -> (f64, f64, f64) {
+        (self.x, self.y, self.z)
+    }
+
+    pub fn with_x(&self, x: f64) -> Tuple {
+        Tuple { x, ..*self }
+    }
+
+    pub fn with_y(&self, y: f64) -> Tuple {
+        Tuple { y, ..*self }
+    }
+
+    pub fn with_z(&self, z: f64) -> Tuple {
+        Tuple { z, ..*self }
+    }
 }
 
 impl From<Color> for Tuple {
@@ -74,6 +111,29 @@ impl From<Color> for Tuple {
     }
 }
 
+/**
+   Points and vectors are labelled as such and leave out `w`, as in
+   `point(x: 1, y: 2, z: 3)`. A precision such as `{:.2}` applies to every
+   component.
+*/
+impl Display for Tuple {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (x, y, z) = (
+            format_number(self.x, f),
+            format_number(self.y, f),
+            format_number(self.z, f),
+        );
+        if self.is_point() {
+            write!(f, "point(x: {x}, y: {y}, z: {z})")
+        } else if self.is_vector() {
+            write!(f, "vector(x: {x}, y: {y}, z: {z})")
+        } else {
+            let w = format_number(self.w, f);
+            write!(f, "tuple(x: {x}, y: {y}, z: {z}, w: {w})")
+        }
+    }
+}
+
 impl PartialEq for Tuple {
     fn eq(&self, other: &Self) -> bool {
         eq_f64(self.x, other.x)
@@ -321,5 +381,47 @@ mod tests {
         assert_eq!(Tuple::vector(1.0, 0.0, 0.0), r);
     }
 
+    #[test]
+    fn mapping_a_tuple_keeps_its_w() {
+        let p = Tuple::point(1.5, -2.5, 3.0).map(f64::floor);
+        let v = Tuple::vector(1.0, 2.0, 3.0).map(|c| c * 2.0);
 
+        assert_eq!(Tuple::point(1.0, -3.0, 3.0), p);
+        assert_eq!(Tuple::vector(2.0, 4.0, 6.0), v);
+    }
+
+    #[test]
+    fn swizzling_tuple_components() {
+        let p = Tuple::point(1.0, 2.0, 3.0);
+
+        assert_eq!((1.0, 2.0, 3.0), p.xyz());
+        assert_eq!(Tuple::point(5.0, 2.0, 3.0), p.with_x(5.0));
+        assert_eq!(Tuple::point(1.0, 5.0, 3.0), p.with_y(5.0));
+        assert_eq!(Tuple::point(1.0, 2.0, 5.0), p.with_z(5.0));
+    }
+
+    #[test]
+    fn tuples_can_be_built_at_compile_time() {
+        const UP: Tuple = Tuple::vector(0.0, 1.0, 0.0);
+        const ORIGIN: Tuple = Tuple::origin();
+
+        assert!(UP.is_vector());
+        assert_eq!(Tuple::point(0.0, 1.0, 0.0), ORIGIN + UP);
+    }
+
+    #[test]
+    fn displaying_points_vectors_and_tuples() {
+        assert_eq!(
+            "point(x: 1, y: -2.5, z: 0)",
+            Tuple::point(1.0, -2.5, -0.0).to_string()
+        );
+        assert_eq!(
+            "vector(x: 0.33, y: 1.00, z: 0.00)",
+            format!("{:.2}", Tuple::vector(1.0 / 3.0, 1.0, 0.0))
+        );
+        assert_eq!(
+            "tuple(x: 1, y: 2, z: 3, w: 4)",
+            Tuple::new(1.0, 2.0, 3.0, 4.0).to_string()
+        );
+    }
 }
@@ -0,0 +1,125 @@
+use std::{f64::consts::PI, time::Instant};
+
+use crate::{
+    camera::Camera,
+    error::RayTraceResult,
+    metadata::{frame_name, RenderMetadata},
+    transformation::Transformation,
+    tuple::Tuple,
+    world::World,
+};
+
+/**
+   View transformations for `frames` evenly spaced positions on a circle of
+   `radius` around `target`, at a height of `height`. The first frame looks
+   from -z and the camera moves towards +x from there.
+*/
+pub fn turntable_views(
+    frames: usize,
+    radius: f64,
+    height: f64,
+    target: Tuple,
+) -> Vec<Transformation> {
+    (0..frames)
+        .map(|frame| {
+            let angle = 2.0 * PI * frame as f64 / frames as f64;
+            let from = Tuple::point(
+                target.x() + radius * angle.sin(),
+                height,
+                target.z() - radius * angle.cos(),
+            );
+
+            Transformation::view(from, target, Tuple::vector(0.0, 1.0, 0.0))
+        })
+        .collect()
+}
+
+/**
+   Orbit `camera` once around `target` over `frames` frames, saving each
+   under `pattern` with its frame number filled in by `frame_name`, along
+   with how long it took to render. The camera is left at the last frame's
+   position.
+*/
+pub fn render_turntable(
+    camera: &mut Camera,
+    world: &World,
+    frames: usize,
+    radius: f64,
+    height: f64,
+    target: Tuple,
+    pattern: &str,
+) -> RayTraceResult<()> {
+    for (frame, view) in turntable_views(frames, radius, height, target)
+        .into_iter()
+        .enumerate()
+    {
+        camera.set_transformation(view);
+        let start = Instant::now();
+        let image = camera.render(world);
+        let metadata = RenderMetadata::new().with_duration(start.elapsed());
+        image.save_with_metadata(&frame_name(pattern, frame), &metadata)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use uuid::Uuid;
+
+    use crate::testing::default_world;
+
+    use super::*;
+
+    #[test]
+    fn turntable_views_circle_the_target() {
+        let target = Tuple::point(1.0, 0.5, 0.0);
+
+        let views = turntable_views(4, 5.0, 2.0, target);
+        let up = Tuple::vector(0.0, 1.0, 0.0);
+
+        assert_eq!(4, views.len());
+        assert_eq!(
+            Transformation::view(Tuple::point(1.0, 2.0, -5.0), target, up),
+            views[0]
+        );
+        assert_eq!(
+            Transformation::view(Tuple::point(6.0, 2.0, 0.0), target, up),
+            views[1]
+        );
+        assert_eq!(
+            Transformation::view(Tuple::point(1.0, 2.0, 5.0), target, up),
+            views[2]
+        );
+    }
+
+    #[test]
+    fn the_camera_orbits_the_target_frame_by_frame() {
+        let directory = std::env::temp_dir().join(format!("turntable-{}", Uuid::new_v4()));
+        fs::create_dir_all(&directory).unwrap();
+        let pattern = directory.join("frame_%02d").to_string_lossy().into_owned();
+        let target = Tuple::point(0.0, 1.0, 0.0);
+        let position = |view: &Transformation| view.inverse().unwrap() * Tuple::origin();
+
+        let views = turntable_views(6, 4.0, 3.0, target);
+        for (frame, view) in views.iter().enumerate() {
+            let angle = PI / 3.0 * frame as f64;
+            assert_eq!(
+                Tuple::point(4.0 * angle.sin(), 3.0, -4.0 * angle.cos()),
+                position(view)
+            );
+        }
+
+        let mut camera = Camera::new(4, 4, PI / 3.0);
+        render_turntable(&mut camera, &default_world(), 6, 4.0, 3.0, target, &pattern).unwrap();
+        let frames = (0..6)
+            .map(|frame| directory.join(format!("frame_{frame:02}.ppm")).exists())
+            .collect::<Vec<_>>();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(vec![true; 6], frames);
+        assert_eq!(position(&views[5]), camera.ray_for_pixel(2, 2).origin());
+    }
+}
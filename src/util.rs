@@ -1,22 +1,171 @@
 use core::f64;
+use std::{cmp::Ordering, fmt::Formatter};
 
-pub(crate) const EPSILON: f64 = 0.00001;
+pub const EPSILON: f64 = 0.00001;
 
 pub fn eq_f64(a: f64, b: f64) -> bool {
+    approx_eq(a, b, EPSILON)
+}
+
+/**
+   Compare two values to within `tolerance`. Geometry is compared with
+   `EPSILON`; sampled and textured results usually need something looser.
+*/
+pub fn approx_eq(a: f64, b: f64, tolerance: f64) -> bool {
     if (a == f64::INFINITY && b == f64::INFINITY)
         || (a == f64::NEG_INFINITY && b == f64::NEG_INFINITY)
     {
         true
     } else {
-        (a - b).abs() < EPSILON
+        (a - b).abs() < tolerance
+    }
+}
+
+/**
+   Write `value` the way `{}` would, or to the precision asked for as in
+   `{:.2}`. Negative zero is written as zero.
+*/
+pub(crate) fn format_number(value: f64, f: &Formatter<'_>) -> String {
+    match f.precision() {
+        Some(precision) => format!("{:.*}", precision, value + 0.0),
+        None => format!("{}", value + 0.0),
+    }
+}
+
+/**
+   How close two numbers must be to count as equal. `Absolute` is what
+   `eq_f64` uses and suits a scene of everyday sizes. `Relative` scales the
+   tolerance with the numbers, so it keeps working for very large or very
+   small scenes, but no number but zero is close to zero. `Ulps` allows a
+   number of representable doubles between the two, which is the tightest
+   and cheapest to pass but tolerates the least accumulated error.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrecisionPolicy {
+    Absolute(f64),
+    Relative(f64),
+    Ulps(u64),
+}
+
+impl Default for PrecisionPolicy {
+    fn default() -> Self {
+        Self::Absolute(EPSILON)
     }
 }
 
+impl PrecisionPolicy {
+    pub fn eq(&self, a: f64, b: f64) -> bool {
+        match *self {
+            Self::Absolute(tolerance) => approx_eq(a, b, tolerance),
+            Self::Relative(tolerance) => {
+                a == b || (a - b).abs() <= tolerance * a.abs().max(b.abs())
+            }
+            Self::Ulps(ulps) => {
+                let ordered = |x: f64| {
+                    let bits = x.to_bits() as i64;
+                    if bits < 0 {
+                        i64::MIN as i128 - bits as i128
+                    } else {
+                        bits as i128
+                    }
+                };
+                a == b
+                    || (!a.is_nan()
+                        && !b.is_nan()
+                        && (ordered(a) - ordered(b)).unsigned_abs() <= ulps as u128)
+            }
+        }
+    }
+
+    /**
+       Whether `value` is too small to tell from zero, when it was worked
+       out from numbers around `scale` in size.
+    */
+    pub fn is_negligible(&self, value: f64, scale: f64) -> bool {
+        match *self {
+            Self::Absolute(tolerance) => value.abs() < tolerance,
+            Self::Relative(tolerance) => value.abs() <= tolerance * scale.abs(),
+            Self::Ulps(ulps) => value.abs() <= ulps as f64 * f64::EPSILON * scale.abs(),
+        }
+    }
+
+    /**
+       Order two numbers, counting them as equal when they are within
+       the policy of each other.
+    */
+    pub fn compare(&self, a: f64, b: f64) -> Ordering {
+        if self.eq(a, b) {
+            Ordering::Equal
+        } else {
+            a.total_cmp(&b)
+        }
+    }
+
+    /**
+       Round `value` down, unless it is within the policy of the integer
+       above it. Patterns use this to decide which cell a point on a
+       boundary falls in, so that a point a rounding error short of the
+       boundary does not flicker into the cell before it.
+    */
+    pub fn floor(&self, value: f64) -> f64 {
+        let nearest = value.round();
+        if self.eq(value, nearest) {
+            nearest
+        } else {
+            value.floor()
+        }
+    }
+}
+
+/**
+   Assert two colors are equal channel by channel, to within the tolerance
+   if one is given and `EPSILON` otherwise.
+*/
+#[macro_export]
+macro_rules! assert_color_eq {
+    ($expected:expr, $actual:expr) => {
+        assert_eq!($expected, $actual)
+    };
+    ($expected:expr, $actual:expr, $tolerance:expr) => {{
+        let (expected, actual) = ($expected, $actual);
+        assert!(
+            expected.approx_eq(&actual, $tolerance),
+            "colors differ by more than {}\n  expected: {:?}\n    actual: {:?}",
+            $tolerance,
+            expected,
+            actual,
+        )
+    }};
+}
+
+/**
+   Assert two tuples are equal component by component, to within the
+   tolerance if one is given and `EPSILON` otherwise.
+*/
+#[macro_export]
+macro_rules! assert_tuple_eq {
+    ($expected:expr, $actual:expr) => {
+        assert_eq!($expected, $actual)
+    };
+    ($expected:expr, $actual:expr, $tolerance:expr) => {{
+        let (expected, actual) = ($expected, $actual);
+        assert!(
+            expected.approx_eq(&actual, $tolerance),
+            "tuples differ by more than {}\n  expected: {:?}\n    actual: {:?}",
+            $tolerance,
+            expected,
+            actual,
+        )
+    }};
+}
+
 #[cfg(test)]
 mod tests {
 
     use core::f64;
 
+    use crate::{color::Color, tuple::Tuple};
+
     use super::*;
 
     #[test]
@@ -28,4 +177,75 @@ mod tests {
         assert!(eq_f64(f64::NEG_INFINITY, f64::NEG_INFINITY));
         assert!(!eq_f64(f64::NEG_INFINITY, f64::INFINITY));
     }
+
+    #[test]
+    fn approx_eq_uses_the_given_tolerance() {
+        assert!(approx_eq(1.0, 1.004, 0.01));
+        assert!(!approx_eq(1.0, 1.004, 0.001));
+        assert!(approx_eq(f64::INFINITY, f64::INFINITY, 0.0));
+    }
+
+    #[test]
+    fn precision_policies_compare_numbers_differently() {
+        let absolute = PrecisionPolicy::default();
+        let relative = PrecisionPolicy::Relative(1e-9);
+        let ulps = PrecisionPolicy::Ulps(4);
+
+        assert!(absolute.eq(1.0, 1.000001));
+        assert!(!relative.eq(1.0, 1.000001));
+        assert!(!absolute.eq(1e9, 1e9 + 1e-3));
+        assert!(relative.eq(1e9, 1e9 + 1e-3));
+        assert!(absolute.eq(0.0, 1e-9));
+        assert!(!relative.eq(0.0, 1e-9));
+
+        assert!(ulps.eq(0.1 + 0.2, 0.3));
+        assert!(ulps.eq(-0.0, 0.0));
+        assert!(ulps.eq(
+            f64::MIN_POSITIVE * -f64::EPSILON,
+            f64::MIN_POSITIVE * f64::EPSILON
+        ));
+        assert!(!ulps.eq(1.0, 1.0 + 8.0 * f64::EPSILON));
+        assert!(!ulps.eq(f64::NAN, f64::NAN));
+        assert!(ulps.eq(f64::INFINITY, f64::INFINITY));
+
+        assert_eq!(Ordering::Equal, absolute.compare(2.0, 2.000001));
+        assert_eq!(Ordering::Less, relative.compare(2.0, 2.000001));
+    }
+
+    #[test]
+    fn precision_policies_decide_what_is_negligible_by_scale() {
+        assert!(PrecisionPolicy::default().is_negligible(1e-6, 1e-6));
+        assert!(!PrecisionPolicy::Relative(1e-9).is_negligible(1e-6, 1e-6));
+        assert!(PrecisionPolicy::Relative(1e-9).is_negligible(1e-6, 1e6));
+        assert!(PrecisionPolicy::Ulps(4).is_negligible(f64::EPSILON, 1.0));
+        assert!(!PrecisionPolicy::Ulps(4).is_negligible(1e-6, 1.0));
+    }
+
+    #[test]
+    fn flooring_snaps_values_just_short_of_a_boundary() {
+        let absolute = PrecisionPolicy::default();
+        assert_eq!(1.0, absolute.floor(0.999999));
+        assert_eq!(0.0, absolute.floor(0.9));
+        assert_eq!(-1.0, absolute.floor(-0.5));
+        assert_eq!(1.0, PrecisionPolicy::Ulps(4).floor(1.0 - f64::EPSILON));
+        assert_eq!(0.0, PrecisionPolicy::Ulps(4).floor(0.999999));
+    }
+
+    #[test]
+    fn asserting_colors_and_tuples_with_a_tolerance() {
+        assert_color_eq!(Color::new(0.5, 0.5, 0.5), Color::new(0.5, 0.5, 0.5));
+        assert_color_eq!(Color::new(0.5, 0.5, 0.5), Color::new(0.51, 0.49, 0.5), 0.02);
+        assert_tuple_eq!(Tuple::point(1.0, 2.0, 3.0), Tuple::point(1.0, 2.0, 3.0));
+        assert_tuple_eq!(
+            Tuple::vector(1.0, 0.0, 0.0),
+            Tuple::vector(0.99, 0.0, 0.01),
+            0.02
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "colors differ by more than 0.001")]
+    fn asserting_colors_outside_the_tolerance_panics() {
+        assert_color_eq!(Color::new(0.5, 0.5, 0.5), Color::new(0.51, 0.5, 0.5), 0.001);
+    }
 }
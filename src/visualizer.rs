@@ -0,0 +1,331 @@
+use std::{fs::File, io::Write};
+
+use crate::{
+    camera::Camera,
+    error::RayTraceResult,
+    intersection::{prepcomputation::PrepComputations, ray::Ray},
+    tuple::Tuple,
+    util::eq_f64,
+    world::World,
+};
+
+/**
+   How far a ray that hits nothing is drawn.
+*/
+const MISS_LENGTH: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    Primary,
+    Reflected,
+    Refracted,
+    Teleported,
+}
+
+impl SegmentKind {
+    fn name(&self) -> &'static str {
+        match self {
+            SegmentKind::Primary => "primary",
+            SegmentKind::Reflected => "reflected",
+            SegmentKind::Refracted => "refracted",
+            SegmentKind::Teleported => "teleported",
+        }
+    }
+
+    fn stroke(&self) -> &'static str {
+        match self {
+            SegmentKind::Primary => "black",
+            SegmentKind::Reflected => "blue",
+            SegmentKind::Refracted => "red",
+            SegmentKind::Teleported => "green",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaySegment {
+    from: Tuple,
+    to: Tuple,
+    kind: SegmentKind,
+    depth: usize,
+    hit: bool,
+}
+
+impl RaySegment {
+    pub fn from(&self) -> Tuple {
+        self.from
+    }
+
+    pub fn to(&self) -> Tuple {
+        self.to
+    }
+
+    pub fn kind(&self) -> SegmentKind {
+        self.kind
+    }
+
+    /**
+       How many bounces away from the primary ray this segment is.
+    */
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /**
+       Whether the segment ends on a surface rather than running off into
+       empty space.
+    */
+    pub fn hit(&self) -> bool {
+        self.hit
+    }
+}
+
+/**
+   Records the tree of rays the world spawns for a single primary ray and
+   exports it as line segments, for inspecting reflection and refraction in
+   an external viewer.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct RayVisualizer {
+    segments: Vec<RaySegment>,
+}
+
+impl RayVisualizer {
+    pub fn trace(world: &World, ray: Ray) -> Self {
+        let mut visualizer = Self::default();
+        visualizer.follow(world, ray, SegmentKind::Primary, 0);
+        visualizer
+    }
+
+    pub fn trace_pixel(world: &World, camera: &Camera, px: usize, py: usize) -> Self {
+        Self::trace(world, camera.ray_for_pixel(px, py))
+    }
+
+    pub fn segments(&self) -> &[RaySegment] {
+        &self.segments
+    }
+
+    fn follow(&mut self, world: &World, ray: Ray, kind: SegmentKind, depth: usize) {
+        let intersections = world.intersects(&ray);
+        let Some(hit) = intersections.hit() else {
+            self.segments.push(RaySegment {
+                from: ray.origin(),
+                to: ray.position(MISS_LENGTH),
+                kind,
+                depth,
+                hit: false,
+            });
+            return;
+        };
+
+        self.segments.push(RaySegment {
+            from: ray.origin(),
+            to: ray.position(hit.t()),
+            kind,
+            depth,
+            hit: true,
+        });
+        if depth >= world.max_depth() {
+            return;
+        }
+
+        let teleported = hit.object().read().unwrap().teleport(
+            hit.object_id(),
+            ray.position(hit.t()),
+            ray.direction(),
+        );
+        if let Some(teleported) = teleported {
+            self.follow(world, teleported, SegmentKind::Teleported, depth + 1);
+            return;
+        }
+
+        let comps = PrepComputations::new(hit, ray, &intersections);
+        let material = comps
+            .object()
+            .read()
+            .unwrap()
+            .material(comps.object_id())
+            .unwrap_or_default();
+
+        if !eq_f64(material.reflective(), 0.0) {
+            let reflected = Ray::new(comps.over_point(), comps.reflect_v());
+            self.follow(world, reflected, SegmentKind::Reflected, depth + 1);
+        }
+        if !eq_f64(material.transparency(), 0.0) {
+            if let Some(direction) = comps.refract_v() {
+                let refracted = Ray::new(comps.under_point(), direction);
+                self.follow(world, refracted, SegmentKind::Refracted, depth + 1);
+            }
+        }
+    }
+
+    /**
+       The segments as OBJ polylines, one group per kind of ray.
+    */
+    pub fn to_obj(&self) -> String {
+        let mut obj = String::new();
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            let (from, to) = (segment.from, segment.to);
+            obj.push_str(&format!("g {}\n", segment.kind.name()));
+            obj.push_str(&format!("v {} {} {}\n", from.x(), from.y(), from.z()));
+            obj.push_str(&format!("v {} {} {}\n", to.x(), to.y(), to.z()));
+            obj.push_str(&format!("l {} {}\n", 2 * i + 1, 2 * i + 2));
+        }
+
+        obj
+    }
+
+    /**
+       The segments seen from above, projected onto the xz plane with +z
+       pointing up the image, scaled to fit a `size` pixel square.
+    */
+    pub fn to_svg(&self, size: f64) -> String {
+        let points = self.segments.iter().flat_map(|s| [s.from, s.to]);
+        let (min_x, max_x, min_z, max_z) = points.fold(
+            (
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |(min_x, max_x, min_z, max_z), p| {
+                (
+                    min_x.min(p.x()),
+                    max_x.max(p.x()),
+                    min_z.min(p.z()),
+                    max_z.max(p.z()),
+                )
+            },
+        );
+        let margin = size * 0.05;
+        let extent = (max_x - min_x).max(max_z - min_z).max(f64::EPSILON);
+        let scale = (size - 2.0 * margin) / extent;
+        let project = |p: Tuple| {
+            (
+                margin + (p.x() - min_x) * scale,
+                size - margin - (p.z() - min_z) * scale,
+            )
+        };
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\">\n"
+        );
+        for segment in &self.segments {
+            let (x1, y1) = project(segment.from);
+            let (x2, y2) = project(segment.to);
+            svg.push_str(&format!(
+                "  <line x1=\"{x1:.3}\" y1=\"{y1:.3}\" x2=\"{x2:.3}\" y2=\"{y2:.3}\" stroke=\"{}\"{} />\n",
+                segment.kind.stroke(),
+                if segment.hit { "" } else { " stroke-dasharray=\"4\"" },
+            ));
+        }
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+
+    pub fn save_obj(&self, filename: &str) -> RayTraceResult<()> {
+        save(filename, "obj", &self.to_obj())
+    }
+
+    pub fn save_svg(&self, filename: &str, size: f64) -> RayTraceResult<()> {
+        save(filename, "svg", &self.to_svg(size))
+    }
+}
+
+fn save(filename: &str, extension: &str, contents: &str) -> RayTraceResult<()> {
+    let mut filename = filename.to_owned();
+
+    if !filename.ends_with(&format!(".{extension}")) {
+        filename = format!("{filename}.{extension}");
+    }
+
+    let mut file = File::create(filename)?;
+    file.write_all(contents.as_bytes())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        shape::{material::Material, sphere::Sphere},
+        testing::default_world_with_floor,
+    };
+
+    use super::*;
+
+    #[test]
+    fn a_ray_that_misses_is_one_open_segment() {
+        let w = World::new();
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        let v = RayVisualizer::trace(&w, r);
+
+        assert_eq!(1, v.segments().len());
+        assert!(!v.segments()[0].hit());
+        assert_eq!(Tuple::point(0.0, 0.0, 5.0), v.segments()[0].to());
+    }
+
+    #[test]
+    fn a_reflective_surface_spawns_a_reflected_segment() {
+        let (w, _) = default_world_with_floor(Material::new().with_reflective(0.5));
+        let r = Ray::new(
+            Tuple::point(0.0, 0.0, -3.0),
+            Tuple::vector(0.0, -(2f64.sqrt()) / 2.0, 2f64.sqrt() / 2.0),
+        );
+
+        let v = RayVisualizer::trace(&w, r);
+        let segments = v.segments();
+
+        assert_eq!(SegmentKind::Primary, segments[0].kind());
+        assert_eq!(Tuple::point(0.0, -1.0, -2.0), segments[0].to());
+        assert_eq!(SegmentKind::Reflected, segments[1].kind());
+        assert_eq!(1, segments[1].depth());
+        assert_eq!(segments[0].to(), segments[1].from());
+    }
+
+    #[test]
+    fn a_glass_sphere_spawns_refracted_segments() {
+        let mut w = World::new();
+        w.add_shape(Sphere::glassy().into());
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        let v = RayVisualizer::trace(&w, r);
+        let kinds = v.segments().iter().map(|s| s.kind()).collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                SegmentKind::Primary,
+                SegmentKind::Refracted,
+                SegmentKind::Refracted
+            ],
+            kinds
+        );
+        assert!(!v.segments()[2].hit());
+    }
+
+    #[test]
+    fn exporting_segments_as_obj_polylines() {
+        let w = World::new();
+        let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(1.0, 0.0, 0.0));
+
+        let obj = RayVisualizer::trace(&w, r).to_obj();
+
+        assert_eq!("g primary\nv 0 0 0\nv 10 0 0\nl 1 2\n", obj);
+    }
+
+    #[test]
+    fn exporting_segments_as_a_top_down_svg() {
+        let w = World::new();
+        let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        let svg = RayVisualizer::trace(&w, r).to_svg(100.0);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(
+            "<line x1=\"5.000\" y1=\"95.000\" x2=\"5.000\" y2=\"5.000\" stroke=\"black\" stroke-dasharray=\"4\" />"
+        ));
+    }
+}
@@ -1,19 +1,106 @@
-use std::vec;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    vec,
+};
+
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    rngs::StdRng,
+    seq::index,
+    Rng, SeedableRng,
+};
+
+use uuid::Uuid;
 
 use crate::{
     color::{Color, Colors},
-    intersection::{prepcomputation::PrepComputations, ray::Ray, IntersectionHeap},
+    error::{RayTraceError, RayTraceResult},
+    intersection::{
+        prepcomputation::PrepComputations,
+        ray::{Ray, RayKind},
+        scratch::Scratch,
+        IntersectionHeap,
+    },
+    irradiance::IrradianceProbes,
+    light::Light,
+    light_link::LightLink,
     point_light::PointLight,
-    shape::{material::Material, sphere::Sphere, Shape, ShapeContainer},
+    sampling::with_rng,
+    shape::{
+        bounded_box::BoundedBox,
+        group::{Group, GroupContainer},
+        material::Material,
+        plane::Plane,
+        sphere::Sphere,
+        Shape, ShapeContainer,
+    },
+    sky::Sky,
+    stats::SceneStats,
     transformation::Transformation,
     tuple::Tuple,
-    util::eq_f64,
+    util::{eq_f64, PrecisionPolicy},
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LightSampling {
+    #[default]
+    All,
+    Uniform(usize),
+    PowerWeighted(usize),
+}
+
+/**
+   Where a ray first meets the world. The normal faces back along the ray and
+   the distance is measured in world units, whatever the ray direction's
+   length.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearestHit {
+    point: Tuple,
+    normal: Tuple,
+    shape_id: Uuid,
+    distance: f64,
+}
+
+impl NearestHit {
+    pub fn point(&self) -> Tuple {
+        self.point
+    }
+
+    pub fn normal(&self) -> Tuple {
+        self.normal
+    }
+
+    /**
+       The id of the primitive that was hit, rather than the group holding it.
+    */
+    pub fn shape_id(&self) -> Uuid {
+        self.shape_id
+    }
+
+    pub fn distance(&self) -> f64 {
+        self.distance
+    }
+}
+
 #[derive(Debug)]
 pub struct World {
     shapes: Vec<ShapeContainer>,
-    lights: Vec<PointLight>,
+    lights: Vec<Light>,
+    light_sampling: LightSampling,
+    max_depth: usize,
+    roulette_threshold: f64,
+    precision: PrecisionPolicy,
+    shadow_cache: Vec<AtomicUsize>,
+    light_links: Vec<LightLink>,
+    names: HashMap<String, Uuid>,
+    sky: Option<Arc<dyn Sky>>,
+    sun_light: Option<usize>,
+    irradiance_probes: Option<Arc<IrradianceProbes>>,
 }
 
 impl World {
@@ -21,7 +108,126 @@ impl World {
         Self {
             shapes: vec![],
             lights: vec![],
+            light_sampling: LightSampling::default(),
+            max_depth: 5,
+            roulette_threshold: 0.0,
+            precision: PrecisionPolicy::default(),
+            shadow_cache: vec![],
+            light_links: vec![],
+            names: HashMap::new(),
+            sky: None,
+            sun_light: None,
+            irradiance_probes: None,
+        }
+    }
+
+    /**
+       A floor scattered with `n` small spheres around three large ones, in
+       the spirit of the "Ray Tracing in One Weekend" cover. Most of the small
+       spheres are matte, some are mirrors and a few are glass, and none of
+       them overlap. The spheres are gathered into a bounding volume
+       hierarchy, so large `n` stays quick to render. The same `seed` always
+       gives the same scene. Looks best from around (13, 2, 3) towards the
+       origin.
+    */
+    pub fn random_spheres(n: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut world = Self::new();
+        world.add_light(PointLight::new(
+            Tuple::point(-10.0, 20.0, 10.0),
+            Colors::White.into(),
+        ));
+
+        let mut floor = Plane::new();
+        floor.set_material(
+            Material::new()
+                .with_color(Color::new(0.5, 0.5, 0.5))
+                .with_specular(0.0),
+        );
+        world.add_shape(floor.into());
+
+        let spheres = GroupContainer::from(Group::new());
+        let mut large = [
+            (Tuple::point(0.0, 1.0, 0.0), Sphere::glassy()),
+            (Tuple::point(-4.0, 1.0, 0.0), Sphere::new()),
+            (Tuple::point(4.0, 1.0, 0.0), Sphere::new()),
+        ];
+        large[1].1.set_material(
+            Material::new()
+                .with_color(Color::new(0.4, 0.2, 0.1))
+                .with_specular(0.0),
+        );
+        large[2].1.set_material(
+            Material::new()
+                .with_color(Color::new(0.7, 0.6, 0.5))
+                .with_diffuse(0.1)
+                .with_reflective(0.9),
+        );
+        let centers = large.each_ref().map(|(center, _)| *center);
+        for (center, mut sphere) in large {
+            sphere.set_transformation(Transformation::identity().translation(
+                center.x(),
+                center.y(),
+                center.z(),
+            ));
+            spheres.add_child(sphere.into());
+        }
+
+        // Each small sphere keeps inside its own grid cell, so they never
+        // touch. Cells too close to the large spheres are skipped, so widen
+        // the grid until every small sphere has somewhere to go.
+        let mut cells = (n as f64).sqrt().ceil() as usize;
+        let spots = loop {
+            let spacing = 22.0 / cells.max(1) as f64;
+            let radius = (spacing * 0.15).min(0.2);
+            let spots = (0..cells * cells)
+                .map(|i| {
+                    let x = spacing * ((i % cells) as f64 + 0.15 + 0.7 * rng.gen::<f64>());
+                    let z = spacing * ((i / cells) as f64 + 0.15 + 0.7 * rng.gen::<f64>());
+                    Tuple::point(x - 11.0, radius, z - 11.0)
+                })
+                .filter(|&spot| {
+                    centers
+                        .iter()
+                        .all(|&center| (spot - center).magnitude() >= 1.0 + radius)
+                })
+                .collect::<Vec<_>>();
+            if spots.len() >= n {
+                break spots;
+            }
+            cells += 1;
+        };
+
+        for spot in spots.into_iter().take(n) {
+            let choice = rng.gen::<f64>();
+            let color = Color::new(rng.gen(), rng.gen(), rng.gen());
+            let mut sphere = if choice < 0.95 {
+                Sphere::new()
+            } else {
+                Sphere::glassy()
+            };
+            if choice < 0.8 {
+                sphere.set_material(Material::new().with_color(color * color).with_specular(0.0));
+            } else if choice < 0.95 {
+                sphere.set_material(
+                    Material::new()
+                        .with_color(color * 0.5 + Color::new(0.5, 0.5, 0.5))
+                        .with_diffuse(0.2)
+                        .with_reflective(0.5 + 0.5 * rng.gen::<f64>()),
+                );
+            }
+            let radius = spot.y();
+            sphere.set_transformation(
+                Transformation::identity()
+                    .scale(radius, radius, radius)
+                    .translation(spot.x(), spot.y(), spot.z()),
+            );
+            spheres.add_child(sphere.into());
         }
+        spheres.divide(4);
+        world.add_shape(spheres.into());
+
+        world
     }
 
     pub fn shapes(&self) -> &Vec<ShapeContainer> {
@@ -30,174 +236,678 @@ impl World {
 
     pub fn add_shape(&mut self, shape: ShapeContainer) {
         self.shapes.push(shape);
+        self.refresh_sun_light();
     }
 
     pub fn shapes_mut(&mut self) -> &mut Vec<ShapeContainer> {
         &mut self.shapes
     }
 
-    pub fn lights(&self) -> &Vec<PointLight> {
+    /**
+       Add a shape that can later be looked up by name, for example to link
+       lights to it.
+    */
+    pub fn add_named_shape(&mut self, name: &str, shape: ShapeContainer) {
+        self.names.insert(name.to_string(), shape.id());
+        self.add_shape(shape);
+    }
+
+    pub fn shape_id(&self, name: &str) -> RayTraceResult<Uuid> {
+        self.names
+            .get(name)
+            .copied()
+            .ok_or_else(|| RayTraceError::UnknownShape(name.to_string()))
+    }
+
+    pub fn lights(&self) -> &Vec<Light> {
         &self.lights
     }
 
-    pub fn add_light(&mut self, point_light: PointLight) {
-        self.lights.push(point_light);
+    pub fn add_light(&mut self, light: impl Into<Light>) {
+        self.lights.push(light.into());
+        self.shadow_cache.push(AtomicUsize::new(NO_OCCLUDER));
+        self.light_links.push(LightLink::All);
+    }
+
+    /**
+       Restrict the shapes lit by the light at `index`, in the order lights
+       were added.
+    */
+    pub fn set_light_link(&mut self, index: usize, light_link: LightLink) {
+        if let Some(link) = self.light_links.get_mut(index) {
+            *link = light_link;
+        }
+    }
+
+    pub fn light_link(&self, index: usize) -> Option<&LightLink> {
+        self.light_links.get(index)
+    }
+
+    /**
+       Light only the named shapes with the light at `index`, or everything
+       but them if `include` is false.
+    */
+    pub fn link_light_by_name(
+        &mut self,
+        index: usize,
+        names: &[&str],
+        include: bool,
+    ) -> RayTraceResult<()> {
+        let ids = names
+            .iter()
+            .map(|name| self.shape_id(name))
+            .collect::<RayTraceResult<Vec<_>>>()?;
+
+        self.set_light_link(
+            index,
+            if include {
+                LightLink::Include(ids)
+            } else {
+                LightLink::Exclude(ids)
+            },
+        );
+        Ok(())
+    }
+
+    fn lights_hit(&self, index: usize, comps: &PrepComputations) -> bool {
+        self.light_links
+            .get(index)
+            .is_none_or(|link| link.affects(&[comps.object().id(), comps.object_id()]))
+    }
+
+    /**
+       Cache every shape's transformation to world space so shading does not
+       have to walk up through groups for each ray. Cameras call this before
+       rendering; call it again after changing the transformation of a shape
+       inside a group.
+    */
+    pub fn prepare(&self) {
+        for shape in &self.shapes {
+            shape
+                .write()
+                .unwrap()
+                .prepare(&Transformation::identity(), self.precision);
+        }
+    }
+
+    pub fn stats(&self) -> SceneStats {
+        SceneStats::new(&self.shapes, &self.lights)
+    }
+
+    /**
+       What rays that miss every shape see, in place of black.
+    */
+    pub fn set_sky<T: Sky + 'static>(&mut self, sky: T) {
+        self.sky = Some(Arc::new(sky));
+        self.refresh_sun_light();
+    }
+
+    /**
+       Light the world from the sky's sun, with a light placed far beyond
+       the shapes in the direction the sun is drawn in. The light follows
+       the sun when the sky is replaced and moves back as shapes are added,
+       so the two can't drift apart.
+    */
+    pub fn add_sun_light(&mut self) -> RayTraceResult<()> {
+        if self.sun_light.is_some() {
+            return Ok(());
+        }
+        let light = self.light_from_sun().ok_or_else(|| {
+            RayTraceError::InvalidFormat("the sky has no sun to light the world".to_string())
+        })?;
+        self.add_light(light);
+        self.sun_light = Some(self.lights.len() - 1);
+
+        Ok(())
+    }
+
+    /**
+       The index of the light added by `add_sun_light`, if any.
+    */
+    pub fn sun_light(&self) -> Option<usize> {
+        self.sun_light
+    }
+
+    /**
+       A sky without a sun leaves the sun light in place but dark, so the
+       indexes of the other lights don't change.
+    */
+    fn refresh_sun_light(&mut self) {
+        if let Some(index) = self.sun_light {
+            let light = self
+                .light_from_sun()
+                .unwrap_or_else(|| PointLight::new(Tuple::origin(), Colors::Black.into()));
+            self.lights[index] = light.into();
+            self.shadow_cache[index] = AtomicUsize::new(NO_OCCLUDER);
+        }
+    }
+
+    /**
+       Planes and other unbounded shapes are left out of the bounds the
+       light is placed beyond.
+    */
+    fn light_from_sun(&self) -> Option<PointLight> {
+        let sun = self.sky.as_ref()?.sun()?;
+        let mut bounds = BoundedBox::empty();
+        for shape in &self.shapes {
+            let shape_bounds = shape.read().unwrap().parent_space_bounds();
+            if shape_bounds.is_finite() {
+                bounds.add_box(shape_bounds);
+            }
+        }
+
+        Some(sun.light(&bounds))
+    }
+
+    pub fn sky(&self) -> Option<&dyn Sky> {
+        self.sky.as_deref()
+    }
+
+    /**
+       Light baked into `probes` is added to every diffuse surface on top of
+       its direct lighting, see `IrradianceProbes`.
+    */
+    pub fn set_irradiance_probes(&mut self, probes: IrradianceProbes) {
+        self.irradiance_probes = Some(Arc::new(probes));
+    }
+
+    pub fn irradiance_probes(&self) -> Option<&IrradianceProbes> {
+        self.irradiance_probes.as_deref()
+    }
+
+    pub fn light_sampling(&self) -> LightSampling {
+        self.light_sampling
+    }
+
+    pub fn set_light_sampling(&mut self, light_sampling: LightSampling) {
+        self.light_sampling = light_sampling;
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn precision(&self) -> PrecisionPolicy {
+        self.precision
+    }
+
+    /**
+       How close numbers must be to count as equal when rendering: which of
+       two nearly equal hits is nearer, and whether a shape's transformation
+       can be inverted when the world is prepared. Very large or very small
+       scenes want a relative policy.
+    */
+    pub fn set_precision(&mut self, precision: PrecisionPolicy) {
+        self.precision = precision;
+    }
+
+    pub fn roulette_threshold(&self) -> f64 {
+        self.roulette_threshold
+    }
+
+    /**
+       Reflected and refracted rays whose accumulated attenuation falls below
+       the threshold are terminated at random instead of always being traced
+       to the maximum depth. A threshold of zero disables this.
+    */
+    pub fn set_roulette_threshold(&mut self, roulette_threshold: f64) {
+        self.roulette_threshold = roulette_threshold;
+    }
+
+    /**
+       Pick the lights used to shade a single hit along with the weight each
+       contribution is scaled by, so that the expected sum over the sampled
+       lights matches shading every light.
+    */
+    pub fn sample_lights(&self) -> Vec<(Light, f64)> {
+        self.sample_light_indices()
+            .into_iter()
+            .map(|(i, weight)| (self.lights[i], weight))
+            .collect()
+    }
+
+    /**
+       Like `sample_lights`, but gives the index of each light so that
+       identical lights keep their own links and shadow caches.
+    */
+    fn sample_light_indices(&self) -> Vec<(usize, f64)> {
+        let count = self.lights.len();
+
+        match self.light_sampling {
+            LightSampling::Uniform(n) if n > 0 && n < count => {
+                let weight = count as f64 / n as f64;
+                with_rng(|rng| index::sample(rng, count, n))
+                    .into_iter()
+                    .map(|i| (i, weight))
+                    .collect()
+            }
+            LightSampling::PowerWeighted(n) if n > 0 && count > 0 => {
+                let powers = self.lights.iter().map(light_power).collect::<Vec<_>>();
+                let total = powers.iter().sum::<f64>();
+                let Ok(distribution) = WeightedIndex::new(&powers) else {
+                    return vec![];
+                };
+                with_rng(|rng| {
+                    (0..n)
+                        .map(|_| distribution.sample(rng))
+                        .map(|i| (i, total / (n as f64 * powers[i])))
+                        .collect()
+                })
+            }
+            _ => (0..count).map(|i| (i, 1.0)).collect(),
+        }
     }
 
-    pub fn intersects(&self, r: Ray) -> IntersectionHeap {
+    pub fn intersects(&self, r: &Ray) -> IntersectionHeap {
         let mut heap = IntersectionHeap::new();
+        self.intersects_into(r, &mut heap);
+        heap
+    }
+
+    /**
+       Like `intersects`, but fills `heap` after clearing it so a caller
+       tracing many rays can reuse one allocation.
+    */
+    pub fn intersects_into(&self, r: &Ray, heap: &mut IntersectionHeap) {
+        heap.clear();
 
         for s in self.shapes() {
-            let intersections = r.intersections(s.clone());
+            let intersections = r.intersections(s);
             for i in intersections {
                 heap.push(i);
             }
         }
+    }
 
+    pub fn intersects_for(&self, r: &Ray, kind: RayKind) -> IntersectionHeap {
+        let mut heap = IntersectionHeap::new();
+        self.intersects_for_into(r, kind, &mut heap);
         heap
     }
 
+    /**
+       Like `intersects_for`, but fills a reused `heap` the way
+       `intersects_into` does.
+    */
+    pub fn intersects_for_into(&self, r: &Ray, kind: RayKind, heap: &mut IntersectionHeap) {
+        self.intersects_into(r, heap);
+        heap.retain(|i| {
+            i.object()
+                .read()
+                .unwrap()
+                .visibility(i.object_id())
+                .unwrap_or_default()
+                .is_visible_to(kind)
+        });
+    }
+
+    /**
+       The closest surface along `r`, without shading it, for collision and
+       picking queries.
+    */
+    pub fn nearest_hit(&self, r: &Ray) -> Option<NearestHit> {
+        let intersections = self.intersects(r);
+        let hit = intersections.hit_with(self.precision)?;
+        let distance = hit.t() * r.direction().magnitude();
+        let comps = PrepComputations::new(hit, *r, &intersections);
+
+        Some(NearestHit {
+            point: comps.point(),
+            normal: comps.normal_v(),
+            shape_id: comps.object_id(),
+            distance,
+        })
+    }
+
     pub fn shade_hit(&self, comps: &PrepComputations) -> Color {
-        self.shade_hit_recursive(comps, 5)
+        self.shade_hit_recursive(comps, self.max_depth)
     }
 
     pub fn shade_hit_recursive(&self, comps: &PrepComputations, remaining: usize) -> Color {
-        let shadowed = self.is_shadowed(comps.over_point());
-        let mut color = Colors::Black.into();
+        self.shade(comps, remaining, 1.0, &mut Scratch::new())
+    }
 
-        for light in self.lights() {
-            let surface = comps
-                .object()
-                .read()
-                .unwrap()
-                .material(comps.object_id())
-                .unwrap_or_default()
-                .lighting(
-                    comps.object().clone(),
-                    *light,
-                    comps.over_point(),
-                    comps.eye_v(),
-                    comps.normal_v(),
-                    shadowed,
-                );
+    fn shade(
+        &self,
+        comps: &PrepComputations,
+        remaining: usize,
+        throughput: f64,
+        scratch: &mut Scratch,
+    ) -> Color {
+        let material = comps
+            .object()
+            .read()
+            .unwrap()
+            .material(comps.object_id())
+            .unwrap_or_default();
+        let material = match comps.vertex_color() {
+            Some(color) => material.with_color(color),
+            None => material,
+        };
+        let surface =
+            self.direct_lighting(comps, &material) + self.indirect_lighting(comps, &material);
+
+        let reflected = self.reflected_color(comps, remaining, throughput, scratch);
+        let refracted = self.refracted_color(comps, remaining, throughput, scratch);
+
+        if material.reflective() > 0.0 && material.transparency() > 0.0 {
+            let reflectance = comps.schlick();
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
+    }
 
-            let reflected = self.reflected_color(comps, remaining);
-            let refracted = self.refracted_color(comps, remaining);
+    /**
+       The light reaching the hit in `comps` straight from the world's
+       lights, shaded with `material`, including its ambient term.
+    */
+    pub(crate) fn direct_lighting(&self, comps: &PrepComputations, material: &Material) -> Color {
+        let mut surface: Color = Colors::Black.into();
 
-            let material = comps
-                .object()
-                .read()
-                .unwrap()
-                .material(comps.object_id())
-                .unwrap();
-            if material.reflective() > 0.0 && material.transparency() > 0.0 {
-                let reflectance = comps.schlick();
-                color += surface + reflected * reflectance + refracted * (1.0 - reflectance);
-            } else {
-                color += surface + reflected + refracted
+        for (index, weight) in self.sample_light_indices() {
+            if !self.lights_hit(index, comps) {
+                continue;
             }
-
+            let light = self.lights[index];
+            surface += material.lighting_with_samples(
+                &*comps.object().read().unwrap(),
+                light.intensity(),
+                &light.points(),
+                comps.over_point(),
+                comps.eye_v(),
+                comps.normal_v(),
+                self.light_intensity_at(index, comps.over_point()),
+                if eq_f64(material.subsurface(), 0.0) {
+                    0.0
+                } else {
+                    self.light_transmission_at(index, comps.over_point(), comps.object().id())
+                },
+            ) * weight;
         }
 
-        color
+        surface
+    }
+
+    /**
+       The light baked into the world's irradiance probes that a diffuse
+       surface at the hit in `comps` passes on, or black without probes.
+    */
+    fn indirect_lighting(&self, comps: &PrepComputations, material: &Material) -> Color {
+        let Some(probes) = &self.irradiance_probes else {
+            return Colors::Black.into();
+        };
+        let color = material.pattern().color_at_surface(
+            &*comps.object().read().unwrap(),
+            comps.over_point(),
+            comps.normal_v(),
+        );
+
+        color * probes.irradiance_at(comps.over_point(), comps.normal_v()) * material.diffuse()
     }
 
     pub fn color_at(&self, ray: Ray) -> Color {
-        self.color_at_recursive(ray, 5)
+        self.color_at_with(ray, &mut Scratch::new())
+    }
+
+    /**
+       Like `color_at`, but traces through `scratch` instead of allocating
+       new buffers, for callers shading many rays on one thread.
+    */
+    pub fn color_at_with(&self, ray: Ray, scratch: &mut Scratch) -> Color {
+        self.trace(ray, self.max_depth, RayKind::Camera, 1.0, scratch)
     }
 
     pub fn color_at_recursive(&self, ray: Ray, remaining: usize) -> Color {
-        let intersections = self.intersects(ray);
+        self.trace(ray, remaining, RayKind::Secondary, 1.0, &mut Scratch::new())
+    }
+
+    pub(crate) fn trace(
+        &self,
+        ray: Ray,
+        remaining: usize,
+        kind: RayKind,
+        throughput: f64,
+        scratch: &mut Scratch,
+    ) -> Color {
+        self.intersects_for_into(&ray, kind, &mut scratch.heap);
+
+        if let Some(hit) = scratch.heap.hit_with(self.precision) {
+            let teleported = hit.object().read().unwrap().teleport(
+                hit.object_id(),
+                ray.position(hit.t()),
+                ray.direction(),
+            );
+            if let Some(teleported) = teleported {
+                return if remaining == 0 {
+                    Colors::Black.into()
+                } else {
+                    self.trace(teleported, remaining - 1, kind, throughput, scratch)
+                };
+            }
 
-        if let Some(hit) = intersections.hit() {
-            let comps = PrepComputations::new(hit, ray.clone(), &intersections);
-            self.shade_hit_recursive(&comps, remaining)
+            let clear = hit
+                .object()
+                .read()
+                .unwrap()
+                .material(hit.object_id())
+                .is_some_and(|material| material.is_clear());
+
+            let comps = PrepComputations::from_scratch(hit, ray, scratch);
+            if clear {
+                // Nothing to shade, so skip the lights and carry on from just
+                // past the surface.
+                let through = Ray::new(comps.under_point(), ray.direction());
+                return if remaining == 0 {
+                    Colors::Black.into()
+                } else {
+                    self.trace(through, remaining - 1, kind, throughput, scratch)
+                };
+            }
+            self.shade(&comps, remaining, throughput, scratch)
         } else {
-            Colors::Black.into()
+            self.sky
+                .as_ref()
+                .map_or(Colors::Black.into(), |sky| sky.color_at(ray.direction()))
         }
     }
 
     pub fn is_shadowed(&self, point: Tuple) -> bool {
-        for l in self.lights() {
-            let v = l.position() - point;
+        (0..self.lights.len()).any(|i| self.is_shadowed_from_light(i, point))
+    }
+
+    /**
+       Whether `point` is in shadow from `light`, which need not be one of
+       the world's lights. Nothing is cached; use `is_shadowed_from_light`
+       for the world's own lights.
+    */
+    pub fn is_shadowed_from(&self, light: impl Into<Light>, point: Tuple) -> bool {
+        self.shadowed(light.into().position(), point, None)
+    }
+
+    /**
+       Whether `point` is in shadow from the light at `index`, in the order
+       lights were added. The last top level shape found blocking each light
+       is remembered and tested first on the next query, which skips most of
+       the scene when a few large occluders cast most of the shadows.
+    */
+    pub fn is_shadowed_from_light(&self, index: usize, point: Tuple) -> bool {
+        self.lights.get(index).is_some_and(|light| {
+            self.shadowed(light.position(), point, self.shadow_cache.get(index))
+        })
+    }
+
+    fn shadowed(&self, position: Tuple, point: Tuple, cache: Option<&AtomicUsize>) -> bool {
+        let v = position - point;
+
+        let distance = v.magnitude();
+        let direction = v.normalize();
+
+        let r = Ray::new(point, direction);
 
-            let distance = v.magnitude();
-            let direction = v.normalize();
+        let cached = cache.map(|c| c.load(Ordering::Relaxed));
 
-            let r = Ray::new(point, direction);
+        if let Some(shape) = cached.and_then(|i| self.shapes.get(i)) {
+            if occludes(shape, &r, distance, None) {
+                return true;
+            }
+        }
 
-            if let Some(h) = self.intersects(r).hit() {
-                if h.t() < distance {
-                    return true;
+        for (i, shape) in self.shapes.iter().enumerate() {
+            if Some(i) != cached && occludes(shape, &r, distance, None) {
+                if let Some(cache) = cache {
+                    cache.store(i, Ordering::Relaxed);
                 }
+                return true;
             }
         }
+
         false
     }
 
-    fn reflected_color(&self, comps: &PrepComputations, remaining: usize) -> Color {
-        if remaining <= 0
-            || eq_f64(
-                comps
-                    .object()
-                    .read()
-                    .unwrap()
-                    .material(comps.object_id())
-                    .unwrap()
-                    .reflective(),
-                0.0,
-            )
-        {
-            return Colors::Black.into();
+    /**
+       Like `Light::intensity_at` for the light at `index`, but shadows are
+       tested with that light's cached occluder.
+    */
+    fn light_intensity_at(&self, index: usize, point: Tuple) -> f64 {
+        let light = self.lights[index];
+        match light {
+            Light::Area(_) => light.intensity_at(point, self),
+            _ if self.is_shadowed_from_light(index, point) => 0.0,
+            _ => light.attenuation_at(point),
         }
+    }
 
-        let reflect_ray = Ray::new(comps.over_point(), comps.reflect_v());
-        let color = self.color_at_recursive(reflect_ray, remaining - 1);
+    /**
+       Whether anything that casts shadows lies between `point` and `target`.
+       Unlike `is_shadowed_from_light` nothing is cached, so it suits targets that
+       change with every query such as samples across an area light.
+    */
+    pub fn is_occluded(&self, point: Tuple, target: Tuple) -> bool {
+        let v = target - point;
+        let r = Ray::new(point, v.normalize());
 
-        color
-            * comps
-                .object()
-                .read()
-                .unwrap()
-                .material(comps.object_id())
-                .unwrap()
-                .reflective()
-    }
-
-    fn refracted_color(&self, comps: &PrepComputations, remaining: usize) -> Color {
-        if remaining == 0
-            || eq_f64(
-                comps
-                    .object()
-                    .read()
-                    .unwrap()
-                    .material(comps.object_id())
-                    .unwrap()
-                    .transparency(),
-                0.0,
-            )
-        {
+        self.shapes
+            .iter()
+            .any(|shape| occludes(shape, &r, v.magnitude(), None))
+    }
+
+    /**
+       How much of the light at `index` reaches `point` through `object`
+       alone: its attenuation, times the fraction of its points that no
+       other shape hides. Subsurface light passes through the object it
+       scatters in, but not through anything else.
+    */
+    fn light_transmission_at(&self, index: usize, point: Tuple, object: Uuid) -> f64 {
+        let light = self.lights[index];
+        let points = light.points();
+        let clear = points
+            .iter()
+            .filter(|&&target| {
+                let v = target - point;
+                let r = Ray::new(point, v.normalize());
+                !self
+                    .shapes
+                    .iter()
+                    .any(|shape| occludes(shape, &r, v.magnitude(), Some(object)))
+            })
+            .count();
+
+        light.attenuation_at(point) * clear as f64 / points.len() as f64
+    }
+
+    /**
+       Russian roulette: once a path's throughput drops below the threshold it
+       survives with probability `throughput / threshold`, and survivors are
+       boosted by the inverse of that so the expected color is unchanged.
+       Returns the boost, or `None` if the path is terminated.
+    */
+    fn roulette(&self, throughput: f64) -> Option<f64> {
+        if throughput >= self.roulette_threshold {
+            return Some(1.0);
+        }
+
+        let survival = throughput / self.roulette_threshold;
+        if with_rng(|rng| rng.gen::<f64>()) < survival {
+            Some(1.0 / survival)
+        } else {
+            None
+        }
+    }
+
+    fn reflected_color(
+        &self,
+        comps: &PrepComputations,
+        remaining: usize,
+        throughput: f64,
+        scratch: &mut Scratch,
+    ) -> Color {
+        let reflective = comps
+            .object()
+            .read()
+            .unwrap()
+            .material(comps.object_id())
+            .unwrap()
+            .reflective();
+        if remaining == 0 || eq_f64(reflective, 0.0) {
             return Colors::Black.into();
         }
-        let n_ratio = comps.n1() / comps.n2();
-        let cos_i = comps.eye_v() * comps.normal_v();
-        let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+        let throughput = throughput * reflective;
+        let Some(boost) = self.roulette(throughput) else {
+            return Colors::Black.into();
+        };
+
+        let reflect_ray = Ray::new(comps.reflect_origin(), comps.reflect_v());
+        let color = self.trace(
+            reflect_ray,
+            remaining - 1,
+            RayKind::Secondary,
+            throughput * boost,
+            scratch,
+        );
 
-        if sin2_t > 1.0 {
+        color * reflective * boost
+    }
+
+    fn refracted_color(
+        &self,
+        comps: &PrepComputations,
+        remaining: usize,
+        throughput: f64,
+        scratch: &mut Scratch,
+    ) -> Color {
+        let transparency = comps
+            .object()
+            .read()
+            .unwrap()
+            .material(comps.object_id())
+            .unwrap()
+            .transparency();
+        if remaining == 0 || eq_f64(transparency, 0.0) {
             return Colors::Black.into();
         }
+        let Some(direction) = comps.refract_v() else {
+            return Colors::Black.into();
+        };
+        let throughput = throughput * transparency;
+        let Some(boost) = self.roulette(throughput) else {
+            return Colors::Black.into();
+        };
+
+        let refract_ray = Ray::new(comps.refract_origin(direction), direction);
+        let color = self.trace(
+            refract_ray,
+            remaining - 1,
+            RayKind::Secondary,
+            throughput * boost,
+            scratch,
+        );
 
-        let cos_t = (1.0 - sin2_t).sqrt();
-        let direction = comps.normal_v() * (n_ratio * cos_i - cos_t) - comps.eye_v() * n_ratio;
-        let refract_ray = Ray::new(comps.under_point(), direction);
-        self.color_at_recursive(refract_ray, remaining - 1)
-            * comps
-                .object()
-                .read()
-                .unwrap()
-                .material(comps.object_id())
-                .unwrap()
-                .transparency()
+        color * transparency * boost
     }
 }
 
@@ -218,22 +928,183 @@ impl Default for World {
         let light = PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Colors::White.into());
         Self {
             shapes: vec![s1.into(), s2.into()],
-            lights: vec![light],
+            lights: vec![light.into()],
+            light_sampling: LightSampling::default(),
+            max_depth: 5,
+            roulette_threshold: 0.0,
+            precision: PrecisionPolicy::default(),
+            shadow_cache: vec![AtomicUsize::new(NO_OCCLUDER)],
+            light_links: vec![LightLink::All],
+            names: HashMap::new(),
+            sky: None,
+            sun_light: None,
+            irradiance_probes: None,
         }
     }
 }
 
+const NO_OCCLUDER: usize = usize::MAX;
+
+/**
+   Whether `shape` casts a shadow along `ray` before `distance`, leaving out
+   the shape with the id `ignore`.
+*/
+fn occludes(shape: &ShapeContainer, ray: &Ray, distance: f64, ignore: Option<Uuid>) -> bool {
+    let shape = shape.read().unwrap();
+
+    shape.intersects(ray).into_iter().any(|i| {
+        i.t().is_sign_positive()
+            && i.t() < distance
+            && Some(i.object()) != ignore
+            && shape
+                .visibility(i.object())
+                .unwrap_or_default()
+                .is_visible_to(RayKind::Shadow)
+    })
+}
+
+fn light_power(light: &Light) -> f64 {
+    let intensity = light.intensity();
+    (intensity.red() + intensity.green() + intensity.blue()) / 3.0
+}
+
 #[cfg(test)]
 mod tests {
+    use std::f64::consts::PI;
 
     use crate::{
         intersection::ShapeIntersection,
         intersections,
-        shape::{material::pattern::TestPattern, plane::Plane},
+        point_light::Falloff,
+        shape::{
+            group::Descendants, material::pattern::TestPattern, plane::Plane, portal::Portal,
+            triangle::Triangle, visibility::Visibility,
+        },
+        sky::{GradientSky, Sun},
+        spot_light::SpotLight,
+        util::EPSILON,
     };
 
     use super::*;
 
+    #[test]
+    fn the_nearest_hit_reports_point_normal_shape_and_distance() {
+        let w = World::default();
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 2.0));
+
+        let hit = w.nearest_hit(&r).unwrap();
+
+        assert_eq!(Tuple::point(0.0, 0.0, -1.0), hit.point());
+        assert_eq!(Tuple::vector(0.0, 0.0, -1.0), hit.normal());
+        assert_eq!(w.shapes()[0].id(), hit.shape_id());
+        assert!(eq_f64(4.0, hit.distance()));
+        assert!(w
+            .nearest_hit(&Ray::new(r.origin(), Tuple::vector(0.0, 1.0, 0.0)))
+            .is_none());
+    }
+
+    #[test]
+    fn a_relative_precision_prepares_and_hits_tiny_shapes() {
+        let mut w = World::new();
+        let mut s = Sphere::new();
+        s.set_transformation(Transformation::identity().scale(0.01, 0.01, 0.01));
+        w.add_shape(s.into());
+        w.set_precision(PrecisionPolicy::Relative(1e-9));
+        w.prepare();
+
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let hit = w.nearest_hit(&r).unwrap();
+
+        assert!(eq_f64(4.99, hit.distance()));
+        assert_eq!(Tuple::vector(0.0, 0.0, -1.0), hit.normal());
+    }
+
+    #[test]
+    fn random_spheres_are_repeatable_for_a_seed() {
+        let w = World::random_spheres(50, 7);
+
+        assert_eq!(3 + 50, w.stats().count("Sphere"));
+        assert_eq!(1, w.lights().len());
+        assert_eq!(spheres(&w), spheres(&World::random_spheres(50, 7)));
+        assert_ne!(spheres(&w), spheres(&World::random_spheres(50, 8)));
+    }
+
+    #[test]
+    fn random_spheres_do_not_overlap_and_sit_in_a_hierarchy() {
+        let w = World::random_spheres(600, 3);
+        let spheres = spheres(&w);
+
+        assert!(w.stats().bvh_nodes() > 1);
+        for (i, (a, a_radius)) in spheres.iter().enumerate() {
+            for (b, b_radius) in &spheres[i + 1..] {
+                assert!((*a - *b).magnitude() >= a_radius + b_radius - EPSILON);
+            }
+        }
+    }
+
+    fn spheres(w: &World) -> Vec<(Tuple, f64)> {
+        w.shapes()
+            .iter()
+            .flat_map(|shape| Descendants::new(shape.clone()))
+            .map(|(shape, _)| shape)
+            .filter(|shape| shape.read().unwrap().type_name() == "Sphere")
+            .map(|shape| {
+                let shape = shape.read().unwrap();
+                let center = shape.object_to_world(Tuple::origin());
+                let edge = shape.object_to_world(Tuple::point(1.0, 0.0, 0.0));
+                (center, (edge - center).magnitude())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rays_that_miss_see_the_sky() {
+        let mut w = World::default();
+        let sun = Sun::new(Tuple::vector(0.0, 1.0, 0.0), Color::new(2.0, 2.0, 2.0));
+        w.set_sky(GradientSky::new(Colors::Blue.into(), Colors::White.into()).with_sun(sun));
+
+        let up = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 1.0, 0.0));
+        let away = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, -1.0));
+
+        assert_eq!(Color::new(2.0, 2.0, 2.0), w.color_at(up));
+        assert_eq!(Color::from(Colors::White), w.color_at(away));
+    }
+
+    #[test]
+    fn the_sun_light_follows_the_sun_in_the_sky() {
+        let sky = |direction: Tuple| {
+            GradientSky::new(Colors::Blue.into(), Colors::White.into())
+                .with_sun(Sun::new(direction, Colors::White.into()))
+        };
+        let mut w = World::new();
+        assert!(w.add_sun_light().is_err());
+        w.add_shape(Plane::new().into());
+        let mut s = Sphere::new();
+        s.set_transformation(Transformation::identity().translation(20.0, 1.0, 0.0));
+        w.add_shape(s.into());
+        w.set_sky(sky(Tuple::vector(0.0, 1.0, 0.0)));
+
+        w.add_sun_light().unwrap();
+        let index = w.sun_light().unwrap();
+        let overhead = w.lights()[index].position();
+
+        assert_eq!(20.0, overhead.x());
+        assert!(overhead.y() > 1000.0);
+        assert!(w.is_shadowed_from_light(index, Tuple::point(20.0, 0.0, 0.0)));
+        assert!(!w.is_shadowed_from_light(index, Tuple::point(22.0, 0.0, 0.0)));
+
+        w.set_sky(sky(Tuple::vector(1.0, 0.0, 0.0)));
+        let beside = w.lights()[index].position();
+
+        assert_eq!(1, w.lights().len());
+        assert_eq!(1.0, beside.y());
+        assert!(beside.x() > 1000.0);
+
+        w.set_sky(GradientSky::new(Colors::Blue.into(), Colors::White.into()));
+
+        assert_eq!(Color::from(Colors::Black), w.lights()[index].intensity());
+    }
+
     #[test]
     fn creating_a_world() {
         let w = World::new();
@@ -275,7 +1146,7 @@ mod tests {
         let w = World::default();
         let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
 
-        let xs = w.intersects(r);
+        let xs = w.intersects(&r);
 
         assert_eq!(4, xs.len());
         assert_eq!(4.0, xs[0].t());
@@ -284,6 +1155,20 @@ mod tests {
         assert_eq!(6.0, xs[3].t());
     }
 
+    #[test]
+    fn intersecting_into_a_reused_heap_replaces_its_contents() {
+        let w = World::default();
+        let mut xs = IntersectionHeap::with_capacity(4);
+        let hit = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let miss = Ray::new(Tuple::point(0.0, 5.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        w.intersects_into(&hit, &mut xs);
+        assert_eq!(4, xs.len());
+
+        w.intersects_into(&miss, &mut xs);
+        assert!(xs.is_empty());
+    }
+
     #[test]
     fn shading_an_intersection() {
         let w = World::default();
@@ -299,12 +1184,61 @@ mod tests {
     }
 
     #[test]
-    fn shading_an_intersection_from_the_inside() {
+    fn irradiance_probes_add_baked_light_to_diffuse_surfaces() {
+        let gray = Color::new(0.5, 0.5, 0.5);
+        let mut open = World::new();
+        open.set_sky(GradientSky::new(gray, gray).with_ground(gray));
+        let probes = IrradianceProbes::bake(
+            &open,
+            Tuple::point(-2.0, -2.0, -2.0),
+            Tuple::point(2.0, 2.0, 2.0),
+            (2, 2, 2),
+            64,
+        );
         let mut w = World::default();
-        w.lights = vec![PointLight::new(
-            Tuple::point(0.0, 0.25, 0.0),
-            Colors::White.into(),
-        )];
+        w.set_irradiance_probes(probes);
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let shape = w.shapes()[0].clone();
+        let i = ShapeIntersection::new(4.0, shape.clone(), shape.id());
+
+        let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
+
+        let c = w.shade_hit(&comps);
+
+        let baked = Color::new(0.8, 1.0, 0.6) * 0.5 * 0.7;
+        assert!(c.approx_eq(&(Color::new(0.38066, 0.47583, 0.2855) + baked), 1e-3));
+    }
+
+    #[test]
+    fn shading_with_several_lights_adds_each_lights_contribution() {
+        let key = PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Colors::White.into());
+        let fill = PointLight::new(Tuple::point(10.0, 0.0, -10.0), Color::new(0.3, 0.3, 0.3));
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let lit_by = |lights: Vec<PointLight>| {
+            let mut w = World::new();
+            for shape in World::default().shapes() {
+                w.add_shape(shape.clone());
+            }
+            for light in lights {
+                w.add_light(light);
+            }
+            w.color_at(r)
+        };
+
+        assert_eq!(
+            lit_by(vec![key]) + lit_by(vec![fill]),
+            lit_by(vec![key, fill])
+        );
+    }
+
+    #[test]
+    fn shading_an_intersection_from_the_inside() {
+        let w = World {
+            lights: vec![
+                PointLight::new(Tuple::point(0.0, 0.25, 0.0), Colors::White.into()).into(),
+            ],
+            ..World::default()
+        };
         let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
         let shape = w.shapes()[1].clone();
         let i = ShapeIntersection::new(0.5, shape.clone(), shape.id());
@@ -334,6 +1268,19 @@ mod tests {
         assert_eq!(Color::new(0.38066, 0.47583, 0.2855), c);
     }
 
+    #[test]
+    fn tracing_through_a_reused_scratch_keeps_its_buffers() {
+        let w = World::default();
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let mut scratch = Scratch::new();
+
+        assert_eq!(w.color_at(r), w.color_at_with(r, &mut scratch));
+        let capacity = scratch.heap().capacity();
+        assert_eq!(w.color_at(r), w.color_at_with(r, &mut scratch));
+        assert_eq!(4, scratch.heap().len());
+        assert_eq!(capacity, scratch.heap().capacity());
+    }
+
     #[test]
     fn the_color_with_an_intersection_behind_the_ray() {
         let w = World::default();
@@ -400,10 +1347,8 @@ mod tests {
     #[test]
     fn shade_hit_is_given_an_intersection_in_shadow() {
         let mut w = World::new();
-        w.lights = vec![PointLight::new(
-            Tuple::point(0.0, 0.0, -10.0),
-            Colors::White.into(),
-        )];
+        w.lights =
+            vec![PointLight::new(Tuple::point(0.0, 0.0, -10.0), Colors::White.into()).into()];
 
         let s1 = Sphere::new();
         w.add_shape(s1.into());
@@ -422,6 +1367,76 @@ mod tests {
         assert_eq!(Color::new(0.1, 0.1, 0.1), c);
     }
 
+    #[test]
+    fn subsurface_scattering_lights_a_sphere_from_behind() {
+        let shade = |subsurface: f64| {
+            let mut w = World::new();
+            w.lights =
+                vec![PointLight::new(Tuple::point(10.0, 0.0, 0.0), Colors::White.into()).into()];
+            let mut s = Sphere::new();
+            s.set_material(
+                Material::new()
+                    .with_subsurface(subsurface)
+                    .with_scatter_distance(10f64.ln()),
+            );
+            w.add_shape(s.into());
+
+            let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+            let i = ShapeIntersection::new(4.0, w.shapes()[0].clone(), w.shapes()[0].id());
+            let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
+
+            assert!(w.is_shadowed(comps.over_point()));
+            w.shade_hit(&comps)
+        };
+
+        assert_eq!(Color::new(0.1, 0.1, 0.1), shade(0.0));
+        assert!(shade(1.0).red() > 0.4);
+    }
+
+    #[test]
+    fn subsurface_light_is_stopped_by_other_shapes_and_dimmed_by_the_light() {
+        let shade = |light: Light, wall: bool| {
+            let mut w = World::new();
+            w.add_light(light);
+            let mut s = Sphere::new();
+            s.set_material(
+                Material::new()
+                    .with_subsurface(1.0)
+                    .with_scatter_distance(10f64.ln()),
+            );
+            w.add_shape(s.into());
+            if wall {
+                let mut wall = Plane::new();
+                wall.set_transformation(
+                    Transformation::identity()
+                        .rotate_z(PI / 2.0)
+                        .translation(5.0, 0.0, 0.0),
+                );
+                w.add_shape(wall.into());
+            }
+
+            let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+            let i = ShapeIntersection::new(4.0, w.shapes()[0].clone(), w.shapes()[0].id());
+            w.shade_hit(&PrepComputations::new(i, r, &IntersectionHeap::new()))
+        };
+        let position = Tuple::point(10.0, 0.0, 0.0);
+        let white = Colors::White.into();
+        let point = PointLight::new(position, white);
+        let ambient = Color::new(0.1, 0.1, 0.1);
+
+        assert!(shade(point.into(), false).red() > 0.4);
+        assert_eq!(ambient, shade(point.into(), true));
+        assert_eq!(
+            ambient,
+            shade(
+                SpotLight::aimed_at(position, Tuple::point(10.0, 0.0, 10.0), white).into(),
+                false
+            )
+        );
+        let dimmed = shade(point.with_falloff(Falloff::InverseSquare).into(), false);
+        assert!(dimmed.red() > ambient.red() && dimmed.red() < 0.11);
+    }
+
     #[test]
     fn the_reflected_color_for_a_nonreflective_material() {
         let w = World::default();
@@ -434,7 +1449,7 @@ mod tests {
             .set_material(Material::new().with_ambient(1.0));
         let i = ShapeIntersection::new(1.0, w.shapes()[1].clone(), w.shapes()[1].id());
         let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
-        let color = w.reflected_color(&comps, 5);
+        let color = w.reflected_color(&comps, 5, 1.0, &mut Scratch::new());
 
         assert_eq!(color, Colors::Black.into());
     }
@@ -454,7 +1469,7 @@ mod tests {
         );
         let i = ShapeIntersection::new(2f64.sqrt(), shape.clone(), shape.id());
         let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
-        let color = w.reflected_color(&comps, 5);
+        let color = w.reflected_color(&comps, 5, 1.0, &mut Scratch::new());
 
         assert_eq!(Color::new(0.19033, 0.23791, 0.14274), color);
 
@@ -498,7 +1513,7 @@ mod tests {
         );
         let i = ShapeIntersection::new(2f64.sqrt(), shape.clone(), shape.id());
         let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
-        let color = w.reflected_color(&comps, 0);
+        let color = w.reflected_color(&comps, 0, 1.0, &mut Scratch::new());
 
         assert_eq!(color, Colors::Black.into());
     }
@@ -513,7 +1528,7 @@ mod tests {
             ShapeIntersection::new(6.0, shape.clone(), shape.id())
         );
         let comps = PrepComputations::new(xs[0].clone(), r, &xs);
-        let c = w.refracted_color(&comps, 5);
+        let c = w.refracted_color(&comps, 5, 1.0, &mut Scratch::new());
 
         assert_eq!(c, Colors::Black.into());
     }
@@ -535,7 +1550,7 @@ mod tests {
             ShapeIntersection::new(6.0, shape.clone(), shape.id())
         );
         let comps = PrepComputations::new(xs[0].clone(), r, &xs);
-        let c = w.refracted_color(&comps, 0);
+        let c = w.refracted_color(&comps, 0, 1.0, &mut Scratch::new());
 
         assert_eq!(c, Colors::Black.into());
     }
@@ -559,7 +1574,7 @@ mod tests {
             ShapeIntersection::new(2f64.sqrt() / 2.0, shape.clone(), shape.id())
         );
         let comps = PrepComputations::new(xs[1].clone(), r, &xs);
-        let c = w.refracted_color(&comps, 5);
+        let c = w.refracted_color(&comps, 5, 1.0, &mut Scratch::new());
 
         assert_eq!(c, Colors::Black.into());
     }
@@ -588,7 +1603,7 @@ mod tests {
         );
 
         let comps = PrepComputations::new(xs[2].clone(), r, &xs);
-        let c = w.refracted_color(&comps, 5);
+        let c = w.refracted_color(&comps, 5, 1.0, &mut Scratch::new());
         assert_eq!(c, Color::new(0.0, 0.99887, 0.04722));
     }
 
@@ -633,6 +1648,34 @@ mod tests {
         assert_eq!(color, Color::new(0.93642, 0.68642, 0.68642));
     }
 
+    #[test]
+    fn rays_carry_on_through_clear_surfaces_without_shading_them() {
+        let mut w = World::default();
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let expected = w.color_at(r);
+
+        let mut bubble = Sphere::new();
+        bubble.set_transformation(
+            Transformation::identity()
+                .scale(0.5, 0.5, 0.5)
+                .translation(0.0, 0.0, -4.0),
+        );
+        bubble.set_material(
+            Material::new()
+                .with_color(Color::new(1.0, 0.0, 0.0))
+                .with_ambient(1.0)
+                .with_transparency(1.0),
+        );
+        w.add_shape(bubble.into());
+
+        assert!(Material::new().with_transparency(1.0).is_clear());
+        assert!(!Material::new()
+            .with_transparency(1.0)
+            .with_refractive_index(1.5)
+            .is_clear());
+        assert_eq!(expected, w.color_at(r));
+    }
+
     #[test]
     fn shade_hit_with_a_reflective_transparent_material() {
         let mut w = World::default();
@@ -672,4 +1715,367 @@ mod tests {
         let color = w.shade_hit(&comps);
         assert_eq!(color, Color::new(0.93391, 0.69643, 0.69243));
     }
+
+    #[test]
+    fn the_default_light_sampling_uses_every_light() {
+        let mut w = World::default();
+        w.add_light(PointLight::new(
+            Tuple::point(10.0, 10.0, -10.0),
+            Colors::White.into(),
+        ));
+
+        assert_eq!(w.light_sampling(), LightSampling::All);
+        let samples = w.sample_lights();
+        assert_eq!(samples.len(), 2);
+        assert!(samples.iter().all(|(_, weight)| eq_f64(*weight, 1.0)));
+    }
+
+    #[test]
+    fn uniform_light_sampling_weights_the_chosen_subset() {
+        let mut w = World::new();
+        for x in 0..4 {
+            w.add_light(PointLight::new(
+                Tuple::point(x as f64, 10.0, -10.0),
+                Colors::White.into(),
+            ));
+        }
+        w.set_light_sampling(LightSampling::Uniform(2));
+
+        let samples = w.sample_lights();
+
+        assert_eq!(samples.len(), 2);
+        assert_ne!(samples[0].0, samples[1].0);
+        assert!(samples.iter().all(|(_, weight)| eq_f64(*weight, 2.0)));
+    }
+
+    #[test]
+    fn uniform_light_sampling_larger_than_the_light_count_uses_every_light() {
+        let mut w = World::default();
+        w.set_light_sampling(LightSampling::Uniform(3));
+
+        let samples = w.sample_lights();
+
+        assert_eq!(samples.len(), 1);
+        assert!(eq_f64(samples[0].1, 1.0));
+    }
+
+    #[test]
+    fn power_weighted_light_sampling_never_picks_a_dark_light() {
+        let mut w = World::new();
+        let bright = PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::new(3.0, 3.0, 3.0));
+        let dark = PointLight::new(Tuple::point(10.0, 10.0, -10.0), Colors::Black.into());
+        w.add_light(bright);
+        w.add_light(dark);
+        w.set_light_sampling(LightSampling::PowerWeighted(4));
+
+        let samples = w.sample_lights();
+
+        assert_eq!(samples.len(), 4);
+        for (light, weight) in samples {
+            assert_eq!(light, bright);
+            assert!(eq_f64(weight, 0.25));
+        }
+    }
+
+    #[test]
+    fn power_weighted_sampling_of_a_single_light_matches_shading_all_lights() {
+        let mut w = World::default();
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let expected = w.color_at(r);
+
+        w.set_light_sampling(LightSampling::PowerWeighted(3));
+
+        assert_eq!(expected, w.color_at(r));
+    }
+
+    #[test]
+    fn shadows_are_tested_per_light() {
+        let mut w = World::default();
+        let p = Tuple::point(10.0, -10.0, 10.0);
+        let unblocked = PointLight::new(Tuple::point(20.0, -10.0, 10.0), Colors::White.into());
+        w.add_light(unblocked);
+
+        assert!(w.is_shadowed(p));
+        assert!(w.is_shadowed_from(w.lights()[0], p));
+        assert!(!w.is_shadowed_from(unblocked, p));
+    }
+
+    #[test]
+    fn a_ray_passing_through_a_portal_continues_from_its_twin() {
+        let mut w = World::default();
+        let (entry, exit) = Portal::pair(
+            Transformation::identity()
+                .rotate_x(PI / 2.0)
+                .translation(10.0, 0.0, -3.0),
+            Transformation::identity()
+                .rotate_x(PI / 2.0)
+                .translation(0.0, 0.0, -3.0),
+        );
+        w.add_shape(entry);
+        w.add_shape(exit);
+
+        let r = Ray::new(Tuple::point(10.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        assert_eq!(w.color_at(r), Color::new(0.38066, 0.47583, 0.2855));
+
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        assert_eq!(w.color_at(r), Colors::Black.into());
+    }
+
+    #[test]
+    fn a_shape_hidden_from_the_camera_is_skipped_by_primary_rays() {
+        let w = World::default();
+        w.shapes()[0]
+            .write()
+            .unwrap()
+            .set_visibility(Visibility::new().with_camera(false));
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        let xs = w.intersects_for(&r, RayKind::Camera);
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t(), 4.5);
+        assert_eq!(w.intersects_for(&r, RayKind::Secondary).len(), 4);
+    }
+
+    #[test]
+    fn a_shape_hidden_from_the_camera_still_casts_shadows() {
+        let w = World::default();
+        w.shapes()[0]
+            .write()
+            .unwrap()
+            .set_visibility(Visibility::new().with_camera(false));
+        let p = Tuple::point(10.0, -10.0, 10.0);
+
+        assert!(w.is_shadowed(p));
+    }
+
+    #[test]
+    fn a_shape_hidden_from_shadows_does_not_block_light() {
+        let w = World::default();
+        for s in w.shapes() {
+            s.write()
+                .unwrap()
+                .set_visibility(Visibility::new().with_shadow(false));
+        }
+        let p = Tuple::point(10.0, -10.0, 10.0);
+
+        assert!(!w.is_shadowed(p));
+    }
+
+    #[test]
+    fn a_shape_hidden_from_secondary_rays_is_not_reflected() {
+        let mut w = World::default();
+        let mut shape = Plane::new();
+        shape.set_material(Material::new().with_reflective(0.5));
+        shape.set_transformation(Transformation::identity().translation(0.0, -1.0, 0.0));
+        let shape = ShapeContainer::from(shape);
+        w.shapes_mut().push(shape.clone());
+        for s in w.shapes().iter().take(2) {
+            s.write()
+                .unwrap()
+                .set_visibility(Visibility::new().with_secondary(false));
+        }
+
+        let r = Ray::new(
+            Tuple::point(0.0, 0.0, -3.0),
+            Tuple::vector(0.0, -2f64.sqrt() / 2.0, 2f64.sqrt() / 2.0),
+        );
+        let i = ShapeIntersection::new(2f64.sqrt(), shape.clone(), shape.id());
+        let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
+        let color = w.reflected_color(&comps, 5, 1.0, &mut Scratch::new());
+
+        assert_eq!(color, Colors::Black.into());
+    }
+
+    #[test]
+    fn roulette_is_disabled_by_default() {
+        let w = World::default();
+
+        assert!(eq_f64(w.roulette_threshold(), 0.0));
+        assert_eq!(w.roulette(0.0), Some(1.0));
+    }
+
+    #[test]
+    fn roulette_keeps_paths_above_the_threshold() {
+        let mut w = World::default();
+        w.set_roulette_threshold(0.1);
+
+        assert_eq!(w.roulette(0.5), Some(1.0));
+        assert_eq!(w.roulette(0.1), Some(1.0));
+    }
+
+    #[test]
+    fn roulette_boosts_surviving_paths_and_terminates_empty_ones() {
+        let mut w = World::default();
+        w.set_roulette_threshold(0.5);
+
+        for _ in 0..20 {
+            if let Some(boost) = w.roulette(0.25) {
+                assert!(eq_f64(boost, 2.0));
+            }
+        }
+        assert_eq!(w.roulette(0.0), None);
+    }
+
+    #[test]
+    fn roulette_preserves_the_expected_reflected_color() {
+        let mut w = World::default();
+        let mut shape = Plane::new();
+        shape.set_material(Material::new().with_reflective(0.5));
+        shape.set_transformation(Transformation::identity().translation(0.0, -1.0, 0.0));
+        let shape = ShapeContainer::from(shape);
+        w.shapes_mut().push(shape.clone());
+        w.set_roulette_threshold(1.0);
+
+        let r = Ray::new(
+            Tuple::point(0.0, 0.0, -3.0),
+            Tuple::vector(0.0, -2f64.sqrt() / 2.0, 2f64.sqrt() / 2.0),
+        );
+        let i = ShapeIntersection::new(2f64.sqrt(), shape.clone(), shape.id());
+        let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
+
+        let samples = 4000;
+        let mut sum: Color = Colors::Black.into();
+        for _ in 0..samples {
+            let color = w.reflected_color(&comps, 5, 1.0, &mut Scratch::new());
+            assert!(color == Colors::Black.into() || color == Color::new(0.38066, 0.47583, 0.2855));
+            sum += color;
+        }
+        let mean = sum * (1.0 / samples as f64);
+
+        assert!((mean.green() - 0.23791).abs() < 0.03);
+    }
+
+    #[test]
+    fn the_maximum_depth_limits_recursion() {
+        let mut w = World::default();
+        let mut shape = Plane::new();
+        shape.set_material(Material::new().with_reflective(0.5));
+        shape.set_transformation(Transformation::identity().translation(0.0, -1.0, 0.0));
+        w.add_shape(shape.into());
+        w.set_max_depth(0);
+
+        let r = Ray::new(
+            Tuple::point(0.0, 0.0, -3.0),
+            Tuple::vector(0.0, -2f64.sqrt() / 2.0, 2f64.sqrt() / 2.0),
+        );
+
+        assert_eq!(w.max_depth(), 0);
+        assert_eq!(w.color_at(r), Color::new(0.68642, 0.68642, 0.68642));
+    }
+
+    #[test]
+    fn the_last_occluder_of_a_light_is_cached() {
+        let w = World::default();
+        let p = Tuple::point(10.0, -10.0, 10.0);
+
+        assert_eq!(w.shadow_cache[0].load(Ordering::Relaxed), NO_OCCLUDER);
+        assert!(w.is_shadowed(p));
+        assert_eq!(w.shadow_cache[0].load(Ordering::Relaxed), 0);
+        assert!(w.is_shadowed(p));
+        assert!(!w.is_shadowed(Tuple::point(-2.0, 2.0, -2.0)));
+    }
+
+    #[test]
+    fn a_stale_occluder_does_not_hide_other_occluders() {
+        let mut w = World::default();
+        let mut wall = Plane::new();
+        wall.set_transformation(
+            Transformation::identity()
+                .rotate_x(PI / 2.0)
+                .translation(0.0, 0.0, 20.0),
+        );
+        w.add_shape(wall.into());
+
+        assert!(w.is_shadowed(Tuple::point(10.0, -10.0, 10.0)));
+        assert_eq!(w.shadow_cache[0].load(Ordering::Relaxed), 0);
+
+        assert!(w.is_shadowed(Tuple::point(0.0, 0.0, 30.0)));
+        assert_eq!(w.shadow_cache[0].load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn the_stats_of_the_default_world() {
+        let stats = World::default().stats();
+
+        assert_eq!(stats.shapes(), 2);
+        assert_eq!(stats.count("Sphere"), 2);
+        assert_eq!(stats.lights(), 1);
+    }
+
+    #[test]
+    fn vertex_colors_replace_the_material_color() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let mut triangle = Triangle::new(
+            Tuple::point(0.0, 1.0, 0.0),
+            Tuple::point(-1.0, 0.0, 0.0),
+            Tuple::point(1.0, 0.0, 0.0),
+        )
+        .with_vertex_colors(red, red, red);
+        triangle.set_material(Material::new().with_specular(0.0));
+        let mut w = World::new();
+        w.add_shape(triangle.into());
+        w.add_light(PointLight::new(
+            Tuple::point(0.0, 0.0, -10.0),
+            Colors::White.into(),
+        ));
+        let r = Ray::new(Tuple::point(0.0, 0.5, -2.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        let c = w.color_at(r);
+
+        assert!(c.red() > 0.0);
+        assert_eq!(0.0, c.green());
+        assert_eq!(0.0, c.blue());
+    }
+
+    #[test]
+    fn an_excluded_shape_is_not_lit_at_all() {
+        let mut w = World::default();
+        let shape = w.shapes()[0].clone();
+        w.set_light_link(0, LightLink::Exclude(vec![shape.id()]));
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let i = ShapeIntersection::new(4.0, shape.clone(), shape.id());
+
+        let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
+
+        assert_eq!(Color::from(Colors::Black), w.shade_hit(&comps));
+    }
+
+    #[test]
+    fn identical_lights_keep_their_own_links() {
+        let mut w = World::default();
+        let shape = w.shapes()[0].clone();
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let i = ShapeIntersection::new(4.0, shape.clone(), shape.id());
+        let comps = PrepComputations::new(i, r, &IntersectionHeap::new());
+        let expected = w.shade_hit(&comps);
+
+        w.add_light(w.lights()[0]);
+        w.set_light_link(0, LightLink::Exclude(vec![shape.id()]));
+
+        assert_eq!(expected, w.shade_hit(&comps));
+    }
+
+    #[test]
+    fn linking_lights_to_shapes_by_name() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(
+            Tuple::point(-10.0, 10.0, -10.0),
+            Colors::White.into(),
+        ));
+        w.add_named_shape("rim", Sphere::new().into());
+
+        w.link_light_by_name(0, &["rim"], true).unwrap();
+
+        assert_eq!(
+            Some(&LightLink::Include(vec![w.shape_id("rim").unwrap()])),
+            w.light_link(0)
+        );
+        assert!(matches!(
+            w.link_light_by_name(0, &["fill"], true),
+            Err(RayTraceError::UnknownShape(_))
+        ));
+    }
 }